@@ -22,6 +22,7 @@ mod wallet;
 pub mod fixed_term;
 pub mod margin;
 pub mod margin_pool;
+pub mod swaps;
 
 pub use error::ClientError;
 use wallet::WalletAdapter;
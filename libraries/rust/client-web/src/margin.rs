@@ -6,7 +6,7 @@ use jet_client::margin::{MarginAccountClient, MarginClient, MarginPosition};
 
 use crate::{
     fixed_term::MarginAccountFixedTermMarketWebClient, margin_pool::MarginAccountPoolWebClient,
-    ClientError,
+    swaps::MarginAccountSwapsWebClient, ClientError,
 };
 
 #[wasm_bindgen]
@@ -80,6 +80,10 @@ impl MarginAccountWebClient {
         MarginAccountPoolWebClient(self.inner.pool(token))
     }
 
+    pub fn swaps(&self) -> MarginAccountSwapsWebClient {
+        MarginAccountSwapsWebClient(self.inner.swaps())
+    }
+
     #[wasm_bindgen(js_name = fixedTermMarket)]
     pub fn fixed_term_market(
         &self,
@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+
+use solana_sdk::pubkey::Pubkey;
+
+use jet_client::swaps::MarginAccountSwapsClient;
+
+use crate::ClientError;
+
+#[wasm_bindgen]
+pub struct MarginAccountSwapsWebClient(pub(crate) MarginAccountSwapsClient);
+
+#[wasm_bindgen]
+impl MarginAccountSwapsWebClient {
+    pub fn quote(
+        &self,
+        source_token: &Pubkey,
+        target_token: &Pubkey,
+        amount: u64,
+    ) -> Result<SwapQuote, ClientError> {
+        Ok(SwapQuote(self.0.quote(
+            source_token,
+            target_token,
+            amount,
+        )?))
+    }
+
+    pub async fn swap(
+        &self,
+        source_token: &Pubkey,
+        target_token: &Pubkey,
+        amount: u64,
+        slippage_bps: u16,
+    ) -> Result<(), ClientError> {
+        Ok(self
+            .0
+            .swap(source_token, target_token, amount, slippage_bps)
+            .await?)
+    }
+
+    #[wasm_bindgen(js_name = routeSwap)]
+    pub async fn route_swap(
+        &self,
+        quote: &SwapQuote,
+        minimum_amount_out: u64,
+    ) -> Result<(), ClientError> {
+        Ok(self.0.route_swap(&quote.0, minimum_amount_out).await?)
+    }
+}
+
+/// A route for swapping tokens, and the amount it is expected to return
+#[wasm_bindgen]
+pub struct SwapQuote(jet_client::swaps::SwapQuote);
+
+#[wasm_bindgen]
+impl SwapQuote {
+    #[wasm_bindgen(getter, js_name = sourceToken)]
+    pub fn source_token(&self) -> Pubkey {
+        self.0.source_token
+    }
+
+    #[wasm_bindgen(getter, js_name = targetToken)]
+    pub fn target_token(&self) -> Pubkey {
+        self.0.target_token
+    }
+
+    #[wasm_bindgen(getter, js_name = amountIn)]
+    pub fn amount_in(&self) -> u64 {
+        self.0.amount_in
+    }
+
+    #[wasm_bindgen(getter, js_name = amountOut)]
+    pub fn amount_out(&self) -> u64 {
+        self.0.amount_out
+    }
+
+    /// The pools used by the route, in order
+    pub fn pools(&self) -> js_sys::Array {
+        let pools = self.0.legs.iter().flat_map(|leg| {
+            std::iter::once(leg.pool)
+                .chain(leg.split_pool)
+                .map(JsValue::from)
+        });
+
+        js_sys::Array::from_iter(pools)
+    }
+
+    #[wasm_bindgen(js_name = minimumAmountOut)]
+    pub fn minimum_amount_out(&self, slippage_bps: u16) -> u64 {
+        self.0.minimum_amount_out(slippage_bps)
+    }
+}
@@ -38,6 +38,12 @@ impl From<bincode::Error> for ClientError {
     }
 }
 
+impl From<jet_instructions::JetIxError> for ClientError {
+    fn from(err: jet_instructions::JetIxError) -> Self {
+        Self::Unexpected(err.to_string())
+    }
+}
+
 /// Central object for client implementations, containing the global configuration and any
 /// caching for account data.
 pub struct ClientState {
@@ -11,7 +11,7 @@ use jet_fixed_term::{
         origination_fee::FEE_UNIT,
         state::{AutoRollConfig, TermLoan},
     },
-    orderbook::state::OrderParams,
+    orderbook::state::{OrderParams, TimeInForce},
     tickets::state::TermDeposit,
 };
 use jet_instructions::fixed_term::{derive, FixedTermIxBuilder};
@@ -171,6 +171,7 @@ impl MarginAccountMarketClient {
             post_allowed: true,
             auto_stake: true,
             auto_roll: self.should_auto_roll_lend_order(),
+            time_in_force: TimeInForce::GoodTilCancelled,
         };
 
         self.offer_loan_with_params(params).await
@@ -192,6 +193,7 @@ impl MarginAccountMarketClient {
             post_allowed: true,
             auto_stake: true,
             auto_roll: self.should_auto_roll_borrow_order(),
+            time_in_force: TimeInForce::GoodTilCancelled,
         };
 
         self.request_loan_with_params(params).await
@@ -213,6 +215,7 @@ impl MarginAccountMarketClient {
             post_allowed: true,
             auto_stake: true,
             auto_roll: false,
+            time_in_force: TimeInForce::GoodTilCancelled,
         };
 
         self.sell_tickets_with_params(params).await
@@ -234,6 +237,7 @@ impl MarginAccountMarketClient {
             post_allowed: false,
             auto_stake: true,
             auto_roll: self.should_auto_roll_lend_order(),
+            time_in_force: TimeInForce::GoodTilCancelled,
         };
 
         self.offer_loan_with_params(params).await
@@ -255,6 +259,7 @@ impl MarginAccountMarketClient {
             post_allowed: false,
             auto_stake: true,
             auto_roll: self.should_auto_roll_borrow_order(),
+            time_in_force: TimeInForce::GoodTilCancelled,
         };
 
         self.request_loan_with_params(params).await
@@ -9,10 +9,14 @@ use spl_associated_token_account::{
 };
 
 use jet_instructions::{
-    margin::{derive_margin_account, derive_token_config, MarginIxBuilder},
+    margin::{
+        derive_margin_account, derive_token_config, derive_token_oracle_set, MarginIxBuilder,
+    },
     margin_pool::derive_margin_pool,
 };
-use jet_margin::{AccountPosition, MarginAccount, TokenAdmin, TokenConfig, TokenKind, TokenOracle};
+use jet_margin::{
+    AccountPosition, MarginAccount, TokenAdmin, TokenConfig, TokenKind, TokenOracleSet,
+};
 use jet_margin_pool::{Amount, MarginPool, PoolAction};
 use jet_program_common::Number128;
 use jet_solana_client::{
@@ -318,6 +322,22 @@ impl MarginAccountClient {
         Ok(address)
     }
 
+    pub(crate) fn token_fallback_oracles(&self, config: &TokenConfig) -> ClientResult<Vec<Pubkey>> {
+        if !config.has_oracle_set {
+            return Ok(vec![]);
+        }
+
+        let address = derive_token_oracle_set(&derive_token_config(&self.airspace(), &config.mint));
+
+        self.client
+            .state()
+            .get::<TokenOracleSet>(&address)
+            .map(|set| set.fallbacks.iter().map(|o| o.price_account()).collect())
+            .ok_or_else(|| {
+                ClientError::Unexpected(format!("no oracle set found for token {}", config.mint))
+            })
+    }
+
     pub(crate) fn token_config(&self, token: &Pubkey) -> ClientResult<TokenConfig> {
         let address = derive_token_config(&self.airspace(), token);
 
@@ -342,13 +362,20 @@ impl MarginAccountClient {
             match position.adapter {
                 id if id == Pubkey::default() => {
                     let oracle = match token_config.oracle() {
-                        Some(TokenOracle::Pyth { price, .. }) => price,
-                        _ => bail!("deposit position should have an oracle: {}", position.token),
+                        Some(oracle) => oracle.price_account(),
+                        None => bail!("deposit position should have an oracle: {}", position.token),
                     };
 
+                    let fallback_oracles = self.token_fallback_oracles(&token_config)?;
+
                     txns.push(
                         self.builder
-                            .refresh_deposit_position(position.token, &oracle, true)
+                            .refresh_deposit_position_with_fallbacks(
+                                position.token,
+                                &oracle,
+                                &fallback_oracles,
+                                true,
+                            )
                             .into(),
                     );
                 }
@@ -424,10 +451,7 @@ impl MarginAccountClient {
         };
 
         let oracle = match underlying_config.admin {
-            TokenAdmin::Margin { oracle } => {
-                let TokenOracle::Pyth { price, .. } = oracle;
-                price
-            }
+            TokenAdmin::Margin { oracle } => oracle.price_account(),
             _ => {
                 log::error!(
                     "did not find oracle in config for position with underlying token {}",
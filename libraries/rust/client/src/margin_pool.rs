@@ -279,10 +279,18 @@ impl MarginAccountPoolClient {
 
     fn instruction_for_refresh(&self) -> ClientResult<Instruction> {
         let token_info = self.client.state().token_info(&self.builder.token_mint)?;
+        let fallback_oracles = match self.account.token_config(&self.builder.token_mint) {
+            Ok(config) => self.account.token_fallback_oracles(&config)?,
+            Err(_) => vec![],
+        };
 
         Ok(self.account.builder.accounting_invoke(
-            self.builder
-                .margin_refresh_position(self.account.address, token_info.oracle),
+            self.builder.margin_refresh_position_with_fallbacks(
+                &self.account.airspace(),
+                self.account.address,
+                token_info.oracle,
+                &fallback_oracles,
+            ),
         ))
     }
 
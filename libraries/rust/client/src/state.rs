@@ -1,25 +1,35 @@
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
 use jet_instructions::airspace::derive_airspace;
-use jet_solana_client::rpc::SolanaRpc;
+use jet_program_common::Number128;
+use jet_solana_client::rpc::{SolanaRpc, SolanaSubscriptions};
 use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc::UnboundedReceiver;
 
 use crate::{
     client::ClientResult,
     config::{DexInfo, JetAppConfig, TokenInfo},
     ClientError,
 };
+use oracles::PriceOracleState;
 
 pub mod fixed_term;
+pub mod live;
 pub mod margin;
 pub mod margin_pool;
+pub mod openbook;
 pub mod oracles;
+pub mod saber_swap;
 pub mod spl_swap;
 pub mod tokens;
+pub mod whirlpool;
 
 /// A utility for synchronizing information about the current protocol state
 /// with an active Solana network.
@@ -73,16 +83,30 @@ impl AccountStates {
     }
 
     pub async fn sync_all(&self) -> ClientResult<()> {
-        self::oracles::sync(self).await?;
         self::spl_swap::sync(self).await?;
+        self::saber_swap::sync(self).await?;
+        self::whirlpool::sync(self).await?;
+        self::openbook::sync(self).await?;
         self::margin_pool::sync(self).await?;
         self::fixed_term::sync(self).await?;
         self::margin::sync(self).await?;
+
+        // after the configs, which determine the oracles to load and who may publish to them
+        self::oracles::sync(self).await?;
         self::tokens::sync(self).await?;
 
         Ok(())
     }
 
+    /// Load the current state, then keep it up to date from account subscriptions
+    pub async fn sync_live(
+        &self,
+        subscriptions: Arc<dyn SolanaSubscriptions>,
+    ) -> ClientResult<live::LiveSync<'_>> {
+        self.sync_all().await?;
+        live::LiveSync::new(self, subscriptions).await
+    }
+
     pub fn token_info(&self, token: &Pubkey) -> ClientResult<TokenInfo> {
         self.config
             .tokens
@@ -95,6 +119,49 @@ impl AccountStates {
     pub fn get_current_time(&self) -> i64 {
         chrono::Utc::now().timestamp()
     }
+
+    /// Call a function when the price of a token moves more than the given number of basis
+    /// points away from the price it was last reported at
+    pub fn observe_price_move(
+        &self,
+        token: &Pubkey,
+        threshold_bps: u16,
+        observer: impl Fn(&PriceMove) + Send + Sync + 'static,
+    ) -> ClientResult<ObserverId> {
+        let token = *token;
+        let oracle = self.token_info(&token)?.oracle;
+        let threshold = Number128::from_bps(threshold_bps);
+        let reference = Mutex::new(self.get::<PriceOracleState>(&oracle).map(|s| s.price));
+
+        let id = self.observe_address(oracle, move |change: &Change<PriceOracleState>| {
+            let price = change.new.price;
+            let price_move = {
+                let mut reference = reference.lock().unwrap();
+                let last = match *reference {
+                    Some(last) if last != Number128::ZERO => last,
+                    _ => {
+                        *reference = Some(price);
+                        return;
+                    }
+                };
+
+                if ((price - last) / last).abs() <= threshold {
+                    return;
+                }
+
+                *reference = Some(price);
+                PriceMove {
+                    token,
+                    reference: last,
+                    price,
+                }
+            };
+
+            observer(&price_move);
+        });
+
+        Ok(id)
+    }
 }
 
 impl std::ops::Deref for AccountStates {
@@ -116,9 +183,56 @@ pub struct StateConfig {
 
 type StoredStateObj = Arc<dyn Any + Send + Sync>;
 
+/// Returns false once the observer should be removed
+type StoredObserver =
+    Arc<dyn Fn(&Pubkey, Option<&StoredStateObj>, &StoredStateObj) -> bool + Send + Sync>;
+
+/// Identifies an observer registered with an [`AccountCache`], so that it can be removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u64);
+
+/// A change to a state in the cache
+#[derive(Debug)]
+pub struct Change<T> {
+    /// The address of the account for the state
+    pub address: Pubkey,
+
+    /// The previous state, if it had been loaded before
+    pub old: Option<Arc<T>>,
+
+    /// The state that replaced it
+    pub new: Arc<T>,
+}
+
+impl<T> Clone for Change<T> {
+    fn clone(&self) -> Self {
+        Self {
+            address: self.address,
+            old: self.old.clone(),
+            new: self.new.clone(),
+        }
+    }
+}
+
+/// A move in the price of a token, beyond the threshold of an observer
+#[derive(Debug, Clone, Copy)]
+pub struct PriceMove {
+    /// The mint of the token
+    pub token: Pubkey,
+
+    /// The price that the move is measured from, which is the price when the observer
+    /// was last called, or when it first saw the price
+    pub reference: Number128,
+
+    /// The new price
+    pub price: Number128,
+}
+
 #[derive(Default)]
 pub struct AccountCache {
     states: Mutex<HashMap<TypeId, HashMap<Pubkey, Option<StoredStateObj>>>>,
+    observers: Mutex<HashMap<TypeId, Vec<(ObserverId, StoredObserver)>>>,
+    next_observer_id: AtomicU64,
 }
 
 impl AccountCache {
@@ -218,18 +332,23 @@ impl AccountCache {
 
     pub fn set<T: Any + Send + Sync>(&self, address: &Pubkey, data: T) {
         let type_id = TypeId::of::<T>();
+        let new: StoredStateObj = Arc::new(data);
 
-        let mut states = self.states.lock().unwrap();
+        let old = {
+            let mut states = self.states.lock().unwrap();
 
-        let accounts = match states.get_mut(&type_id) {
-            Some(accounts) => accounts,
-            None => {
-                states.insert(type_id, HashMap::new());
-                states.get_mut(&type_id).unwrap()
-            }
+            let accounts = match states.get_mut(&type_id) {
+                Some(accounts) => accounts,
+                None => {
+                    states.insert(type_id, HashMap::new());
+                    states.get_mut(&type_id).unwrap()
+                }
+            };
+
+            accounts.insert(*address, Some(new.clone())).flatten()
         };
 
-        accounts.insert(*address, Some(Arc::new(data)));
+        self.notify(type_id, address, old.as_ref(), &new);
     }
 
     pub fn register<T: Any + Send + Sync>(&self, address: &Pubkey) {
@@ -249,4 +368,183 @@ impl AccountCache {
             accounts.insert(*address, None);
         }
     }
+
+    /// Remove the state for an address, such as when its account has been closed
+    pub fn remove<T: Any + Send + Sync>(&self, address: &Pubkey) {
+        let mut states = self.states.lock().unwrap();
+
+        if let Some(accounts) = states.get_mut(&TypeId::of::<T>()) {
+            accounts.remove(address);
+        }
+    }
+
+    /// Call a function whenever a state of type `T` is set, with its previous and new values
+    pub fn observe<T: Any + Send + Sync>(
+        &self,
+        observer: impl Fn(&Change<T>) + Send + Sync + 'static,
+    ) -> ObserverId {
+        self.add_observer(move |change: &Change<T>| {
+            observer(change);
+            true
+        })
+    }
+
+    /// Call a function whenever the state of type `T` for an address is set
+    pub fn observe_address<T: Any + Send + Sync>(
+        &self,
+        address: Pubkey,
+        observer: impl Fn(&Change<T>) + Send + Sync + 'static,
+    ) -> ObserverId {
+        self.observe(move |change: &Change<T>| {
+            if change.address == address {
+                observer(change);
+            }
+        })
+    }
+
+    /// Receive every change to states of type `T` from a channel
+    ///
+    /// The observer is removed after the receiver is dropped.
+    pub fn observe_channel<T: Any + Send + Sync>(&self) -> UnboundedReceiver<Change<T>> {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        self.add_observer(move |change: &Change<T>| sender.send(change.clone()).is_ok());
+
+        receiver
+    }
+
+    /// Remove an observer, returning false if it was not registered
+    pub fn unobserve(&self, id: ObserverId) -> bool {
+        let mut observers = self.observers.lock().unwrap();
+
+        for entries in observers.values_mut() {
+            if let Some(position) = entries.iter().position(|(entry_id, _)| *entry_id == id) {
+                entries.remove(position);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn add_observer<T: Any + Send + Sync>(
+        &self,
+        observer: impl Fn(&Change<T>) -> bool + Send + Sync + 'static,
+    ) -> ObserverId {
+        let id = ObserverId(self.next_observer_id.fetch_add(1, Ordering::Relaxed));
+        let stored: StoredObserver = Arc::new(move |address, old, new| {
+            observer(&Change {
+                address: *address,
+                old: old.map(|old| Arc::downcast(old.clone()).unwrap()),
+                new: Arc::downcast(new.clone()).unwrap(),
+            })
+        });
+
+        self.observers
+            .lock()
+            .unwrap()
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .push((id, stored));
+
+        id
+    }
+
+    fn notify(
+        &self,
+        type_id: TypeId,
+        address: &Pubkey,
+        old: Option<&StoredStateObj>,
+        new: &StoredStateObj,
+    ) {
+        // observers are called without holding any locks, so they can read from the cache
+        let observers = match self.observers.lock().unwrap().get(&type_id) {
+            Some(observers) => observers.clone(),
+            None => return,
+        };
+
+        for (id, observer) in observers {
+            if !observer(address, old, new) {
+                self.unobserve(id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    #[test]
+    fn observers_receive_old_and_new_values() {
+        let cache = AccountCache::default();
+        let address = Pubkey::new_unique();
+        let seen = Arc::new(Mutex::new(vec![]));
+
+        let seen_by_observer = seen.clone();
+        cache.observe(move |change: &Change<u64>| {
+            seen_by_observer
+                .lock()
+                .unwrap()
+                .push((change.old.as_deref().copied(), *change.new));
+        });
+
+        cache.register::<u64>(&address);
+        cache.set(&address, 1u64);
+        cache.set(&address, 2u64);
+        cache.set(&address, "not observed");
+
+        assert_eq!(*seen.lock().unwrap(), vec![(None, 1), (Some(1), 2)]);
+    }
+
+    #[test]
+    fn address_observers_can_be_removed() {
+        let cache = AccountCache::default();
+        let address = Pubkey::new_unique();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let observer_calls = calls.clone();
+        let id = cache.observe_address(address, move |_: &Change<u64>| {
+            observer_calls.fetch_add(1, Ordering::Relaxed);
+        });
+
+        cache.set(&address, 1u64);
+        cache.set(&Pubkey::new_unique(), 1u64);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        assert!(cache.unobserve(id));
+        assert!(!cache.unobserve(id));
+        cache.set(&address, 2u64);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn channel_observers_are_removed_with_the_receiver() {
+        let cache = AccountCache::default();
+        let address = Pubkey::new_unique();
+
+        let mut receiver = cache.observe_channel::<u64>();
+        cache.set(&address, 1u64);
+
+        let change = receiver.try_recv().unwrap();
+        assert_eq!(change.address, address);
+        assert_eq!(*change.new, 1);
+
+        drop(receiver);
+        cache.set(&address, 2u64);
+        assert!(cache.observers.lock().unwrap()[&TypeId::of::<u64>()].is_empty());
+    }
+
+    #[test]
+    fn removed_states_are_forgotten() {
+        let cache = AccountCache::default();
+        let address = Pubkey::new_unique();
+
+        cache.set(&address, 1u64);
+        cache.remove::<u64>(&address);
+
+        assert!(cache.get::<u64>(&address).is_none());
+        assert!(cache.addresses_of::<u64>().is_empty());
+    }
 }
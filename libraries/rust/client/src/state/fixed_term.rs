@@ -27,6 +27,7 @@ use super::AccountStates;
 use crate::{
     client::ClientResult,
     fixed_term::util::{f64_to_price, price_to_rate, ui_price},
+    ClientError,
 };
 
 pub type FixedTermUser = MarginUser;
@@ -78,6 +79,7 @@ impl Ord for OrderEntry {
     }
 }
 
+#[derive(Clone)]
 pub struct UserState {
     state: MarginUser,
     loans: BTreeMap<u64, Arc<TermLoan>>,
@@ -161,19 +163,44 @@ pub async fn sync_markets(states: &AccountStates) -> ClientResult<()> {
                 continue;
             };
 
-            let Ok((asks, bids)) = parse_bid_asks(&address, market.borrow_tenor, asks_acc, bids_acc) else {
-                continue;
-            };
-
-            states
-                .cache
-                .set(&address, MarketState { market, asks, bids });
+            set_market(states, &address, market, asks_acc, bids_acc);
         }
     }
 
     Ok(())
 }
 
+/// Update the cached state of a market from the accounts for the market and its orderbook
+pub(crate) fn apply_market(
+    states: &AccountStates,
+    address: &Pubkey,
+    market: &Account,
+    asks: Account,
+    bids: Account,
+) -> ClientResult<()> {
+    let market = Market::try_deserialize(&mut &market.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    set_market(states, address, market, asks, bids);
+    Ok(())
+}
+
+fn set_market(
+    states: &AccountStates,
+    address: &Pubkey,
+    market: Market,
+    asks: Account,
+    bids: Account,
+) {
+    let Ok((asks, bids)) = parse_bid_asks(address, market.borrow_tenor, asks, bids) else {
+        return;
+    };
+
+    states
+        .cache
+        .set(address, MarketState { market, asks, bids });
+}
+
 /// Sync latest state for all fixed term user data
 ///
 /// The user data for all loaded margin accounts are fetched
@@ -206,6 +233,129 @@ pub async fn sync_user_accounts(states: &AccountStates) -> ClientResult<()> {
     Ok(())
 }
 
+/// Register the fixed term users a margin account may have in each market, so that they are
+/// loaded once they exist
+pub(crate) fn register_users(states: &AccountStates, margin_account: &Pubkey) {
+    for market in states.addresses_of::<MarketState>() {
+        states.register::<UserState>(&derive::margin_user(&market, margin_account));
+    }
+}
+
+/// Update the cached state of a fixed term user from its account data
+///
+/// The loans and deposits that are still active are kept, and any new ones are registered,
+/// but not loaded.
+pub(crate) fn apply_user(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let user = FixedTermUser::try_deserialize(&mut &account.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+    let (market, margin_account) = (user.market, user.margin_account);
+    let mut state = UserState::new(user);
+
+    for seqno in state.debt().active_loans() {
+        let loan_address = derive::term_loan(&market, address, seqno);
+        match states.get::<TermLoan>(&loan_address) {
+            Some(loan) => {
+                state.loans.insert(seqno, loan);
+            }
+            None => states.register::<TermLoan>(&loan_address),
+        }
+    }
+
+    for seqno in state.assets().active_deposits() {
+        let deposit_address = derive::term_deposit(&market, &margin_account, seqno);
+        match states.get::<TermDeposit>(&deposit_address) {
+            Some(deposit) => {
+                state.deposits.insert(seqno, deposit);
+            }
+            None => states.register::<TermDeposit>(&deposit_address),
+        }
+    }
+
+    states.cache.set(address, state);
+    Ok(())
+}
+
+/// Update the cached state of a term loan from its account data
+pub(crate) fn apply_loan(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let loan = TermLoan::try_deserialize(&mut &account.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+    let (user, seqno) = (loan.margin_user, loan.sequence_number);
+
+    states.cache.set(address, loan);
+    let loan = states.cache.get::<TermLoan>(address).unwrap();
+
+    update_user(states, &user, |state| {
+        if state.debt().active_loans().contains(&seqno) {
+            state.loans.insert(seqno, loan);
+        }
+    });
+
+    Ok(())
+}
+
+/// Update the cached state of a term deposit from its account data
+pub(crate) fn apply_deposit(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let deposit = TermDeposit::try_deserialize(&mut &account.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+    let user = derive::margin_user(&deposit.market, &deposit.owner);
+    let seqno = deposit.sequence_number;
+
+    states.cache.set(address, deposit);
+    let deposit = states.cache.get::<TermDeposit>(address).unwrap();
+
+    update_user(states, &user, |state| {
+        if state.assets().active_deposits().contains(&seqno) {
+            state.deposits.insert(seqno, deposit);
+        }
+    });
+
+    Ok(())
+}
+
+/// Remove a closed term loan from the cache, and from the state of its user
+pub(crate) fn remove_loan(states: &AccountStates, address: &Pubkey) {
+    if let Some(loan) = states.get::<TermLoan>(address) {
+        update_user(states, &loan.margin_user, |state| {
+            state.loans.remove(&loan.sequence_number);
+        });
+    }
+
+    states.remove::<TermLoan>(address);
+}
+
+/// Remove a closed term deposit from the cache, and from the state of its user
+pub(crate) fn remove_deposit(states: &AccountStates, address: &Pubkey) {
+    if let Some(deposit) = states.get::<TermDeposit>(address) {
+        let user = derive::margin_user(&deposit.market, &deposit.owner);
+        update_user(states, &user, |state| {
+            state.deposits.remove(&deposit.sequence_number);
+        });
+    }
+
+    states.remove::<TermDeposit>(address);
+}
+
+/// Change the cached state of a user, if it has been loaded
+fn update_user(states: &AccountStates, address: &Pubkey, update: impl FnOnce(&mut UserState)) {
+    if let Some(state) = states.get::<UserState>(address) {
+        let mut state = UserState::clone(&state);
+        update(&mut state);
+        states.cache.set(address, state);
+    }
+}
+
 async fn sync_user_debt_assets(states: &AccountStates) -> ClientResult<()> {
     let loans: Vec<Arc<TermLoan>> = load_user_positions(
         states,
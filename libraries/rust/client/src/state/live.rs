@@ -0,0 +1,516 @@
+//! Keep the cached state up to date from account subscriptions, rather than reloading
+//! every account with [`AccountStates::sync_all`].
+
+use std::{collections::HashMap, sync::Arc};
+
+use orca_whirlpool::state::{TickArray, Whirlpool};
+use saber_client::state::SwapInfo;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+use spl_token_swap::state::SwapV1;
+
+use jet_fixed_term::{margin::state::TermLoan, tickets::state::TermDeposit};
+use jet_margin::{MarginAccount, TokenConfig, TokenOracleSet};
+use jet_margin_pool::MarginPool;
+use jet_solana_client::rpc::{
+    AccountFilter, AccountUpdate, AccountUpdateReceiver, AccountUpdateSender, SolanaRpcExtra,
+    SolanaSubscriptions,
+};
+
+use super::{
+    fixed_term::{self, MarketState, UserState},
+    margin, margin_pool,
+    openbook::{self, BookSide, OpenBookMarket},
+    oracles::{self, PriceOracleState},
+    saber_swap, spl_swap,
+    tokens::{self, Mint, TokenAccount},
+    whirlpool, AccountStates,
+};
+use crate::{client::ClientResult, ClientError};
+
+/// A change made to the cached state from an account update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateChange {
+    /// The address of the account that changed
+    pub address: Pubkey,
+
+    /// The slot the change was made in
+    pub slot: u64,
+}
+
+/// How an account update is applied to the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Oracle,
+    SwapPool,
+    SaberSwapPool,
+    Whirlpool,
+    TickArray,
+    OpenBookMarket,
+    OpenBookBookSide,
+    MarginPool,
+    /// The market account, or one side of its orderbook
+    Market(Pubkey),
+    FixedTermUser,
+    TermLoan,
+    TermDeposit,
+    TokenConfig,
+    TokenOracleSet,
+    MarginAccount,
+    TokenAccount,
+    Mint,
+}
+
+type Listener<'a> = Box<dyn Fn(&StateChange) + Send + Sync + 'a>;
+
+/// Keeps the cache of an [`AccountStates`] up to date from account subscriptions
+///
+/// Every account in the cache is subscribed to, along with any token account owned by the
+/// wallet. Accounts added to the cache as updates are applied (e.g. new margin positions)
+/// are subscribed to as they appear, and accounts that are closed are removed from the cache.
+pub struct LiveSync<'a> {
+    states: &'a AccountStates,
+    subscriptions: Arc<dyn SolanaSubscriptions>,
+    sender: AccountUpdateSender,
+    updates: AccountUpdateReceiver,
+    tracked: HashMap<Pubkey, Target>,
+    market_accounts: HashMap<Pubkey, Account>,
+    listeners: Vec<Listener<'a>>,
+}
+
+impl<'a> LiveSync<'a> {
+    /// Subscribe to the accounts currently in the cache
+    pub async fn new(
+        states: &'a AccountStates,
+        subscriptions: Arc<dyn SolanaSubscriptions>,
+    ) -> ClientResult<LiveSync<'a>> {
+        let (sender, updates) = tokio::sync::mpsc::unbounded_channel();
+
+        // token accounts created for the wallet are found by subscribing to the token program
+        subscriptions
+            .program_subscribe(
+                &spl_token::ID,
+                &[
+                    AccountFilter::DataSize(TokenAccount::LEN),
+                    AccountFilter::Memcmp {
+                        offset: 32,
+                        bytes: states.wallet.to_bytes().to_vec(),
+                    },
+                ],
+                sender.clone(),
+            )
+            .await?;
+
+        for margin_account in states.addresses_of::<MarginAccount>() {
+            fixed_term::register_users(states, &margin_account);
+        }
+
+        let mut live = Self {
+            states,
+            subscriptions,
+            sender,
+            updates,
+            tracked: HashMap::new(),
+            market_accounts: HashMap::new(),
+            listeners: vec![],
+        };
+
+        live.subscribe_new().await?;
+        Ok(live)
+    }
+
+    /// Add a function to be called with every change made to the cache
+    pub fn add_listener(&mut self, listener: impl Fn(&StateChange) + Send + Sync + 'a) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Wait for the next account update, and apply it to the cache
+    pub async fn next(&mut self) -> ClientResult<StateChange> {
+        loop {
+            let Some(update) = self.updates.recv().await else {
+                return Err(ClientError::Unexpected("account subscriptions closed".to_string()));
+            };
+
+            if let Some(change) = self.apply_update(update).await? {
+                for listener in &self.listeners {
+                    listener(&change);
+                }
+
+                return Ok(change);
+            }
+        }
+    }
+
+    /// Apply account updates to the cache until an error occurs
+    pub async fn run(&mut self) -> ClientResult<()> {
+        loop {
+            self.next().await?;
+        }
+    }
+
+    async fn apply_update(&mut self, update: AccountUpdate) -> ClientResult<Option<StateChange>> {
+        let AccountUpdate {
+            address,
+            slot,
+            account,
+        } = update;
+
+        let target = match self.tracked.get(&address) {
+            Some(target) => *target,
+            // a new wallet token account, which is subscribed to once it is in the cache
+            None if account.owner == spl_token::ID => Target::TokenAccount,
+            None => return Ok(None),
+        };
+
+        if account.lamports == 0 {
+            self.remove(&address, target);
+            return Ok(Some(StateChange { address, slot }));
+        }
+
+        self.apply(address, target, account)?;
+        self.subscribe_new().await?;
+
+        Ok(Some(StateChange { address, slot }))
+    }
+
+    fn apply(&mut self, address: Pubkey, target: Target, account: Account) -> ClientResult<()> {
+        let states = self.states;
+
+        match target {
+            Target::Oracle => oracles::apply(states, &address, account),
+            Target::SwapPool => spl_swap::apply(states, &address, &account)?,
+            Target::SaberSwapPool => saber_swap::apply(states, &address, &account)?,
+            Target::Whirlpool => whirlpool::apply(states, &address, &account)?,
+            Target::TickArray => whirlpool::apply_tick_array(states, &address, &account)?,
+            Target::OpenBookMarket => openbook::apply(states, &address, &account)?,
+            Target::OpenBookBookSide => openbook::apply_book_side(states, &address, &account)?,
+            Target::MarginPool => margin_pool::apply(states, &address, &account)?,
+            Target::Market(market) => {
+                self.market_accounts.insert(address, account);
+                self.apply_market(&market)?;
+            }
+            Target::FixedTermUser => fixed_term::apply_user(states, &address, &account)?,
+            Target::TermLoan => fixed_term::apply_loan(states, &address, &account)?,
+            Target::TermDeposit => fixed_term::apply_deposit(states, &address, &account)?,
+            Target::TokenConfig => margin::apply_config(states, &address, &account)?,
+            Target::TokenOracleSet => margin::apply_oracle_set(states, &address, &account)?,
+            Target::MarginAccount => {
+                margin::apply_margin_account(states, &address, &account)?;
+                fixed_term::register_users(states, &address);
+            }
+            Target::TokenAccount => tokens::apply_account(states, &address, &account)?,
+            Target::Mint => tokens::apply_mint(states, &address, &account),
+        }
+
+        Ok(())
+    }
+
+    /// Remove the state of a closed account from the cache. The account stays subscribed
+    /// to, in case it is opened again.
+    fn remove(&mut self, address: &Pubkey, target: Target) {
+        let states = self.states;
+
+        match target {
+            Target::Oracle => states.remove::<PriceOracleState>(address),
+            Target::SwapPool => states.remove::<SwapV1>(address),
+            Target::SaberSwapPool => states.remove::<SwapInfo>(address),
+            Target::Whirlpool => states.remove::<Whirlpool>(address),
+            Target::TickArray => states.remove::<TickArray>(address),
+            Target::OpenBookMarket => states.remove::<OpenBookMarket>(address),
+            Target::OpenBookBookSide => states.remove::<BookSide>(address),
+            Target::MarginPool => states.remove::<MarginPool>(address),
+            Target::Market(market) => {
+                self.market_accounts.remove(address);
+                if *address == market {
+                    states.remove::<MarketState>(address);
+                }
+            }
+            Target::FixedTermUser => states.remove::<UserState>(address),
+            Target::TermLoan => fixed_term::remove_loan(states, address),
+            Target::TermDeposit => fixed_term::remove_deposit(states, address),
+            Target::TokenConfig => states.remove::<TokenConfig>(address),
+            Target::TokenOracleSet => states.remove::<TokenOracleSet>(address),
+            Target::MarginAccount => states.remove::<MarginAccount>(address),
+            Target::TokenAccount => states.remove::<TokenAccount>(address),
+            Target::Mint => states.remove::<Mint>(address),
+        }
+    }
+
+    fn apply_market(&self, market: &Pubkey) -> ClientResult<()> {
+        let Some(state) = self.states.get::<MarketState>(market) else {
+            return Ok(());
+        };
+
+        let (Some(market_account), Some(asks), Some(bids)) = (
+            self.market_accounts.get(market),
+            self.market_accounts.get(&state.market.asks),
+            self.market_accounts.get(&state.market.bids),
+        ) else {
+            // the rest of the market has not been loaded yet
+            return Ok(());
+        };
+
+        fixed_term::apply_market(
+            self.states,
+            market,
+            market_account,
+            asks.clone(),
+            bids.clone(),
+        )
+    }
+
+    /// Subscribe to any accounts added to the cache since the last check, and load the
+    /// ones that were registered without being loaded
+    async fn subscribe_new(&mut self) -> ClientResult<()> {
+        loop {
+            let new_targets = cached_targets(self.states)
+                .into_iter()
+                .filter(|(address, _)| !self.tracked.contains_key(address))
+                .collect::<Vec<_>>();
+
+            if new_targets.is_empty() {
+                return Ok(());
+            }
+
+            // wallet token accounts are also covered by the subscription to the token program,
+            // but only an account subscription sees them being closed
+            for (address, target) in &new_targets {
+                self.tracked.insert(*address, *target);
+                self.subscriptions
+                    .account_subscribe(address, self.sender.clone())
+                    .await?;
+            }
+
+            // loaded after subscribing, so that no changes are missed in between
+            let unloaded = new_targets
+                .into_iter()
+                .filter(|(address, target)| !self.is_loaded(address, *target))
+                .collect::<Vec<_>>();
+
+            let addresses = unloaded.iter().map(|(a, _)| *a).collect::<Vec<_>>();
+            let accounts = self.states.network.get_accounts_all(&addresses).await?;
+
+            for ((address, target), account) in unloaded.into_iter().zip(accounts) {
+                if let Some(account) = account {
+                    self.apply(address, target, account)?;
+                }
+            }
+        }
+    }
+
+    fn is_loaded(&self, address: &Pubkey, target: Target) -> bool {
+        match target {
+            Target::Oracle => self.states.get::<PriceOracleState>(address).is_some(),
+            Target::TokenAccount => self.states.get::<TokenAccount>(address).is_some(),
+            Target::Mint => self.states.get::<Mint>(address).is_some(),
+            Target::FixedTermUser => self.states.get::<UserState>(address).is_some(),
+            Target::TermLoan => self.states.get::<TermLoan>(address).is_some(),
+            Target::TermDeposit => self.states.get::<TermDeposit>(address).is_some(),
+            Target::TickArray => self.states.get::<TickArray>(address).is_some(),
+            Target::OpenBookBookSide => self.states.get::<BookSide>(address).is_some(),
+
+            // the raw market accounts are needed to apply later changes to the orderbook
+            Target::Market(_) => false,
+
+            // the other states are only in the cache once they have been loaded
+            _ => true,
+        }
+    }
+}
+
+/// The accounts in the cache, and how updates to each are applied
+fn cached_targets(states: &AccountStates) -> Vec<(Pubkey, Target)> {
+    let mut targets = vec![];
+    let mut add = |addresses: Vec<Pubkey>, target: Target| {
+        targets.extend(addresses.into_iter().map(|address| (address, target)))
+    };
+
+    add(states.addresses_of::<PriceOracleState>(), Target::Oracle);
+    add(states.addresses_of::<SwapV1>(), Target::SwapPool);
+    add(states.addresses_of::<SwapInfo>(), Target::SaberSwapPool);
+    add(states.addresses_of::<Whirlpool>(), Target::Whirlpool);
+    add(states.addresses_of::<TickArray>(), Target::TickArray);
+    add(
+        states.addresses_of::<OpenBookMarket>(),
+        Target::OpenBookMarket,
+    );
+    add(states.addresses_of::<BookSide>(), Target::OpenBookBookSide);
+    add(states.addresses_of::<MarginPool>(), Target::MarginPool);
+    add(states.addresses_of::<UserState>(), Target::FixedTermUser);
+    add(states.addresses_of::<TermLoan>(), Target::TermLoan);
+    add(states.addresses_of::<TermDeposit>(), Target::TermDeposit);
+    add(states.addresses_of::<TokenConfig>(), Target::TokenConfig);
+    add(
+        states.addresses_of::<TokenOracleSet>(),
+        Target::TokenOracleSet,
+    );
+    add(
+        states.addresses_of::<MarginAccount>(),
+        Target::MarginAccount,
+    );
+    add(states.addresses_of::<TokenAccount>(), Target::TokenAccount);
+    add(states.addresses_of::<Mint>(), Target::Mint);
+
+    for (market, state) in states.get_all::<MarketState>() {
+        add(
+            vec![market, state.market.asks, state.market.bids],
+            Target::Market(market),
+        );
+    }
+
+    targets
+}
+
+#[cfg(test)]
+mod test {
+    use jet_simulation::{runtime::ProcessInstruction, TestRuntime};
+    use jet_solana_client::rpc::SolanaRpc;
+    use solana_sdk::{
+        instruction::Instruction,
+        native_token::LAMPORTS_PER_SOL,
+        program_option::COption,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::Transaction,
+    };
+
+    use super::*;
+    use crate::config::{AirspaceInfo, JetAppConfig};
+
+    async fn send(rpc: &dyn SolanaRpc, signers: &[&Keypair], instructions: &[Instruction]) {
+        let blockhash = rpc.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signers[0].pubkey()),
+            signers,
+            blockhash,
+        );
+
+        rpc.send_transaction_legacy(&tx).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn wallet_token_accounts_are_added_and_removed() {
+        let runtime = TestRuntime::new(
+            [(
+                spl_token::ID,
+                spl_token::processor::Processor::process as ProcessInstruction,
+            )],
+            [],
+        );
+        let rpc = runtime.rpc();
+        let wallet = Keypair::new();
+        let token_account = Keypair::new();
+        let mint = Pubkey::new_unique();
+
+        let mut mint_data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                mint_authority: COption::Some(wallet.pubkey()),
+                decimals: 6,
+                is_initialized: true,
+                ..Default::default()
+            },
+            &mut mint_data,
+        )
+        .unwrap();
+        runtime.set_account(
+            &mint,
+            &Account {
+                lamports: LAMPORTS_PER_SOL,
+                data: mint_data,
+                owner: spl_token::ID,
+                ..Default::default()
+            },
+        );
+        rpc.airdrop(&wallet.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .await
+            .unwrap();
+
+        let config = JetAppConfig {
+            airspaces: vec![AirspaceInfo {
+                name: "default".to_string(),
+                tokens: vec![],
+                fixed_term_markets: vec![],
+                lookup_registry_authority: None,
+            }],
+            ..Default::default()
+        };
+        let states = AccountStates::new(
+            Arc::new(rpc.clone()),
+            wallet.pubkey(),
+            config,
+            "default".into(),
+        )
+        .unwrap();
+        let mut live = LiveSync::new(&states, Arc::new(rpc.clone())).await.unwrap();
+
+        // a new token account is found through the subscription to the token program
+        send(
+            &rpc,
+            &[&wallet, &token_account],
+            &[
+                system_instruction::create_account(
+                    &wallet.pubkey(),
+                    &token_account.pubkey(),
+                    LAMPORTS_PER_SOL,
+                    TokenAccount::LEN as u64,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::initialize_account(
+                    &spl_token::ID,
+                    &token_account.pubkey(),
+                    &mint,
+                    &wallet.pubkey(),
+                )
+                .unwrap(),
+                spl_token::instruction::mint_to(
+                    &spl_token::ID,
+                    &mint,
+                    &token_account.pubkey(),
+                    &wallet.pubkey(),
+                    &[],
+                    100,
+                )
+                .unwrap(),
+            ],
+        )
+        .await;
+
+        let change = live.next().await.unwrap();
+        assert_eq!(change.address, token_account.pubkey());
+        let cached = states.get::<TokenAccount>(&token_account.pubkey()).unwrap();
+        assert_eq!(cached.amount, 100);
+
+        // once it is closed, it is removed from the cache
+        send(
+            &rpc,
+            &[&wallet],
+            &[
+                spl_token::instruction::burn(
+                    &spl_token::ID,
+                    &token_account.pubkey(),
+                    &mint,
+                    &wallet.pubkey(),
+                    &[],
+                    100,
+                )
+                .unwrap(),
+                spl_token::instruction::close_account(
+                    &spl_token::ID,
+                    &token_account.pubkey(),
+                    &wallet.pubkey(),
+                    &wallet.pubkey(),
+                    &[],
+                )
+                .unwrap(),
+            ],
+        )
+        .await;
+
+        let change = live.next().await.unwrap();
+        assert_eq!(change.address, token_account.pubkey());
+        assert!(states
+            .get::<TokenAccount>(&token_account.pubkey())
+            .is_none());
+    }
+}
@@ -1,12 +1,20 @@
-use solana_sdk::pubkey::Pubkey;
+use anchor_lang::AccountDeserialize;
+use solana_sdk::{account::Account, pubkey::Pubkey};
 
-use jet_instructions::margin::{derive_margin_account, derive_token_config};
-use jet_margin::{MarginAccount, TokenAdmin, TokenConfig, TokenOracle};
+use jet_instructions::margin::{
+    derive_margin_account, derive_token_config, derive_token_oracle_set,
+};
+use jet_margin::{MarginAccount, TokenAdmin, TokenConfig, TokenOracleSet};
 use jet_margin_pool::MarginPool;
 use jet_solana_client::rpc::SolanaRpcExtra;
 
-use super::{fixed_term::MarketState, oracles::PriceOracleState, tokens::Mint, AccountStates};
-use crate::{client::ClientResult, state::tokens};
+use super::{
+    fixed_term::MarketState,
+    oracles::PriceOracleState,
+    tokens::{Mint, TokenAccount},
+    AccountStates,
+};
+use crate::{client::ClientResult, state::tokens, ClientError};
 
 /// Refresh state for all currently loaded margin accounts
 pub async fn sync(states: &AccountStates) -> ClientResult<()> {
@@ -50,6 +58,8 @@ pub async fn sync_configs(states: &AccountStates) -> ClientResult<()> {
         .try_get_anchor_accounts::<TokenConfig>(&configs)
         .await?;
 
+    let mut oracle_sets = vec![];
+
     for (index, account) in accounts.into_iter().enumerate() {
         let address = configs[index];
 
@@ -61,23 +71,80 @@ pub async fn sync_configs(states: &AccountStates) -> ClientResult<()> {
                 )
             }
 
-            Some(config) => {
-                states.register::<Mint>(&config.mint);
-                states.register::<Mint>(&config.underlying_mint);
+            Some(config) => oracle_sets.extend(set_config(states, &address, config)),
+        }
+    }
 
-                if let TokenAdmin::Margin { oracle } = &config.admin {
-                    let TokenOracle::Pyth { price, .. } = oracle;
-                    states.register::<PriceOracleState>(price);
-                }
+    sync_oracle_sets(states, &oracle_sets).await
+}
 
-                states.set(&address, config);
-            }
+/// Update the cached state of a token config from its account data
+pub(crate) fn apply_config(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let config = TokenConfig::try_deserialize(&mut &account.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    set_config(states, address, config);
+    Ok(())
+}
+
+/// Cache a token config, returning the address of its oracle set if it has one
+fn set_config(states: &AccountStates, address: &Pubkey, config: TokenConfig) -> Option<Pubkey> {
+    states.register::<Mint>(&config.mint);
+    states.register::<Mint>(&config.underlying_mint);
+
+    if let TokenAdmin::Margin { oracle } = &config.admin {
+        states.register::<PriceOracleState>(&oracle.price_account());
+    }
+
+    let oracle_set = config
+        .has_oracle_set
+        .then(|| derive_token_oracle_set(address));
+
+    states.set(address, config);
+    oracle_set
+}
+
+async fn sync_oracle_sets(states: &AccountStates, addresses: &[Pubkey]) -> ClientResult<()> {
+    let accounts = states
+        .network
+        .try_get_anchor_accounts::<TokenOracleSet>(addresses)
+        .await?;
+
+    for (address, account) in addresses.iter().zip(accounts) {
+        match account {
+            None => log::warn!("missing expected margin token oracle set {address}"),
+            Some(oracle_set) => set_oracle_set(states, address, oracle_set),
         }
     }
 
     Ok(())
 }
 
+/// Update the cached state of a token oracle set from its account data
+pub(crate) fn apply_oracle_set(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let oracle_set = TokenOracleSet::try_deserialize(&mut &account.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    set_oracle_set(states, address, oracle_set);
+    Ok(())
+}
+
+fn set_oracle_set(states: &AccountStates, address: &Pubkey, oracle_set: TokenOracleSet) {
+    for oracle in &oracle_set.fallbacks {
+        states.register::<PriceOracleState>(&oracle.price_account());
+    }
+
+    states.set(address, oracle_set);
+}
+
 /// Sync all latest state for all previouly loaded margin accounts
 pub async fn sync_margin_accounts(states: &AccountStates) -> ClientResult<()> {
     load_user_margin_accounts(states).await?;
@@ -98,8 +165,7 @@ pub async fn load_margin_accounts(
 
     for (address, account) in addresses.iter().zip(accounts) {
         if let Some(account) = account {
-            positions.extend(account.positions().map(|p| p.address));
-            states.cache.set(address, account);
+            positions.extend(set_margin_account(states, address, account));
         }
     }
 
@@ -131,8 +197,7 @@ pub async fn load_user_margin_accounts(states: &AccountStates) -> ClientResult<(
 
     for (address, maybe_account) in possible_accounts.into_iter().zip(maybe_accounts) {
         if let Some(account) = maybe_account {
-            positions.extend(account.positions().map(|p| p.address));
-            states.cache.set(&address, account);
+            positions.extend(set_margin_account(states, &address, account));
         }
     }
 
@@ -140,3 +205,33 @@ pub async fn load_user_margin_accounts(states: &AccountStates) -> ClientResult<(
 
     Ok(())
 }
+
+/// Update the cached state of a margin account from its account data
+///
+/// The token accounts for any new positions are registered, but not loaded.
+pub(crate) fn apply_margin_account(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let margin_account = MarginAccount::try_deserialize(&mut &account.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    for position in set_margin_account(states, address, margin_account) {
+        states.register::<TokenAccount>(&position);
+    }
+
+    Ok(())
+}
+
+/// Cache a margin account, returning the addresses of its position token accounts
+fn set_margin_account(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: MarginAccount,
+) -> Vec<Pubkey> {
+    let positions = account.positions().map(|p| p.address).collect();
+    states.cache.set(address, account);
+
+    positions
+}
@@ -1,13 +1,14 @@
 use std::sync::Arc;
 
-use solana_sdk::pubkey::Pubkey;
+use anchor_lang::AccountDeserialize;
+use solana_sdk::{account::Account, pubkey::Pubkey};
 
 use jet_instructions::margin_pool::derive_margin_pool;
 use jet_margin_pool::MarginPool;
 use jet_solana_client::rpc::SolanaRpcExtra;
 
 use super::AccountStates;
-use crate::client::ClientResult;
+use crate::{client::ClientResult, ClientError};
 
 pub trait MarginPoolCacheExt {
     fn get_pool(&self, token: &Pubkey) -> Option<Arc<MarginPool>>;
@@ -33,18 +34,31 @@ pub async fn sync(states: &AccountStates) -> ClientResult<()> {
         .try_get_anchor_accounts::<MarginPool>(&pools)
         .await?;
 
-    let time = states.get_current_time();
-
     for (index, account) in accounts.into_iter().enumerate() {
-        let address = pools[index];
-
-        if let Some(mut pool) = account {
-            // make sure local client sees current interest
-            while !pool.accrue_interest(time) {}
-
-            states.cache.set(&address, pool);
+        if let Some(pool) = account {
+            set_pool(states, &pools[index], pool);
         }
     }
 
     Ok(())
 }
+
+/// Update the cached state of a pool from its account data
+pub(crate) fn apply(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let pool = MarginPool::try_deserialize(&mut &account.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    set_pool(states, address, pool);
+    Ok(())
+}
+
+fn set_pool(states: &AccountStates, address: &Pubkey, mut pool: MarginPool) {
+    // make sure local client sees current interest
+    while !pool.accrue_interest(states.get_current_time()) {}
+
+    states.cache.set(address, pool);
+}
@@ -0,0 +1,126 @@
+use std::mem::size_of;
+
+use anchor_spl::dex::serum_dex::state::{gen_vault_signer_key, MarketState};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use jet_instructions::swap_quote::read_book_side;
+use jet_program_common::programs::{OPENBOOK, OPENBOOK_DEVNET};
+use jet_solana_client::rpc::SolanaRpcExtra;
+
+use super::AccountStates;
+use crate::{bail, client::ClientResult, ClientError};
+
+/// The accounts and lot sizes of an openbook market
+#[derive(Debug, Clone)]
+pub struct OpenBookMarket {
+    pub program: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub request_queue: Pubkey,
+    pub event_queue: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub vault_signer: Pubkey,
+    pub base_lot_size: u64,
+    pub quote_lot_size: u64,
+}
+
+/// The orders on one side of an openbook market, as (price, quantity) with prices in quote
+/// lots per base lot and quantities in base lots
+#[derive(Debug, Clone)]
+pub struct BookSide {
+    pub orders: Vec<(u64, u64)>,
+}
+
+/// Sync latest state for all openbook markets, and their orderbooks
+pub async fn sync(states: &AccountStates) -> ClientResult<()> {
+    let addresses = states
+        .config
+        .exchanges
+        .iter()
+        .filter_map(|dex| {
+            (dex.program == OPENBOOK || dex.program == OPENBOOK_DEVNET).then_some(dex.address)
+        })
+        .collect::<Vec<_>>();
+
+    load(states, &addresses).await?;
+
+    // registered while applying the markets
+    let book_sides = states.cache.addresses_of::<BookSide>();
+    let accounts = states.network.get_accounts_all(&book_sides).await?;
+
+    for (address, maybe_account) in book_sides.iter().zip(accounts) {
+        if let Some(account) = maybe_account {
+            apply_book_side(states, address, &account)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Load state for given markets
+async fn load(states: &AccountStates, addresses: &[Pubkey]) -> ClientResult<()> {
+    let accounts = states.network.get_accounts_all(addresses).await?;
+
+    for (address, maybe_account) in addresses.iter().zip(accounts) {
+        if let Some(account) = maybe_account {
+            apply(states, address, &account)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Update the cached state of a market from its account data
+pub(crate) fn apply(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    // the market state follows a 5 byte header
+    let Some(data) = account.data.get(5..5 + size_of::<MarketState>()) else {
+        bail!("openbook market {address} is too small");
+    };
+
+    let market = bytemuck::pod_read_unaligned::<MarketState>(data);
+    let key = |words: [u64; 4]| Pubkey::new_from_array(bytemuck::cast(words));
+    let vault_signer = gen_vault_signer_key(market.vault_signer_nonce, address, &account.owner)
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    let market = OpenBookMarket {
+        program: account.owner,
+        base_mint: key(market.coin_mint),
+        quote_mint: key(market.pc_mint),
+        request_queue: key(market.req_q),
+        event_queue: key(market.event_q),
+        bids: key(market.bids),
+        asks: key(market.asks),
+        base_vault: key(market.coin_vault),
+        quote_vault: key(market.pc_vault),
+        vault_signer,
+        base_lot_size: market.coin_lot_size,
+        quote_lot_size: market.pc_lot_size,
+    };
+
+    states.cache.register::<BookSide>(&market.bids);
+    states.cache.register::<BookSide>(&market.asks);
+    states.cache.set(address, market);
+
+    Ok(())
+}
+
+/// Update the cached orders of one side of a market from its account data
+pub(crate) fn apply_book_side(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let Some(orders) = read_book_side(&account.data) else {
+        bail!("orderbook {address} is too small");
+    };
+
+    states.cache.set(address, BookSide { orders });
+    Ok(())
+}
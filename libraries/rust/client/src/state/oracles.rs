@@ -1,13 +1,18 @@
 use std::collections::HashSet;
 
 use anchor_lang::prelude::Pubkey;
-use jet_program_common::Number128;
+use jet_margin::{TokenAdmin, TokenConfig, TokenOracle, TokenOracleSet, MAX_ORACLE_STALENESS};
+use jet_margin_pool::MarginPool;
+use jet_program_common::{oracle::PushPriceFeed, Number128};
 use jet_solana_client::rpc::SolanaRpcExtra;
 use pyth_sdk_solana::{
     state::{load_price_account, PriceAccount, PriceStatus},
     PythError,
 };
-use solana_sdk::account_info::{Account, IntoAccountInfo};
+use solana_sdk::{
+    account::Account as SolanaAccount,
+    account_info::{Account, IntoAccountInfo},
+};
 
 use super::AccountStates;
 use crate::client::ClientResult;
@@ -32,33 +37,80 @@ pub async fn sync(states: &AccountStates) -> ClientResult<()> {
     for (index, account) in accounts.into_iter().enumerate() {
         let address = oracles[index];
 
-        let mut account = match account {
-            Some(account) => account,
-            None => {
-                log::error!("oracle {address} does not exist");
-                continue;
-            }
-        };
+        match account {
+            Some(account) => apply(states, &address, account),
+            None => log::error!("oracle {address} does not exist"),
+        }
+    }
 
-        let price_account = match load_price_account_from_account(&address, &mut account) {
-            Ok(feed) => feed,
-            Err(e) => {
-                log::error!("could not parse oracle '{address}': {e}");
-                continue;
-            }
-        };
-        let current_price = price_account.to_price_feed(&address).get_price_unchecked();
+    Ok(())
+}
+
+/// Update the cached state of an oracle from its account data
+pub(crate) fn apply(states: &AccountStates, address: &Pubkey, mut account: SolanaAccount) {
+    if let Some(feed) = PushPriceFeed::load(&account.data) {
+        let is_trusted = push_oracle_programs(states, address).contains(&account.owner);
+        let age = states.get_current_time() - feed.publish_time;
+
+        if !is_trusted {
+            log::error!("push oracle '{address}' is not owned by a configured program",);
+        }
 
-        let price = Number128::from_decimal(current_price.price, current_price.expo);
         let state = PriceOracleState {
-            price,
-            is_valid: matches!(price_account.agg.status, PriceStatus::Trading),
+            price: Number128::from_decimal(feed.price, feed.exponent),
+            is_valid: is_trusted && feed.publish_time > 0 && age <= MAX_ORACLE_STALENESS,
         };
 
-        states.cache.set(&address, state);
+        states.cache.set(address, state);
+        return;
     }
 
-    Ok(())
+    let price_account = match load_price_account_from_account(address, &mut account) {
+        Ok(feed) => feed,
+        Err(e) => {
+            log::error!("could not parse oracle '{address}': {e}");
+            return;
+        }
+    };
+    let current_price = price_account.to_price_feed(address).get_price_unchecked();
+
+    let price = Number128::from_decimal(current_price.price, current_price.expo);
+    let state = PriceOracleState {
+        price,
+        is_valid: matches!(price_account.agg.status, PriceStatus::Trading),
+    };
+
+    states.cache.set(address, state);
+}
+
+/// The programs configured to publish prices to a push oracle, by the margin token
+/// configs and pools that use it
+fn push_oracle_programs(states: &AccountStates, address: &Pubkey) -> Vec<Pubkey> {
+    let push_program = |oracle: &TokenOracle| match oracle {
+        TokenOracle::Push { price, program } if price == address => Some(*program),
+        _ => None,
+    };
+
+    let mut programs = states
+        .get_all::<TokenConfig>()
+        .into_iter()
+        .filter_map(|(_, config)| match &config.admin {
+            TokenAdmin::Margin { oracle } => push_program(oracle),
+            TokenAdmin::Adapter(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (_, oracle_set) in states.get_all::<TokenOracleSet>() {
+        programs.extend(oracle_set.fallbacks.iter().filter_map(push_program));
+    }
+
+    for (_, pool) in states.get_all::<MarginPool>() {
+        if pool.token_price_oracle == *address {
+            programs.extend(pool.token_price_program);
+        }
+    }
+
+    programs
 }
 
 /// copy of `pyth_sdk_solana::load_price_feed_from_account` that returns one
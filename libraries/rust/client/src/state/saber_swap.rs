@@ -0,0 +1,53 @@
+use saber_client::state::SwapInfo;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+
+use jet_program_common::programs::SABER;
+use jet_solana_client::rpc::SolanaRpcExtra;
+
+use super::{tokens::TokenAccount, AccountStates};
+use crate::{client::ClientResult, ClientError};
+
+/// Sync latest state for all saber swap pools
+pub async fn sync(states: &AccountStates) -> ClientResult<()> {
+    let addresses = states
+        .config
+        .exchanges
+        .iter()
+        .filter_map(|dex| (dex.program == SABER).then_some(dex.address))
+        .collect::<Vec<_>>();
+
+    load(states, &addresses).await
+}
+
+/// Load state for given swap pools
+async fn load(states: &AccountStates, addresses: &[Pubkey]) -> ClientResult<()> {
+    let accounts = states.network.get_accounts_all(addresses).await?;
+
+    for (address, maybe_account) in addresses.iter().zip(accounts) {
+        if let Some(account) = maybe_account {
+            apply(states, address, &account)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Update the cached state of a swap pool from its account data
+pub(crate) fn apply(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let data =
+        SwapInfo::unpack(&account.data).map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    states
+        .cache
+        .register::<TokenAccount>(&data.token_a.reserves);
+    states
+        .cache
+        .register::<TokenAccount>(&data.token_b.reserves);
+    states.cache.set(address, data);
+
+    Ok(())
+}
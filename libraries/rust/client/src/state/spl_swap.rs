@@ -1,4 +1,4 @@
-use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
 use spl_token_swap::state::SwapV1;
 
 use jet_program_common::programs::ORCA_V2;
@@ -31,14 +31,25 @@ async fn load(states: &AccountStates, addresses: &[Pubkey]) -> ClientResult<()>
 
     for (address, maybe_account) in addresses.iter().zip(accounts) {
         if let Some(account) = maybe_account {
-            let data = SwapV1::unpack(&account.data[1..])
-                .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
-
-            states.cache.register::<TokenAccount>(&data.token_a);
-            states.cache.register::<TokenAccount>(&data.token_b);
-            states.cache.set(address, data);
+            apply(states, address, &account)?;
         }
     }
 
     Ok(())
 }
+
+/// Update the cached state of a swap pool from its account data
+pub(crate) fn apply(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let data =
+        SwapV1::unpack(&account.data[1..]).map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    states.cache.register::<TokenAccount>(&data.token_a);
+    states.cache.register::<TokenAccount>(&data.token_b);
+    states.cache.set(address, data);
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
 use spl_associated_token_account::get_associated_token_address;
 
 use jet_solana_client::rpc::SolanaRpcExtra;
@@ -32,15 +32,7 @@ pub async fn sync_mints(states: &AccountStates) -> ClientResult<()> {
 
     for (address, maybe_account) in addresses.into_iter().zip(accounts) {
         if let Some(account) = maybe_account {
-            let data = match Mint::unpack(&account.data) {
-                Ok(data) => data,
-                Err(e) => {
-                    log::error!("could not parse mint {address}: {e}");
-                    continue;
-                }
-            };
-
-            states.cache.set(&address, data);
+            apply_mint(states, &address, &account);
         }
     }
 
@@ -72,14 +64,32 @@ pub async fn load_accounts(states: &AccountStates, addresses: &[Pubkey]) -> Clie
 
     for (address, maybe_account) in addresses.iter().zip(accounts) {
         if let Some(account) = maybe_account {
-            let data = TokenAccount::unpack(&account.data).map_err(|e| {
-                eprintln!("{account:?}");
-                ClientError::Deserialize(Box::new(e))
-            })?;
-
-            states.cache.set(address, data);
+            apply_account(states, address, &account)?;
         }
     }
 
     Ok(())
 }
+
+/// Update the cached state of a mint from its account data
+pub(crate) fn apply_mint(states: &AccountStates, address: &Pubkey, account: &Account) {
+    match Mint::unpack(&account.data) {
+        Ok(data) => states.cache.set(address, data),
+        Err(e) => log::error!("could not parse mint {address}: {e}"),
+    }
+}
+
+/// Update the cached state of a token account from its account data
+pub(crate) fn apply_account(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let data = TokenAccount::unpack(&account.data).map_err(|e| {
+        eprintln!("{account:?}");
+        ClientError::Deserialize(Box::new(e))
+    })?;
+
+    states.cache.set(address, data);
+    Ok(())
+}
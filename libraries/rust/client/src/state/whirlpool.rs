@@ -0,0 +1,86 @@
+use anchor_lang::AccountDeserialize;
+use orca_whirlpool::state::{TickArray, Whirlpool};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use jet_instructions::{
+    orca::{derive_tick_array, start_tick_index},
+    swap_quote::read_tick_array,
+};
+use jet_program_common::programs::ORCA_WHIRLPOOL;
+use jet_solana_client::rpc::SolanaRpcExtra;
+
+use super::AccountStates;
+use crate::{bail, client::ClientResult, ClientError};
+
+/// Sync latest state for all whirlpools, and the tick arrays around their current price
+pub async fn sync(states: &AccountStates) -> ClientResult<()> {
+    let addresses = states
+        .config
+        .exchanges
+        .iter()
+        .filter_map(|dex| (dex.program == ORCA_WHIRLPOOL).then_some(dex.address))
+        .collect::<Vec<_>>();
+
+    load(states, &addresses).await?;
+
+    // registered while applying the whirlpools
+    let tick_arrays = states.cache.addresses_of::<TickArray>();
+    let accounts = states.network.get_accounts_all(&tick_arrays).await?;
+
+    for (address, maybe_account) in tick_arrays.iter().zip(accounts) {
+        if let Some(account) = maybe_account {
+            apply_tick_array(states, address, &account)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Load state for given whirlpools
+async fn load(states: &AccountStates, addresses: &[Pubkey]) -> ClientResult<()> {
+    let accounts = states.network.get_accounts_all(addresses).await?;
+
+    for (address, maybe_account) in addresses.iter().zip(accounts) {
+        if let Some(account) = maybe_account {
+            apply(states, address, &account)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Update the cached state of a whirlpool from its account data
+pub(crate) fn apply(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let whirlpool = Whirlpool::try_deserialize(&mut &account.data[..])
+        .map_err(|e| ClientError::Deserialize(Box::new(e)))?;
+
+    // a swap in either direction can cross up to two tick arrays past the current one
+    for offset in -2..=2 {
+        let start_index =
+            start_tick_index(whirlpool.tick_current_index, whirlpool.tick_spacing, offset);
+        let tick_array = derive_tick_array(address, start_index, whirlpool.tick_spacing);
+
+        states.cache.register::<TickArray>(&tick_array);
+    }
+
+    states.cache.set(address, whirlpool);
+    Ok(())
+}
+
+/// Update the cached state of a whirlpool tick array from its account data
+pub(crate) fn apply_tick_array(
+    states: &AccountStates,
+    address: &Pubkey,
+    account: &Account,
+) -> ClientResult<()> {
+    let Some(tick_array) = read_tick_array(&account.data) else {
+        bail!("tick array {address} is too small");
+    };
+
+    states.cache.set(address, tick_array);
+    Ok(())
+}
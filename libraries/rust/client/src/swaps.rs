@@ -1,21 +1,91 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
-use solana_sdk::pubkey::Pubkey;
-use spl_token_swap::state::SwapV1;
+use anchor_lang::ToAccountMetas;
+use orca_whirlpool::state::{TickArray, Whirlpool};
+use saber_client::state::SwapInfo;
+use solana_sdk::{instruction::AccountMeta, pubkey::Pubkey, rent::Rent, sysvar::SysvarId};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token_swap::{curve::calculator::TradeDirection, state::SwapV1};
 
 use jet_instructions::{
     margin::derive_position_token_account,
-    margin_swap::{pool_spl_swap, SplSwap},
+    margin_swap::{
+        derive_spl_swap_authority, pool_spl_swap, MarginSwapRouteIxBuilder, SplSwap, SwapAccounts,
+        SwapContext,
+    },
+    openbook::create_open_orders,
+    orca::derive_whirlpool_oracle,
+    swap_quote::{self, quote_openbook, quote_saber, quote_whirlpool, whirlpool_tick_arrays},
+};
+use jet_margin_pool::{ChangeKind, TokenChange};
+use jet_margin_swap::{
+    accounts as ix_accounts, seeds::OPENBOOK_OPEN_ORDERS, SwapRouteIdentifier,
+    ROUTE_SWAP_MAX_SPLIT, ROUTE_SWAP_MIN_SPLIT,
+};
+use jet_program_common::{
+    programs::{ORCA_V2, ORCA_WHIRLPOOL, SABER},
+    CONTROL_AUTHORITY,
 };
-use jet_margin_pool::ChangeKind;
-use jet_program_common::programs::ORCA_V2;
 
 use crate::{
     bail,
     client::{ClientResult, ClientState},
     margin::MarginAccountClient,
+    state::{
+        openbook::{BookSide, OpenBookMarket},
+        tokens::TokenAccount,
+        AccountStates,
+    },
 };
 
+/// The difference between the percentages tried when splitting a leg between two pools
+const SPLIT_STEP: usize = 10;
+
+/// One step in a swap route, which may be split between two pools for the same pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapLeg {
+    /// The token swapped into the pools
+    pub source_token: Pubkey,
+
+    /// The token received from the pools
+    pub destination_token: Pubkey,
+
+    /// The pool receiving `split` percent of the input, or all of it if the leg is not split
+    pub pool: Pubkey,
+
+    /// The pool receiving the rest of the input, if the leg is split
+    pub split_pool: Option<Pubkey>,
+
+    /// The percentage of the input sent to `pool`, or 0 if the leg is not split
+    pub split: u8,
+}
+
+/// A route for swapping tokens, and the amount it is expected to return
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapQuote {
+    /// The token being exchanged
+    pub source_token: Pubkey,
+
+    /// The token being received
+    pub target_token: Pubkey,
+
+    /// The amount of the source token to be exchanged
+    pub amount_in: u64,
+
+    /// The amount of the target token expected, at the current state of the pools
+    pub amount_out: u64,
+
+    /// The steps of the route, in order
+    pub legs: Vec<SwapLeg>,
+}
+
+impl SwapQuote {
+    /// The least output to accept, given a tolerance for slippage in basis points
+    pub fn minimum_amount_out(&self, slippage_bps: u16) -> u64 {
+        swap_quote::minimum_amount_out(self.amount_out, slippage_bps)
+    }
+}
+
 /// Client for interacting with swap protocols, from the perspective of a margin account
 pub struct MarginAccountSwapsClient {
     client: Arc<ClientState>,
@@ -30,6 +100,175 @@ impl MarginAccountSwapsClient {
         }
     }
 
+    /// Find the route returning the most of the target token for an amount of the source token
+    ///
+    /// Routes are made of up to two legs through the loaded swap pools, whirlpools and
+    /// openbook markets, where each leg may be split between two venues of the same kind.
+    pub fn quote(
+        &self,
+        source_token: &Pubkey,
+        target_token: &Pubkey,
+        amount_in: u64,
+    ) -> ClientResult<SwapQuote> {
+        let pools = self.route_pools();
+        let mut best = self
+            .best_leg(&pools, source_token, target_token, amount_in)
+            .map(|(leg, amount_out)| (vec![leg], amount_out));
+
+        let intermediate_tokens = pools
+            .iter()
+            .flat_map(|pool| {
+                let (token_a, token_b) = pool.pool_tokens();
+                [token_a, token_b]
+            })
+            .filter(|token| token != source_token && token != target_token)
+            .collect::<HashSet<_>>();
+
+        for token in intermediate_tokens {
+            let first = self.best_leg(&pools, source_token, &token, amount_in);
+            let Some((first, amount)) = first else {
+                continue;
+            };
+            let second = self.best_leg(&pools, &token, target_token, amount);
+            let Some((second, amount_out)) = second else {
+                continue;
+            };
+
+            if best
+                .as_ref()
+                .map_or(true, |(_, best_out)| amount_out > *best_out)
+            {
+                best = Some((vec![first, second], amount_out));
+            }
+        }
+
+        match best {
+            Some((legs, amount_out)) if amount_out > 0 => Ok(SwapQuote {
+                source_token: *source_token,
+                target_token: *target_token,
+                amount_in,
+                amount_out,
+                legs,
+            }),
+            _ => bail!("no swap route found from {source_token} to {target_token}"),
+        }
+    }
+
+    /// Swap tokens in margin pools, using the best route available
+    ///
+    /// # Parameters
+    ///
+    /// * `source_token` - The source token to be exchanged
+    /// * `target_token` - The desired token
+    /// * `amount` - The amount of the source token to exchange
+    /// * `slippage_bps` - How far below the quoted output the swap may fill, in basis points
+    pub async fn swap(
+        &self,
+        source_token: &Pubkey,
+        target_token: &Pubkey,
+        amount: u64,
+        slippage_bps: u16,
+    ) -> ClientResult<()> {
+        let quote = self.quote(source_token, target_token, amount)?;
+
+        self.route_swap(&quote, quote.minimum_amount_out(slippage_bps))
+            .await
+    }
+
+    /// Swap tokens in margin pools, following a quoted route
+    ///
+    /// # Parameters
+    ///
+    /// * `quote` - The route to swap along, and the amount to swap
+    /// * `minimum_amount_out` - Limit the possible fill price
+    pub async fn route_swap(&self, quote: &SwapQuote, minimum_amount_out: u64) -> ClientResult<()> {
+        let pools = self.route_pools();
+        let find_pool = |address: &Pubkey, source_token: Pubkey| {
+            let Some(pool) = pools.iter().find(|p| p.address() == *address) else {
+                bail!("no swap pool found in cache with address {address}");
+            };
+
+            Ok(RouteSwap {
+                pool: pool.clone(),
+                source_token,
+            })
+        };
+
+        let mut builder = MarginSwapRouteIxBuilder::try_new(
+            SwapContext::MarginPool,
+            self.account.address,
+            quote.source_token,
+            quote.target_token,
+            TokenChange::shift(quote.amount_in),
+            minimum_amount_out,
+        )?;
+
+        let mut swaps = vec![];
+
+        for leg in &quote.legs {
+            match leg.split_pool {
+                None => {
+                    let swap = find_pool(&leg.pool, leg.source_token)?;
+                    builder.add_swap_leg(&swap, 0)?;
+                    swaps.push(swap);
+                }
+                Some(split_pool) => {
+                    let swap = find_pool(&leg.pool, leg.source_token)?;
+                    let split_swap = find_pool(&split_pool, leg.source_token)?;
+                    builder.add_swap_leg(&swap, leg.split)?;
+                    builder.add_swap_leg(&split_swap, 0)?;
+                    swaps.extend([swap, split_swap]);
+                }
+            }
+        }
+
+        builder.finalize()?;
+
+        let mut instructions = vec![];
+
+        // Ensure there are open orders accounts to trade in openbook markets with
+        for swap in &swaps {
+            let RoutePool::OpenBook { address, market } = &swap.pool else {
+                continue;
+            };
+
+            let (create_open_orders, open_orders) = create_open_orders(
+                self.account.address,
+                *address,
+                self.client.signer(),
+                &market.program,
+            );
+
+            if !self.client.account_exists(&open_orders).await? {
+                instructions.push(self.account.builder.adapter_invoke(create_open_orders));
+            }
+        }
+
+        // Ensure transit accounts exist
+        for token in builder.get_spl_token_mints() {
+            instructions.extend(
+                (!self.account.has_position(token))
+                    .then(|| self.account.builder.create_deposit_position(*token)),
+            );
+        }
+
+        // Ensure there are positions to deposit the target tokens into
+        for deposit_note in builder.get_pool_note_mints() {
+            instructions.extend(
+                (!self.account.has_position(deposit_note))
+                    .then(|| self.account.builder.register_position(*deposit_note)),
+            );
+        }
+
+        instructions.push(
+            self.account
+                .builder
+                .adapter_invoke(builder.get_instruction()?),
+        );
+
+        self.account.send_with_refresh(&instructions).await
+    }
+
     /// Swap tokens in a margin pool, using Orca V2
     ///
     /// # Parameters
@@ -110,6 +349,121 @@ impl MarginAccountSwapsClient {
         self.client.send(&instructions).await
     }
 
+    /// The loaded venues that can be used in a route
+    fn route_pools(&self) -> Vec<RoutePool> {
+        let states = self.client.state();
+        let program_of = |address: &Pubkey, default: Pubkey| {
+            states
+                .config
+                .exchanges
+                .iter()
+                .find(|dex| dex.address == *address)
+                .map(|dex| dex.program)
+                .unwrap_or(default)
+        };
+
+        let spl_pools = states
+            .get_all::<SwapV1>()
+            .into_iter()
+            .map(|(address, swap)| RoutePool::Spl {
+                program: program_of(&address, ORCA_V2),
+                address,
+                swap,
+            });
+
+        let saber_pools = states
+            .get_all::<SwapInfo>()
+            .into_iter()
+            .filter(|(_, swap)| swap.is_initialized && !swap.is_paused)
+            .map(|(address, swap)| RoutePool::Saber {
+                program: program_of(&address, SABER),
+                address,
+                swap,
+            });
+
+        let whirlpools = states
+            .get_all::<Whirlpool>()
+            .into_iter()
+            .map(|(address, whirlpool)| RoutePool::Whirlpool { address, whirlpool });
+
+        let openbook_markets = states
+            .get_all::<OpenBookMarket>()
+            .into_iter()
+            .map(|(address, market)| RoutePool::OpenBook { address, market });
+
+        spl_pools
+            .chain(saber_pools)
+            .chain(whirlpools)
+            .chain(openbook_markets)
+            .collect()
+    }
+
+    /// The best way to swap between two tokens in a single leg, and the amount it returns
+    fn best_leg(
+        &self,
+        pools: &[RoutePool],
+        source_token: &Pubkey,
+        destination_token: &Pubkey,
+        amount_in: u64,
+    ) -> Option<(SwapLeg, u64)> {
+        let states = self.client.state();
+        let candidates = pools
+            .iter()
+            .filter(|pool| pool.exchanges(source_token, destination_token))
+            .collect::<Vec<_>>();
+
+        let mut best: Option<(SwapLeg, u64)> = None;
+        let mut consider = |pool: &RoutePool, split_pool: Option<&RoutePool>, split, amount_out| {
+            if best.map_or(true, |(_, best_out)| amount_out > best_out) {
+                let leg = SwapLeg {
+                    source_token: *source_token,
+                    destination_token: *destination_token,
+                    pool: pool.address(),
+                    split_pool: split_pool.map(|p| p.address()),
+                    split,
+                };
+                best = Some((leg, amount_out));
+            }
+        };
+
+        for &pool in &candidates {
+            if let Some(quote) = pool.quote(states, source_token, amount_in) {
+                consider(pool, None, 0, quote.amount_out);
+            }
+        }
+
+        // both parts of a split leg are executed by the swap program as the same kind of pool
+        for &pool in &candidates {
+            for &split_pool in &candidates {
+                if pool.address() == split_pool.address()
+                    || pool.route_type() != split_pool.route_type()
+                {
+                    continue;
+                }
+
+                for split in (ROUTE_SWAP_MIN_SPLIT..=ROUTE_SWAP_MAX_SPLIT).step_by(SPLIT_STEP) {
+                    let split_amount = (amount_in as u128 * split as u128 / 100) as u64;
+                    let rest = amount_in - split_amount;
+                    if split_amount == 0 || rest == 0 {
+                        continue;
+                    }
+
+                    let (Some(split_quote), Some(rest_quote)) = (
+                        pool.quote(states, source_token, split_amount),
+                        split_pool.quote(states, source_token, rest),
+                    ) else {
+                        continue;
+                    };
+
+                    let amount_out = split_quote.amount_out + rest_quote.amount_out;
+                    consider(pool, Some(split_pool), split, amount_out);
+                }
+            }
+        }
+
+        best
+    }
+
     fn get_orca_v2_swap(&self, swap_pool: &Pubkey) -> ClientResult<SplSwap> {
         let swap = match self.client.state().get::<SwapV1>(swap_pool) {
             Some(swap) => swap,
@@ -128,3 +482,251 @@ impl MarginAccountSwapsClient {
         })
     }
 }
+
+/// A loaded venue that can be used in a route
+#[derive(Clone)]
+enum RoutePool {
+    Spl {
+        address: Pubkey,
+        program: Pubkey,
+        swap: Arc<SwapV1>,
+    },
+    Saber {
+        address: Pubkey,
+        program: Pubkey,
+        swap: Arc<SwapInfo>,
+    },
+    Whirlpool {
+        address: Pubkey,
+        whirlpool: Arc<Whirlpool>,
+    },
+    OpenBook {
+        address: Pubkey,
+        market: Arc<OpenBookMarket>,
+    },
+}
+
+impl RoutePool {
+    fn address(&self) -> Pubkey {
+        match self {
+            Self::Spl { address, .. }
+            | Self::Saber { address, .. }
+            | Self::Whirlpool { address, .. }
+            | Self::OpenBook { address, .. } => *address,
+        }
+    }
+
+    fn pool_tokens(&self) -> (Pubkey, Pubkey) {
+        match self {
+            Self::Spl { swap, .. } => (swap.token_a_mint, swap.token_b_mint),
+            Self::Saber { swap, .. } => (swap.token_a.mint, swap.token_b.mint),
+            Self::Whirlpool { whirlpool, .. } => (whirlpool.token_mint_a, whirlpool.token_mint_b),
+            Self::OpenBook { market, .. } => (market.base_mint, market.quote_mint),
+        }
+    }
+
+    fn route_type(&self) -> SwapRouteIdentifier {
+        match self {
+            Self::Spl { .. } => SwapRouteIdentifier::Spl,
+            Self::Saber { .. } => SwapRouteIdentifier::SaberStable,
+            Self::Whirlpool { .. } => SwapRouteIdentifier::Whirlpool,
+            Self::OpenBook { .. } => SwapRouteIdentifier::OpenBook,
+        }
+    }
+
+    fn exchanges(&self, source_token: &Pubkey, destination_token: &Pubkey) -> bool {
+        let (token_a, token_b) = self.pool_tokens();
+
+        (token_a == *source_token && token_b == *destination_token)
+            || (token_b == *source_token && token_a == *destination_token)
+    }
+
+    /// The expected result of swapping into the pool, at the current state of its accounts
+    fn quote(
+        &self,
+        states: &AccountStates,
+        source_token: &Pubkey,
+        amount_in: u64,
+    ) -> Option<swap_quote::SwapQuote> {
+        match self {
+            Self::Spl { swap, .. } => {
+                let reserve_a = states.get::<TokenAccount>(&swap.token_a)?.amount;
+                let reserve_b = states.get::<TokenAccount>(&swap.token_b)?.amount;
+
+                let a_to_b = *source_token == swap.token_a_mint;
+                let (destination_token, direction) = if a_to_b {
+                    (swap.token_b_mint, TradeDirection::AtoB)
+                } else {
+                    (swap.token_a_mint, TradeDirection::BtoA)
+                };
+                let (source_reserve, destination_reserve) = if a_to_b {
+                    (reserve_a, reserve_b)
+                } else {
+                    (reserve_b, reserve_a)
+                };
+
+                let result = swap.swap_curve.swap(
+                    amount_in as u128,
+                    source_reserve as u128,
+                    destination_reserve as u128,
+                    direction,
+                    &swap.fees,
+                )?;
+
+                Some(swap_quote::SwapQuote::new(
+                    *source_token,
+                    destination_token,
+                    u64::try_from(result.source_amount_swapped).ok()?,
+                    u64::try_from(result.destination_amount_swapped).ok()?,
+                    u64::try_from(result.trade_fee + result.owner_fee).ok()?,
+                    *source_token,
+                    destination_reserve as f64 / source_reserve as f64,
+                ))
+            }
+            Self::Saber { swap, .. } => {
+                let reserve_a = states.get::<TokenAccount>(&swap.token_a.reserves)?.amount;
+                let reserve_b = states.get::<TokenAccount>(&swap.token_b.reserves)?.amount;
+
+                quote_saber(
+                    swap,
+                    reserve_a,
+                    reserve_b,
+                    source_token,
+                    amount_in,
+                    states.get_current_time(),
+                )
+            }
+            Self::Whirlpool { address, whirlpool } => {
+                let a_to_b = *source_token == whirlpool.token_mint_a;
+                let tick_arrays = whirlpool_tick_arrays(address, whirlpool, a_to_b)
+                    .iter()
+                    .map_while(|tick_array| states.get::<TickArray>(tick_array))
+                    .map(|tick_array| *tick_array)
+                    .collect();
+
+                // the swap fails if it runs out of liquidity in the tick arrays it is given
+                quote_whirlpool(
+                    whirlpool,
+                    tick_arrays,
+                    source_token,
+                    amount_in,
+                    states.get_current_time() as u64,
+                )
+                .filter(|quote| quote.amount_in == amount_in)
+            }
+            Self::OpenBook { market, .. } => {
+                let book_side = if *source_token == market.base_mint {
+                    market.bids
+                } else {
+                    market.asks
+                };
+                let orders = states.get::<BookSide>(&book_side)?.orders.clone();
+
+                quote_openbook(
+                    &market.base_mint,
+                    &market.quote_mint,
+                    market.base_lot_size,
+                    market.quote_lot_size,
+                    orders,
+                    source_token,
+                    amount_in,
+                )
+            }
+        }
+    }
+}
+
+/// A venue in a route, along with the token it is swapping from
+struct RouteSwap {
+    pool: RoutePool,
+    source_token: Pubkey,
+}
+
+impl SwapAccounts for RouteSwap {
+    fn to_account_meta(&self, authority: Pubkey) -> Vec<AccountMeta> {
+        match &self.pool {
+            RoutePool::Spl {
+                address,
+                program,
+                swap,
+            } => ix_accounts::SplSwapInfo {
+                swap_pool: *address,
+                authority: derive_spl_swap_authority(program, address),
+                vault_a: swap.token_a,
+                vault_b: swap.token_b,
+                token_mint: swap.pool_mint,
+                fee_account: swap.pool_fee_account,
+                swap_program: *program,
+            }
+            .to_account_metas(None),
+            RoutePool::Saber {
+                address,
+                program,
+                swap,
+            } => ix_accounts::SaberSwapInfo {
+                swap_pool: *address,
+                authority: derive_spl_swap_authority(program, address),
+                vault_a: swap.token_a.reserves,
+                vault_b: swap.token_b.reserves,
+                admin_fee_a: swap.token_a.admin_fees,
+                admin_fee_b: swap.token_b.admin_fees,
+                swap_program: *program,
+            }
+            .to_account_metas(None),
+            RoutePool::Whirlpool { address, whirlpool } => {
+                let a_to_b = self.source_token == whirlpool.token_mint_a;
+                let tick_arrays = whirlpool_tick_arrays(address, whirlpool, a_to_b);
+
+                ix_accounts::OrcaWhirlpoolSwapPoolInfo {
+                    swap_program: ORCA_WHIRLPOOL,
+                    whirlpool: *address,
+                    vault_a: whirlpool.token_vault_a,
+                    vault_b: whirlpool.token_vault_b,
+                    tick_array_0: tick_arrays[0],
+                    tick_array_1: tick_arrays[1],
+                    tick_array_2: tick_arrays[2],
+                    oracle: derive_whirlpool_oracle(address),
+                }
+                .to_account_metas(None)
+            }
+            RoutePool::OpenBook { address, market } => {
+                let (open_orders, _) = Pubkey::find_program_address(
+                    &[OPENBOOK_OPEN_ORDERS, authority.as_ref(), address.as_ref()],
+                    &jet_margin_swap::ID,
+                );
+
+                ix_accounts::OpenbookSwapInfo {
+                    market: *address,
+                    open_orders,
+                    request_queue: market.request_queue,
+                    event_queue: market.event_queue,
+                    market_bids: market.bids,
+                    market_asks: market.asks,
+                    base_vault: market.base_vault,
+                    quote_vault: market.quote_vault,
+                    quote_mint: market.quote_mint,
+                    vault_signer: market.vault_signer,
+                    referrer_account: get_associated_token_address(
+                        &CONTROL_AUTHORITY,
+                        &market.quote_mint,
+                    ),
+                    dex_program: market.program,
+                    rent: Rent::id(),
+                }
+                .to_account_metas(None)
+            }
+        }
+    }
+
+    fn pool_tokens(&self) -> (Pubkey, Pubkey) {
+        self.pool.pool_tokens()
+    }
+
+    fn route_type(&self) -> SwapRouteIdentifier {
+        self.pool.route_type()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -49,6 +49,23 @@ pub fn resolve_swap_program(network: NetworkKind, name: &str) -> Result<Pubkey,
     Err(BuilderError::UnknownSwapProgram(name.to_string()))
 }
 
+/// The address of the state account of the exchange the builder creates for a pair of tokens
+pub fn derive_swap_state(program: &Pubkey, base: &Pubkey, quote: &Pubkey) -> Pubkey {
+    match *program {
+        p if p == ORCA_WHIRLPOOL => {
+            let (token_a, token_b) = (std::cmp::min(base, quote), std::cmp::max(base, quote));
+            let config = derive_whirlpool_config();
+
+            derive_whirlpool(&config, token_a, token_b, DEFAULT_TICK_SPACING).0
+        }
+        // the market state does not depend on the payer
+        p if p == OPENBOOK || p == OPENBOOK_DEVNET => {
+            derive_openbook_market(program, base, quote, &Pubkey::default()).state
+        }
+        _ => derive_spl_swap_pool(program, base, quote).state,
+    }
+}
+
 pub async fn create_swap_pools<'a>(
     builder: &mut Builder,
     config: &EnvironmentConfig,
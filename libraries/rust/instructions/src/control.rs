@@ -16,7 +16,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use anchor_lang::{InstructionData, ToAccountMetas};
-use jet_margin_pool::MarginPoolConfig;
+use jet_margin_pool::{InterestRateCurve, MarginPoolConfig, PoolLimits};
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_program};
 
 use super::get_metadata_address;
@@ -126,9 +126,11 @@ impl ControlIxBuilder {
 
             pyth_product: config.pyth_product.unwrap_or_default(),
             pyth_price: config.pyth_price.unwrap_or_default(),
+            payer: self.payer,
 
             margin_pool_program: jet_margin_pool::ID,
             metadata_program: jet_metadata::ID,
+            system_program: system_program::ID,
         }
         .to_account_metas(None);
 
@@ -142,6 +144,56 @@ impl ControlIxBuilder {
             .data(),
         }
     }
+
+    /// Instruction to set the interest rate curve for a margin pool.
+    pub fn configure_margin_pool_interest_curve(
+        &self,
+        token: &Pubkey,
+        curve: InterestRateCurve,
+    ) -> Instruction {
+        let pool_builder = MarginPoolIxBuilder::new(*token);
+        let accounts = jet_control::accounts::ConfigureMarginPoolInterestCurve {
+            requester: self.requester,
+            authority: get_control_authority_address(),
+            token_mint: *token,
+            margin_pool: pool_builder.address,
+            payer: self.payer,
+            margin_pool_program: jet_margin_pool::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            accounts,
+            program_id: jet_control::ID,
+            data: jet_control::instruction::ConfigureMarginPoolInterestCurve { curve }.data(),
+        }
+    }
+
+    /// Instruction to set the deposit and borrow caps for a margin pool.
+    pub fn configure_margin_pool_limits(
+        &self,
+        token: &Pubkey,
+        limits: Option<PoolLimits>,
+    ) -> Instruction {
+        let pool_builder = MarginPoolIxBuilder::new(*token);
+        let accounts = jet_control::accounts::ConfigureMarginPoolLimits {
+            requester: self.requester,
+            authority: get_control_authority_address(),
+            token_mint: *token,
+            margin_pool: pool_builder.address,
+            payer: self.payer,
+            margin_pool_program: jet_margin_pool::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+
+        Instruction {
+            accounts,
+            program_id: jet_control::ID,
+            data: jet_control::instruction::ConfigureMarginPoolLimits { limits }.data(),
+        }
+    }
 }
 
 /// Parameters used to configer a margin pool
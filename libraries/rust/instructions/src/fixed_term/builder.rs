@@ -4,11 +4,20 @@ use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use spl_associated_token_account::get_associated_token_address;
 
 use jet_fixed_term::{
-    control::{instructions::InitializeMarketParams, state::Market},
-    margin::state::AutoRollConfig,
-    orderbook::state::OrderParams,
+    control::{
+        instructions::{FeeScheduleParams, InitializeMarketParams},
+        state::{Market, PrepaymentMode, TicketPriceSource},
+    },
+    margin::{
+        instructions::BatchOrderParams,
+        state::{AutoRollConfig, AutoRollPolicyConfig},
+    },
+    orderbook::{instructions::AmendOrderParams, state::OrderParams},
+    tickets::{instructions::OfferDepositParams, state::DepositOffer},
 };
 
+use crate::margin_pool::MarginPoolIxBuilder;
+
 use super::{derive, ix, MarginUser, MarketAdmin, OrderbookAddresses};
 
 #[derive(Clone, Debug)]
@@ -144,6 +153,7 @@ impl FixedTermIxBuilder {
             event_queue: self.orderbook.event_queue,
             bids: self.orderbook.bids,
             asks: self.orderbook.asks,
+            fee_schedule: derive::fee_schedule(&self.market),
         }
     }
 
@@ -164,12 +174,20 @@ impl FixedTermIxBuilder {
             seed,
             events,
             self.market,
-            self.orderbook.event_queue,
+            self.orderbook,
             self.payer,
             self.payer,
         )
     }
 
+    pub fn expire_orders(&self, order_ids: Vec<u128>) -> Instruction {
+        ix::expire_orders(order_ids, self.market, self.orderbook, self.payer)
+    }
+
+    pub fn sample_ticket_twap(&self) -> Instruction {
+        ix::sample_ticket_twap(self.market, self.orderbook, self.payer)
+    }
+
     /// initializes the associated token account for the underlying mint owned
     /// by the authority of the market. this only returns an instruction if
     /// you've opted to use the default fee_destination, which is the ata for
@@ -281,6 +299,57 @@ impl FixedTermIxBuilder {
         ))
     }
 
+    /// Offer a deposit for sale, paid to the owner's associated token account unless another
+    /// destination is given
+    pub fn offer_deposit(
+        &self,
+        owner: Pubkey,
+        deposit: Pubkey,
+        params: OfferDepositParams,
+        payment_destination: Option<Pubkey>,
+    ) -> Instruction {
+        ix::offer_deposit(
+            params,
+            self.market,
+            owner,
+            deposit,
+            payment_destination
+                .unwrap_or_else(|| get_associated_token_address(&owner, &self.underlying_mint)),
+            self.payer,
+        )
+    }
+
+    pub fn cancel_deposit_offer(
+        &self,
+        seller: Pubkey,
+        deposit: Pubkey,
+        payer: Pubkey,
+    ) -> Instruction {
+        ix::cancel_deposit_offer(self.market, seller, deposit, payer)
+    }
+
+    /// Buy a deposit that has been offered, paying from the buyer's associated token account
+    /// unless another source is given. The purchase fails if the offer has been replaced by one
+    /// with a higher price than `offer`.
+    pub fn accept_deposit_offer(
+        &self,
+        buyer: Pubkey,
+        deposit: Pubkey,
+        offer: &DepositOffer,
+        payment_source: Option<Pubkey>,
+    ) -> Instruction {
+        ix::accept_deposit_offer(
+            self.market,
+            buyer,
+            deposit,
+            payment_source
+                .unwrap_or_else(|| get_associated_token_address(&buyer, &self.underlying_mint)),
+            offer.payment_destination,
+            offer.payer,
+            offer.price,
+        )
+    }
+
     pub fn settle(&self, margin_account: Pubkey) -> Instruction {
         ix::settle(self.market, self.underlying_mint, margin_account)
     }
@@ -415,6 +484,22 @@ impl FixedTermIxBuilder {
         )
     }
 
+    /// Cancel and place several post only orders for a margin account in one instruction
+    pub fn margin_batch_orders(
+        &self,
+        margin_account: Pubkey,
+        lender_tokens: Option<Pubkey>,
+        params: BatchOrderParams,
+    ) -> Instruction {
+        ix::margin_batch_orders(
+            params,
+            margin_account,
+            lender_tokens,
+            self.orderbook_mut(),
+            &self.underlying_mint,
+        )
+    }
+
     pub fn auto_roll_lend_order(
         &self,
         margin_account: Pubkey,
@@ -432,6 +517,52 @@ impl FixedTermIxBuilder {
         )
     }
 
+    /// Roll a deposit matured in `source_market` into an order in this market
+    pub fn auto_roll_lend_order_to_market(
+        &self,
+        source_market: Pubkey,
+        margin_account: Pubkey,
+        deposit: Pubkey,
+        rent_receiver: Pubkey,
+        next_deposit_seqno: u64,
+    ) -> Instruction {
+        ix::auto_roll_lend_order_to_market(
+            next_deposit_seqno,
+            source_market,
+            margin_account,
+            deposit,
+            rent_receiver,
+            self.orderbook_mut(),
+            self.payer,
+        )
+    }
+
+    /// Deposit a matured deposit into the margin pool for the underlying token, when the
+    /// deposit cannot be rolled into `roll_market`
+    pub fn auto_roll_lend_to_pool(
+        &self,
+        margin_account: Pubkey,
+        deposit: Pubkey,
+        rent_receiver: Pubkey,
+        roll_market: &FixedTermIxBuilder,
+        pool_deposit: Pubkey,
+    ) -> Instruction {
+        let pool = MarginPoolIxBuilder::new(self.underlying_mint);
+        ix::auto_roll_lend_to_pool(
+            self.market,
+            margin_account,
+            deposit,
+            rent_receiver,
+            roll_market.market,
+            roll_market.orderbook.bids,
+            roll_market.orderbook.asks,
+            pool.address,
+            pool.vault,
+            pool.deposit_note_mint,
+            pool_deposit,
+        )
+    }
+
     pub fn auto_roll_borrow_order(
         &self,
         margin_account: Pubkey,
@@ -455,6 +586,41 @@ impl FixedTermIxBuilder {
         ix::cancel_order(order_id, owner, self.orderbook_mut())
     }
 
+    /// Amend an order placed by a signer, which receives any underlying tokens released
+    /// from a repriced lend order in its associated token account
+    pub fn amend_order(
+        &self,
+        owner: Pubkey,
+        order_id: u128,
+        params: AmendOrderParams,
+    ) -> Instruction {
+        let settlement = get_associated_token_address(&owner, &self.underlying_mint);
+        ix::amend_order(order_id, params, owner, &[settlement], self.orderbook_mut())
+    }
+
+    /// Amend an order placed by a margin account
+    pub fn margin_amend_order(
+        &self,
+        margin_account: Pubkey,
+        order_id: u128,
+        params: AmendOrderParams,
+    ) -> Instruction {
+        let user = self.margin_user(margin_account);
+        ix::amend_order(
+            order_id,
+            params,
+            margin_account,
+            &[
+                user.address,
+                user.ticket_collateral,
+                self.collateral(),
+                user.underlying_collateral,
+                self.underlying_collateral(),
+            ],
+            self.orderbook_mut(),
+        )
+    }
+
     pub fn pause_order_matching(&self) -> Instruction {
         ix::pause_order_matching(
             self.market_admin(),
@@ -466,6 +632,22 @@ impl FixedTermIxBuilder {
         ix::resume_order_matching(self.market_admin(), self.orderbook)
     }
 
+    pub fn configure_ticket_price_source(&self, source: TicketPriceSource) -> Instruction {
+        ix::configure_ticket_price_source(source, self.market_admin())
+    }
+
+    pub fn configure_default_handling(&self, grace_period: u32, penalty: u16) -> Instruction {
+        ix::configure_default_handling(grace_period, penalty, self.market_admin())
+    }
+
+    pub fn configure_prepayment(&self, mode: PrepaymentMode, rebate: u16) -> Instruction {
+        ix::configure_prepayment(mode, rebate, self.market_admin())
+    }
+
+    pub fn configure_fee_schedule(&self, params: FeeScheduleParams) -> Instruction {
+        ix::configure_fee_schedule(params, self.market_admin(), self.payer)
+    }
+
     pub fn pause_ticket_redemption(&self) -> Instruction {
         ix::pause_ticket_redemption(self.market_admin())
     }
@@ -501,6 +683,55 @@ impl FixedTermIxBuilder {
         )
     }
 
+    /// Repay a loan that is past due beyond the grace period, using the borrower's assets in
+    /// the market and then up to `max_source_amount` from the source account. The borrower's
+    /// orders in `order_ids` are cancelled, and the tickets staked in `deposits` may be used.
+    pub fn repay_past_due(
+        &self,
+        source_authority: &Pubkey,
+        payer: &Pubkey,
+        margin_account: &Pubkey,
+        source: &Pubkey,
+        term_loan_seqno: u64,
+        max_source_amount: u64,
+        order_ids: Vec<u128>,
+        deposits: &[Pubkey],
+    ) -> Instruction {
+        ix::repay_past_due(
+            term_loan_seqno,
+            max_source_amount,
+            order_ids,
+            self.orderbook_mut(),
+            *source_authority,
+            *payer,
+            derive::margin_user(&self.market, margin_account),
+            *source,
+            deposits,
+        )
+    }
+
+    /// Repay a loan before it matures, paying with tickets or underlying tokens depending on
+    /// the prepayment mode of the market
+    pub fn prepay(
+        &self,
+        source_authority: &Pubkey,
+        payer: &Pubkey,
+        margin_account: &Pubkey,
+        source: &Pubkey,
+        term_loan_seqno: u64,
+        amount: u64,
+    ) -> Instruction {
+        ix::prepay(
+            term_loan_seqno,
+            amount,
+            self.market,
+            *source_authority,
+            *payer,
+            derive::margin_user(&self.market, margin_account),
+            *source,
+        )
+    }
+
     pub fn configure_auto_roll(
         &self,
         margin_account: Pubkey,
@@ -510,6 +741,15 @@ impl FixedTermIxBuilder {
         ix::configure_auto_roll(self.market, margin_account, owner, config)
     }
 
+    pub fn configure_auto_roll_policy(
+        &self,
+        margin_account: Pubkey,
+        owner: Pubkey,
+        config: AutoRollPolicyConfig,
+    ) -> Instruction {
+        ix::configure_auto_roll_policy(self.market, margin_account, owner, self.payer, config)
+    }
+
     pub fn toggle_auto_roll_deposit(&self, margin_account: Pubkey, deposit: Pubkey) -> Instruction {
         ix::toggle_auto_roll_deposit(margin_account, deposit)
     }
@@ -69,6 +69,10 @@ pub fn term_deposit(market: &Pubkey, owner: &Pubkey, deposit_seqno: u64) -> Pubk
     ])
 }
 
+pub fn deposit_offer(deposit: &Pubkey) -> Pubkey {
+    fixed_term_address(&[jet_fixed_term::seeds::DEPOSIT_OFFER, deposit.as_ref()])
+}
+
 pub fn term_loan_bytes(market: &Pubkey, margin_user: &Pubkey, seed: &[u8]) -> Pubkey {
     fixed_term_address(&[
         jet_fixed_term::seeds::TERM_LOAN,
@@ -137,3 +141,7 @@ pub fn orderbook_market_state(market: &Pubkey) -> Pubkey {
 pub fn fee_vault(market: &Pubkey) -> Pubkey {
     fixed_term_address(&[jet_fixed_term::seeds::FEE_VAULT, market.as_ref()])
 }
+
+pub fn fee_schedule(market: &Pubkey) -> Pubkey {
+    fixed_term_address(&[jet_fixed_term::seeds::FEE_SCHEDULE, market.as_ref()])
+}
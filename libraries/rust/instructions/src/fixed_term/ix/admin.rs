@@ -2,7 +2,10 @@
 
 use anchor_lang::{prelude::Pubkey, InstructionData, ToAccountMetas};
 use jet_fixed_term::{
-    control::instructions::{InitializeMarketParams, InitializeOrderbookParams},
+    control::{
+        instructions::{FeeScheduleParams, InitializeMarketParams, InitializeOrderbookParams},
+        state::{PrepaymentMode, TicketPriceSource},
+    },
     orderbook::state::{event_queue_len, orderbook_slab_len},
 };
 use solana_sdk::instruction::Instruction;
@@ -160,6 +163,76 @@ pub fn resume_order_matching(
     Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
 }
 
+pub fn configure_ticket_price_source(
+    source: TicketPriceSource,
+    market_admin: MarketAdmin,
+) -> Instruction {
+    let data = jet_fixed_term::instruction::ConfigureTicketPriceSource { source }.data();
+    let accounts = jet_fixed_term::accounts::ConfigureTicketPriceSource {
+        market: market_admin.market,
+        authority: market_admin.authority,
+        airspace: market_admin.airspace,
+    }
+    .to_account_metas(None);
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
+pub fn configure_default_handling(
+    grace_period: u32,
+    penalty: u16,
+    market_admin: MarketAdmin,
+) -> Instruction {
+    let data = jet_fixed_term::instruction::ConfigureDefaultHandling {
+        grace_period,
+        penalty,
+    }
+    .data();
+    let accounts = jet_fixed_term::accounts::ConfigureDefaultHandling {
+        market: market_admin.market,
+        authority: market_admin.authority,
+        airspace: market_admin.airspace,
+    }
+    .to_account_metas(None);
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
+pub fn configure_prepayment(
+    mode: PrepaymentMode,
+    rebate: u16,
+    market_admin: MarketAdmin,
+) -> Instruction {
+    let data = jet_fixed_term::instruction::ConfigurePrepayment { mode, rebate }.data();
+    let accounts = jet_fixed_term::accounts::ConfigurePrepayment {
+        market: market_admin.market,
+        authority: market_admin.authority,
+        airspace: market_admin.airspace,
+    }
+    .to_account_metas(None);
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
+pub fn configure_fee_schedule(
+    params: FeeScheduleParams,
+    market_admin: MarketAdmin,
+    payer: Pubkey,
+) -> Instruction {
+    let data = jet_fixed_term::instruction::ConfigureFeeSchedule { params }.data();
+    let accounts = jet_fixed_term::accounts::ConfigureFeeSchedule {
+        market: market_admin.market,
+        fee_schedule: fee_schedule(&market_admin.market),
+        authority: market_admin.authority,
+        airspace: market_admin.airspace,
+        payer,
+        system_program: solana_sdk::system_program::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
 pub fn pause_ticket_redemption(market_admin: MarketAdmin) -> Instruction {
     modify_market([true as u8].into(), 8 + 32 * 16 + 2, market_admin)
 }
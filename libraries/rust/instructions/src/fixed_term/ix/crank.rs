@@ -7,13 +7,13 @@ use anchor_lang::{
 use solana_sdk::instruction::Instruction;
 use spl_associated_token_account::get_associated_token_address as ata;
 
-use super::super::derive::*;
+use super::super::{derive::*, OrderbookAddresses};
 
 pub fn consume_events(
     seed: &[u8],
     events: impl IntoIterator<Item = impl Into<Vec<Pubkey>>>,
     market: Pubkey,
-    event_queue: Pubkey,
+    orderbook: OrderbookAddresses,
     crank: Pubkey,
     payer: Pubkey,
 ) -> Instruction {
@@ -23,7 +23,10 @@ pub fn consume_events(
         underlying_token_vault: underlying_token_vault(&market),
         fee_vault: fee_vault(&market),
         orderbook_market_state: orderbook_market_state(&market),
-        event_queue,
+        event_queue: orderbook.event_queue,
+        bids: orderbook.bids,
+        asks: orderbook.asks,
+        fee_schedule: fee_schedule(&market),
         crank_authorization: crank_authorization(&market, &crank),
         crank,
         payer,
@@ -47,6 +50,47 @@ pub fn consume_events(
     Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
 }
 
+pub fn expire_orders(
+    order_ids: Vec<u128>,
+    market: Pubkey,
+    orderbook: OrderbookAddresses,
+    crank: Pubkey,
+) -> Instruction {
+    let accounts = jet_fixed_term::accounts::ExpireOrders {
+        market,
+        orderbook_market_state: orderbook_market_state(&market),
+        event_queue: orderbook.event_queue,
+        bids: orderbook.bids,
+        asks: orderbook.asks,
+        crank_authorization: crank_authorization(&market, &crank),
+        crank,
+    };
+    Instruction::new_with_bytes(
+        jet_fixed_term::ID,
+        &jet_fixed_term::instruction::ExpireOrders { order_ids }.data(),
+        accounts.to_account_metas(None),
+    )
+}
+
+pub fn sample_ticket_twap(
+    market: Pubkey,
+    orderbook: OrderbookAddresses,
+    crank: Pubkey,
+) -> Instruction {
+    let accounts = jet_fixed_term::accounts::SampleTicketTwap {
+        market,
+        bids: orderbook.bids,
+        asks: orderbook.asks,
+        crank_authorization: crank_authorization(&market, &crank),
+        crank,
+    };
+    Instruction::new_with_bytes(
+        jet_fixed_term::ID,
+        &jet_fixed_term::instruction::SampleTicketTwap {}.data(),
+        accounts.to_account_metas(None),
+    )
+}
+
 pub fn settle(market: Pubkey, underlying_mint: Pubkey, margin_account: Pubkey) -> Instruction {
     let margin_user = margin_user(&market, &margin_account);
     let ticket_mint = ticket_mint(&market);
@@ -1,11 +1,16 @@
 //! Instructions that are invoked by an end user through a margin account.
 
 use anchor_lang::{prelude::Pubkey, InstructionData, ToAccountMetas};
-use solana_sdk::instruction::Instruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
 use spl_associated_token_account::get_associated_token_address as ata;
 
 use jet_fixed_term::{
-    accounts::OrderbookMut, margin::state::AutoRollConfig, orderbook::state::OrderParams,
+    accounts::{AutoRollSource, OrderbookMut},
+    margin::{
+        instructions::BatchOrderParams,
+        state::{AutoRollConfig, AutoRollPolicyConfig},
+    },
+    orderbook::state::OrderParams,
 };
 
 use crate::fixed_term::derive::*;
@@ -168,6 +173,35 @@ pub fn margin_lend_order(
     Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
 }
 
+pub fn margin_batch_orders(
+    params: BatchOrderParams,
+    margin_account: Pubkey,
+    lender_tokens: Option<Pubkey>,
+    orderbook_mut: OrderbookMut,
+    underlying_mint: &Pubkey,
+) -> Instruction {
+    let market = orderbook_mut.market;
+    let margin_user = margin_user(&market, &margin_account);
+    let data = jet_fixed_term::instruction::MarginBatchOrders { params }.data();
+    let accounts = jet_fixed_term::accounts::MarginBatchOrders {
+        margin_user,
+        margin_account,
+        claims: user_claims(&margin_user),
+        claims_mint: claims_mint(&market),
+        ticket_collateral: user_ticket_collateral(&margin_user),
+        ticket_collateral_mint: ticket_collateral_mint(&market),
+        underlying_collateral: user_underlying_collateral(&margin_user),
+        underlying_collateral_mint: underlying_collateral_mint(&market),
+        orderbook_mut,
+        lender_tokens: lender_tokens.unwrap_or_else(|| ata(&margin_account, underlying_mint)),
+        underlying_token_vault: underlying_token_vault(&market),
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
 pub fn margin_repay(
     term_loan_seqno: u64,
     amount: u64,
@@ -196,6 +230,74 @@ pub fn margin_repay(
     Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
 }
 
+pub fn repay_past_due(
+    term_loan_seqno: u64,
+    max_source_amount: u64,
+    order_ids: Vec<u128>,
+    orderbook_mut: OrderbookMut,
+    source_authority: Pubkey,
+    payer: Pubkey,
+    margin_user: Pubkey,
+    source: Pubkey,
+    deposits: &[Pubkey],
+) -> Instruction {
+    let market = orderbook_mut.market;
+    let data = jet_fixed_term::instruction::RepayPastDue {
+        max_source_amount,
+        order_ids,
+    }
+    .data();
+    let mut accounts = jet_fixed_term::accounts::RepayPastDue {
+        term_loan: term_loan(&market, &margin_user, term_loan_seqno),
+        next_term_loan: term_loan(&market, &margin_user, term_loan_seqno + 1),
+        claims: user_claims(&margin_user),
+        claims_mint: claims_mint(&market),
+        ticket_collateral: user_ticket_collateral(&margin_user),
+        ticket_collateral_mint: ticket_collateral_mint(&market),
+        orderbook_mut,
+        margin_user,
+        source,
+        payer,
+        source_authority,
+        underlying_token_vault: underlying_token_vault(&market),
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(deposits.iter().map(|d| AccountMeta::new(*d, false)));
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
+pub fn prepay(
+    term_loan_seqno: u64,
+    amount: u64,
+    market: Pubkey,
+    source_authority: Pubkey,
+    payer: Pubkey,
+    margin_user: Pubkey,
+    source: Pubkey,
+) -> Instruction {
+    let data = jet_fixed_term::instruction::Prepay { amount }.data();
+    let accounts = jet_fixed_term::accounts::Prepay {
+        term_loan: term_loan(&market, &margin_user, term_loan_seqno),
+        next_term_loan: term_loan(&market, &margin_user, term_loan_seqno + 1),
+        claims: user_claims(&margin_user),
+        claims_mint: claims_mint(&market),
+        fee_vault: fee_vault(&market),
+        ticket_mint: ticket_mint(&market),
+        market,
+        margin_user,
+        source,
+        payer,
+        source_authority,
+        underlying_token_vault: underlying_token_vault(&market),
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
 pub fn configure_auto_roll(
     market: Pubkey,
     margin_account: Pubkey,
@@ -226,6 +328,39 @@ pub fn configure_auto_roll(
     }
 }
 
+pub fn configure_auto_roll_policy(
+    market: Pubkey,
+    margin_account: Pubkey,
+    owner: Pubkey,
+    payer: Pubkey,
+    config: AutoRollPolicyConfig,
+) -> Instruction {
+    let margin_user = margin_user(&market, &margin_account);
+    let accounts = jet_fixed_term::accounts::ConfigureAutoRollPolicy {
+        margin_user,
+        margin_account,
+        owner,
+        market,
+        payer,
+        system_program: solana_sdk::system_program::ID,
+    }
+    .to_account_metas(None);
+
+    match config {
+        AutoRollPolicyConfig::Borrow(policy) => Instruction::new_with_bytes(
+            jet_fixed_term::ID,
+            &jet_fixed_term::instruction::ConfigureAutoRollBorrowPolicy { policy }.data(),
+            accounts,
+        ),
+
+        AutoRollPolicyConfig::Lend(policy) => Instruction::new_with_bytes(
+            jet_fixed_term::ID,
+            &jet_fixed_term::instruction::ConfigureAutoRollLendPolicy { policy }.data(),
+            accounts,
+        ),
+    }
+}
+
 pub fn toggle_auto_roll_deposit(margin_account: Pubkey, deposit: Pubkey) -> Instruction {
     let accounts = jet_fixed_term::accounts::ToggleAutoRollDeposit {
         margin_account,
@@ -325,6 +460,94 @@ pub fn auto_roll_borrow_order(
     Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
 }
 
+fn auto_roll_source(
+    source_market: Pubkey,
+    margin_account: Pubkey,
+    deposit: Pubkey,
+    rent_receiver: Pubkey,
+) -> AutoRollSource {
+    let margin_user = margin_user(&source_market, &margin_account);
+    AutoRollSource {
+        margin_user,
+        deposit,
+        ticket_collateral: user_ticket_collateral(&margin_user),
+        ticket_collateral_mint: ticket_collateral_mint(&source_market),
+        market: source_market,
+        underlying_token_vault: underlying_token_vault(&source_market),
+        rent_receiver,
+    }
+}
+
+pub fn auto_roll_lend_order_to_market(
+    next_deposit_seqno: u64,
+    source_market: Pubkey,
+    margin_account: Pubkey,
+    deposit: Pubkey,
+    rent_receiver: Pubkey,
+    orderbook_mut: OrderbookMut,
+    payer: Pubkey,
+) -> Instruction {
+    let market = &orderbook_mut.market;
+    let margin_user = margin_user(market, &margin_account);
+    let data = jet_fixed_term::instruction::AutoRollLendOrderToMarket {}.data();
+    let accounts = jet_fixed_term::accounts::AutoRollLendOrderToMarket {
+        margin_user,
+        margin_account,
+        source: auto_roll_source(source_market, margin_account, deposit, rent_receiver),
+        new_deposit: term_deposit(market, &margin_account, next_deposit_seqno),
+        ticket_collateral: user_ticket_collateral(&margin_user),
+        ticket_collateral_mint: ticket_collateral_mint(market),
+        ticket_mint: ticket_mint(market),
+        underlying_token_vault: underlying_token_vault(market),
+        orderbook_mut,
+        payer,
+        system_program: solana_sdk::system_program::ID,
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
+pub fn auto_roll_lend_to_pool(
+    market: Pubkey,
+    margin_account: Pubkey,
+    deposit: Pubkey,
+    rent_receiver: Pubkey,
+    roll_market: Pubkey,
+    roll_bids: Pubkey,
+    roll_asks: Pubkey,
+    margin_pool: Pubkey,
+    margin_pool_vault: Pubkey,
+    deposit_note_mint: Pubkey,
+    pool_deposit: Pubkey,
+) -> Instruction {
+    let margin_user = margin_user(&market, &margin_account);
+    let data = jet_fixed_term::instruction::AutoRollLendToPool {}.data();
+    let accounts = jet_fixed_term::accounts::AutoRollLendToPool {
+        margin_user,
+        margin_account,
+        deposit,
+        ticket_collateral: user_ticket_collateral(&margin_user),
+        ticket_collateral_mint: ticket_collateral_mint(&market),
+        market,
+        underlying_token_vault: underlying_token_vault(&market),
+        rent_receiver,
+        roll_market,
+        bids: roll_bids,
+        asks: roll_asks,
+        margin_pool,
+        margin_pool_vault,
+        deposit_note_mint,
+        pool_deposit,
+        margin_pool_program: jet_margin_pool::ID,
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
 // fn margin_lend_order_accounts(
 //     &self,
 //     margin_account: Pubkey,
@@ -1,9 +1,13 @@
 //! Instructions invoked by an end user, agnostic of margin accounts.
 
-use anchor_lang::{prelude::Pubkey, InstructionData, ToAccountMetas};
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    InstructionData, ToAccountMetas,
+};
 use jet_fixed_term::{
-    accounts::OrderbookMut, orderbook::state::OrderParams,
-    tickets::instructions::StakeTicketsParams,
+    accounts::OrderbookMut,
+    orderbook::{instructions::AmendOrderParams, state::OrderParams},
+    tickets::instructions::{OfferDepositParams, StakeTicketsParams},
 };
 use solana_sdk::instruction::Instruction;
 use spl_associated_token_account::get_associated_token_address as ata;
@@ -98,6 +102,89 @@ pub fn redeem_deposit_accounts(
     }
 }
 
+/// The owner may be a margin account, in which case the deposit leaves its collateral
+pub fn offer_deposit(
+    params: OfferDepositParams,
+    market: Pubkey,
+    owner: Pubkey,
+    deposit: Pubkey,
+    payment_destination: Pubkey,
+    payer: Pubkey,
+) -> Instruction {
+    let margin_user = margin_user(&market, &owner);
+    let accounts = jet_fixed_term::accounts::OfferDeposit {
+        deposit,
+        owner,
+        offer: deposit_offer(&deposit),
+        payment_destination,
+        ticket_collateral: user_ticket_collateral(&margin_user),
+        ticket_collateral_mint: ticket_collateral_mint(&market),
+        margin_user,
+        market,
+        payer,
+        token_program: spl_token::ID,
+        system_program: solana_sdk::system_program::ID,
+    }
+    .to_account_metas(None);
+    let data = jet_fixed_term::instruction::OfferDeposit { params }.data();
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
+pub fn cancel_deposit_offer(
+    market: Pubkey,
+    seller: Pubkey,
+    deposit: Pubkey,
+    payer: Pubkey,
+) -> Instruction {
+    let margin_user = margin_user(&market, &seller);
+    let accounts = jet_fixed_term::accounts::CancelDepositOffer {
+        offer: deposit_offer(&deposit),
+        deposit,
+        seller,
+        payer,
+        ticket_collateral: user_ticket_collateral(&margin_user),
+        ticket_collateral_mint: ticket_collateral_mint(&market),
+        margin_user,
+        market,
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    let data = jet_fixed_term::instruction::CancelDepositOffer {}.data();
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
+/// The buyer may be a margin account, in which case the deposit is added to its collateral
+pub fn accept_deposit_offer(
+    market: Pubkey,
+    buyer: Pubkey,
+    deposit: Pubkey,
+    payment_source: Pubkey,
+    payment_destination: Pubkey,
+    payer: Pubkey,
+    max_price: u64,
+) -> Instruction {
+    let margin_user = margin_user(&market, &buyer);
+    let accounts = jet_fixed_term::accounts::AcceptDepositOffer {
+        offer: deposit_offer(&deposit),
+        deposit,
+        buyer,
+        payment_source,
+        payment_destination,
+        payer,
+        ticket_collateral: user_ticket_collateral(&margin_user),
+        ticket_collateral_mint: ticket_collateral_mint(&market),
+        margin_user,
+        market,
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    let data = jet_fixed_term::instruction::AcceptDepositOffer { max_price }.data();
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
 pub fn refresh_position(
     expect_price: bool,
     market: Pubkey,
@@ -216,6 +303,29 @@ pub fn lend_order_accounts(
     }
 }
 
+/// `settlement` is the `MarginUser` for margin orders, followed by its collateral accounts and
+/// mints, or the token account that receives underlying tokens for other orders. It is only
+/// needed when the order is moved to a new price.
+pub fn amend_order(
+    order_id: u128,
+    params: AmendOrderParams,
+    owner: Pubkey,
+    settlement: &[Pubkey],
+    orderbook_mut: OrderbookMut,
+) -> Instruction {
+    let data = jet_fixed_term::instruction::AmendOrder { order_id, params }.data();
+    let mut accounts = jet_fixed_term::accounts::AmendOrder {
+        owner,
+        underlying_token_vault: underlying_token_vault(&orderbook_mut.market),
+        orderbook_mut,
+        token_program: spl_token::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(settlement.iter().map(|a| AccountMeta::new(*a, false)));
+
+    Instruction::new_with_bytes(jet_fixed_term::ID, &data, accounts)
+}
+
 pub fn cancel_order(order_id: u128, owner: Pubkey, orderbook_mut: OrderbookMut) -> Instruction {
     let data = jet_fixed_term::instruction::CancelOrder { order_id }.data();
     let accounts = jet_fixed_term::accounts::CancelOrder {
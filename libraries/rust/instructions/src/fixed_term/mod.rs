@@ -6,7 +6,7 @@ use solana_sdk::pubkey::Pubkey;
 
 pub use jet_fixed_term::{
     control::{instructions::InitializeMarketParams, state::Market},
-    orderbook::state::{event_queue_len, orderbook_slab_len, OrderParams},
+    orderbook::state::{event_queue_len, orderbook_slab_len, OrderParams, TimeInForce},
     ID as FIXED_TERM_PROGRAM,
 };
 
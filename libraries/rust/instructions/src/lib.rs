@@ -8,6 +8,7 @@ pub mod margin_pool;
 pub mod margin_swap;
 pub mod openbook;
 pub mod orca;
+pub mod swap_quote;
 
 /// Instruction builder for the protocol test service
 pub mod test_service;
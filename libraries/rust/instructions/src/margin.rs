@@ -28,12 +28,20 @@ use anchor_lang::{system_program, InstructionData};
 
 use jet_margin::instruction as ix_data;
 use jet_margin::program::JetMargin;
-use jet_margin::seeds::{ADAPTER_CONFIG_SEED, PERMIT_SEED, TOKEN_CONFIG_SEED};
-use jet_margin::{accounts as ix_account, MarginAccount};
+use jet_margin::seeds::{
+    ADAPTER_CONFIG_SEED, CONDITIONAL_ACTION_SEED, LIQUIDATION_AUCTION_SEED,
+    LIQUIDATION_CONFIG_SEED, MARGIN_DELEGATE_SEED, PERMIT_SEED, POSITION_PAGE_SEED,
+    TOKEN_CONFIG_SEED, TOKEN_ISOLATION_SEED, TOKEN_ORACLE_SET_SEED,
+};
+use jet_margin::{accounts as ix_account, ConditionalAction, MarginAccount};
 use jet_program_common::ADDRESS_LOOKUP_REGISTRY_ID;
 
 pub use jet_margin::ID as MARGIN_PROGRAM;
-pub use jet_margin::{TokenAdmin, TokenConfigUpdate, TokenKind, TokenOracle};
+pub use jet_margin::{
+    ActionTrigger, DelegateMintLimitUpdate, DelegatePermissions, LiquidationConfigUpdate,
+    MarginDelegateUpdate, OracleAggregation, TokenAdmin, TokenConfigUpdate, TokenIsolation,
+    TokenKind, TokenOracle, TokenOracleSetUpdate,
+};
 use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
 
 use crate::airspace::derive_permit;
@@ -265,7 +273,7 @@ impl MarginIxBuilder {
         let config = MarginConfigIxBuilder::new(self.airspace, self.payer(), None)
             .derive_token_config(&position_token_mint);
 
-        let accounts = ix_account::RegisterPosition {
+        let mut accounts = ix_account::RegisterPosition {
             authority: self.authority(),
             payer: self.payer(),
             margin_account: self.address,
@@ -275,12 +283,18 @@ impl MarginIxBuilder {
             token_program: spl_token::ID,
             system_program: System::id(),
             rent: Rent::id(),
-        };
+        }
+        .to_account_metas(None);
+
+        accounts.push(AccountMeta::new_readonly(
+            derive_token_isolation(&config),
+            false,
+        ));
 
         Instruction {
             program_id: JetMargin::id(),
             data: ix_data::RegisterPosition {}.data(),
-            accounts: accounts.to_account_metas(None),
+            accounts,
         }
     }
 
@@ -312,6 +326,118 @@ impl MarginIxBuilder {
         }
     }
 
+    /// Get instruction to add a page for storing positions to the margin account
+    ///
+    /// # Params
+    ///
+    /// `page` - The index of the new page, which must be the current number of pages
+    pub fn add_position_page(&self, page: u8) -> Instruction {
+        let accounts = ix_account::AddPositionPage {
+            owner: self.owner,
+            payer: self.payer(),
+            margin_account: self.address,
+            page: derive_position_page(&self.address, page),
+            system_program: SYSTEM_PROGAM_ID,
+        };
+
+        Instruction {
+            program_id: JetMargin::id(),
+            data: ix_data::AddPositionPage.data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
+    /// Get instruction to register a deposit position in one of the account's pages
+    ///
+    /// # Params
+    ///
+    /// `page` - The index of the page to store the position in
+    /// `token_mint` - The mint for the deposited token
+    /// `pages` - The number of position pages for the account, which must all be provided
+    pub fn register_paged_position(&self, page: u8, token_mint: Pubkey, pages: u8) -> Instruction {
+        let page = derive_position_page(&self.address, page);
+        let config = derive_token_config(&self.airspace, &token_mint);
+        let mut accounts = ix_account::RegisterPagedPosition {
+            authority: self.authority(),
+            payer: self.payer(),
+            margin_account: self.address,
+            page,
+            mint: token_mint,
+            config,
+            token_account: derive_paged_position_token_account(&page, &token_mint),
+            token_program: spl_token::ID,
+            rent: Rent::id(),
+            system_program: SYSTEM_PROGAM_ID,
+        }
+        .to_account_metas(None);
+
+        accounts.extend((0..pages).map(|page| {
+            AccountMeta::new_readonly(derive_position_page(&self.address, page), false)
+        }));
+        accounts.push(AccountMeta::new_readonly(
+            derive_token_isolation(&config),
+            false,
+        ));
+
+        Instruction {
+            program_id: JetMargin::id(),
+            data: ix_data::RegisterPagedPosition.data(),
+            accounts,
+        }
+    }
+
+    /// Get instruction to close a deposit position in one of the account's pages
+    ///
+    /// # Params
+    ///
+    /// `page` - The index of the page storing the position
+    /// `token_mint` - The mint for the deposited token
+    pub fn close_paged_position(&self, page: u8, token_mint: Pubkey) -> Instruction {
+        let page = derive_position_page(&self.address, page);
+        let accounts = ix_account::ClosePagedPosition {
+            authority: self.authority(),
+            receiver: self.payer(),
+            margin_account: self.address,
+            page,
+            position_token_mint: token_mint,
+            token_account: derive_paged_position_token_account(&page, &token_mint),
+            token_program: spl_token::ID,
+        };
+
+        Instruction {
+            program_id: JetMargin::id(),
+            data: ix_data::ClosePagedPosition.data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
+    /// Get instruction to release the debt recorded against the debt ceiling for an
+    /// isolated collateral position, after the debt in its isolation group is repaid
+    ///
+    /// # Params
+    ///
+    /// `token_mint` - The mint for the isolated collateral
+    /// `pages` - The number of position pages for the account
+    pub fn release_isolated_debt(&self, token_mint: Pubkey, pages: u8) -> Instruction {
+        let config = derive_token_config(&self.airspace, &token_mint);
+        let mut accounts = ix_account::ReleaseIsolatedDebt {
+            margin_account: self.address,
+            isolation_config: derive_token_isolation(&config),
+        }
+        .to_account_metas(None);
+
+        accounts.extend(
+            (0..pages)
+                .map(|page| AccountMeta::new(derive_position_page(&self.address, page), false)),
+        );
+
+        Instruction {
+            program_id: JetMargin::id(),
+            data: ix_data::ReleaseIsolatedDebt.data(),
+            accounts,
+        }
+    }
+
     /// Get instruction to refresh the config for a position
     ///
     /// # Params
@@ -321,17 +447,23 @@ impl MarginIxBuilder {
         let config = MarginConfigIxBuilder::new(self.airspace, self.payer(), None)
             .derive_token_config(position_token_mint);
 
-        let accounts = ix_account::RefreshPositionConfig {
+        let mut accounts = ix_account::RefreshPositionConfig {
             config,
             margin_account: self.address,
             permit: derive_margin_permit(&self.airspace, &self.authority()),
             refresher: self.authority(),
-        };
+        }
+        .to_account_metas(None);
+
+        accounts.push(AccountMeta::new_readonly(
+            derive_token_isolation(&config),
+            false,
+        ));
 
         Instruction {
             program_id: JetMargin::id(),
             data: ix_data::RefreshPositionConfig.data(),
-            accounts: accounts.to_account_metas(None),
+            accounts,
         }
     }
 
@@ -356,12 +488,45 @@ impl MarginIxBuilder {
         )
     }
 
+    /// Get instruction to refresh the price value for a deposit account, for a
+    /// token which is also priced by the oracles in a token oracle set
+    ///
+    /// # Params
+    ///
+    /// `price_oracle` - The price oracle for the token, stored in the token config
+    /// `fallback_oracles` - The price oracles stored in the oracle set, in order
+    pub fn refresh_deposit_position_with_fallbacks(
+        &self,
+        mint: Pubkey,
+        price_oracle: &Pubkey,
+        fallback_oracles: &[Pubkey],
+        refresh_balance: bool,
+    ) -> Instruction {
+        refresh_deposit_position_with_fallbacks(
+            &self.airspace,
+            self.address,
+            mint,
+            *price_oracle,
+            fallback_oracles,
+            refresh_balance,
+        )
+    }
+
     /// Get instruction to invoke through an adapter
     ///
     /// # Params
     ///
     /// `adapter_ix` - The instruction to be invoked
     pub fn adapter_invoke(&self, adapter_ix: Instruction) -> Instruction {
+        if self.authority() != self.owner {
+            return delegate_adapter_invoke(
+                self.airspace,
+                self.authority(),
+                self.address,
+                adapter_ix,
+            );
+        }
+
         invoke!(
             self.airspace,
             self.address,
@@ -370,6 +535,121 @@ impl MarginIxBuilder {
         )
     }
 
+    /// Invoke action as owner or delegate, recording the debt backed by isolated
+    /// collateral against the debt ceilings for the tokens afterwards
+    ///
+    /// # Params
+    ///
+    /// `adapter_ix` - The instruction to be invoked
+    /// `isolated_mints` - The mints for the isolated collateral backing the debt
+    pub fn adapter_invoke_isolated(
+        &self,
+        adapter_ix: Instruction,
+        isolated_mints: &[Pubkey],
+    ) -> Instruction {
+        let mut ix = self.adapter_invoke(adapter_ix);
+
+        ix.accounts.extend(isolated_mints.iter().map(|mint| {
+            let config = derive_token_config(&self.airspace, mint);
+            AccountMeta::new(derive_token_isolation(&config), false)
+        }));
+
+        ix
+    }
+
+    /// Get instruction to pre-authorize an adapter instruction, to be executed by any
+    /// keeper once the trigger holds
+    ///
+    /// # Params
+    ///
+    /// `seed` - A seed to distinguish the actions for the margin account
+    /// `trigger` - The condition under which the action may be executed
+    /// `adapter_ix` - The instruction to be invoked through the margin account
+    /// `tip` - The lamports paid to the keeper that executes the action
+    pub fn create_conditional_action(
+        &self,
+        seed: u16,
+        trigger: ActionTrigger,
+        adapter_ix: &Instruction,
+        tip: u64,
+    ) -> Instruction {
+        let accounts = ix_account::CreateConditionalAction {
+            owner: self.owner,
+            payer: self.payer(),
+            margin_account: self.address,
+            action: derive_conditional_action(&self.address, seed),
+            system_program: SYSTEM_PROGAM_ID,
+        };
+        let instruction_hash = ConditionalAction::hash_instruction(
+            &adapter_ix.program_id,
+            &adapter_ix.data,
+            adapter_ix
+                .accounts
+                .iter()
+                .map(|meta| (&meta.pubkey, meta.is_writable)),
+        );
+
+        Instruction {
+            program_id: JetMargin::id(),
+            data: ix_data::CreateConditionalAction {
+                seed,
+                trigger,
+                adapter_program: adapter_ix.program_id,
+                instruction_hash,
+                tip,
+            }
+            .data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
+    /// Get instruction to cancel a conditional action
+    ///
+    /// # Params
+    ///
+    /// `seed` - The seed of the action to cancel
+    pub fn cancel_conditional_action(&self, seed: u16) -> Instruction {
+        let accounts = ix_account::CancelConditionalAction {
+            owner: self.owner,
+            receiver: self.payer(),
+            margin_account: self.address,
+            action: derive_conditional_action(&self.address, seed),
+        };
+
+        Instruction {
+            program_id: JetMargin::id(),
+            data: ix_data::CancelConditionalAction.data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
+    /// Get instruction to authorize a delegate to invoke adapters for the account
+    ///
+    /// # Params
+    ///
+    /// `delegate` - The address being authorized
+    /// `update` - The permissions and limits for the delegate, or `None` to revoke it
+    pub fn configure_delegate(
+        &self,
+        delegate: Pubkey,
+        update: Option<MarginDelegateUpdate>,
+    ) -> Instruction {
+        let accounts = ix_account::ConfigureDelegate {
+            owner: self.owner,
+            payer: self.payer(),
+            margin_account: self.address,
+            delegate,
+            delegation: derive_margin_delegate(&self.address, &delegate),
+            system_program: SYSTEM_PROGAM_ID,
+        };
+
+        Instruction {
+            program_id: JetMargin::id(),
+            data: ix_data::ConfigureDelegate { update }.data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
     /// Get instruction to invoke through an adapter for permissionless accounting instructions
     ///
     /// # Params
@@ -408,6 +688,12 @@ impl MarginIxBuilder {
         )
     }
 
+    /// Begin a liquidation auction for this margin account, which may be done
+    /// by anyone while the account is unhealthy
+    pub fn auction_begin(&self) -> Instruction {
+        auction_begin(self.airspace, self.address, self.payer())
+    }
+
     /// Create a new token account registered as a position
     ///
     /// Can be used to deposit tokens into the custody of the margin account, without
@@ -567,22 +853,30 @@ pub fn create_deposit_position(
     mint: Pubkey,
 ) -> Instruction {
     let config_ix = MarginConfigIxBuilder::new(airspace, payer, None);
+    let config = config_ix.derive_token_config(&mint);
     let token_account = get_associated_token_address(&margin_account, &mint);
-    let accounts = ix_account::CreateDepositPosition {
+    let mut accounts = ix_account::CreateDepositPosition {
         margin_account,
         authority,
         payer,
         mint,
-        config: config_ix.derive_token_config(&mint),
+        config,
         token_account,
         associated_token_program: spl_associated_token_account::ID,
         token_program: spl_token::ID,
         system_program: system_program::ID,
         rent: Rent::id(),
-    };
+    }
+    .to_account_metas(None);
+
+    accounts.push(AccountMeta::new_readonly(
+        derive_token_isolation(&config),
+        false,
+    ));
+
     Instruction {
         program_id: jet_margin::ID,
-        accounts: accounts.to_account_metas(None),
+        accounts,
         data: ix_data::CreateDepositPosition.data(),
     }
 }
@@ -600,6 +894,7 @@ pub fn liquidate_begin(
         payer,
         liquidator,
         permit,
+        liquidation_config: derive_liquidation_config(&airspace),
         liquidation,
         system_program: system_program::ID,
     };
@@ -628,6 +923,69 @@ pub fn liquidate_end(
     }
 }
 
+/// Begin a permissionless liquidation auction for an unhealthy margin account
+pub fn auction_begin(airspace: Pubkey, margin_account: Pubkey, payer: Pubkey) -> Instruction {
+    let accounts = ix_account::AuctionBegin {
+        margin_account,
+        payer,
+        liquidation_config: derive_liquidation_config(&airspace),
+        auction: derive_liquidation_auction(&margin_account),
+        system_program: system_program::ID,
+    };
+    Instruction {
+        program_id: JetMargin::id(),
+        accounts: accounts.to_account_metas(None),
+        data: ix_data::AuctionBegin.data(),
+    }
+}
+
+/// Repay a claim in an auctioned margin account, and receive collateral for it
+///
+/// # Params
+///
+/// `claim_account` - The token account for the claim position being repaid
+/// `collateral_account` - The token account for the collateral position paid out
+/// `destination` - The bidder's token account to receive the collateral
+/// `repay_ix` - The adapter instruction repaying the claim, authorized by the bidder
+pub fn auction_bid(
+    airspace: Pubkey,
+    margin_account: Pubkey,
+    bidder: Pubkey,
+    claim_account: Pubkey,
+    collateral_account: Pubkey,
+    destination: Pubkey,
+    repay_ix: Instruction,
+) -> Instruction {
+    invoke!(
+        airspace,
+        margin_account,
+        repay_ix,
+        AuctionBid {
+            bidder,
+            auction: derive_liquidation_auction(&margin_account),
+            claim_account,
+            collateral_account,
+            destination,
+            token_program: spl_token::ID,
+        }
+    )
+}
+
+/// End the liquidation auction for a margin account, refunding rent to the
+/// address that began the auction
+pub fn auction_end(margin_account: Pubkey, payer: Pubkey) -> Instruction {
+    let accounts = ix_account::AuctionEnd {
+        margin_account,
+        payer,
+        auction: derive_liquidation_auction(&margin_account),
+    };
+    Instruction {
+        program_id: JetMargin::id(),
+        accounts: accounts.to_account_metas(None),
+        data: ix_data::AuctionEnd.data(),
+    }
+}
+
 /// Get instruction to refresh the price and balance value for a deposit account
 ///
 /// # Params
@@ -641,12 +999,50 @@ pub fn refresh_deposit_position(
     price_oracle: Pubkey,
     refresh_balance: bool,
 ) -> Instruction {
+    refresh_deposit_position_with_fallbacks(
+        airspace,
+        margin_account,
+        mint,
+        price_oracle,
+        &[],
+        refresh_balance,
+    )
+}
+
+/// Get instruction to refresh the price and balance value for a deposit account,
+/// for a token which is also priced by the oracles in a token oracle set
+///
+/// # Params
+///
+/// `price_oracle` - The price oracle for the token, stored in the token config
+/// `fallback_oracles` - The price oracles stored in the oracle set, in order. If
+///                      empty, the token is assumed to have no oracle set.
+pub fn refresh_deposit_position_with_fallbacks(
+    airspace: &Pubkey,
+    margin_account: Pubkey,
+    mint: Pubkey,
+    price_oracle: Pubkey,
+    fallback_oracles: &[Pubkey],
+    refresh_balance: bool,
+) -> Instruction {
+    let config = derive_token_config(airspace, &mint);
     let mut accounts = ix_account::RefreshDepositPosition {
-        config: derive_token_config(airspace, &mint),
+        config,
         price_oracle,
         margin_account,
     }
     .to_account_metas(None);
+    if !fallback_oracles.is_empty() {
+        accounts.push(AccountMeta::new_readonly(
+            derive_token_oracle_set(&config),
+            false,
+        ));
+        accounts.extend(
+            fallback_oracles
+                .iter()
+                .map(|oracle| AccountMeta::new_readonly(*oracle, false)),
+        );
+    }
     if refresh_balance {
         accounts.push(AccountMeta {
             pubkey: get_associated_token_address(&margin_account, &mint),
@@ -681,6 +1077,48 @@ pub fn adapter_invoke(
     )
 }
 
+/// Get instruction to invoke through an adapter, signed by a delegate of the owner
+///
+/// # Params
+///
+/// `adapter_ix` - The instruction to be invoked
+pub fn delegate_adapter_invoke(
+    airspace: Pubkey,
+    delegate: Pubkey,
+    margin_account: Pubkey,
+    adapter_ix: Instruction,
+) -> Instruction {
+    let mut ix = adapter_invoke(airspace, delegate, margin_account, adapter_ix);
+    ix.accounts.push(AccountMeta::new(
+        derive_margin_delegate(&margin_account, &delegate),
+        false,
+    ));
+
+    ix
+}
+
+/// Get instruction for a keeper to execute a conditional action through an adapter
+///
+/// # Params
+///
+/// `action` - The address of the conditional action
+/// `adapter_ix` - The instruction that was authorized by the action
+pub fn execute_conditional_action(
+    airspace: Pubkey,
+    keeper: Pubkey,
+    margin_account: Pubkey,
+    action: Pubkey,
+    adapter_ix: Instruction,
+) -> Instruction {
+    let mut ix = adapter_invoke(airspace, keeper, margin_account, adapter_ix);
+
+    // the keeper receives the tip
+    ix.accounts[0].is_writable = true;
+    ix.accounts.push(AccountMeta::new(action, false));
+
+    ix
+}
+
 /// Invoke action as liquidator
 pub fn liquidator_invoke(
     airspace: Pubkey,
@@ -706,6 +1144,9 @@ pub fn liquidator_invoke(
 
 /// Get instruction to invoke through an adapter for permissionless accounting instructions
 ///
+/// The delegation address of each signer of the adapter instruction is included, which the
+/// margin program requires for any signer that is not the owner of the account.
+///
 /// # Params
 ///
 /// `adapter_ix` - The instruction to be invoked
@@ -714,7 +1155,21 @@ pub fn accounting_invoke(
     margin_account: Pubkey,
     adapter_ix: Instruction,
 ) -> Instruction {
-    invoke!(airspace, margin_account, adapter_ix, AccountingInvoke)
+    let mut signers: Vec<Pubkey> = vec![];
+    for acc in adapter_ix.accounts.iter().filter(|acc| acc.is_signer) {
+        if acc.pubkey != margin_account && !signers.contains(&acc.pubkey) {
+            signers.push(acc.pubkey);
+        }
+    }
+
+    let mut ix = invoke!(airspace, margin_account, adapter_ix, AccountingInvoke);
+    ix.accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new(derive_margin_delegate(&margin_account, signer), false)),
+    );
+
+    ix
 }
 
 /// Utility for creating instructions that modify configuration for the margin program within
@@ -764,6 +1219,52 @@ impl MarginConfigIxBuilder {
         }
     }
 
+    /// Set the fallback oracles used to price a token, which must already be configured
+    pub fn configure_token_oracles(
+        &self,
+        token_mint: Pubkey,
+        update: Option<TokenOracleSetUpdate>,
+    ) -> Instruction {
+        let token_config = self.derive_token_config(&token_mint);
+        let accounts = ix_account::ConfigureTokenOracles {
+            authority: self.authority,
+            airspace: self.airspace,
+            payer: self.payer,
+            token_config,
+            oracle_set: derive_token_oracle_set(&token_config),
+            system_program: system_program::ID,
+        };
+
+        Instruction {
+            program_id: jet_margin::ID,
+            data: ix_data::ConfigureTokenOracles { update }.data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
+    /// Isolate a token, which must already be configured, or remove its isolation
+    pub fn configure_token_isolation(
+        &self,
+        token_mint: Pubkey,
+        update: Option<TokenIsolation>,
+    ) -> Instruction {
+        let token_config = self.derive_token_config(&token_mint);
+        let accounts = ix_account::ConfigureTokenIsolation {
+            authority: self.authority,
+            airspace: self.airspace,
+            payer: self.payer,
+            token_config,
+            isolation_config: derive_token_isolation(&token_config),
+            system_program: system_program::ID,
+        };
+
+        Instruction {
+            program_id: jet_margin::ID,
+            data: ix_data::ConfigureTokenIsolation { update }.data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
     /// Set the configuration for an adapter program
     pub fn configure_adapter(&self, program_id: Pubkey, is_adapter: bool) -> Instruction {
         let accounts = ix_account::ConfigureAdapter {
@@ -782,6 +1283,23 @@ impl MarginConfigIxBuilder {
         }
     }
 
+    /// Set the close factor and bonus schedule for liquidations in the airspace
+    pub fn configure_liquidation(&self, update: Option<LiquidationConfigUpdate>) -> Instruction {
+        let accounts = ix_account::ConfigureLiquidation {
+            authority: self.authority,
+            airspace: self.airspace,
+            payer: self.payer,
+            liquidation_config: derive_liquidation_config(&self.airspace),
+            system_program: system_program::ID,
+        };
+
+        Instruction {
+            program_id: jet_margin::ID,
+            data: ix_data::ConfigureLiquidation { update }.data(),
+            accounts: accounts.to_account_metas(None),
+        }
+    }
+
     /// Set the configuration for a liquidator
     pub fn configure_liquidator(&self, liquidator: Pubkey, is_liquidator: bool) -> Instruction {
         Instruction {
@@ -878,6 +1396,24 @@ pub fn derive_token_config(airspace: &Pubkey, token_mint: &Pubkey) -> Pubkey {
     .0
 }
 
+/// Derive address for the fallback oracles of a given token config
+pub fn derive_token_oracle_set(token_config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[TOKEN_ORACLE_SET_SEED, token_config.as_ref()],
+        &jet_margin::ID,
+    )
+    .0
+}
+
+/// Derive address for the isolation restrictions of a given token config
+pub fn derive_token_isolation(token_config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[TOKEN_ISOLATION_SEED, token_config.as_ref()],
+        &jet_margin::ID,
+    )
+    .0
+}
+
 /// Derive address for the config account for a given adapter
 pub fn derive_adapter_config(airspace: &Pubkey, adapter_program_id: &Pubkey) -> Pubkey {
     Pubkey::find_program_address(
@@ -891,6 +1427,55 @@ pub fn derive_adapter_config(airspace: &Pubkey, adapter_program_id: &Pubkey) ->
     .0
 }
 
+/// Derive address for the liquidation config account for an airspace
+pub fn derive_liquidation_config(airspace: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[LIQUIDATION_CONFIG_SEED, airspace.as_ref()],
+        &jet_margin::ID,
+    )
+    .0
+}
+
+/// Derive address for a conditional action of a margin account
+pub fn derive_conditional_action(margin_account: &Pubkey, seed: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            CONDITIONAL_ACTION_SEED,
+            margin_account.as_ref(),
+            seed.to_le_bytes().as_ref(),
+        ],
+        &jet_margin::ID,
+    )
+    .0
+}
+
+/// Derive address for the delegation of a margin account to another signer
+pub fn derive_margin_delegate(margin_account: &Pubkey, delegate: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            MARGIN_DELEGATE_SEED,
+            margin_account.as_ref(),
+            delegate.as_ref(),
+        ],
+        &jet_margin::ID,
+    )
+    .0
+}
+
+/// Derive address for a page storing positions for a margin account
+pub fn derive_position_page(margin_account: &Pubkey, page: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[POSITION_PAGE_SEED, margin_account.as_ref(), &[page]],
+        &jet_margin::ID,
+    )
+    .0
+}
+
+/// Derive address for the token account of a position stored in a page
+pub fn derive_paged_position_token_account(page: &Pubkey, token_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[page.as_ref(), token_mint.as_ref()], &jet_margin::ID).0
+}
+
 /// Derive address for the config account for a given liquidator
 #[deprecated(note = "use derive_margin_permit")]
 pub fn derive_liquidator_config(airspace: &Pubkey, liquidator: &Pubkey) -> Pubkey {
@@ -914,6 +1499,15 @@ pub fn derive_liquidation(margin_account: Pubkey, liquidator: Pubkey) -> Pubkey
     .0
 }
 
+/// Derive the address for the liquidation auction of a margin account
+pub fn derive_liquidation_auction(margin_account: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[LIQUIDATION_AUCTION_SEED, margin_account.as_ref()],
+        &jet_margin::id(),
+    )
+    .0
+}
+
 /// Generic invocation logic that can be applied to any margin account invoke
 /// instruction, such as adapter_invoke, liquidate_invoke, and accounting_invoke
 macro_rules! invoke {
@@ -17,7 +17,7 @@
 
 use anchor_lang::prelude::{Id, System, ToAccountMetas};
 use anchor_lang::InstructionData;
-use solana_sdk::instruction::Instruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::sysvar::{rent::Rent, SysvarId};
 
@@ -27,7 +27,7 @@ use jet_margin_pool::{accounts as ix_accounts, TokenChange};
 
 pub use jet_margin_pool::ID as MARGIN_POOL_PROGRAM;
 
-use crate::margin::MarginConfigIxBuilder;
+use crate::margin::{derive_token_config, derive_token_oracle_set, MarginConfigIxBuilder};
 
 /// Utility for creating instructions to interact with the margin
 /// pools program for a specific pool.
@@ -337,16 +337,56 @@ impl MarginPoolIxBuilder {
     ///
     /// # Params
     ///
+    /// `airspace` - The airspace of the margin account
     /// `margin_account` - The margin account with the deposit to be withdrawn
     /// `oracle` - The oracle account for this pool
-    pub fn margin_refresh_position(&self, margin_account: Pubkey, oracle: Pubkey) -> Instruction {
-        let accounts = ix_accounts::MarginRefreshPosition {
+    pub fn margin_refresh_position(
+        &self,
+        airspace: &Pubkey,
+        margin_account: Pubkey,
+        oracle: Pubkey,
+    ) -> Instruction {
+        self.margin_refresh_position_with_fallbacks(airspace, margin_account, oracle, &[])
+    }
+
+    /// Instruction to refresh the position on a margin account, for a token which is
+    /// also priced by the oracles in a token oracle set
+    ///
+    /// # Params
+    ///
+    /// `airspace` - The airspace of the margin account
+    /// `margin_account` - The margin account with the deposit to be withdrawn
+    /// `oracle` - The oracle account for this pool
+    /// `fallback_oracles` - The price oracles stored in the oracle set, in order. If
+    ///                      empty, the token is assumed to have no oracle set.
+    pub fn margin_refresh_position_with_fallbacks(
+        &self,
+        airspace: &Pubkey,
+        margin_account: Pubkey,
+        oracle: Pubkey,
+        fallback_oracles: &[Pubkey],
+    ) -> Instruction {
+        let token_config = derive_token_config(airspace, &self.token_mint);
+        let mut accounts = ix_accounts::MarginRefreshPosition {
             margin_account,
             margin_pool: self.address,
             token_price_oracle: oracle,
         }
         .to_account_metas(None);
 
+        accounts.push(AccountMeta::new_readonly(token_config, false));
+        if !fallback_oracles.is_empty() {
+            accounts.push(AccountMeta::new_readonly(
+                derive_token_oracle_set(&token_config),
+                false,
+            ));
+            accounts.extend(
+                fallback_oracles
+                    .iter()
+                    .map(|oracle| AccountMeta::new_readonly(*oracle, false)),
+            );
+        }
+
         Instruction {
             program_id: jet_margin_pool::ID,
             data: ix_data::MarginRefreshPosition {}.data(),
@@ -0,0 +1,453 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Estimate the result of a swap before submitting it, by simulating each venue's pricing
+//! off-chain from the current state of its accounts.
+//!
+//! The state is passed in by the caller, so that the same pricing can be used with accounts
+//! fetched from the network or read from a cache.
+
+use std::{cell::RefCell, mem::size_of};
+
+use anchor_spl::dex::serum_dex::fees::FeeTier;
+use orca_whirlpool::{
+    manager::swap_manager,
+    math::{MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64},
+    state::{TickArray, Whirlpool},
+    util::SwapTickSequence,
+};
+use saber_client::state::SwapInfo;
+use saber_math::curve::StableSwap;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::orca::{derive_tick_array, start_tick_index};
+
+/// The expected result of swapping through a single pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapQuote {
+    /// The token being sold
+    pub source_mint: Pubkey,
+
+    /// The token being bought
+    pub destination_mint: Pubkey,
+
+    /// The amount of the source token taken by the swap. This can be less than the
+    /// requested amount when the pool does not have enough liquidity to fill all of it.
+    pub amount_in: u64,
+
+    /// The amount of the destination token received, after fees
+    pub amount_out: u64,
+
+    /// The fees charged by the pool
+    pub fee: u64,
+
+    /// The token the fees are charged in
+    pub fee_mint: Pubkey,
+
+    /// How much worse the price received is than the pool's spot price, before fees,
+    /// as a fraction (0.01 = 1%)
+    pub price_impact: f64,
+}
+
+impl SwapQuote {
+    /// Create a quote, deriving the price impact from the spot price of the pool in
+    /// destination tokens per source token
+    pub fn new(
+        source_mint: Pubkey,
+        destination_mint: Pubkey,
+        amount_in: u64,
+        amount_out: u64,
+        fee: u64,
+        fee_mint: Pubkey,
+        spot_price: f64,
+    ) -> Self {
+        let (gross_in, gross_out) = if fee_mint == source_mint {
+            (amount_in.saturating_sub(fee), amount_out)
+        } else {
+            (amount_in, amount_out.saturating_add(fee))
+        };
+
+        let price_impact = if gross_in == 0 || spot_price <= 0.0 {
+            0.0
+        } else {
+            let price = gross_out as f64 / gross_in as f64;
+            (1.0 - price / spot_price).max(0.0)
+        };
+
+        Self {
+            source_mint,
+            destination_mint,
+            amount_in,
+            amount_out,
+            fee,
+            fee_mint,
+            price_impact,
+        }
+    }
+
+    /// The least output to accept when submitting the swap, allowing for the price to
+    /// move by `slippage_bps` before it executes
+    pub fn minimum_amount_out(&self, slippage_bps: u16) -> u64 {
+        minimum_amount_out(self.amount_out, slippage_bps)
+    }
+}
+
+/// The least of `amount_out` to accept, allowing for the price to move by `slippage_bps`
+pub fn minimum_amount_out(amount_out: u64, slippage_bps: u16) -> u64 {
+    let slippage_bps = slippage_bps.min(10_000) as u128;
+    (amount_out as u128 * (10_000 - slippage_bps) / 10_000) as u64
+}
+
+/// Estimate the result of selling `amount_in` of `source_mint` in a saber pool, given the
+/// balances of its reserves
+pub fn quote_saber(
+    info: &SwapInfo,
+    reserve_a: u64,
+    reserve_b: u64,
+    source_mint: &Pubkey,
+    amount_in: u64,
+    timestamp: i64,
+) -> Option<SwapQuote> {
+    let (destination_mint, source_balance, destination_balance) = match *source_mint {
+        mint if mint == info.token_a.mint => (info.token_b.mint, reserve_a, reserve_b),
+        mint if mint == info.token_b.mint => (info.token_a.mint, reserve_b, reserve_a),
+        _ => return None,
+    };
+
+    let curve = StableSwap::new(
+        info.initial_amp_factor,
+        info.target_amp_factor,
+        timestamp,
+        info.start_ramp_ts,
+        info.stop_ramp_ts,
+    );
+
+    let swap = |amount: u64| curve.swap_to(amount, source_balance, destination_balance, &info.fees);
+
+    // the curve has no closed form price, so it is measured with a trade too small to
+    // move it noticeably
+    let probe = (source_balance / 10_000).max(1);
+    let probe_result = swap(probe)?;
+    let spot_price = (probe_result.amount_swapped + probe_result.fee) as f64 / probe as f64;
+
+    let result = swap(amount_in)?;
+
+    Some(SwapQuote::new(
+        *source_mint,
+        destination_mint,
+        amount_in,
+        result.amount_swapped,
+        result.fee,
+        destination_mint,
+        spot_price,
+    ))
+}
+
+/// The tick arrays a swap in a whirlpool can cross from its current price, in the order
+/// they are crossed
+pub fn whirlpool_tick_arrays(address: &Pubkey, whirlpool: &Whirlpool, a_to_b: bool) -> [Pubkey; 3] {
+    let offsets: [i32; 3] = if a_to_b { [0, -1, -2] } else { [0, 1, 2] };
+
+    offsets.map(|offset| {
+        let tick_index =
+            start_tick_index(whirlpool.tick_current_index, whirlpool.tick_spacing, offset);
+        derive_tick_array(address, tick_index, whirlpool.tick_spacing)
+    })
+}
+
+/// Estimate the result of selling `amount_in` of `source_mint` in a whirlpool.
+///
+/// The `tick_arrays` are the ones returned by [whirlpool_tick_arrays] for the direction of
+/// the swap, up to the first that does not exist, as the swap can not cross a gap.
+pub fn quote_whirlpool(
+    whirlpool: &Whirlpool,
+    tick_arrays: Vec<TickArray>,
+    source_mint: &Pubkey,
+    amount_in: u64,
+    timestamp: u64,
+) -> Option<SwapQuote> {
+    let (destination_mint, a_to_b) = match *source_mint {
+        mint if mint == whirlpool.token_mint_a => (whirlpool.token_mint_b, true),
+        mint if mint == whirlpool.token_mint_b => (whirlpool.token_mint_a, false),
+        _ => return None,
+    };
+
+    let cells = tick_arrays
+        .into_iter()
+        .map(RefCell::new)
+        .collect::<Vec<_>>();
+    let mut tick_arrays = cells.iter().map(|array| array.borrow_mut());
+    let mut tick_sequence =
+        SwapTickSequence::new(tick_arrays.next()?, tick_arrays.next(), tick_arrays.next());
+
+    let sqrt_price_limit = if a_to_b {
+        MIN_SQRT_PRICE_X64
+    } else {
+        MAX_SQRT_PRICE_X64
+    };
+
+    let update = swap_manager::swap(
+        whirlpool,
+        &mut tick_sequence,
+        amount_in,
+        sqrt_price_limit,
+        true,
+        a_to_b,
+        timestamp,
+    )
+    .ok()?;
+
+    let (consumed, amount_out) = if a_to_b {
+        (update.amount_a, update.amount_b)
+    } else {
+        (update.amount_b, update.amount_a)
+    };
+
+    // the fee rate is in hundredths of a basis point
+    let fee = (consumed as u128 * whirlpool.fee_rate as u128 + 999_999) / 1_000_000;
+
+    let sqrt_price = whirlpool.sqrt_price as f64 / 2f64.powi(64);
+    let price_b_per_a = sqrt_price * sqrt_price;
+    let spot_price = if a_to_b {
+        price_b_per_a
+    } else {
+        1.0 / price_b_per_a
+    };
+
+    Some(SwapQuote::new(
+        *source_mint,
+        destination_mint,
+        consumed,
+        amount_out,
+        fee as u64,
+        *source_mint,
+        spot_price,
+    ))
+}
+
+/// Estimate the result of selling `amount_in` of `source_mint` in an openbook market.
+///
+/// The `orders` are the ones on the side of the book the swap takes from, as returned by
+/// [read_book_side]: the bids when selling the base token, or the asks when buying it.
+pub fn quote_openbook(
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    base_lot_size: u64,
+    quote_lot_size: u64,
+    mut orders: Vec<(u64, u64)>,
+    source_mint: &Pubkey,
+    amount_in: u64,
+) -> Option<SwapQuote> {
+    let selling_base = match *source_mint {
+        mint if mint == *base_mint => true,
+        mint if mint == *quote_mint => false,
+        _ => return None,
+    };
+
+    if base_lot_size == 0 || quote_lot_size == 0 {
+        return None;
+    }
+
+    // levels without a price or quantity can't be traded against
+    orders.retain(|&(price, quantity)| price > 0 && quantity > 0);
+
+    if selling_base {
+        orders.sort_by(|a, b| b.0.cmp(&a.0));
+    } else {
+        orders.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+
+    let &(best_price, _) = orders.first()?;
+
+    let lot_value = |price: u64| price.checked_mul(quote_lot_size);
+    let taker_fee = |quote_amount: u64| FeeTier::Base.taker_fee(quote_amount);
+
+    if selling_base {
+        // base is sold in whole lots, and the fee is taken from the quote received
+        let mut remaining = amount_in / base_lot_size;
+        let mut proceeds = 0u64;
+
+        for (price, quantity) in orders {
+            let lots = remaining.min(quantity);
+            proceeds = proceeds.checked_add(lots.checked_mul(lot_value(price)?)?)?;
+            remaining -= lots;
+
+            if remaining == 0 {
+                break;
+            }
+        }
+
+        let sold = amount_in / base_lot_size - remaining;
+        let fee = taker_fee(proceeds);
+
+        Some(SwapQuote::new(
+            *base_mint,
+            *quote_mint,
+            sold * base_lot_size,
+            proceeds.checked_sub(fee)?,
+            fee,
+            *quote_mint,
+            lot_value(best_price)? as f64 / base_lot_size as f64,
+        ))
+    } else {
+        // the fee is charged on top of the quote spent, so it is reserved up front
+        let mut budget = amount_in.checked_sub(taker_fee(amount_in))?;
+        let mut spent = 0u64;
+        let mut bought = 0u64;
+
+        for (price, quantity) in orders {
+            let value = lot_value(price)?;
+            let lots = quantity.min(budget / value);
+            let cost = lots.checked_mul(value)?;
+
+            spent = spent.checked_add(cost)?;
+            budget -= cost;
+            bought = bought.checked_add(lots)?;
+
+            if lots < quantity {
+                break;
+            }
+        }
+
+        let fee = taker_fee(spent);
+
+        Some(SwapQuote::new(
+            *quote_mint,
+            *base_mint,
+            spent.checked_add(fee)?,
+            bought.checked_mul(base_lot_size)?,
+            fee,
+            *quote_mint,
+            base_lot_size as f64 / lot_value(best_price)? as f64,
+        ))
+    }
+}
+
+/// Read a whirlpool tick array from its account data
+pub fn read_tick_array(data: &[u8]) -> Option<TickArray> {
+    let data = data.get(8..8 + size_of::<TickArray>())?;
+
+    Some(bytemuck::pod_read_unaligned(data))
+}
+
+// Layout of the orderbook accounts in the openbook dex
+const BOOK_HEADER_LEN: usize = 5 + 8 + 32;
+const BOOK_PADDING_LEN: usize = 7;
+const BOOK_NODE_LEN: usize = 72;
+const BOOK_LEAF_TAG: u32 = 2;
+
+/// Read the (price, quantity) of the orders on one side of an openbook market, with prices
+/// in quote lots per base lot and quantities in base lots
+pub fn read_book_side(data: &[u8]) -> Option<Vec<(u64, u64)>> {
+    if data.len() < BOOK_HEADER_LEN + BOOK_PADDING_LEN {
+        return None;
+    }
+
+    let nodes = &data[BOOK_HEADER_LEN..data.len() - BOOK_PADDING_LEN];
+
+    Some(
+        nodes
+            .chunks_exact(BOOK_NODE_LEN)
+            .filter(|node| u32::from_le_bytes(node[0..4].try_into().unwrap()) == BOOK_LEAF_TAG)
+            .map(|leaf| {
+                let key = u128::from_le_bytes(leaf[8..24].try_into().unwrap());
+                let quantity = u64::from_le_bytes(leaf[56..64].try_into().unwrap());
+
+                ((key >> 64) as u64, quantity)
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn price_impact_excludes_fees() {
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+
+        // fee taken from the input at the spot price has no impact
+        let quote = SwapQuote::new(source, destination, 1_000, 1_980, 10, source, 2.0);
+        assert_eq!(quote.price_impact, 0.0);
+
+        // fee taken from the output at the spot price has no impact
+        let quote = SwapQuote::new(source, destination, 1_000, 1_990, 10, destination, 2.0);
+        assert_eq!(quote.price_impact, 0.0);
+
+        let quote = SwapQuote::new(source, destination, 1_000, 1_500, 0, source, 2.0);
+        assert!((quote.price_impact - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn minimum_amount_out_applies_slippage() {
+        assert_eq!(minimum_amount_out(10_000, 50), 9_950);
+        assert_eq!(minimum_amount_out(10_000, 0), 10_000);
+        assert_eq!(minimum_amount_out(10_000, u16::MAX), 0);
+    }
+
+    #[test]
+    fn read_book_side_finds_leaves() {
+        let mut data = vec![0u8; BOOK_HEADER_LEN + 3 * BOOK_NODE_LEN + BOOK_PADDING_LEN];
+
+        for (i, (tag, price, quantity)) in [(BOOK_LEAF_TAG, 5u64, 7u64), (1, 0, 0), (2, 9, 3)]
+            .into_iter()
+            .enumerate()
+        {
+            let node = &mut data[BOOK_HEADER_LEN + i * BOOK_NODE_LEN..][..BOOK_NODE_LEN];
+            node[0..4].copy_from_slice(&tag.to_le_bytes());
+            node[8..24].copy_from_slice(&(((price as u128) << 64) | 42).to_le_bytes());
+            node[56..64].copy_from_slice(&quantity.to_le_bytes());
+        }
+
+        assert_eq!(read_book_side(&data).unwrap(), vec![(5, 7), (9, 3)]);
+    }
+
+    #[test]
+    fn openbook_quote_walks_the_book() {
+        let base = Pubkey::new_unique();
+        let quote = Pubkey::new_unique();
+        let bids = vec![(9, 2), (10, 1)];
+
+        // 2 lots are sold at the best bid of 10 and the next one of 9
+        let result = quote_openbook(&base, &quote, 100, 10, bids, &base, 250).unwrap();
+
+        assert_eq!(result.amount_in, 200);
+        assert_eq!(result.amount_out + result.fee, 190);
+        assert_eq!(result.destination_mint, quote);
+        assert!(quote_openbook(&base, &quote, 100, 10, vec![], &base, 250).is_none());
+    }
+
+    #[test]
+    fn openbook_quote_rejects_bad_values() {
+        let base = Pubkey::new_unique();
+        let quote = Pubkey::new_unique();
+
+        // zero priced levels are skipped rather than dividing by zero
+        let asks = vec![(0, 5), (10, 1)];
+        let result = quote_openbook(&base, &quote, 100, 10, asks, &quote, 1_000).unwrap();
+        assert_eq!(result.amount_out, 100);
+
+        assert!(quote_openbook(&base, &quote, 100, 0, vec![(10, 1)], &quote, 1_000).is_none());
+        assert!(quote_openbook(&base, &quote, 0, 10, vec![(10, 1)], &base, 1_000).is_none());
+
+        // proceeds that don't fit in a u64 can't be quoted
+        let bids = vec![(u64::MAX, u64::MAX)];
+        assert!(quote_openbook(&base, &quote, 1, 10, bids, &base, u64::MAX).is_none());
+    }
+}
@@ -31,7 +31,7 @@ use jet_test_service::{
     seeds::{
         OPENBOOK_MARKET, OPENBOOK_MARKET_INFO, OPENBOOK_OPEN_ORDERS, ORCA_WHIRLPOOL_CONFIG,
         SWAP_POOL_FEES, SWAP_POOL_INFO, SWAP_POOL_MINT, SWAP_POOL_STATE, SWAP_POOL_TOKENS,
-        TOKEN_INFO, TOKEN_MINT, TOKEN_PYTH_PRICE, TOKEN_PYTH_PRODUCT,
+        TOKEN_INFO, TOKEN_MINT, TOKEN_PUSH_PRICE, TOKEN_PYTH_PRICE, TOKEN_PYTH_PRODUCT,
     },
     OpenBookMarketCancelOrdersParams, OpenBookMarketCreateParams, OpenBookMarketMakeParams,
     SaberSwapPoolCreateParams,
@@ -162,6 +162,50 @@ pub fn token_update_pyth_price(
     }
 }
 
+/// Create a push oracle for a token
+pub fn token_create_push_oracle(
+    payer: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    expo: i32,
+) -> Instruction {
+    let accounts = jet_test_service::accounts::TokenCreatePushOracle {
+        payer: *payer,
+        oracle_authority: *authority,
+        info: derive_token_info(mint),
+        push_price: derive_push_price(mint),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        program_id: jet_test_service::ID,
+        accounts,
+        data: jet_test_service::instruction::TokenCreatePushOracle { expo }.data(),
+    }
+}
+
+/// Update the push oracle price for a token
+pub fn token_update_push_price(
+    authority: &Pubkey,
+    mint: &Pubkey,
+    price: i64,
+    conf: i64,
+) -> Instruction {
+    let accounts = jet_test_service::accounts::TokenUpdatePushPrice {
+        oracle_authority: *authority,
+        info: derive_token_info(mint),
+        push_price: derive_push_price(mint),
+    }
+    .to_account_metas(None);
+
+    Instruction {
+        program_id: jet_test_service::ID,
+        accounts,
+        data: jet_test_service::instruction::TokenUpdatePushPrice { price, conf }.data(),
+    }
+}
+
 /// Create the config for whirlpools
 pub fn orca_whirlpool_create_config(
     payer: &Pubkey,
@@ -572,6 +616,11 @@ pub fn derive_pyth_price(mint: &Pubkey) -> Pubkey {
     Pubkey::find_program_address(&[TOKEN_PYTH_PRICE, mint.as_ref()], &jet_test_service::ID).0
 }
 
+/// Get the push oracle price account
+pub fn derive_push_price(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[TOKEN_PUSH_PRICE, mint.as_ref()], &jet_test_service::ID).0
+}
+
 /// Get the pyth price account
 pub fn derive_ticket_mint(market: &Pubkey) -> Pubkey {
     Pubkey::find_program_address(&[seeds::TICKET_MINT, market.as_ref()], &jet_fixed_term::ID).0
@@ -1,28 +1,57 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use anchor_lang::AccountDeserialize;
+use anchor_lang::{AccountDeserialize, Discriminator};
 use futures::future::join_all;
 use jet_fixed_term::{
-    margin::state::{MarginUser, TermLoan, TermLoanFlags},
+    control::state::Market,
+    margin::state::{AutoRollPolicy, MarginUser, TermLoan, TermLoanFlags},
+    orderbook::state::slab_best_prices,
     tickets::state::{TermDeposit, TermDepositFlags},
 };
 use jet_instructions::{
     fixed_term::{derive, FixedTermIxBuilder},
     margin::accounting_invoke,
+    margin_pool::MarginPoolIxBuilder,
 };
+use jet_program_common::interest_pricing::{InterestPricer, PricerImpl};
 use jet_simulation::SolanaRpcClient;
 use jet_solana_client::rpc::AccountFilter;
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
 use thiserror::Error;
 
 use crate::solana::transaction::SendTransactionBuilder;
 
 type KeyAccount<T> = (Pubkey, T);
 
+/// Offset of the market in a `MarginUser` account: discriminator, version, margin account
+const MARGIN_USER_MARKET_OFFSET: usize = 8 + 1 + 32;
+
+/// Where a user's matured deposits are rolled to
+struct LendTarget<'a> {
+    ix: &'a FixedTermIxBuilder,
+    next_deposit_seqno: u64,
+    /// whether the orderbook prices allow the deposits to be rolled under the user's policy
+    fillable: bool,
+}
+
+/// The yearly interest rate in bps at the midpoint of the best bid and ask
+fn mid_rate(prices: (Option<u64>, Option<u64>), tenor: u64) -> Option<u64> {
+    match prices {
+        (Some(bid), Some(ask)) => {
+            let price = bid / 2 + ask / 2 + (bid % 2 + ask % 2) / 2;
+            Some(PricerImpl::price_fp32_to_bps_yearly_interest(price, tenor))
+        }
+        _ => None,
+    }
+}
+
 pub struct AutoRollServicer {
     ix: FixedTermIxBuilder,
     rpc: Arc<dyn SolanaRpcClient>,
     min_order_size: u64,
+    /// Builders for the markets that lend policies may roll deposits into
+    markets: HashMap<Pubkey, FixedTermIxBuilder>,
 }
 
 impl AutoRollServicer {
@@ -31,9 +60,18 @@ impl AutoRollServicer {
             ix,
             rpc,
             min_order_size,
+            markets: HashMap::new(),
         }
     }
 
+    /// Allow deposits to be rolled into any of the given markets, when a user's lend policy
+    /// targets them
+    pub fn with_markets(mut self, markets: impl IntoIterator<Item = FixedTermIxBuilder>) -> Self {
+        self.markets
+            .extend(markets.into_iter().map(|ix| (ix.market(), ix)));
+        self
+    }
+
     pub async fn service_all(&self) {
         let users = match self.fetch_users().await {
             Ok(u) => u.into_iter().map(|u| self.service_user(u)),
@@ -111,6 +149,15 @@ impl AutoRollServicer {
             );
         }
 
+        let mut policy = user.1.borrow_roll_policy;
+        let rate_in_band = match &policy {
+            Some(p) => {
+                let (market, prices) = self.market_prices(&self.ix).await?;
+                p.rate_in_band(mid_rate(prices, market.borrow_tenor))
+            }
+            None => true,
+        };
+
         let mut next_debt_seqno = user.1.debt().next_new_loan_seqno();
         let mut next_unpaid_loan_seqno =
             user.1.debt().next_term_loan_to_repay().unwrap_or_default() + 1;
@@ -124,6 +171,17 @@ impl AutoRollServicer {
             if loan.strike_timestamp + user.1.borrow_roll_config.as_ref().unwrap().roll_tenor as i64
                 >= current_time
             {
+                if let Some(p) = policy.as_mut() {
+                    if !p.has_rolls_remaining() || !rate_in_band {
+                        tracing::debug!(
+                            "borrow policy of user [{}] does not allow rolling loan [{}]",
+                            user.0,
+                            loan_key
+                        );
+                        continue;
+                    }
+                    p.record_roll();
+                }
                 tracing::debug!("attempting to auto-borrow for loan [{}]", loan_key,);
                 let auto_borrow = self.ix.auto_roll_borrow_order(
                     user.1.margin_account,
@@ -167,42 +225,172 @@ impl AutoRollServicer {
                 current_time
             );
         }
-        let mut next_deposit_seqno = user.1.assets().next_new_deposit_seqno();
-        for (deposit_key, deposit) in deposits {
-            if !deposit.flags.contains(TermDepositFlags::AUTO_ROLL) {
-                continue;
-            }
-            if deposit.amount < self.min_order_size {
-                continue;
-            }
-            if deposit.matures_at <= current_time {
+        let matured = deposits
+            .into_iter()
+            .filter(|(_, d)| {
+                d.flags.contains(TermDepositFlags::AUTO_ROLL)
+                    && d.amount >= self.min_order_size
+                    && d.matures_at <= current_time
+            })
+            .collect::<Vec<_>>();
+        if matured.is_empty() {
+            return Ok(());
+        }
+
+        let mut policy = user.1.lend_roll_policy;
+        let target = self.lend_target(user, policy.as_ref()).await?;
+        let mut next_deposit_seqno = target.next_deposit_seqno;
+        for (deposit_key, deposit) in matured {
+            let can_roll = match policy.as_mut() {
+                Some(p) if !p.has_rolls_remaining() || !target.fillable => false,
+                Some(p) => {
+                    p.record_roll();
+                    true
+                }
+                None => true,
+            };
+
+            let ix = if can_roll {
                 tracing::debug!("attempting to auto-lend for deposit [{}]", deposit_key);
-                let auto_lend = self.ix.auto_roll_lend_order(
+                let ix = if target.ix.market() == self.ix.market() {
+                    self.ix.auto_roll_lend_order(
+                        user.1.margin_account,
+                        deposit_key,
+                        deposit.payer,
+                        next_deposit_seqno,
+                    )
+                } else {
+                    target.ix.auto_roll_lend_order_to_market(
+                        self.ix.market(),
+                        user.1.margin_account,
+                        deposit_key,
+                        deposit.payer,
+                        next_deposit_seqno,
+                    )
+                };
+                next_deposit_seqno += 1;
+                ix
+            } else if policy.map_or(false, |p| p.pool_fallback) {
+                tracing::debug!(
+                    "attempting to deposit [{}] into the margin pool",
+                    deposit_key
+                );
+                let pool = MarginPoolIxBuilder::new(self.ix.token_mint());
+                self.ix.auto_roll_lend_to_pool(
                     user.1.margin_account,
                     deposit_key,
                     deposit.payer,
-                    next_deposit_seqno,
+                    target.ix,
+                    get_associated_token_address(&user.1.margin_account, &pool.deposit_note_mint),
+                )
+            } else {
+                tracing::debug!(
+                    "lend policy of user [{}] does not allow rolling deposit [{}]",
+                    user.0,
+                    deposit_key
                 );
-                ixns.push(accounting_invoke(
-                    self.ix.airspace(),
-                    user.1.margin_account,
-                    auto_lend,
-                ));
-                next_deposit_seqno += 1;
-            }
+                continue;
+            };
+            ixns.push(accounting_invoke(
+                self.ix.airspace(),
+                user.1.margin_account,
+                ix,
+            ));
         }
         Ok(())
     }
 
+    /// Find the market a user's matured deposits should be rolled into, and whether the
+    /// roll is allowed by the user's policy at current orderbook prices
+    async fn lend_target<'a>(
+        &'a self,
+        user: &KeyAccount<MarginUser>,
+        policy: Option<&AutoRollPolicy>,
+    ) -> Result<LendTarget<'a>> {
+        let market = self.ix.market();
+        let target = policy.map_or(market, |p| p.target_market(&market));
+        let ix = if target == market {
+            &self.ix
+        } else {
+            self.markets
+                .get(&target)
+                .ok_or(ServicerError::MissingIxBuilder(target))?
+        };
+
+        let next_deposit_seqno = if target == market {
+            user.1.assets().next_new_deposit_seqno()
+        } else {
+            let target_user = derive::margin_user(&target, &user.1.margin_account);
+            match self
+                .load_accounts::<MarginUser>(&[target_user])
+                .await?
+                .pop()
+            {
+                Some((_, u)) => u.assets().next_new_deposit_seqno(),
+                None => return Err(ServicerError::MissingTargetUser(target_user)),
+            }
+        };
+
+        let fillable = match policy {
+            Some(p) => {
+                let (market, (bid, ask)) = self.market_prices(ix).await?;
+                let limit_in_reach = match (&user.1.lend_roll_config, ask) {
+                    (Some(config), Some(ask)) => ask <= config.limit_price,
+                    _ => false,
+                };
+                p.rate_in_band(mid_rate((bid, ask), market.lend_tenor)) && limit_in_reach
+            }
+            None => true,
+        };
+
+        Ok(LendTarget {
+            ix,
+            next_deposit_seqno,
+            fillable,
+        })
+    }
+
+    /// Load a market, along with the prices of its best bid and ask
+    async fn market_prices(
+        &self,
+        ix: &FixedTermIxBuilder,
+    ) -> Result<(Market, (Option<u64>, Option<u64>))> {
+        let orderbook = ix.orderbook_mut();
+        let mut accounts = self
+            .rpc
+            .get_multiple_accounts(&[orderbook.market, orderbook.bids, orderbook.asks])
+            .await?
+            .into_iter();
+        let (market, bids, asks) = match (accounts.next(), accounts.next(), accounts.next()) {
+            (Some(Some(m)), Some(Some(b)), Some(Some(a))) => (m, b, a),
+            _ => return Err(ServicerError::MissingMarket(orderbook.market)),
+        };
+        let market = Market::try_deserialize(&mut market.data.as_ref())?;
+
+        let mut bids_buf = bids.data;
+        let mut asks_buf = asks.data;
+        let prices = slab_best_prices(&mut bids_buf, &mut asks_buf)
+            .map_err(|_| ServicerError::MissingMarket(orderbook.market))?;
+
+        Ok((market, prices))
+    }
+
     async fn fetch_users(&self) -> Result<Vec<KeyAccount<MarginUser>>> {
         tracing::trace!("fetching users from market [{}]", self.ix.market());
         let users = self
             .rpc
             .get_program_accounts(
                 &jet_fixed_term::ID,
-                vec![AccountFilter::DataSize(
-                    8 + std::mem::size_of::<MarginUser>(),
-                )],
+                vec![
+                    AccountFilter::Memcmp {
+                        offset: 0,
+                        bytes: MarginUser::DISCRIMINATOR.to_vec(),
+                    },
+                    AccountFilter::Memcmp {
+                        offset: MARGIN_USER_MARKET_OFFSET,
+                        bytes: self.ix.market().to_bytes().to_vec(),
+                    },
+                ],
             )
             .await?
             .into_iter()
@@ -310,5 +498,11 @@ pub enum ServicerError {
 
     #[error("failed to fetch the instruction builder for market: {0}")]
     MissingIxBuilder(Pubkey),
+
+    #[error("failed to load the market or its orderbook: {0}")]
+    MissingMarket(Pubkey),
+
+    #[error("the margin user targeted by a lend policy does not exist: {0}")]
+    MissingTargetUser(Pubkey),
 }
 type Result<T> = std::result::Result<T, ServicerError>;
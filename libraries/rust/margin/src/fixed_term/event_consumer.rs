@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use agnostic_orderbook::state::{
@@ -18,10 +18,11 @@ use solana_sdk::{
 use thiserror::Error;
 
 use jet_fixed_term::{
-    control::state::Market,
+    control::state::{Market, TicketPriceSource},
     margin::state::MarginUser,
     orderbook::state::{
-        CallbackFlags, CallbackInfo, MarginCallbackInfo, SignerCallbackInfo, UserCallbackInfo,
+        find_expired_orders, CallbackFlags, CallbackInfo, MarginCallbackInfo, SignerCallbackInfo,
+        UserCallbackInfo,
     },
 };
 use jet_simulation::solana_rpc_api::SolanaRpcClient;
@@ -33,6 +34,13 @@ use super::{FixedTermIxBuilder, OwnedEventQueue};
 
 const MAX_EVENTS_PER_TX: usize = 8;
 
+/// The maximum number of expired orders removed from a book in one transaction
+const MAX_EXPIRED_ORDERS_PER_TX: usize = 8;
+
+/// How often the orderbook rate is sampled for markets that price tickets with it, while there
+/// are no events to consume
+const TWAP_SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Error, Debug)]
 pub enum EventConsumerError {
     #[error("rpc error: {0}")]
@@ -113,6 +121,7 @@ impl EventConsumer {
                 users: HashMap::new(),
                 builder,
                 margin_accounts_to_settle: margin_account_settlement_sink,
+                twap_sampled_at: None,
             })),
         );
     }
@@ -123,6 +132,7 @@ impl EventConsumer {
             if let Err(e) = self.sync_and_consume_all(targets).await {
                 tracing::error!("Error while consuming events: {e:?}");
             }
+            self.maintain().await;
             tokio::time::sleep(delay).await;
         }
     }
@@ -266,6 +276,22 @@ impl EventConsumer {
         Ok(())
     }
 
+    /// Run the crank instructions that keep the markets current while there are no events to
+    /// consume. Logs errors.
+    pub async fn maintain(&self) {
+        let tasks = self
+            .markets()
+            .map(|(address, state)| async move {
+                let mut state = state.lock().await;
+                if let Err(e) = state.maintain(&*self.rpc).await {
+                    tracing::error!(market = ?address, "failed maintaining market because: {e}");
+                }
+            })
+            .collect::<Vec<_>>();
+
+        futures::future::join_all(tasks).await;
+    }
+
     /// Count the events waiting to be consumed in a market
     pub async fn pending_events(&self, market: &Pubkey) -> Result<usize, EventConsumerError> {
         match self.get_market(market) {
@@ -299,6 +325,8 @@ struct MarketState {
     builder: FixedTermIxBuilder,
     /// send margin accounts here once they need to be settled
     margin_accounts_to_settle: Option<AsyncNoDupeQueue<Pubkey>>,
+    /// when the orderbook rate was last included in the market's average
+    twap_sampled_at: Option<Instant>,
 }
 
 impl MarketState {
@@ -410,6 +438,7 @@ impl MarketState {
         self.pop_events(consume_params.len())?;
 
         rpc.send_and_confirm_transaction(&consume_tx).await?;
+        self.twap_sampled_at = Some(Instant::now());
         if let Some(sink) = self.margin_accounts_to_settle.as_ref() {
             sink.push_many(margin_accounts_to_settle).await;
         }
@@ -417,6 +446,74 @@ impl MarketState {
         Ok(())
     }
 
+    #[instrument(skip(self, rpc), fields(market = %self.market_address))]
+    async fn maintain(&mut self, rpc: &dyn SolanaRpcClient) -> Result<(), EventConsumerError> {
+        self.expire_orders(rpc).await?;
+        self.sample_ticket_twap(rpc).await
+    }
+
+    /// Remove orders that have expired from the book. Their unfilled amounts are returned to
+    /// their owners once the resulting events are consumed.
+    async fn expire_orders(&mut self, rpc: &dyn SolanaRpcClient) -> Result<(), EventConsumerError> {
+        let mut accounts = rpc
+            .get_multiple_accounts(&[self.market.bids, self.market.asks])
+            .await?
+            .into_iter();
+        let (Some(Some(mut bids)), Some(Some(mut asks))) = (accounts.next(), accounts.next()) else {
+            return Err(EventConsumerError::InvalidMarketAccount(self.market_address));
+        };
+
+        let timestamp = rpc.get_clock().await?.unix_timestamp;
+        let expired = find_expired_orders(
+            &mut bids.data,
+            &mut asks.data,
+            timestamp,
+            MAX_EXPIRED_ORDERS_PER_TX,
+        )
+        .map_err(|e| EventConsumerError::Program(e.to_string()))?;
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        let count = expired.len();
+        let tx = Transaction::new_signed_with_payer(
+            &[self.builder.expire_orders(expired)],
+            Some(&rpc.payer().pubkey()),
+            &[rpc.payer()],
+            rpc.get_latest_blockhash().await?,
+        );
+        rpc.send_and_confirm_transaction(&tx).await?;
+
+        tracing::debug!("expired {count} orders");
+        Ok(())
+    }
+
+    /// Keep the average orderbook rate current while there are no events to consume
+    async fn sample_ticket_twap(
+        &mut self,
+        rpc: &dyn SolanaRpcClient,
+    ) -> Result<(), EventConsumerError> {
+        if self.market.ticket_price_source() != TicketPriceSource::Orderbook
+            || self
+                .twap_sampled_at
+                .map_or(false, |at| at.elapsed() < TWAP_SAMPLE_INTERVAL)
+        {
+            return Ok(());
+        }
+
+        let tx = Transaction::new_signed_with_payer(
+            &[self.builder.sample_ticket_twap()],
+            Some(&rpc.payer().pubkey()),
+            &[rpc.payer()],
+            rpc.get_latest_blockhash().await?,
+        );
+        rpc.send_and_confirm_transaction(&tx).await?;
+        self.twap_sampled_at = Some(Instant::now());
+
+        tracing::trace!("sampled orderbook rate");
+        Ok(())
+    }
+
     fn margin_fill_accounts(
         &mut self,
         seed: &mut Vec<u8>,
@@ -434,7 +531,7 @@ impl MarketState {
                 .users
                 .get_mut(&info.margin_user)
                 .ok_or(EventConsumerError::InvalidUserKey(info.margin_user))?
-                .maker_fill_lend_order(true, 1)
+                .maker_fill_lend_order(true, 1, 1)
                 .map_err(|e| EventConsumerError::Program(e.to_string()))?
                 .to_le_bytes()
                 .to_vec();
@@ -5,6 +5,7 @@ pub mod error;
 pub mod event_consumer;
 mod ix_builder;
 pub mod settler;
+pub mod yield_curve;
 
 use futures::future::{join_all, try_join_all};
 pub use ix_builder::*;
@@ -63,6 +64,10 @@ impl Crank {
         let consumer = EventConsumer::new(rpc.clone());
         let mut settlers = vec![];
         let mut servicers = vec![];
+        let builders = markets
+            .iter()
+            .map(|market| FixedTermIxBuilder::new_from_state(rpc.payer().pubkey(), market))
+            .collect::<Vec<_>>();
         for market in markets {
             let margin_accounts = AsyncNoDupeQueue::new();
             let ix = FixedTermIxBuilder::new_from_state(rpc.payer().pubkey(), &market);
@@ -77,11 +82,10 @@ impl Crank {
             let orderbook_state =
                 MarketState::from_buffer(&mut orderbook_state_account.data, AccountTag::Market)
                     .unwrap();
-            servicers.push(AutoRollServicer::new(
-                rpc.clone(),
-                ix,
-                orderbook_state.min_base_order_size,
-            ));
+            servicers.push(
+                AutoRollServicer::new(rpc.clone(), ix, orderbook_state.min_base_order_size)
+                    .with_markets(builders.iter().cloned()),
+            );
         }
 
         Ok(Self {
@@ -0,0 +1,282 @@
+//! Term structure of interest rates across the fixed term markets of a single token.
+//!
+//! Each fixed term market trades a single tenor. This module combines the
+//! orderbooks of every market for a token in an airspace into one curve of
+//! yearly rates, which can be interpolated between the listed tenors and used
+//! to derive forward rates. Tickets pay out once at maturity, so the rate
+//! quoted by each market is already a zero rate for its tenor.
+//!
+//! Rates are yearly rates expressed as fractions (0.05 is 5%), converted from
+//! prices with the same [PricerImpl] used by the fixed term orderbook.
+
+use std::sync::Arc;
+
+use jet_fixed_term::{control::state::Market, orderbook::state::slab_best_prices};
+use jet_program_common::interest_pricing::{fp32_to_f64, InterestPricer, PricerImpl};
+use jet_simulation::SolanaRpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use super::find_markets;
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+/// Which rate of a quote to read from the curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveSide {
+    /// The rate implied by the best bid, paid by a borrower selling tickets
+    Bid,
+    /// The rate implied by the best ask, earned by a lender buying tickets
+    Ask,
+    /// The rate implied by the midpoint of the best bid and ask prices
+    Mid,
+}
+
+/// The rates quoted by the orderbook of a single market
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TenorQuote {
+    /// The address of the `Market`
+    pub market: Pubkey,
+    /// Seconds until a ticket bought now matures
+    pub tenor: u64,
+    /// Rate implied by the best bid, if there are any bids
+    pub bid: Option<f64>,
+    /// Rate implied by the best ask, if there are any asks
+    pub ask: Option<f64>,
+    /// Rate implied by the midpoint price, if both sides have orders
+    pub mid: Option<f64>,
+}
+
+impl TenorQuote {
+    /// Build a quote from the best prices on each side of a market's orderbook
+    pub fn from_prices(
+        market: Pubkey,
+        tenor: u64,
+        best_bid: Option<u64>,
+        best_ask: Option<u64>,
+    ) -> Self {
+        let bid = best_bid.map(fp32_to_f64);
+        let ask = best_ask.map(fp32_to_f64);
+        let mid = match (bid, ask) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+            _ => None,
+        };
+
+        Self {
+            market,
+            tenor,
+            bid: bid.and_then(|p| price_to_rate(p, tenor)),
+            ask: ask.and_then(|p| price_to_rate(p, tenor)),
+            mid: mid.and_then(|p| price_to_rate(p, tenor)),
+        }
+    }
+
+    /// Decode the orderbook slabs of a market and quote its best prices
+    pub fn from_orderbook(
+        address: Pubkey,
+        market: &Market,
+        bids: &mut [u8],
+        asks: &mut [u8],
+    ) -> anyhow::Result<Self> {
+        let (best_bid, best_ask) = slab_best_prices(bids, asks)
+            .map_err(|e| anyhow::anyhow!("failed to decode orderbook: {e}"))?;
+        Ok(Self::from_prices(
+            address,
+            market.lend_tenor,
+            best_bid,
+            best_ask,
+        ))
+    }
+
+    /// The rate for one side of the quote
+    pub fn rate(&self, side: CurveSide) -> Option<f64> {
+        match side {
+            CurveSide::Bid => self.bid,
+            CurveSide::Ask => self.ask,
+            CurveSide::Mid => self.mid,
+        }
+    }
+}
+
+/// The term structure of rates for a token, built from the quotes of every market
+/// that trades it
+#[derive(Debug, Clone, Default)]
+pub struct YieldCurve {
+    quotes: Vec<TenorQuote>,
+}
+
+impl YieldCurve {
+    /// Build a curve from a set of quotes, in any order
+    pub fn new(mut quotes: Vec<TenorQuote>) -> Self {
+        quotes.sort_by_key(|q| q.tenor);
+        Self { quotes }
+    }
+
+    /// Load every market for a token in an airspace, and build a curve from their orderbooks
+    pub async fn load(
+        rpc: &Arc<dyn SolanaRpcClient>,
+        airspace: &Pubkey,
+        token_mint: &Pubkey,
+    ) -> anyhow::Result<Self> {
+        let markets = find_markets(rpc)
+            .await?
+            .into_iter()
+            .filter(|(_, m)| m.airspace == *airspace && m.underlying_token_mint == *token_mint)
+            .collect::<Vec<_>>();
+
+        let books = markets
+            .iter()
+            .flat_map(|(_, m)| [m.bids, m.asks])
+            .collect::<Vec<_>>();
+        let accounts = rpc.get_multiple_accounts(&books).await?;
+
+        let mut quotes = Vec::with_capacity(markets.len());
+        for ((address, market), book) in markets.iter().zip(accounts.chunks(2)) {
+            match book {
+                [Some(bids), Some(asks)] => {
+                    let mut bids = bids.data.clone();
+                    let mut asks = asks.data.clone();
+                    quotes.push(TenorQuote::from_orderbook(
+                        *address, market, &mut bids, &mut asks,
+                    )?);
+                }
+                _ => tracing::warn!("missing orderbook for market [{address}]"),
+            }
+        }
+
+        Ok(Self::new(quotes))
+    }
+
+    /// The quotes of each market, ordered by tenor
+    pub fn quotes(&self) -> &[TenorQuote] {
+        &self.quotes
+    }
+
+    /// The points of the curve for one side, skipping tenors without a quote on that side
+    pub fn points(&self, side: CurveSide) -> Vec<(u64, f64)> {
+        self.quotes
+            .iter()
+            .filter_map(|q| q.rate(side).map(|r| (q.tenor, r)))
+            .collect()
+    }
+
+    /// The rate for any tenor
+    ///
+    /// Rates between quoted tenors are interpolated linearly, and rates outside
+    /// the quoted tenors are extrapolated flat from the nearest quote.
+    pub fn rate(&self, side: CurveSide, tenor: u64) -> Option<f64> {
+        let points = self.points(side);
+        let after = points.iter().position(|(t, _)| *t >= tenor);
+        match after {
+            None => points.last().map(|(_, r)| *r),
+            Some(0) => points.first().map(|(_, r)| *r),
+            Some(i) => {
+                let (t0, r0) = points[i - 1];
+                let (t1, r1) = points[i];
+                let weight = (tenor - t0) as f64 / (t1 - t0) as f64;
+                Some(r0 + (r1 - r0) * weight)
+            }
+        }
+    }
+
+    /// The value today of one token paid after `tenor` seconds
+    pub fn discount_factor(&self, side: CurveSide, tenor: u64) -> Option<f64> {
+        self.rate(side, tenor)
+            .map(|r| (-r * tenor as f64 / SECONDS_PER_YEAR).exp())
+    }
+
+    /// The rate implied by the curve for lending between `start` and `end`, which are both
+    /// offsets in seconds from now
+    pub fn forward_rate(&self, side: CurveSide, start: u64, end: u64) -> Option<f64> {
+        if end <= start {
+            return None;
+        }
+        let near = self.rate(side, start)? * start as f64;
+        let far = self.rate(side, end)? * end as f64;
+        Some((far - near) / (end - start) as f64)
+    }
+}
+
+/// The yearly rate implied by a ticket price, for a ticket maturing after `tenor` seconds
+fn price_to_rate(price: f64, tenor: u64) -> Option<f64> {
+    if tenor == 0 || price <= 0.0 {
+        return None;
+    }
+    Some(PricerImpl::yield_to_interest_rate(
+        1.0 / price - 1.0,
+        tenor as f64,
+        SECONDS_PER_YEAR,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn quote(tenor: u64, bid: Option<f64>, ask: Option<f64>) -> TenorQuote {
+        let mid = match (bid, ask) {
+            (Some(b), Some(a)) => Some((a + b) / 2.0),
+            _ => None,
+        };
+        TenorQuote {
+            market: Pubkey::new_unique(),
+            tenor,
+            bid,
+            ask,
+            mid,
+        }
+    }
+
+    fn curve() -> YieldCurve {
+        YieldCurve::new(vec![
+            quote(30 * DAY, Some(0.06), Some(0.04)),
+            quote(7 * DAY, Some(0.04), Some(0.02)),
+            quote(90 * DAY, None, Some(0.05)),
+        ])
+    }
+
+    #[test]
+    fn interpolates_between_tenors() {
+        let curve = curve();
+        assert_eq!(curve.quotes()[0].tenor, 7 * DAY);
+
+        let rate = curve.rate(CurveSide::Ask, 60 * DAY).unwrap();
+        assert!((rate - 0.045).abs() < 1e-12);
+
+        // tenors missing a quote are skipped
+        assert_eq!(curve.points(CurveSide::Bid).len(), 2);
+        assert_eq!(curve.rate(CurveSide::Bid, 90 * DAY), Some(0.06));
+    }
+
+    #[test]
+    fn extrapolates_flat() {
+        let curve = curve();
+        assert_eq!(curve.rate(CurveSide::Ask, DAY), Some(0.02));
+        assert_eq!(curve.rate(CurveSide::Ask, 365 * DAY), Some(0.05));
+        assert_eq!(YieldCurve::default().rate(CurveSide::Mid, DAY), None);
+    }
+
+    #[test]
+    fn forward_rates_are_consistent_with_discounting() {
+        let curve = curve();
+        let forward = curve
+            .forward_rate(CurveSide::Ask, 7 * DAY, 30 * DAY)
+            .unwrap();
+        let near = curve.discount_factor(CurveSide::Ask, 7 * DAY).unwrap();
+        let far = curve.discount_factor(CurveSide::Ask, 30 * DAY).unwrap();
+        let between = (-forward * (23 * DAY) as f64 / SECONDS_PER_YEAR).exp();
+        assert!((near * between - far).abs() < 1e-12);
+
+        assert_eq!(curve.forward_rate(CurveSide::Ask, 30 * DAY, 7 * DAY), None);
+    }
+
+    #[test]
+    fn quotes_rates_from_prices() {
+        let half: u64 = 1 << 31;
+        let quote = TenorQuote::from_prices(Pubkey::default(), 365 * DAY, Some(half), None);
+        assert!((quote.bid.unwrap() - std::f64::consts::LN_2).abs() < 1e-9);
+        assert_eq!(quote.ask, None);
+        assert_eq!(quote.mid, None);
+    }
+}
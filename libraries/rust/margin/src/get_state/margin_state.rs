@@ -2,8 +2,8 @@ use std::sync::Arc;
 
 use anchor_lang::AccountDeserialize;
 use anyhow::{Context, Result};
-use jet_instructions::margin::derive_token_config;
-use jet_margin::{MarginAccount, TokenConfig};
+use jet_instructions::margin::{derive_token_config, derive_token_oracle_set};
+use jet_margin::{MarginAccount, TokenConfig, TokenOracleSet};
 use jet_metadata::{PositionTokenMetadata, TokenMetadata};
 use jet_simulation::SolanaRpcClient;
 use solana_sdk::pubkey::Pubkey;
@@ -39,6 +39,27 @@ pub(crate) async fn get_position_config(
     }
 }
 
+/// Get the price accounts of the fallback oracles for a token, in the order they are
+/// stored in its oracle set
+pub(crate) async fn get_fallback_oracles(
+    rpc: &Arc<dyn SolanaRpcClient>,
+    config_address: &Pubkey,
+    config: &TokenConfig,
+) -> Result<Vec<Pubkey>> {
+    if !config.has_oracle_set {
+        return Ok(vec![]);
+    }
+
+    let oracle_set: TokenOracleSet =
+        get_anchor_account(rpc, &derive_token_oracle_set(config_address)).await?;
+
+    Ok(oracle_set
+        .fallbacks
+        .iter()
+        .map(|oracle| oracle.price_account())
+        .collect())
+}
+
 pub(crate) async fn get_token_metadata(
     rpc: &Arc<dyn SolanaRpcClient>,
     token_mint: &Pubkey,
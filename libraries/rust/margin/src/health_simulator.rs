@@ -0,0 +1,755 @@
+//! Predicts the health of a margin account after a set of actions.
+//!
+//! The simulator applies deposits, borrows, swaps and fixed term orders to a
+//! snapshot of a [MarginAccount], converting between tokens and notes with the
+//! same math as the margin pool program, and values the result with
+//! [MarginAccount::valuation]. Bots can use it to check that a transaction
+//! leaves the account healthy before sending it, and to size new positions.
+//!
+//! The result is only as accurate as the snapshot. Pools are accrued up to the
+//! simulation time, but positions are priced with the token prices given to the
+//! simulator, or the prices already stored in the account.
+
+use std::collections::HashMap;
+
+use agnostic_orderbook::state::Side;
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, Context, Result};
+use jet_fixed_term::control::state::Market;
+use jet_margin::{
+    Approver, MarginAccount, PositionConfigUpdate, PriceChangeInfo, PriceInfo, TokenAdmin,
+    TokenConfig, TokenIsolation, TokenKind, Valuation,
+};
+use jet_margin_pool::{Amount, MarginPool, PoolAction};
+use jet_program_common::Number128;
+
+use crate::margin_account_ext::MarginAccountExt;
+
+/// A change to a margin account that can be simulated
+#[derive(Debug, Clone, Copy)]
+pub enum SimulatedAction {
+    /// Deposit tokens from a wallet into the margin pool for the token
+    Deposit {
+        /// The mint of the deposited token
+        token: Pubkey,
+        /// The number of tokens to deposit
+        amount: u64,
+    },
+
+    /// Withdraw tokens from a margin pool deposit to a wallet
+    Withdraw {
+        /// The mint of the withdrawn token
+        token: Pubkey,
+        /// The number of tokens to withdraw
+        amount: u64,
+    },
+
+    /// Borrow tokens from a margin pool, which are deposited back into the pool
+    Borrow {
+        /// The mint of the borrowed token
+        token: Pubkey,
+        /// The number of tokens to borrow
+        amount: u64,
+    },
+
+    /// Repay a margin pool loan with the deposit in the same pool
+    Repay {
+        /// The mint of the repaid token
+        token: Pubkey,
+        /// The number of tokens to repay
+        amount: u64,
+    },
+
+    /// Swap the deposit in one margin pool for a deposit in another
+    Swap {
+        /// The mint of the token withdrawn from its pool
+        from: Pubkey,
+        /// The mint of the token deposited into its pool
+        to: Pubkey,
+        /// The number of tokens sold
+        amount_in: u64,
+        /// The number of tokens expected from the swap
+        amount_out: u64,
+    },
+
+    /// Post an order to a fixed term market
+    ///
+    /// Only the posted order is simulated, so any fills are ignored. Lend orders
+    /// are funded by the margin pool deposit of the underlying token.
+    FixedTermOrder {
+        /// The address of the `Market`
+        market: Pubkey,
+        /// Bid to lend, or ask to borrow
+        side: Side,
+        /// The number of underlying tokens lent or borrowed
+        tokens: u64,
+        /// The number of tickets bought or sold, including any origination fee
+        tickets: u64,
+    },
+}
+
+/// The state of a margin account after the simulated actions
+#[derive(Debug, Clone)]
+pub struct HealthProjection {
+    /// The valuation of the account after all actions are applied
+    pub valuation: Valuation,
+
+    /// The most tokens that could be borrowed from each pool, keyed by token mint
+    pub max_borrow: HashMap<Pubkey, u64>,
+
+    /// The most tokens that could be withdrawn from each pool, keyed by token mint
+    pub max_withdraw: HashMap<Pubkey, u64>,
+
+    /// The token price at which the account would become unhealthy, keyed by
+    /// token mint. Tokens that cannot make the account unhealthy by falling in
+    /// price are left out.
+    pub liquidation_price: HashMap<Pubkey, Number128>,
+}
+
+/// Applies actions to a copy of a margin account to predict its health
+#[derive(Clone)]
+pub struct HealthSimulator {
+    account: MarginAccount,
+    timestamp: u64,
+    pools: HashMap<Pubkey, MarginPool>,
+    markets: HashMap<Pubkey, FixedTermMints>,
+    configs: HashMap<Pubkey, TokenConfig>,
+    isolations: HashMap<Pubkey, TokenIsolation>,
+    prices: HashMap<Pubkey, TokenPrice>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FixedTermMints {
+    underlying: Pubkey,
+    claims: Pubkey,
+    ticket_collateral: Pubkey,
+    underlying_collateral: Pubkey,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenPrice {
+    decimals: u8,
+    price: PriceInfo,
+}
+
+impl HealthSimulator {
+    /// Start a simulation from the current state of a margin account
+    ///
+    /// The timestamp is used to accrue pool interest and to check the staleness
+    /// of positions. It is moved forward if the account was updated after it.
+    pub fn new(account: &MarginAccount, timestamp: u64) -> Self {
+        let timestamp = account
+            .positions()
+            .flat_map(|p| [p.balance_timestamp, p.price.timestamp])
+            .fold(timestamp, std::cmp::max);
+
+        Self {
+            account: *account,
+            timestamp,
+            pools: HashMap::new(),
+            markets: HashMap::new(),
+            configs: HashMap::new(),
+            isolations: HashMap::new(),
+            prices: HashMap::new(),
+        }
+    }
+
+    /// Add the state of a margin pool, which is needed to simulate actions on the pool
+    pub fn with_pool(mut self, mut pool: MarginPool) -> Self {
+        if pool.accrued_until < self.timestamp as i64 {
+            while !pool.accrue_interest(self.timestamp as i64) {}
+        }
+        self.pools.insert(pool.token_mint, pool);
+        self
+    }
+
+    /// Add the config of a token, which is needed to register new positions
+    pub fn with_token_config(mut self, config: TokenConfig) -> Self {
+        self.configs.insert(config.mint, config);
+        self
+    }
+
+    /// Add the isolation restrictions for a token, which are needed to register new
+    /// positions when the token is isolated
+    pub fn with_token_isolation(mut self, mint: Pubkey, isolation: TokenIsolation) -> Self {
+        self.isolations.insert(mint, isolation);
+        self
+    }
+
+    /// Add a fixed term market, which is needed to simulate orders in the market
+    pub fn with_fixed_term_market(mut self, address: Pubkey, market: &Market) -> Self {
+        self.markets.insert(
+            address,
+            FixedTermMints {
+                underlying: market.underlying_token_mint,
+                claims: market.claims_mint,
+                ticket_collateral: market.ticket_collateral_mint,
+                underlying_collateral: market.underlying_collateral_mint,
+            },
+        );
+        self
+    }
+
+    /// Set the price of a token, which replaces the price of every position
+    /// backed by the token except fixed term tickets
+    pub fn with_token_price(mut self, token: Pubkey, decimals: u8, mut price: PriceInfo) -> Self {
+        price.timestamp = std::cmp::min(price.timestamp, self.timestamp);
+        self.prices.insert(token, TokenPrice { decimals, price });
+        self
+    }
+
+    /// The simulated state of the margin account
+    pub fn account(&self) -> &MarginAccount {
+        &self.account
+    }
+
+    /// Value the simulated account
+    pub fn valuation(&self) -> Result<Valuation> {
+        Ok(self.account.valuation(self.timestamp)?)
+    }
+
+    /// Whether the simulated account is healthy, and not past due on any claims
+    pub fn is_healthy(&self) -> Result<bool> {
+        let valuation = self.valuation()?;
+
+        Ok(
+            valuation.required_collateral <= valuation.effective_collateral
+                && !valuation.past_due(),
+        )
+    }
+
+    /// Apply the actions to a copy of the account, and project its health
+    pub fn simulate(&self, actions: &[SimulatedAction]) -> Result<HealthProjection> {
+        let mut sim = self.clone();
+        let tokens: Vec<Pubkey> = sim.prices.keys().copied().collect();
+        for token in &tokens {
+            sim.refresh_price(*token)?;
+        }
+        for action in actions {
+            sim.apply(action)
+                .with_context(|| format!("failed to simulate {action:?}"))?;
+        }
+
+        Ok(HealthProjection {
+            valuation: sim.valuation()?,
+            max_borrow: sim.pools.keys().map(|t| (*t, sim.max_borrow(*t))).collect(),
+            max_withdraw: sim
+                .pools
+                .keys()
+                .map(|t| (*t, sim.max_withdraw(*t)))
+                .collect(),
+            liquidation_price: tokens
+                .into_iter()
+                .filter_map(|t| Some((t, sim.liquidation_price(t)?)))
+                .collect(),
+        })
+    }
+
+    /// Apply a single action to the simulated account
+    pub fn apply(&mut self, action: &SimulatedAction) -> Result<()> {
+        match *action {
+            SimulatedAction::Deposit { token, amount } => self.deposit(token, amount),
+            SimulatedAction::Withdraw { token, amount } => self.withdraw(token, amount),
+            SimulatedAction::Borrow { token, amount } => self.borrow(token, amount),
+            SimulatedAction::Repay { token, amount } => self.repay(token, amount),
+            SimulatedAction::Swap {
+                from,
+                to,
+                amount_in,
+                amount_out,
+            } => {
+                self.withdraw(from, amount_in)?;
+                self.deposit(to, amount_out)
+            }
+            SimulatedAction::FixedTermOrder {
+                market,
+                side,
+                tokens,
+                tickets,
+            } => {
+                let mints = *self
+                    .markets
+                    .get(&market)
+                    .with_context(|| format!("fixed term market {market} is unknown"))?;
+
+                match side {
+                    Side::Bid => {
+                        self.withdraw(mints.underlying, tokens)?;
+                        self.add_balance(mints.ticket_collateral, tickets)
+                    }
+                    Side::Ask => {
+                        self.add_balance(mints.claims, tickets)?;
+                        self.add_balance(mints.underlying_collateral, tokens)
+                    }
+                }
+            }
+        }
+    }
+
+    /// The most tokens that can be borrowed from a pool while keeping the account healthy
+    pub fn max_borrow(&self, token: Pubkey) -> u64 {
+        let liquidity = match self.pools.get(&token) {
+            Some(pool) => pool.deposit_tokens,
+            None => return 0,
+        };
+
+        self.max_amount(liquidity, |amount| SimulatedAction::Borrow {
+            token,
+            amount,
+        })
+    }
+
+    /// The most tokens that can be withdrawn from a pool while keeping the account healthy
+    pub fn max_withdraw(&self, token: Pubkey) -> u64 {
+        let pool = match self.pools.get(&token) {
+            Some(pool) => pool,
+            None => return 0,
+        };
+        let notes = self.account.balance(&pool.deposit_note_mint);
+        let deposited = pool
+            .convert_amount(Amount::notes(notes), PoolAction::Withdraw)
+            .map(|amount| amount.tokens)
+            .unwrap_or_default();
+
+        self.max_amount(std::cmp::min(deposited, pool.deposit_tokens), |amount| {
+            SimulatedAction::Withdraw { token, amount }
+        })
+    }
+
+    /// The highest price of a token at which the account is unhealthy, with
+    /// every other price unchanged
+    ///
+    /// Returns `None` if the account stays healthy even when the token is worthless.
+    pub fn liquidation_price(&self, token: Pubkey) -> Option<Number128> {
+        let price = self.prices.get(&token)?.price;
+        if !price.is_valid() || price.value <= 0 {
+            return None;
+        }
+        let healthy_at = |value: i64| {
+            let mut sim = self.clone();
+            if let Some(p) = sim.prices.get_mut(&token) {
+                p.price.value = value;
+            }
+            sim.refresh_price(token).is_ok() && sim.is_healthy().unwrap_or(false)
+        };
+
+        if !healthy_at(price.value) {
+            return Some(Number128::from_decimal(price.value, price.exponent));
+        }
+        if healthy_at(0) {
+            return None;
+        }
+
+        let (mut unhealthy, mut healthy) = (0, price.value);
+        while unhealthy + 1 < healthy {
+            let mid = unhealthy + (healthy - unhealthy) / 2;
+            match healthy_at(mid) {
+                true => healthy = mid,
+                false => unhealthy = mid,
+            }
+        }
+
+        Some(Number128::from_decimal(unhealthy, price.exponent))
+    }
+
+    /// Search for the largest amount up to `limit` for which the action leaves
+    /// the account healthy
+    fn max_amount(&self, limit: u64, action: impl Fn(u64) -> SimulatedAction) -> u64 {
+        let healthy_after = |amount| {
+            let mut sim = self.clone();
+            sim.apply(&action(amount)).is_ok() && sim.is_healthy().unwrap_or(false)
+        };
+
+        if limit == 0 || !self.is_healthy().unwrap_or(false) {
+            return 0;
+        }
+        if healthy_after(limit) {
+            return limit;
+        }
+
+        let (mut healthy, mut unhealthy) = (0, limit);
+        while healthy + 1 < unhealthy {
+            let mid = healthy + (unhealthy - healthy) / 2;
+            match healthy_after(mid) {
+                true => healthy = mid,
+                false => unhealthy = mid,
+            }
+        }
+
+        healthy
+    }
+
+    fn pool_mut(&mut self, token: Pubkey) -> Result<&mut MarginPool> {
+        self.pools
+            .get_mut(&token)
+            .with_context(|| format!("margin pool for token {token} is unknown"))
+    }
+
+    fn deposit(&mut self, token: Pubkey, amount: u64) -> Result<()> {
+        let pool = self.pool_mut(token)?;
+        let deposit = pool.convert_amount(Amount::tokens(amount), PoolAction::Deposit)?;
+        pool.deposit(&deposit);
+        pool.verify_deposit_cap()?;
+        let deposit_note_mint = pool.deposit_note_mint;
+
+        self.add_balance(deposit_note_mint, deposit.notes)
+    }
+
+    fn withdraw(&mut self, token: Pubkey, amount: u64) -> Result<()> {
+        let pool = self.pool_mut(token)?;
+        let withdraw = pool.convert_amount(Amount::tokens(amount), PoolAction::Withdraw)?;
+        pool.withdraw(&withdraw)?;
+        let deposit_note_mint = pool.deposit_note_mint;
+
+        self.sub_balance(deposit_note_mint, withdraw.notes)
+    }
+
+    fn borrow(&mut self, token: Pubkey, amount: u64) -> Result<()> {
+        let loan_note_mint = self.pool_mut(token)?.loan_note_mint;
+        let loan_notes = self.account.balance(&loan_note_mint);
+
+        let pool = self.pool_mut(token)?;
+        let borrow = pool.convert_amount(Amount::tokens(amount), PoolAction::Borrow)?;
+        pool.borrow(&borrow)?;
+        pool.verify_account_borrow_cap(loan_notes.saturating_add(borrow.notes))?;
+        let deposit = pool.convert_amount(Amount::tokens(borrow.tokens), PoolAction::Deposit)?;
+        pool.deposit(&deposit);
+        let deposit_note_mint = pool.deposit_note_mint;
+
+        self.add_balance(loan_note_mint, borrow.notes)?;
+        self.add_balance(deposit_note_mint, deposit.notes)
+    }
+
+    fn repay(&mut self, token: Pubkey, amount: u64) -> Result<()> {
+        let pool = self.pool_mut(token)?;
+        let repay = pool.convert_amount(Amount::tokens(amount), PoolAction::Repay)?;
+        let withdraw = pool.convert_amount(Amount::tokens(repay.tokens), PoolAction::Withdraw)?;
+        pool.margin_repay(&repay, &withdraw)?;
+        let (loan_note_mint, deposit_note_mint) = (pool.loan_note_mint, pool.deposit_note_mint);
+
+        self.sub_balance(loan_note_mint, repay.notes)?;
+        self.sub_balance(deposit_note_mint, withdraw.notes)
+    }
+
+    fn add_balance(&mut self, mint: Pubkey, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        if self.account.get_position(&mint).is_none() {
+            self.register_position(mint)?;
+        }
+        let timestamp = self.timestamp;
+        let position = self.account.get_position_mut(&mint).unwrap();
+        let balance = position
+            .balance
+            .checked_add(amount)
+            .context("position balance overflow")?;
+        position.set_balance(balance, timestamp);
+
+        Ok(())
+    }
+
+    fn sub_balance(&mut self, mint: Pubkey, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let timestamp = self.timestamp;
+        let position = self
+            .account
+            .get_position_mut(&mint)
+            .with_context(|| format!("no position for token {mint}"))?;
+        let balance = match position.balance.checked_sub(amount) {
+            Some(balance) => balance,
+            None => bail!(
+                "position {mint} holds {} tokens, cannot remove {amount}",
+                position.balance
+            ),
+        };
+        position.set_balance(balance, timestamp);
+
+        Ok(())
+    }
+
+    fn register_position(&mut self, mint: Pubkey) -> Result<()> {
+        let config = self
+            .configs
+            .get(&mint)
+            .with_context(|| format!("no token config to register a position for {mint}"))?;
+        let decimals = self
+            .prices
+            .get(&config.underlying_mint)
+            .with_context(|| format!("no price for token {}", config.underlying_mint))?
+            .decimals;
+        let adapter = match config.admin {
+            TokenAdmin::Adapter(adapter) => adapter,
+            TokenAdmin::Margin { .. } => Pubkey::default(),
+        };
+        let approvals = match config.token_kind {
+            TokenKind::Collateral => vec![Approver::MarginAccountAuthority],
+            TokenKind::Claim | TokenKind::AdapterCollateral => {
+                vec![Approver::MarginAccountAuthority, Approver::Adapter(adapter)]
+            }
+        };
+        let isolation = match config.is_isolated {
+            true => Some(
+                *self
+                    .isolations
+                    .get(&mint)
+                    .with_context(|| format!("no isolation config for token {mint}"))?,
+            ),
+            false => None,
+        };
+        let underlying_mint = config.underlying_mint;
+
+        self.account.register_position(
+            PositionConfigUpdate {
+                mint,
+                decimals,
+                address: Pubkey::default(),
+                airspace: config.airspace,
+                adapter,
+                kind: config.token_kind,
+                value_modifier: config.value_modifier,
+                max_staleness: config.max_staleness,
+                isolation,
+            },
+            &approvals,
+        )?;
+
+        self.refresh_price(underlying_mint)
+    }
+
+    /// Price every registered position backed by a token, as its adapter would
+    fn refresh_price(&mut self, token: Pubkey) -> Result<()> {
+        let price = match self.prices.get(&token) {
+            Some(p) => p.price,
+            None => return Ok(()),
+        };
+        let mut prices = vec![(token, price)];
+
+        if let Some(pool) = self.pools.get(&token) {
+            let notes = pool.calculate_prices(&PriceChangeInfo {
+                value: price.value,
+                confidence: 0,
+                twap: price.value,
+                publish_time: price.timestamp as i64,
+                exponent: price.exponent,
+            })?;
+            let note_price = |value| match price.is_valid() {
+                true => PriceInfo::new_valid(price.exponent, value, price.timestamp),
+                false => PriceInfo::new_invalid(),
+            };
+            prices.push((pool.deposit_note_mint, note_price(notes.deposit_note_price)));
+            prices.push((pool.loan_note_mint, note_price(notes.loan_note_price)));
+        }
+        for market in self.markets.values().filter(|m| m.underlying == token) {
+            prices.push((market.claims, price));
+            prices.push((market.underlying_collateral, price));
+        }
+
+        for (mint, price) in prices {
+            if let Some(position) = self.account.get_position_mut(&mint) {
+                position
+                    .set_price(&price)
+                    .map_err(anchor_lang::error::Error::from)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use jet_margin_pool::PoolFlags;
+
+    use super::*;
+
+    const TIMESTAMP: u64 = 1_700_000_000;
+
+    struct TestPool {
+        token: Pubkey,
+        pool: MarginPool,
+    }
+
+    impl TestPool {
+        fn new(liquidity: u64) -> Self {
+            let token = Pubkey::new_unique();
+            let mut pool = MarginPool {
+                token_mint: token,
+                deposit_note_mint: Pubkey::new_unique(),
+                loan_note_mint: Pubkey::new_unique(),
+                accrued_until: TIMESTAMP as i64,
+                ..Default::default()
+            };
+            pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+            let deposit = pool
+                .convert_amount(Amount::tokens(liquidity), PoolAction::Deposit)
+                .unwrap();
+            pool.deposit(&deposit);
+
+            Self { token, pool }
+        }
+
+        fn configs(&self) -> [TokenConfig; 2] {
+            let config = |mint, token_kind, value_modifier| TokenConfig {
+                mint,
+                underlying_mint: self.token,
+                airspace: Pubkey::default(),
+                token_kind,
+                value_modifier,
+                max_staleness: 0,
+                admin: TokenAdmin::Adapter(jet_margin_pool::ID),
+                has_oracle_set: false,
+                is_isolated: false,
+            };
+
+            [
+                config(self.pool.deposit_note_mint, TokenKind::Collateral, 100),
+                config(self.pool.loan_note_mint, TokenKind::Claim, 1000),
+            ]
+        }
+    }
+
+    /// A simulator for an empty account with pools for usdc at $1 and sol at $20
+    fn simulator() -> (HealthSimulator, Pubkey, Pubkey) {
+        let usdc = TestPool::new(1_000_000_000_000);
+        let sol = TestPool::new(1_000_000_000_000);
+        let account: MarginAccount = bytemuck::Zeroable::zeroed();
+
+        let mut sim = HealthSimulator::new(&account, TIMESTAMP)
+            .with_token_price(
+                usdc.token,
+                6,
+                PriceInfo::new_valid(-8, 100_000_000, TIMESTAMP),
+            )
+            .with_token_price(
+                sol.token,
+                9,
+                PriceInfo::new_valid(-8, 2_000_000_000, TIMESTAMP),
+            );
+        for pool in [&usdc, &sol] {
+            for config in pool.configs() {
+                sim = sim.with_token_config(config);
+            }
+            sim = sim.with_pool(pool.pool.clone());
+        }
+
+        (sim, usdc.token, sol.token)
+    }
+
+    fn within(actual: u64, expected: u64, tolerance: u64) -> bool {
+        actual.abs_diff(expected) <= tolerance
+    }
+
+    #[test]
+    fn borrowing_is_limited_by_required_collateral() {
+        let (sim, usdc, _) = simulator();
+        let projection = sim
+            .simulate(&[SimulatedAction::Deposit {
+                token: usdc,
+                amount: 100_000_000,
+            }])
+            .unwrap();
+
+        // each borrowed dollar is deposited back, so the $100 deposit backs
+        // 10x leverage
+        assert!(within(projection.max_borrow[&usdc], 1_000_000_000, 10));
+        assert_eq!(projection.max_withdraw[&usdc], 100_000_000);
+        assert_eq!(projection.valuation.equity.as_f64(), 100.0);
+    }
+
+    #[test]
+    fn borrowing_reduces_withdrawable_collateral() {
+        let (sim, usdc, _) = simulator();
+        let projection = sim
+            .simulate(&[
+                SimulatedAction::Deposit {
+                    token: usdc,
+                    amount: 100_000_000,
+                },
+                SimulatedAction::Borrow {
+                    token: usdc,
+                    amount: 500_000_000,
+                },
+            ])
+            .unwrap();
+
+        assert_eq!(projection.valuation.liabilities.as_f64(), 500.0);
+        assert_eq!(projection.valuation.required_collateral.as_f64(), 50.0);
+        assert!(within(projection.max_withdraw[&usdc], 50_000_000, 10));
+        assert!(within(projection.max_borrow[&usdc], 500_000_000, 10));
+    }
+
+    #[test]
+    fn collateral_has_liquidation_price() {
+        let (sim, usdc, sol) = simulator();
+        let projection = sim
+            .simulate(&[
+                SimulatedAction::Deposit {
+                    token: sol,
+                    amount: 10_000_000_000,
+                },
+                SimulatedAction::Borrow {
+                    token: usdc,
+                    amount: 1_000_000_000,
+                },
+            ])
+            .unwrap();
+
+        // $1000 of debt requires $100 of collateral, which is 10 sol at $10
+        let price = projection.liquidation_price[&sol].as_f64();
+        assert!((price - 10.0).abs() < 1e-6, "{price}");
+        assert!(!projection.liquidation_price.contains_key(&usdc));
+    }
+
+    #[test]
+    fn swap_moves_deposits_between_pools() {
+        let (sim, usdc, sol) = simulator();
+        let projection = sim
+            .simulate(&[
+                SimulatedAction::Deposit {
+                    token: usdc,
+                    amount: 100_000_000,
+                },
+                SimulatedAction::Swap {
+                    from: usdc,
+                    to: sol,
+                    amount_in: 40_000_000,
+                    amount_out: 2_000_000_000,
+                },
+            ])
+            .unwrap();
+
+        assert_eq!(projection.valuation.equity.as_f64(), 100.0);
+        assert_eq!(projection.max_withdraw[&usdc], 60_000_000);
+        assert_eq!(projection.max_withdraw[&sol], 2_000_000_000);
+    }
+
+    #[test]
+    fn unhealthy_actions_are_reported() {
+        let (sim, usdc, _) = simulator();
+        let actions = [
+            SimulatedAction::Deposit {
+                token: usdc,
+                amount: 100_000_000,
+            },
+            SimulatedAction::Borrow {
+                token: usdc,
+                amount: 2_000_000_000,
+            },
+        ];
+        let mut after = sim.clone();
+        for action in &actions {
+            after.apply(action).unwrap();
+        }
+        assert!(!after.is_healthy().unwrap());
+        assert_eq!(after.max_borrow(usdc), 0);
+
+        let overdraw = sim.simulate(&[SimulatedAction::Withdraw {
+            token: usdc,
+            amount: 1,
+        }]);
+        assert!(overdraw.is_err());
+    }
+}
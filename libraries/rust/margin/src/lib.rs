@@ -53,6 +53,8 @@
 
 /// retrieve on-chain state
 pub mod get_state;
+/// predict the health of a margin account before sending a transaction
+pub mod health_simulator;
 /// Instruction builders for programs and adapters supported by the SDK
 pub mod ix_builder;
 /// ease of use for reading a MarginAccount
@@ -1,13 +1,16 @@
 //! Refresh margin deposits and pool positions.
 
 use anyhow::Result;
-use jet_instructions::margin::refresh_deposit_position;
-use jet_margin::{MarginAccount, TokenOracle};
+use jet_instructions::margin::refresh_deposit_position_with_fallbacks;
+use jet_margin::MarginAccount;
 use jet_simulation::SolanaRpcClient;
 use jet_solana_client::transaction::TransactionBuilder;
 use std::sync::Arc;
 
-use crate::{get_state::get_position_config, margin_account_ext::MarginAccountExt};
+use crate::{
+    get_state::{get_fallback_oracles, get_position_config},
+    margin_account_ext::MarginAccountExt,
+};
 
 use super::position_refresher::define_refresher;
 
@@ -21,22 +24,31 @@ pub async fn refresh_deposit_positions(
     let mut instructions = vec![];
     let address = state.address();
     for position in state.positions() {
-        let (_, p_config) = match get_position_config(rpc, &state.airspace, &position.token).await?
-        {
-            None => continue,
-            Some(r) => r,
-        };
+        let (config_address, p_config) =
+            match get_position_config(rpc, &state.airspace, &position.token).await? {
+                None => continue,
+                Some(r) => r,
+            };
 
         if position.token != p_config.underlying_mint {
             continue;
         }
 
-        let token_oracle = match p_config.oracle().unwrap() {
-            TokenOracle::Pyth { price, .. } => price,
+        let token_oracle = match p_config.oracle() {
+            Some(oracle) => oracle.price_account(),
+            None => continue,
         };
 
-        let refresh =
-            refresh_deposit_position(&state.airspace, address, position.token, token_oracle, true);
+        let fallback_oracles = get_fallback_oracles(rpc, &config_address, &p_config).await?;
+
+        let refresh = refresh_deposit_position_with_fallbacks(
+            &state.airspace,
+            address,
+            position.token,
+            token_oracle,
+            &fallback_oracles,
+            true,
+        );
         instructions.push(refresh.into());
     }
 
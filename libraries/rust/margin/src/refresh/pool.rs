@@ -10,7 +10,9 @@ use solana_sdk::pubkey::Pubkey;
 use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    get_state::{get_position_metadata, get_token_metadata},
+    get_state::{
+        get_fallback_oracles, get_position_config, get_position_metadata, get_token_metadata,
+    },
     margin_account_ext::MarginAccountExt,
 };
 
@@ -47,7 +49,21 @@ pub async fn refresh_all_pool_positions_underlying_to_tx(
         }
         let t_metadata = get_token_metadata(rpc, &p_metadata.underlying_token_mint).await?;
         let ix_builder = MarginPoolIxBuilder::new(p_metadata.underlying_token_mint);
-        let inner = ix_builder.margin_refresh_position(address, t_metadata.pyth_price);
+        let fallback_oracles =
+            match get_position_config(rpc, &state.airspace, &p_metadata.underlying_token_mint)
+                .await?
+            {
+                Some((config_address, config)) => {
+                    get_fallback_oracles(rpc, &config_address, &config).await?
+                }
+                None => vec![],
+            };
+        let inner = ix_builder.margin_refresh_position_with_fallbacks(
+            &state.airspace,
+            address,
+            t_metadata.pyth_price,
+            &fallback_oracles,
+        );
         let ix = accounting_invoke(state.airspace, address, inner);
 
         txns.insert(p_metadata.underlying_token_mint, ix.into());
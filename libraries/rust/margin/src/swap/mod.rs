@@ -25,6 +25,7 @@ use jet_simulation::solana_rpc_api::SolanaRpcClient;
 use solana_sdk::pubkey::Pubkey;
 
 pub mod openbook_swap;
+pub mod quote;
 pub mod saber_swap;
 pub mod spl_swap;
 pub mod whirlpool;
@@ -0,0 +1,318 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Estimate the result of a swap before submitting it, by simulating each venue's pricing
+//! off-chain from the current state of its accounts.
+//!
+//! The accounts are fetched from the network, and priced with [jet_instructions::swap_quote].
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use jet_instructions::swap_quote::{
+    minimum_amount_out, quote_openbook, quote_saber, quote_whirlpool, read_book_side,
+    read_tick_array, whirlpool_tick_arrays,
+};
+use jet_solana_client::rpc::{SolanaRpc, SolanaRpcExtra};
+use jet_static_program_registry::orca_swap_v2::{curve::calculator::TradeDirection, state::SwapV1};
+use orca_whirlpool::state::Whirlpool;
+use saber_client::state::SwapInfo;
+use solana_sdk::{clock::Clock, program_pack::Pack, pubkey::Pubkey, sysvar};
+
+pub use jet_instructions::swap_quote::SwapQuote;
+
+use super::{
+    openbook_swap::OpenBookMarket, saber_swap::SaberSwapPool, spl_swap::SplSwapPool,
+    whirlpool::WhirlpoolSwap,
+};
+
+/// Venues that can estimate the result of a swap
+#[async_trait]
+pub trait SwapQuoter: Sync {
+    /// Estimate the result of selling `amount_in` of `source_mint` in this pool
+    async fn quote(
+        &self,
+        rpc: &(dyn SolanaRpc + 'static),
+        source_mint: &Pubkey,
+        amount_in: u64,
+    ) -> Result<SwapQuote>;
+}
+
+/// A step in a route, through either one pool or a split between two
+#[derive(Clone, Copy)]
+pub enum RouteLeg<'a> {
+    /// Swap the entire amount in one pool
+    Single(&'a dyn SwapQuoter),
+
+    /// Swap `split` percent of the amount in the first pool, and the rest in the second
+    Split {
+        /// The pool receiving `split` percent of the amount
+        pool: &'a dyn SwapQuoter,
+        /// The pool receiving the remainder
+        split_pool: &'a dyn SwapQuoter,
+        /// The percentage of the amount to swap in `pool`
+        split: u8,
+    },
+}
+
+/// The expected result of swapping through a route of pools
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteQuote {
+    /// The amount of the source token sold
+    pub amount_in: u64,
+
+    /// The amount of the final destination token received
+    pub amount_out: u64,
+
+    /// The quotes for each pool in the route, in order
+    pub swaps: Vec<SwapQuote>,
+}
+
+impl RouteQuote {
+    /// The least output to accept when submitting the route swap, allowing for the price
+    /// to move by `slippage_bps` before it executes
+    pub fn minimum_amount_out(&self, slippage_bps: u16) -> u64 {
+        minimum_amount_out(self.amount_out, slippage_bps)
+    }
+}
+
+/// Estimate the result of a route swap, where the output of each leg is sold in the next.
+///
+/// Splits are divided the same way as the margin swap program divides them.
+pub async fn quote_route(
+    rpc: &(dyn SolanaRpc + 'static),
+    source_mint: &Pubkey,
+    amount_in: u64,
+    legs: &[RouteLeg<'_>],
+) -> Result<RouteQuote> {
+    let mut mint = *source_mint;
+    let mut amount = amount_in;
+    let mut swaps = vec![];
+
+    for leg in legs {
+        let (destination, amount_out) = match *leg {
+            RouteLeg::Single(pool) => {
+                let quote = pool.quote(rpc, &mint, amount).await?;
+                let result = (quote.destination_mint, quote.amount_out);
+
+                swaps.push(quote);
+                result
+            }
+            RouteLeg::Split {
+                pool,
+                split_pool,
+                split,
+            } => {
+                let split_amount = (amount as u128 * split as u128 / 100) as u64;
+                let first = pool.quote(rpc, &mint, split_amount).await?;
+                let second = split_pool.quote(rpc, &mint, amount - split_amount).await?;
+
+                if first.destination_mint != second.destination_mint {
+                    bail!("split pools swap {mint} into different tokens");
+                }
+
+                let result = (first.destination_mint, first.amount_out + second.amount_out);
+
+                swaps.extend([first, second]);
+                result
+            }
+        };
+
+        mint = destination;
+        amount = amount_out;
+    }
+
+    Ok(RouteQuote {
+        amount_in,
+        amount_out: amount,
+        swaps,
+    })
+}
+
+#[async_trait]
+impl SwapQuoter for SplSwapPool {
+    async fn quote(
+        &self,
+        rpc: &(dyn SolanaRpc + 'static),
+        source_mint: &Pubkey,
+        amount_in: u64,
+    ) -> Result<SwapQuote> {
+        let (destination_mint, direction) = match *source_mint {
+            mint if mint == self.mint_a => (self.mint_b, TradeDirection::AtoB),
+            mint if mint == self.mint_b => (self.mint_a, TradeDirection::BtoA),
+            _ => bail!("pool {} does not swap {source_mint}", self.pool),
+        };
+
+        let account = rpc
+            .get_account(&self.pool)
+            .await?
+            .with_context(|| format!("swap pool {} not found", self.pool))?;
+        let swap = SwapV1::unpack(&account.data[1..])?;
+
+        let (balance_a, balance_b) = token_balances(rpc, &self.token_a, &self.token_b).await?;
+        let (source_balance, destination_balance) = match direction {
+            TradeDirection::AtoB => (balance_a, balance_b),
+            TradeDirection::BtoA => (balance_b, balance_a),
+        };
+
+        let result = swap
+            .swap_curve
+            .swap(
+                amount_in as u128,
+                source_balance as u128,
+                destination_balance as u128,
+                direction,
+                &swap.fees,
+            )
+            .with_context(|| format!("pool {} cannot swap {amount_in}", self.pool))?;
+
+        Ok(SwapQuote::new(
+            *source_mint,
+            destination_mint,
+            result.source_amount_swapped as u64,
+            result.destination_amount_swapped as u64,
+            (result.trade_fee + result.owner_fee) as u64,
+            *source_mint,
+            destination_balance as f64 / source_balance as f64,
+        ))
+    }
+}
+
+#[async_trait]
+impl SwapQuoter for SaberSwapPool {
+    async fn quote(
+        &self,
+        rpc: &(dyn SolanaRpc + 'static),
+        source_mint: &Pubkey,
+        amount_in: u64,
+    ) -> Result<SwapQuote> {
+        if *source_mint != self.mint_a && *source_mint != self.mint_b {
+            bail!("pool {} does not swap {source_mint}", self.pool);
+        }
+
+        let account = rpc
+            .get_account(&self.pool)
+            .await?
+            .with_context(|| format!("swap pool {} not found", self.pool))?;
+        let info = SwapInfo::unpack(&account.data)?;
+
+        let (balance_a, balance_b) = token_balances(rpc, &self.token_a, &self.token_b).await?;
+        let clock = get_clock(rpc).await?;
+
+        quote_saber(
+            &info,
+            balance_a,
+            balance_b,
+            source_mint,
+            amount_in,
+            clock.unix_timestamp,
+        )
+        .with_context(|| format!("pool {} cannot swap {amount_in}", self.pool))
+    }
+}
+
+#[async_trait]
+impl SwapQuoter for WhirlpoolSwap {
+    async fn quote(
+        &self,
+        rpc: &(dyn SolanaRpc + 'static),
+        source_mint: &Pubkey,
+        amount_in: u64,
+    ) -> Result<SwapQuote> {
+        let a_to_b = match *source_mint {
+            mint if mint == self.token_a => true,
+            mint if mint == self.token_b => false,
+            _ => bail!("whirlpool {} does not swap {source_mint}", self.whirlpool),
+        };
+
+        let whirlpool = rpc.get_anchor_account::<Whirlpool>(&self.whirlpool).await?;
+
+        // the tick arrays are derived from the current price, as the price may have moved
+        // since this swap was loaded
+        let tick_array_addresses = whirlpool_tick_arrays(&self.whirlpool, &whirlpool, a_to_b);
+        let tick_arrays = rpc
+            .get_accounts_all(&tick_array_addresses)
+            .await?
+            .into_iter()
+            .map_while(|account| account.map(|a| read_tick_array(&a.data)))
+            .collect::<Option<Vec<_>>>()
+            .context("tick array account is too small")?;
+
+        let clock = get_clock(rpc).await?;
+
+        quote_whirlpool(
+            &whirlpool,
+            tick_arrays,
+            source_mint,
+            amount_in,
+            clock.unix_timestamp as u64,
+        )
+        .with_context(|| format!("whirlpool {} cannot swap {amount_in}", self.whirlpool))
+    }
+}
+
+#[async_trait]
+impl SwapQuoter for OpenBookMarket {
+    async fn quote(
+        &self,
+        rpc: &(dyn SolanaRpc + 'static),
+        source_mint: &Pubkey,
+        amount_in: u64,
+    ) -> Result<SwapQuote> {
+        let book_side = match *source_mint {
+            mint if mint == self.base_mint => self.bids,
+            mint if mint == self.quote_mint => self.asks,
+            _ => bail!("market {} does not swap {source_mint}", self.market),
+        };
+
+        let account = rpc
+            .get_account(&book_side)
+            .await?
+            .with_context(|| format!("orderbook {book_side} not found"))?;
+        let orders = read_book_side(&account.data).context("orderbook account is too small")?;
+
+        quote_openbook(
+            &self.base_mint,
+            &self.quote_mint,
+            self.base_lot_size,
+            self.quote_lot_size,
+            orders,
+            source_mint,
+            amount_in,
+        )
+        .with_context(|| format!("market {} has no orders to swap against", self.market))
+    }
+}
+
+async fn token_balances(
+    rpc: &(dyn SolanaRpc + 'static),
+    token_a: &Pubkey,
+    token_b: &Pubkey,
+) -> Result<(u64, u64)> {
+    let a = rpc.get_token_account(token_a).await?;
+    let b = rpc.get_token_account(token_b).await?;
+
+    Ok((a.amount, b.amount))
+}
+
+async fn get_clock(rpc: &(dyn SolanaRpc + 'static)) -> Result<Clock> {
+    let account = rpc
+        .get_account(&sysvar::clock::ID)
+        .await?
+        .context("clock sysvar not found")?;
+
+    Ok(bincode::deserialize(&account.data)?)
+}
@@ -1,7 +1,9 @@
 //! This module only defines the generic code for executing margin invocations.
 //! Other modules define ways to use this context to invoke specific adapters.
 
-use jet_instructions::margin::{accounting_invoke, adapter_invoke, liquidator_invoke};
+use jet_instructions::margin::{
+    accounting_invoke, adapter_invoke, delegate_adapter_invoke, liquidator_invoke,
+};
 use jet_solana_client::{signature::NeedsSignature, transaction::TransactionBuilder};
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 
@@ -24,6 +26,8 @@ pub struct MarginInvokeContext {
     pub authority: Pubkey,
     /// Is the authority a liquidator?
     pub is_liquidator: bool,
+    /// Is the authority a delegate of the owner?
+    pub is_delegate: bool,
 }
 
 impl MarginInvokeContext {
@@ -33,10 +37,13 @@ impl MarginInvokeContext {
             margin_account,
             authority,
             is_liquidator,
+            is_delegate,
         } = self;
         if inner.needs_signature(*margin_account) {
             if *is_liquidator {
                 liquidator_invoke(*airspace, *authority, *margin_account, inner)
+            } else if *is_delegate {
+                delegate_adapter_invoke(*airspace, *authority, *margin_account, inner)
             } else {
                 adapter_invoke(*airspace, *authority, *margin_account, inner)
             }
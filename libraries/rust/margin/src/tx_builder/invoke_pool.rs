@@ -74,7 +74,11 @@ impl MarginInvokeContext {
                 payer,
                 pool.deposit_note_mint,
             ),
-            self.invoke(pool.margin_refresh_position(self.margin_account, pool_oracle)),
+            self.invoke(pool.margin_refresh_position(
+                &self.airspace,
+                self.margin_account,
+                pool_oracle
+            )),
         ]
     }
 }
@@ -39,7 +39,9 @@ use jet_margin_pool::{MarginPool, TokenChange};
 use jet_simulation::solana_rpc_api::SolanaRpcClient;
 
 use crate::cat;
-use crate::get_state::{get_margin_account, get_token_metadata};
+use crate::get_state::{
+    get_fallback_oracles, get_margin_account, get_position_config, get_token_metadata,
+};
 use crate::lookup_tables::LookupTable;
 use crate::margin_account_ext::MarginAccountExt;
 use crate::refresh::deposit::refresh_deposit_positions;
@@ -174,6 +176,7 @@ impl MarginTxBuilder {
             margin_account: *self.address(),
             authority: MarginActionAuthority::AccountAuthority.resolve(&self.ix),
             is_liquidator: self.is_liquidator,
+            is_delegate: self.is_delegate(),
         }
     }
 
@@ -182,6 +185,11 @@ impl MarginTxBuilder {
         self.is_liquidator
     }
 
+    /// whether the current builder is for a delegate of the owner
+    pub fn is_delegate(&self) -> bool {
+        !self.is_liquidator && self.ix.authority() != self.ix.owner
+    }
+
     /// Creates a new Self for actions on the same margin account, but
     /// authorized by provided liquidator.
     pub fn liquidator(&self, liquidator: Keypair) -> Self {
@@ -195,6 +203,9 @@ impl MarginTxBuilder {
     }
 
     /// Creates a variant of the builder that has a signer other than the payer.
+    ///
+    /// If the signer is not the owner, it must be a delegate configured by the owner
+    /// with [Self::configure_delegate], and it can only act through adapters.
     pub fn with_signer(mut self, signer: Keypair) -> Self {
         self.ix = self.ix.with_authority(signer.pubkey());
         self.signer = Some(signer);
@@ -271,6 +282,38 @@ impl MarginTxBuilder {
         self.create_transaction(&[self.ix.close_account()]).await
     }
 
+    /// Transaction to pre-authorize an adapter instruction, such as a stop-loss swap,
+    /// that any keeper may execute once the trigger holds
+    pub async fn create_conditional_action(
+        &self,
+        seed: u16,
+        trigger: ActionTrigger,
+        adapter_ix: &Instruction,
+        tip: u64,
+    ) -> Result<Transaction> {
+        self.create_transaction(&[self
+            .ix
+            .create_conditional_action(seed, trigger, adapter_ix, tip)])
+            .await
+    }
+
+    /// Transaction to cancel a conditional action
+    pub async fn cancel_conditional_action(&self, seed: u16) -> Result<Transaction> {
+        self.create_transaction(&[self.ix.cancel_conditional_action(seed)])
+            .await
+    }
+
+    /// Transaction to authorize a delegate to operate the margin account, or to
+    /// revoke it if the update is `None`
+    pub async fn configure_delegate(
+        &self,
+        delegate: Pubkey,
+        update: Option<MarginDelegateUpdate>,
+    ) -> Result<Transaction> {
+        self.create_transaction(&[self.ix.configure_delegate(delegate, update)])
+            .await
+    }
+
     /// Transaction to create an address lookup registry account
     pub async fn init_lookup_registry(&self) -> Result<Transaction> {
         self.create_transaction(&[self.ix.init_lookup_registry()])
@@ -456,8 +499,13 @@ impl MarginTxBuilder {
             .get_or_create_pool_loan_position(&mut instructions, &pool)
             .await?;
 
-        let inner_refresh_loan_ix =
-            pool.margin_refresh_position(self.ix.address, token_metadata.pyth_price);
+        let fallback_oracles = self.pool_fallback_oracles(token_mint).await?;
+        let inner_refresh_loan_ix = pool.margin_refresh_position_with_fallbacks(
+            &self.ix.airspace,
+            self.ix.address,
+            token_metadata.pyth_price,
+            &fallback_oracles,
+        );
         instructions.push(self.ix.accounting_invoke(inner_refresh_loan_ix));
 
         let inner_borrow_ix = pool.margin_borrow(self.ix.address, deposit_position, change);
@@ -727,10 +775,24 @@ impl MarginTxBuilder {
     pub async fn refresh_pool_position(&self, token_mint: &Pubkey) -> Result<Instruction> {
         let ix_builder = MarginPoolIxBuilder::new(*token_mint);
         let pool_oracle = self.get_pool(token_mint).await?.token_price_oracle;
+        let fallback_oracles = self.pool_fallback_oracles(token_mint).await?;
 
         Ok(self
             .ix
-            .accounting_invoke(ix_builder.margin_refresh_position(self.ix.address, pool_oracle)))
+            .accounting_invoke(ix_builder.margin_refresh_position_with_fallbacks(
+                &self.ix.airspace,
+                self.ix.address,
+                pool_oracle,
+                &fallback_oracles,
+            )))
+    }
+
+    /// The fallback oracles that a pool's price is checked against in this airspace
+    async fn pool_fallback_oracles(&self, token_mint: &Pubkey) -> Result<Vec<Pubkey>> {
+        match get_position_config(&self.rpc, &self.ix.airspace, token_mint).await? {
+            Some((address, config)) => get_fallback_oracles(&self.rpc, &address, &config).await,
+            None => Ok(vec![]),
+        }
     }
 
     /// Append instructions to refresh pool positions to instructions
@@ -0,0 +1,105 @@
+//! A minimal price account layout for oracles that push prices on-chain.
+//!
+//! Tokens which are not covered by Pyth can be priced by any program that writes
+//! a [PushPriceFeed] into an account it owns. Consumers must pin both the price
+//! account address and the owning program in their configuration, since the
+//! layout itself carries no proof of where the price came from.
+
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use solana_program::pubkey::Pubkey;
+use static_assertions::const_assert_eq;
+
+/// Identifies an account as containing a [PushPriceFeed] ("JPPF")
+pub const PUSH_PRICE_FEED_MAGIC: u32 = 0x4a50_5046;
+
+/// The latest price published by a push oracle
+#[derive(Pod, Zeroable, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct PushPriceFeed {
+    /// Must be [PUSH_PRICE_FEED_MAGIC]
+    pub magic: u32,
+
+    /// The exponent for the price values
+    pub exponent: i32,
+
+    /// The address allowed to publish new prices to this account
+    pub authority: Pubkey,
+
+    /// The current price
+    pub price: i64,
+
+    /// The confidence interval around the current price
+    pub confidence: u64,
+
+    /// A moving average of the price
+    pub ema_price: i64,
+
+    /// The time the price was published
+    pub publish_time: i64,
+}
+
+const_assert_eq!(72, size_of::<PushPriceFeed>());
+
+impl PushPriceFeed {
+    pub const SIZE: usize = size_of::<Self>();
+
+    /// Create an empty price feed which may be published to by the authority
+    pub fn new(exponent: i32, authority: Pubkey) -> Self {
+        Self {
+            magic: PUSH_PRICE_FEED_MAGIC,
+            exponent,
+            authority,
+            ..Default::default()
+        }
+    }
+
+    /// Read a price feed from the account data, if the data contains one
+    pub fn load(data: &[u8]) -> Option<Self> {
+        if data.len() < Self::SIZE {
+            return None;
+        }
+
+        let feed: Self = bytemuck::try_pod_read_unaligned(&data[..Self::SIZE]).ok()?;
+
+        match feed.magic {
+            PUSH_PRICE_FEED_MAGIC => Some(feed),
+            _ => None,
+        }
+    }
+
+    /// Write this price feed into the account data
+    ///
+    /// Returns `None` if the data is too small to hold the feed
+    pub fn store(&self, data: &mut [u8]) -> Option<()> {
+        data.get_mut(..Self::SIZE)?
+            .copy_from_slice(bytemuck::bytes_of(self));
+
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_requires_magic() {
+        let mut data = [0u8; PushPriceFeed::SIZE];
+        assert!(PushPriceFeed::load(&data).is_none());
+
+        let authority = Pubkey::new_unique();
+        PushPriceFeed::new(-6, authority).store(&mut data).unwrap();
+
+        let feed = PushPriceFeed::load(&data).unwrap();
+        assert_eq!(feed.exponent, -6);
+        assert_eq!(feed.authority, authority);
+    }
+
+    #[test]
+    fn load_rejects_short_data() {
+        let data = [0u8; PushPriceFeed::SIZE - 1];
+        assert!(PushPriceFeed::load(&data).is_none());
+    }
+}
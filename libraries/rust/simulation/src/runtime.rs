@@ -1,7 +1,10 @@
 use std::{cell::RefCell, collections::HashMap, sync::Arc, sync::Mutex};
 
 use async_trait::async_trait;
-use jet_solana_client::rpc::{AccountFilter, ClientError, ClientResult, SolanaRpc};
+use jet_solana_client::rpc::{
+    AccountFilter, AccountUpdate, AccountUpdateSender, ClientError, ClientResult, SolanaRpc,
+    SolanaSubscriptions,
+};
 use lazy_static::lazy_static;
 
 use solana_bpf_loader_program::serialization::{
@@ -169,6 +172,7 @@ impl TestRuntime {
     pub fn rpc(&self) -> TestRuntimeRpcClient {
         TestRuntimeRpcClient {
             manager: Arc::new(BankManager::new(self.bank.clone())),
+            subscriptions: Arc::new(Mutex::new(vec![])),
         }
     }
 }
@@ -571,9 +575,21 @@ impl BankManager {
     }
 }
 
+/// A subscription to changes in accounts, which are sent after each processed transaction
+struct Subscription {
+    watch: Watch,
+    updates: AccountUpdateSender,
+}
+
+enum Watch {
+    Account(Pubkey),
+    Program(Pubkey, Vec<AccountFilter>),
+}
+
 #[derive(Clone)]
 pub struct TestRuntimeRpcClient {
     manager: Arc<BankManager>,
+    subscriptions: Arc<Mutex<Vec<Subscription>>>,
 }
 
 impl TestRuntimeRpcClient {
@@ -581,6 +597,37 @@ impl TestRuntimeRpcClient {
         self.manager.bank.lock().unwrap().clone()
     }
 
+    /// Send the current state of the accounts to any matching subscriptions
+    fn notify(&self, addresses: &[Pubkey]) {
+        let bank = self.bank();
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        subscriptions.retain(|s| !s.updates.is_closed());
+
+        for address in addresses {
+            let account = bank
+                .get_account(address)
+                .map(Account::from)
+                .unwrap_or_default();
+
+            for subscription in subscriptions.iter() {
+                let matches = match &subscription.watch {
+                    Watch::Account(watched) => watched == address,
+                    Watch::Program(program, filters) => {
+                        account.owner == *program && filters.iter().all(|f| f.matches(&account))
+                    }
+                };
+
+                if matches {
+                    let _ = subscription.updates.send(AccountUpdate {
+                        address: *address,
+                        slot: bank.slot(),
+                        account: account.clone(),
+                    });
+                }
+            }
+        }
+    }
+
     pub fn set_clock(&self, new_clock: &Clock) {
         self.bank().set_sysvar_for_tests(new_clock);
     }
@@ -668,19 +715,29 @@ impl SolanaRpc for TestRuntimeRpcClient {
     async fn airdrop(&self, account: &Pubkey, lamports: u64) -> ClientResult<()> {
         match self.bank().deposit(account, lamports) {
             Err(e) => return Err(ClientError::Other(format!("airdrop failed: {:?}", e))),
-            Ok(_) => Ok(()),
+            Ok(_) => {
+                self.notify(&[*account]);
+                Ok(())
+            }
         }
     }
 
     async fn send_transaction_legacy(&self, transaction: &Transaction) -> ClientResult<Signature> {
-        Ok(send_legacy_transaction(&self.bank(), transaction)?)
+        let signature = send_legacy_transaction(&self.bank(), transaction)?;
+        self.notify(&transaction.message.account_keys);
+
+        Ok(signature)
     }
 
     async fn send_transaction(
         &self,
         transaction: &VersionedTransaction,
     ) -> ClientResult<Signature> {
-        Ok(send_transaction(&self.bank(), transaction)?)
+        let signature = send_transaction(&self.bank(), transaction)?;
+        // accounts loaded from lookup tables are not notified
+        self.notify(transaction.message.static_account_keys());
+
+        Ok(signature)
     }
 
     async fn get_program_accounts(
@@ -740,6 +797,36 @@ impl SolanaRpc for TestRuntimeRpcClient {
     }
 }
 
+#[async_trait]
+impl SolanaSubscriptions for TestRuntimeRpcClient {
+    async fn account_subscribe(
+        &self,
+        address: &Pubkey,
+        updates: AccountUpdateSender,
+    ) -> ClientResult<()> {
+        self.subscriptions.lock().unwrap().push(Subscription {
+            watch: Watch::Account(*address),
+            updates,
+        });
+
+        Ok(())
+    }
+
+    async fn program_subscribe(
+        &self,
+        program: &Pubkey,
+        filters: &[AccountFilter],
+        updates: AccountUpdateSender,
+    ) -> ClientResult<()> {
+        self.subscriptions.lock().unwrap().push(Subscription {
+            watch: Watch::Program(*program, filters.to_vec()),
+            updates,
+        });
+
+        Ok(())
+    }
+}
+
 #[macro_export]
 macro_rules! create_test_runtime {
     [$($program:tt),+$(,)?] => {{
@@ -804,4 +891,53 @@ mod test {
 
         assert_eq!(420 * LAMPORTS_PER_SOL, dest_balance);
     }
+
+    #[tokio::test]
+    async fn subscriptions_receive_changed_accounts() {
+        let rt = TestRuntime::new([], []);
+        let rpc = rt.rpc();
+
+        let source_wallet = Keypair::new();
+        let dest_wallet = Keypair::new();
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        rpc.account_subscribe(&dest_wallet.pubkey(), sender.clone())
+            .await
+            .unwrap();
+        rpc.program_subscribe(&solana_sdk::system_program::ID, &[], sender)
+            .await
+            .unwrap();
+
+        SolanaRpc::airdrop(&rpc, &source_wallet.pubkey(), 2 * LAMPORTS_PER_SOL)
+            .await
+            .unwrap();
+        let recent_blockhash = SolanaRpc::get_latest_blockhash(&rpc).await.unwrap();
+        let transfer_tx = system_transaction::transfer(
+            &source_wallet,
+            &dest_wallet.pubkey(),
+            LAMPORTS_PER_SOL,
+            recent_blockhash,
+        );
+        SolanaRpc::send_transaction_legacy(&rpc, &transfer_tx)
+            .await
+            .unwrap();
+
+        let mut updates = vec![];
+        while let Ok(update) = receiver.try_recv() {
+            updates.push((update.address, update.account.lamports));
+        }
+
+        // the program subscription sees both wallets, the account subscription only the destination
+        let dest_updates = updates
+            .iter()
+            .filter(|(address, _)| *address == dest_wallet.pubkey())
+            .collect::<Vec<_>>();
+        assert_eq!(dest_updates.len(), 2);
+        assert!(dest_updates
+            .iter()
+            .all(|(_, lamports)| *lamports == LAMPORTS_PER_SOL));
+        assert!(updates
+            .iter()
+            .any(|(address, _)| *address == source_wallet.pubkey()));
+    }
 }
@@ -121,6 +121,44 @@ pub trait SolanaRpc: Send + Sync {
     }
 }
 
+/// A change to an account, received from a subscription
+#[derive(Debug, Clone)]
+pub struct AccountUpdate {
+    /// The address of the changed account
+    pub address: Pubkey,
+
+    /// The slot the change was made in
+    pub slot: u64,
+
+    /// The new state of the account
+    pub account: Account,
+}
+
+/// The channel that subscriptions send their account updates to
+pub type AccountUpdateSender = tokio::sync::mpsc::UnboundedSender<AccountUpdate>;
+
+/// The channel that account updates are received from
+pub type AccountUpdateReceiver = tokio::sync::mpsc::UnboundedReceiver<AccountUpdate>;
+
+/// A type that allows for subscribing to account changes from a Solana RPC node
+///
+/// Subscriptions send updates until the receiving end of the channel is dropped.
+#[async_trait]
+pub trait SolanaSubscriptions: Send + Sync {
+    async fn account_subscribe(
+        &self,
+        address: &Pubkey,
+        updates: AccountUpdateSender,
+    ) -> ClientResult<()>;
+
+    async fn program_subscribe(
+        &self,
+        program: &Pubkey,
+        filters: &[AccountFilter],
+        updates: AccountUpdateSender,
+    ) -> ClientResult<()>;
+}
+
 /// Extra helper functions for using the Solana RPC API
 #[async_trait]
 pub trait SolanaRpcExtra: SolanaRpc {
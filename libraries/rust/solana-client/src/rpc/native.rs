@@ -1,14 +1,17 @@
 use async_trait::async_trait;
+use futures::StreamExt;
 use serde_json::json;
 use std::{str::FromStr, sync::Arc};
+use tokio::sync::oneshot;
 
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
-    nonblocking::rpc_client::RpcClient,
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
     rpc_config::{
         RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig,
         RpcTokenAccountsFilter,
     },
+    rpc_filter::{Memcmp, RpcFilterType},
     rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
     rpc_response::{Response, RpcKeyedAccount},
 };
@@ -23,7 +26,10 @@ use solana_sdk::{
 };
 use spl_token::state::Account as TokenAccount;
 
-use super::{AccountFilter, ClientError, ClientResult, SolanaRpc};
+use super::{
+    AccountFilter, AccountUpdate, AccountUpdateSender, ClientError, ClientResult, SolanaRpc,
+    SolanaSubscriptions,
+};
 
 /// A wrapper for an RPC client to implement `SolanaRpc` trait
 #[derive(Clone)]
@@ -179,20 +185,8 @@ impl SolanaRpc for RpcConnection {
         program: &Pubkey,
         filters: &[AccountFilter],
     ) -> ClientResult<Vec<(Pubkey, solana_sdk::account::Account)>> {
-        use solana_client::rpc_filter::*;
-
         let config = RpcProgramAccountsConfig {
-            filters: Some(
-                filters
-                    .iter()
-                    .map(|filter| match filter {
-                        AccountFilter::Memcmp { offset, bytes } => {
-                            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(*offset, bytes.clone()))
-                        }
-                        AccountFilter::DataSize(size) => RpcFilterType::DataSize(*size as u64),
-                    })
-                    .collect(),
-            ),
+            filters: Some(convert_filters(filters)),
             account_config: RpcAccountInfoConfig {
                 encoding: Some(UiAccountEncoding::Base64),
                 data_slice: None,
@@ -262,6 +256,161 @@ impl SolanaRpc for RpcConnection {
     }
 }
 
+/// A websocket connection to a node, to implement the `SolanaSubscriptions` trait
+#[derive(Clone)]
+pub struct PubsubConnection {
+    pubsub: Arc<PubsubClient>,
+}
+
+impl PubsubConnection {
+    pub async fn new(url: &str) -> ClientResult<Self> {
+        let pubsub = PubsubClient::new(url)
+            .await
+            .map_err(|e| ClientError::Other(e.to_string()))?;
+
+        Ok(Self {
+            pubsub: Arc::new(pubsub),
+        })
+    }
+}
+
+impl std::fmt::Debug for PubsubConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PubsubConnection").finish()
+    }
+}
+
+#[async_trait]
+impl SolanaSubscriptions for PubsubConnection {
+    async fn account_subscribe(
+        &self,
+        address: &Pubkey,
+        updates: AccountUpdateSender,
+    ) -> ClientResult<()> {
+        let pubsub = self.pubsub.clone();
+        let address = *address;
+        let (subscribed, result) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let config = RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(CommitmentConfig::processed()),
+                ..Default::default()
+            };
+            let (mut stream, unsubscribe) =
+                match pubsub.account_subscribe(&address, Some(config)).await {
+                    Ok(subscription) => subscription,
+                    Err(e) => {
+                        let _ = subscribed.send(Err(ClientError::Other(e.to_string())));
+                        return;
+                    }
+                };
+            let _ = subscribed.send(Ok(()));
+
+            while let Some(response) = stream.next().await {
+                let Some(account) = response.value.decode::<Account>() else {
+                    log::warn!("cannot read account data from subscription to {address}");
+                    continue;
+                };
+                let update = AccountUpdate {
+                    address,
+                    slot: response.context.slot,
+                    account,
+                };
+
+                if updates.send(update).is_err() {
+                    break;
+                }
+            }
+
+            drop(stream);
+            unsubscribe().await;
+        });
+
+        result.await.map_err(|_| {
+            ClientError::Other(format!("subscription to account {address} was dropped"))
+        })?
+    }
+
+    async fn program_subscribe(
+        &self,
+        program: &Pubkey,
+        filters: &[AccountFilter],
+        updates: AccountUpdateSender,
+    ) -> ClientResult<()> {
+        let pubsub = self.pubsub.clone();
+        let program = *program;
+        let filters = convert_filters(filters);
+        let (subscribed, result) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::processed()),
+                    ..Default::default()
+                },
+                with_context: Some(true),
+            };
+            let (mut stream, unsubscribe) =
+                match pubsub.program_subscribe(&program, Some(config)).await {
+                    Ok(subscription) => subscription,
+                    Err(e) => {
+                        let _ = subscribed.send(Err(ClientError::Other(e.to_string())));
+                        return;
+                    }
+                };
+            let _ = subscribed.send(Ok(()));
+
+            while let Some(response) = stream.next().await {
+                let keyed = response.value;
+                let (address, account) = match (
+                    Pubkey::from_str(&keyed.pubkey),
+                    keyed.account.decode::<Account>(),
+                ) {
+                    (Ok(address), Some(account)) => (address, account),
+                    _ => {
+                        log::warn!(
+                            "cannot read account {} from subscription to program {program}",
+                            keyed.pubkey
+                        );
+                        continue;
+                    }
+                };
+                let update = AccountUpdate {
+                    address,
+                    slot: response.context.slot,
+                    account,
+                };
+
+                if updates.send(update).is_err() {
+                    break;
+                }
+            }
+
+            drop(stream);
+            unsubscribe().await;
+        });
+
+        result.await.map_err(|_| {
+            ClientError::Other(format!("subscription to program {program} was dropped"))
+        })?
+    }
+}
+
+fn convert_filters(filters: &[AccountFilter]) -> Vec<RpcFilterType> {
+    filters
+        .iter()
+        .map(|filter| match filter {
+            AccountFilter::Memcmp { offset, bytes } => {
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(*offset, bytes.clone()))
+            }
+            AccountFilter::DataSize(size) => RpcFilterType::DataSize(*size as u64),
+        })
+        .collect()
+}
+
 fn convert_err(e: solana_client::client_error::ClientError) -> ClientError {
     match e.kind {
         solana_client::client_error::ClientErrorKind::TransactionError(e) => {
@@ -30,8 +30,16 @@ export interface OrderParams {
   postAllowed: boolean
   autoStake: boolean
   autoRoll: boolean
+  timeInForce: TimeInForce
 }
 
+/** How long an order may remain on the orderbook */
+export type TimeInForce =
+  | { goodTilCancelled: {} }
+  | { immediateOrCancel: {} }
+  | { fillOrKill: {} }
+  | { goodTilTime: { expiresAt: BN } }
+
 export interface DebtInfo {
   nextNewTermLoanSeqno: BN
   nextUnpaidTermLoanSeqno: BN
@@ -79,6 +87,7 @@ export class FixedTermMarket {
     underlyingTokenMint: PublicKey
     underlyingTokenVault: PublicKey
     feeVault: PublicKey
+    feeSchedule: PublicKey
     ticketMint: PublicKey
     claimsMint: PublicKey
     claimsMetadata: PublicKey
@@ -99,6 +108,7 @@ export class FixedTermMarket {
     ticketCollateralMetadata: PublicKey,
     underlyingCollateralMetadata: PublicKey,
     marginAdapterMetadata: PublicKey,
+    feeSchedule: PublicKey,
     program: Program<JetFixedTermIDL>,
     info: MarketInfo
   ) {
@@ -120,6 +130,7 @@ export class FixedTermMarket {
       ticketCollateralMetadata,
       underlyingCollateralMetadata,
       marginAdapterMetadata,
+      feeSchedule,
       market
     }
     this.program = program
@@ -165,6 +176,7 @@ export class FixedTermMarket {
       [program.programId],
       new PublicKey(jetMarginProgramId)
     )
+    const feeSchedule = await findFixedTermDerivedAccount(["fee_schedule", new PublicKey(market)], program.programId)
 
     return new FixedTermMarket(
       new PublicKey(market),
@@ -172,6 +184,7 @@ export class FixedTermMarket {
       new PublicKey(ticketCollateralMetadata),
       new PublicKey(underlyingCollateralMetadata),
       new PublicKey(marginAdapterMetadata),
+      new PublicKey(feeSchedule),
       program,
       info
     )
@@ -195,7 +208,8 @@ export class FixedTermMarket {
       postOnly: false,
       postAllowed: true,
       autoStake: true,
-      autoRoll
+      autoRoll,
+      timeInForce: { goodTilCancelled: {} }
     }
     return await this.borrowIx(user, payer, params, seed)
   }
@@ -216,7 +230,8 @@ export class FixedTermMarket {
       postOnly: false,
       postAllowed: false,
       autoStake: true,
-      autoRoll
+      autoRoll,
+      timeInForce: { goodTilCancelled: {} }
     }
     return await this.borrowIx(user, payer, params, seed)
   }
@@ -272,7 +287,8 @@ export class FixedTermMarket {
       postOnly: false,
       postAllowed: true,
       autoStake: true,
-      autoRoll
+      autoRoll,
+      timeInForce: { goodTilCancelled: {} }
     }
     return await this.lendIx(user, userTicketVault, userTokenVault, payer, params, seed)
   }
@@ -294,7 +310,8 @@ export class FixedTermMarket {
       postOnly: false,
       postAllowed: false,
       autoStake: true,
-      autoRoll
+      autoRoll,
+      timeInForce: { goodTilCancelled: {} }
     }
     return await this.lendIx(user, userTicketVault, userTokenVault, payer, params, seed)
   }
@@ -411,7 +428,8 @@ export class FixedTermMarket {
       orderbookMarketState: this.addresses.orderbookMarketState,
       eventQueue: this.addresses.eventQueue,
       bids: this.addresses.bids,
-      asks: this.addresses.asks
+      asks: this.addresses.asks,
+      feeSchedule: this.addresses.feeSchedule
     }
   }
 
@@ -207,6 +207,11 @@ export const IDL: JetControlIDL = {
           isMut: false,
           isSigner: false
         },
+        {
+          name: "payer",
+          isMut: true,
+          isSigner: true
+        },
         {
           name: "marginPoolProgram",
           isMut: false,
@@ -216,6 +221,11 @@ export const IDL: JetControlIDL = {
           name: "metadataProgram",
           isMut: false,
           isSigner: false
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false
         }
       ],
       args: [
@@ -68,6 +68,11 @@ export const IDL: JetFixedTermIDL = {
       name: "FEE_VAULT",
       type: "bytes",
       value: "[102, 101, 101, 95, 118, 97, 117, 108, 116]"
+    },
+    {
+      name: "FEE_SCHEDULE",
+      type: "bytes",
+      value: "[102, 101, 101, 95, 115, 99, 104, 101, 100, 117, 108, 101]"
     }
   ],
   instructions: [
@@ -355,6 +360,55 @@ export const IDL: JetFixedTermIDL = {
         }
       ]
     },
+    {
+      name: "configureFeeSchedule",
+      docs: ["Set the maker and taker fees charged on orderbook fills"],
+      accounts: [
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+          docs: ["The `Market` charging the fees"]
+        },
+        {
+          name: "feeSchedule",
+          isMut: true,
+          isSigner: false,
+          docs: ["The fees charged by the market"]
+        },
+        {
+          name: "authority",
+          isMut: false,
+          isSigner: true,
+          docs: ["The authority that must sign to make this change"]
+        },
+        {
+          name: "airspace",
+          isMut: false,
+          isSigner: false,
+          docs: ["The airspace being modified"]
+        },
+        {
+          name: "payer",
+          isMut: true,
+          isSigner: true,
+          docs: ["The address paying the rent for the account"]
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false
+        }
+      ],
+      args: [
+        {
+          name: "params",
+          type: {
+            defined: "FeeScheduleParams"
+          }
+        }
+      ]
+    },
     {
       name: "pauseOrderMatching",
       docs: ["Pause matching of orders placed in the orderbook"],
@@ -533,6 +587,12 @@ export const IDL: JetFixedTermIDL = {
               name: "asks",
               isMut: true,
               isSigner: false
+            },
+            {
+              name: "feeSchedule",
+              isMut: true,
+              isSigner: false,
+              docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
             }
           ]
         },
@@ -641,6 +701,12 @@ export const IDL: JetFixedTermIDL = {
               name: "asks",
               isMut: true,
               isSigner: false
+            },
+            {
+              name: "feeSchedule",
+              isMut: true,
+              isSigner: false,
+              docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
             }
           ]
         },
@@ -959,6 +1025,12 @@ export const IDL: JetFixedTermIDL = {
               name: "asks",
               isMut: true,
               isSigner: false
+            },
+            {
+              name: "feeSchedule",
+              isMut: true,
+              isSigner: false,
+              docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
             }
           ]
         },
@@ -1060,6 +1132,12 @@ export const IDL: JetFixedTermIDL = {
                   name: "asks",
                   isMut: true,
                   isSigner: false
+                },
+                {
+                  name: "feeSchedule",
+                  isMut: true,
+                  isSigner: false,
+                  docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
                 }
               ]
             },
@@ -1205,6 +1283,12 @@ export const IDL: JetFixedTermIDL = {
               name: "asks",
               isMut: true,
               isSigner: false
+            },
+            {
+              name: "feeSchedule",
+              isMut: true,
+              isSigner: false,
+              docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
             }
           ]
         },
@@ -1523,6 +1607,12 @@ export const IDL: JetFixedTermIDL = {
               name: "asks",
               isMut: true,
               isSigner: false
+            },
+            {
+              name: "feeSchedule",
+              isMut: true,
+              isSigner: false,
+              docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
             }
           ]
         },
@@ -1591,6 +1681,12 @@ export const IDL: JetFixedTermIDL = {
               name: "asks",
               isMut: true,
               isSigner: false
+            },
+            {
+              name: "feeSchedule",
+              isMut: true,
+              isSigner: false,
+              docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
             }
           ]
         }
@@ -1640,6 +1736,12 @@ export const IDL: JetFixedTermIDL = {
               name: "asks",
               isMut: true,
               isSigner: false
+            },
+            {
+              name: "feeSchedule",
+              isMut: true,
+              isSigner: false,
+              docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
             }
           ]
         },
@@ -1723,6 +1825,12 @@ export const IDL: JetFixedTermIDL = {
           isSigner: false,
           docs: ["The market token vault"]
         },
+        {
+          name: "feeVault",
+          isMut: true,
+          isSigner: false,
+          docs: ["The market fee vault"]
+        },
         {
           name: "orderbookMarketState",
           isMut: true,
@@ -1733,6 +1841,22 @@ export const IDL: JetFixedTermIDL = {
           isMut: true,
           isSigner: false
         },
+        {
+          name: "bids",
+          isMut: false,
+          isSigner: false
+        },
+        {
+          name: "asks",
+          isMut: false,
+          isSigner: false
+        },
+        {
+          name: "feeSchedule",
+          isMut: true,
+          isSigner: false,
+          docs: ["The fees charged on fills, which may not be initialized if the market charges no fees"]
+        },
         {
           name: "crankAuthorization",
           isMut: false,
@@ -1771,6 +1895,96 @@ export const IDL: JetFixedTermIDL = {
         }
       ]
     },
+    {
+      name: "expireOrders",
+      docs: [
+        "Crank specific instruction, removes `GoodTilTime` orders that have expired from the",
+        "book. Their unfilled amounts are returned to their owners when events are consumed."
+      ],
+      accounts: [
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+          docs: ["The `Market` account tracks global information related to this particular fixed term market"]
+        },
+        {
+          name: "orderbookMarketState",
+          isMut: true,
+          isSigner: false
+        },
+        {
+          name: "eventQueue",
+          isMut: true,
+          isSigner: false
+        },
+        {
+          name: "bids",
+          isMut: true,
+          isSigner: false
+        },
+        {
+          name: "asks",
+          isMut: true,
+          isSigner: false
+        },
+        {
+          name: "crankAuthorization",
+          isMut: false,
+          isSigner: false
+        },
+        {
+          name: "crank",
+          isMut: false,
+          isSigner: true
+        }
+      ],
+      args: [
+        {
+          name: "orderIds",
+          type: {
+            vec: "u128"
+          }
+        }
+      ]
+    },
+    {
+      name: "sampleTicketTwap",
+      docs: [
+        "Crank specific instruction, includes the current orderbook rate in the average used to",
+        "price tickets, for markets that price tickets from the orderbook. Events do not need to",
+        "be waiting in the queue, so the average is kept up to date while the market is quiet."
+      ],
+      accounts: [
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+          docs: ["The `Market` whose average orderbook rate is updated"]
+        },
+        {
+          name: "bids",
+          isMut: false,
+          isSigner: false
+        },
+        {
+          name: "asks",
+          isMut: false,
+          isSigner: false
+        },
+        {
+          name: "crankAuthorization",
+          isMut: false,
+          isSigner: false
+        },
+        {
+          name: "crank",
+          isMut: false,
+          isSigner: true
+        }
+      ],
+      args: []
+    },
     {
       name: "exchangeTokens",
       docs: [
@@ -2224,6 +2438,77 @@ export const IDL: JetFixedTermIDL = {
         ]
       }
     },
+    {
+      name: "feeSchedule",
+      docs: [
+        "The fees charged when orders are filled, in basis points of the tickets filled.",
+        "",
+        "Makers are charged when their fills are processed by `consume_events`, and takers when",
+        "their orders are matched. A negative maker fee is a rebate, paid out of the fee vault.",
+        "",
+        "Lenders are charged in tickets, which are only backed by underlying tokens once the loans",
+        "they fund are repaid. Lend fees are accrued until those tickets mature, and only then",
+        "moved into the fee vault."
+      ],
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "market",
+            docs: ["The market charging these fees"],
+            type: "publicKey"
+          },
+          {
+            name: "lendMakerFee",
+            docs: ["Fee charged to lenders whose resting orders are filled"],
+            type: "i16"
+          },
+          {
+            name: "lendTakerFee",
+            docs: ["Fee charged to lenders whose orders fill against the book"],
+            type: "u16"
+          },
+          {
+            name: "borrowMakerFee",
+            docs: ["Fee charged to borrowers whose resting orders are filled"],
+            type: "i16"
+          },
+          {
+            name: "borrowTakerFee",
+            docs: ["Fee charged to borrowers whose orders fill against the book"],
+            type: "u16"
+          },
+          {
+            name: "unsweptFees",
+            docs: [
+              "Borrow taker fees held in the underlying token vault, which are moved into the fee",
+              "vault when events are consumed"
+            ],
+            type: "u64"
+          },
+          {
+            name: "accruedTicketFees",
+            docs: ["Lend fees withheld as tickets since the current maturing fees were set aside"],
+            type: "u64"
+          },
+          {
+            name: "accruedTicketFeesMatureAt",
+            docs: ["The time when all the tickets in `accrued_ticket_fees` have matured"],
+            type: "i64"
+          },
+          {
+            name: "maturingTicketFees",
+            docs: ["Lend fees withheld as tickets that are waiting to mature"],
+            type: "u64"
+          },
+          {
+            name: "maturingTicketFeesMatureAt",
+            docs: ["The time when all the tickets in `maturing_ticket_fees` have matured"],
+            type: "i64"
+          }
+        ]
+      }
+    },
     {
       name: "marginUser",
       docs: ["An acocunt used to track margin users of the market"],
@@ -2473,6 +2758,35 @@ export const IDL: JetFixedTermIDL = {
         ]
       }
     },
+    {
+      name: "FeeScheduleParams",
+      docs: ["The fees charged on orderbook fills, in basis points of the tickets filled"],
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "lendMakerFee",
+            docs: ["Fee charged to lenders whose resting orders are filled, negative for a rebate"],
+            type: "i16"
+          },
+          {
+            name: "lendTakerFee",
+            docs: ["Fee charged to lenders whose orders fill against the book"],
+            type: "u16"
+          },
+          {
+            name: "borrowMakerFee",
+            docs: ["Fee charged to borrowers whose resting orders are filled, negative for a rebate"],
+            type: "i16"
+          },
+          {
+            name: "borrowTakerFee",
+            docs: ["Fee charged to borrowers whose orders fill against the book"],
+            type: "u16"
+          }
+        ]
+      }
+    },
     {
       name: "InitializeOrderbookParams",
       docs: ["Parameters necessary for orderbook initialization"],
@@ -2675,6 +2989,40 @@ export const IDL: JetFixedTermIDL = {
             name: "autoRoll",
             docs: ["Should the resulting `TermLoan` or `TermDeposit` be subject to an auto roll"],
             type: "bool"
+          },
+          {
+            name: "timeInForce",
+            docs: ["How long the order may remain on the orderbook"],
+            type: {
+              defined: "TimeInForce"
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "TimeInForce",
+      docs: ["How long an order may remain on the orderbook"],
+      type: {
+        kind: "enum",
+        variants: [
+          {
+            name: "GoodTilCancelled"
+          },
+          {
+            name: "ImmediateOrCancel"
+          },
+          {
+            name: "FillOrKill"
+          },
+          {
+            name: "GoodTilTime",
+            fields: [
+              {
+                name: "expiresAt",
+                type: "i64"
+              }
+            ]
           }
         ]
       }
@@ -2940,6 +3288,36 @@ export const IDL: JetFixedTermIDL = {
         }
       ]
     },
+    {
+      name: "FeeScheduleConfigured",
+      fields: [
+        {
+          name: "market",
+          type: "publicKey",
+          index: false
+        },
+        {
+          name: "lendMakerFee",
+          type: "i16",
+          index: false
+        },
+        {
+          name: "lendTakerFee",
+          type: "u16",
+          index: false
+        },
+        {
+          name: "borrowMakerFee",
+          type: "i16",
+          index: false
+        },
+        {
+          name: "borrowTakerFee",
+          type: "u16",
+          index: false
+        }
+      ]
+    },
     {
       name: "ToggleOrderMatching",
       fields: [
@@ -562,6 +562,7 @@ export const IDL: JetMarginIDL = {
         "| `payer` | `signer` | The address paying rent. |",
         "| `liquidator` | `signer` | The liquidator account performing the liquidation. |",
         "| `liquidator_metadata` | `read_only` | The metadata describing the liquidator. |",
+        "| `liquidation_config` | `read_only` | The liquidation config for the airspace, which may be uninitialized. |",
         "| `liquidation` | `writable` | The account to persist the state of liquidation. |",
         "| `system_program` | `read_only` | The [system native program](https://docs.solana.com/developing/runtime-facilities/programs#system-program). |",
         "",
@@ -597,6 +598,12 @@ export const IDL: JetMarginIDL = {
           isSigner: false,
           docs: ["The permit allowing the liquidator to do this"]
         },
+        {
+          name: "liquidationConfig",
+          isMut: false,
+          isSigner: false,
+          docs: ["The liquidation config for the airspace, which may not be initialized"]
+        },
         {
           name: "liquidation",
           isMut: true,
@@ -85,6 +85,16 @@ export const IDL: JetMarginPoolIDL = {
           name: "pythPrice",
           isMut: false,
           isSigner: false
+        },
+        {
+          name: "payer",
+          isMut: true,
+          isSigner: true
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false
         }
       ],
       args: [
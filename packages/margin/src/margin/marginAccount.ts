@@ -1772,11 +1772,36 @@ export class MarginAccount {
           adapterInstruction.programId
         )
       })
-      .remainingAccounts(this.invokeAccounts(adapterInstruction))
+      .remainingAccounts([
+        ...this.invokeAccounts(adapterInstruction),
+        ...this.delegationAccounts(adapterInstruction)
+      ])
       .instruction()
     instructions.push(ix)
   }
 
+  /**
+   * The delegation addresses of the signers of an instruction, which `accounting_invoke`
+   * requires for every signer that is not the owner of the margin account.
+   *
+   * @return {AccountMeta[]} The delegation accounts
+   * @memberof MarginAccount
+   */
+  private delegationAccounts(adapterInstruction: TransactionInstruction): AccountMeta[] {
+    const signers: PublicKey[] = []
+    for (const acc of adapterInstruction.keys) {
+      if (acc.isSigner && !acc.pubkey.equals(this.address) && !signers.some(s => s.equals(acc.pubkey))) {
+        signers.push(acc.pubkey)
+      }
+    }
+
+    return signers.map(signer => ({
+      pubkey: findDerivedAccount(this.programs.margin.programId, "margin-delegate", this.address, signer),
+      isSigner: false,
+      isWritable: true
+    }))
+  }
+
   /**
    * prepares arguments for `adapter_invoke`, `account_invoke`, or `liquidator_invoke`
    *
@@ -702,10 +702,41 @@ export class Pool {
           marginPool: this.address,
           tokenPriceOracle: this.info?.marginPool.tokenPriceOracle
         })
+        .remainingAccounts(await this.refreshOracleAccounts(marginAccount))
         .instruction()
     })
   }
 
+  /**
+   * The accounts the pool needs after the primary oracle to refresh a position price:
+   * the token config, followed by the token oracle set and its fallback oracles when
+   * the config has one.
+   */
+  private async refreshOracleAccounts(marginAccount: MarginAccount): Promise<AccountMeta[]> {
+    const tokenConfig = marginAccount.findTokenConfigAddress(this.tokenMint)
+    const oracleSet = findDerivedAccount(this.programs.config.marginProgramId, "token-oracle-set", tokenConfig)
+    const accounts: AccountMeta[] = [{ pubkey: tokenConfig, isSigner: false, isWritable: false }]
+
+    const oracleSetInfo = await this.programs.marginPool.provider.connection.getAccountInfo(oracleSet)
+    if (!oracleSetInfo) {
+      return accounts
+    }
+
+    // TokenOracleSet layout: discriminator (8), token_config (32), aggregation (1),
+    // max_deviation (2), then a vec of TokenOracle (4 byte length, 65 bytes each)
+    // where each oracle's price account follows its enum tag.
+    const data = oracleSetInfo.data
+    const count = data.readUInt32LE(43)
+    accounts.push({ pubkey: oracleSet, isSigner: false, isWritable: false })
+    for (let i = 0; i < count; i++) {
+      const offset = 47 + i * 65 + 1
+      const price = new PublicKey(data.subarray(offset, offset + 32))
+      accounts.push({ pubkey: price, isSigner: false, isWritable: false })
+    }
+
+    return accounts
+  }
+
   /**
    * Send a transaction to deposit tokens into the pool.
    *
@@ -253,8 +253,10 @@ export class PoolManager {
         loanMetadata: addresses.loanNoteMetadata,
         pythProduct: pythProduct,
         pythPrice: pythPrice,
+        payer: requester,
         marginPoolProgram: programs.config.marginPoolProgramId,
-        metadataProgram: programs.config.metadataProgramId
+        metadataProgram: programs.config.metadataProgramId,
+        systemProgram: SystemProgram.programId
       })
       .instruction()
     instructions.push(ix)
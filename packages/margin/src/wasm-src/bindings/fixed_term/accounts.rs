@@ -7,7 +7,7 @@ use wasm_bindgen::prelude::*;
 
 use jet_fixed_term::{
     control::state::Market,
-    margin::state::{Assets, Debt, MarginUser},
+    margin::state::{Assets, AutoRollPolicy, Debt, MarginUser},
 };
 
 use crate::{bindings::serialization::JsAnchorDeserialize, JsResult};
@@ -37,6 +37,15 @@ export interface MarketInfo {
     seed: string
     orderbookPaused: boolean
     ticketsPaused: boolean
+    ticketPriceSource: number
+    ticketTwapRate: number
+    ticketTwapUpdatedAt: bigint
+    defaultGracePeriod: number
+    defaultPenalty: number
+    prepaymentRebate: number
+    prepaymentMode: number
+    ticketTwapCoverage: number
+    ticketSpotRate: number
     borrowTenor: bigint
     lendTenor: bigint
     originationFee: bigint
@@ -61,6 +70,17 @@ export interface MarginUserInfo {
     assets: Assets,
     borrowRollConfig?: BorrowAutoRollConfig,
     lendRollConfig?: LendAutoRollConfig,
+    borrowRollPolicy?: AutoRollPolicy,
+    lendRollPolicy?: AutoRollPolicy,
+}
+
+export interface AutoRollPolicy {
+    minRateBps: bigint,
+    maxRateBps: bigint,
+    maxRolls: number,
+    rolls: number,
+    targetMarket: string,
+    poolFallback: boolean,
 }
 
 export interface Debt {
@@ -91,7 +111,7 @@ impl Serialize for MarginUserDeserializer {
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("MarginUser", 10)?;
+        let mut s = serializer.serialize_struct("MarginUser", 12)?;
         s.serialize_field("versionTag", &self.0.version)?;
         s.serialize_field("marginAccount", &self.0.margin_account.to_string())?;
         s.serialize_field("market", &self.0.market.to_string())?;
@@ -105,6 +125,14 @@ impl Serialize for MarginUserDeserializer {
         s.serialize_field("assets", &AssetsSerializer(self.0.assets()))?;
         s.serialize_field("borrowRollConfig", &self.0.borrow_roll_config)?;
         s.serialize_field("lendRollConfig", &self.0.lend_roll_config)?;
+        s.serialize_field(
+            "borrowRollPolicy",
+            &self.0.borrow_roll_policy.as_ref().map(AutoRollPolicySerializer),
+        )?;
+        s.serialize_field(
+            "lendRollPolicy",
+            &self.0.lend_roll_policy.as_ref().map(AutoRollPolicySerializer),
+        )?;
         s.end()
     }
 }
@@ -156,6 +184,25 @@ impl<'a> Serialize for DebtSerializer<'a> {
     }
 }
 
+/// A wrapping type to allow us to implement custom serialization logic for an `AutoRollPolicy`
+struct AutoRollPolicySerializer<'a>(&'a AutoRollPolicy);
+
+impl<'a> Serialize for AutoRollPolicySerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("AutoRollPolicy", 6)?;
+        s.serialize_field("minRateBps", &self.0.min_rate_bps)?;
+        s.serialize_field("maxRateBps", &self.0.max_rate_bps)?;
+        s.serialize_field("maxRolls", &self.0.max_rolls)?;
+        s.serialize_field("rolls", &self.0.rolls)?;
+        s.serialize_field("targetMarket", &self.0.target_market.to_string())?;
+        s.serialize_field("poolFallback", &self.0.pool_fallback)?;
+        s.end()
+    }
+}
+
 #[wasm_bindgen(js_name = "deserializeMarginUserFromBuffer")]
 pub fn deserialize_margin_user(buf: &[u8]) -> JsResult {
     MarginUserDeserializer::deserialize_from_buffer(buf)
@@ -16,9 +16,13 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod configure_margin_pool;
+mod configure_margin_pool_interest_curve;
+mod configure_margin_pool_limits;
 mod create_authority;
 mod create_margin_pool;
 
 pub use configure_margin_pool::*;
+pub use configure_margin_pool_interest_curve::*;
+pub use configure_margin_pool_limits::*;
 pub use create_authority::*;
 pub use create_margin_pool::*;
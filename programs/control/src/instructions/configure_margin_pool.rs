@@ -76,8 +76,12 @@ pub struct ConfigureMarginPool<'info> {
     /// CHECK:
     pub pyth_price: UncheckedAccount<'info>,
 
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
     pub margin_pool_program: Program<'info, JetMarginPool>,
     pub metadata_program: Program<'info, JetMetadata>,
+    pub system_program: Program<'info, System>,
 }
 
 impl<'info> ConfigureMarginPool<'info> {
@@ -89,6 +93,8 @@ impl<'info> ConfigureMarginPool<'info> {
                 authority: self.authority.to_account_info(),
                 pyth_product: self.pyth_product.to_account_info(),
                 pyth_price: self.pyth_price.to_account_info(),
+                payer: self.payer.to_account_info(),
+                system_program: self.system_program.to_account_info(),
             },
         )
     }
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_margin_pool::program::JetMarginPool;
+use jet_margin_pool::{cpi::accounts::ConfigureInterestCurve, InterestRateCurve, MarginPool};
+
+#[cfg(not(feature = "testing"))]
+use jet_program_common::GOVERNOR_ID;
+
+use super::Authority;
+
+#[derive(Accounts)]
+pub struct ConfigureMarginPoolInterestCurve<'info> {
+    #[cfg_attr(not(feature = "testing"), account(address = GOVERNOR_ID))]
+    pub requester: Signer<'info>,
+    pub authority: Box<Account<'info, Authority>>,
+
+    /// CHECK:
+    pub token_mint: UncheckedAccount<'info>,
+
+    #[account(mut, has_one = token_mint)]
+    pub margin_pool: Box<Account<'info, MarginPool>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub margin_pool_program: Program<'info, JetMarginPool>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ConfigureMarginPoolInterestCurve<'info> {
+    fn configure_curve_context(
+        &self,
+    ) -> CpiContext<'_, '_, '_, 'info, ConfigureInterestCurve<'info>> {
+        CpiContext::new(
+            self.margin_pool_program.to_account_info(),
+            ConfigureInterestCurve {
+                margin_pool: self.margin_pool.to_account_info(),
+                authority: self.authority.to_account_info(),
+                payer: self.payer.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn configure_margin_pool_interest_curve_handler(
+    ctx: Context<ConfigureMarginPoolInterestCurve>,
+    curve: InterestRateCurve,
+) -> Result<()> {
+    let authority = [&ctx.accounts.authority.seed[..]];
+
+    jet_margin_pool::cpi::configure_interest_curve(
+        ctx.accounts
+            .configure_curve_context()
+            .with_signer(&[&authority]),
+        curve,
+    )
+}
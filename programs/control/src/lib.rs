@@ -17,7 +17,7 @@
 
 use anchor_lang::prelude::*;
 
-use jet_margin_pool::MarginPoolConfig;
+use jet_margin_pool::{InterestRateCurve, MarginPoolConfig, PoolLimits};
 
 mod instructions;
 use instructions::*;
@@ -60,4 +60,20 @@ mod jet_control {
     ) -> Result<()> {
         instructions::configure_margin_pool_handler(ctx, metadata, pool_config)
     }
+
+    /// Set the interest rate curve for a margin pool
+    pub fn configure_margin_pool_interest_curve(
+        ctx: Context<ConfigureMarginPoolInterestCurve>,
+        curve: InterestRateCurve,
+    ) -> Result<()> {
+        instructions::configure_margin_pool_interest_curve_handler(ctx, curve)
+    }
+
+    /// Set the deposit and borrow caps for a margin pool
+    pub fn configure_margin_pool_limits(
+        ctx: Context<ConfigureMarginPoolLimits>,
+        limits: Option<PoolLimits>,
+    ) -> Result<()> {
+        instructions::configure_margin_pool_limits_handler(ctx, limits)
+    }
 }
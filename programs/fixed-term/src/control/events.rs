@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use super::state::{PrepaymentMode, TicketPriceSource};
+
 #[event]
 pub struct MarketInitialized {
     pub version: u64,
@@ -40,3 +42,32 @@ pub struct FeesWithdrawn {
     pub fee_destination: Pubkey,
     pub collected_fees: u64,
 }
+
+#[event]
+pub struct TicketPriceSourceConfigured {
+    pub market: Pubkey,
+    pub source: TicketPriceSource,
+}
+
+#[event]
+pub struct DefaultHandlingConfigured {
+    pub market: Pubkey,
+    pub grace_period: u32,
+    pub penalty: u16,
+}
+
+#[event]
+pub struct PrepaymentConfigured {
+    pub market: Pubkey,
+    pub mode: PrepaymentMode,
+    pub rebate: u16,
+}
+
+#[event]
+pub struct FeeScheduleConfigured {
+    pub market: Pubkey,
+    pub lend_maker_fee: i16,
+    pub lend_taker_fee: u16,
+    pub borrow_maker_fee: i16,
+    pub borrow_taker_fee: u16,
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use jet_airspace::state::Airspace;
+
+use crate::{
+    control::{events::DefaultHandlingConfigured, state::Market},
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct ConfigureDefaultHandling<'info> {
+    /// The `Market` manages asset tokens for a particular tenor
+    #[account(mut, has_one = airspace @ FixedTermErrorCode::WrongAirspace)]
+    pub market: AccountLoader<'info, Market>,
+
+    /// The authority that must sign to make this change
+    pub authority: Signer<'info>,
+
+    /// The airspace being modified
+    #[cfg_attr(not(feature = "testing"), account(has_one = authority @ FixedTermErrorCode::WrongAirspaceAuthorization))]
+    pub airspace: Account<'info, Airspace>,
+}
+
+pub fn handler(
+    ctx: Context<ConfigureDefaultHandling>,
+    grace_period: u32,
+    penalty: u16,
+) -> Result<()> {
+    require!(penalty <= 10_000, FixedTermErrorCode::InvalidDefaultPenalty);
+
+    let mut market = ctx.accounts.market.load_mut()?;
+    market.default_grace_period = grace_period;
+    market.default_penalty = penalty;
+
+    emit!(DefaultHandlingConfigured {
+        market: ctx.accounts.market.key(),
+        grace_period,
+        penalty,
+    });
+
+    Ok(())
+}
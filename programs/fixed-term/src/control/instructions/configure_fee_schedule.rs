@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+
+use jet_airspace::state::Airspace;
+
+use crate::{
+    control::{
+        events::FeeScheduleConfigured,
+        state::{FeeSchedule, Market},
+    },
+    FixedTermErrorCode,
+};
+
+/// The fees charged on orderbook fills, in basis points of the tickets filled
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone, Copy, Default)]
+pub struct FeeScheduleParams {
+    /// Fee charged to lenders whose resting orders are filled, negative for a rebate
+    pub lend_maker_fee: i16,
+    /// Fee charged to lenders whose orders fill against the book
+    pub lend_taker_fee: u16,
+    /// Fee charged to borrowers whose resting orders are filled, negative for a rebate
+    pub borrow_maker_fee: i16,
+    /// Fee charged to borrowers whose orders fill against the book
+    pub borrow_taker_fee: u16,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureFeeSchedule<'info> {
+    /// The `Market` charging the fees
+    #[account(has_one = airspace @ FixedTermErrorCode::WrongAirspace)]
+    pub market: AccountLoader<'info, Market>,
+
+    /// The fees charged by the market
+    #[account(
+        init_if_needed,
+        seeds = [
+            crate::seeds::FEE_SCHEDULE,
+            market.key().as_ref(),
+        ],
+        bump,
+        space = FeeSchedule::SPACE,
+        payer = payer,
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+
+    /// The authority that must sign to make this change
+    pub authority: Signer<'info>,
+
+    /// The airspace being modified
+    #[cfg_attr(not(feature = "testing"), account(has_one = authority @ FixedTermErrorCode::WrongAirspaceAuthorization))]
+    pub airspace: Account<'info, Airspace>,
+
+    /// The address paying the rent for the account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<ConfigureFeeSchedule>, params: FeeScheduleParams) -> Result<()> {
+    let schedule = &mut ctx.accounts.fee_schedule;
+    schedule.market = ctx.accounts.market.key();
+    schedule.lend_maker_fee = params.lend_maker_fee;
+    schedule.lend_taker_fee = params.lend_taker_fee;
+    schedule.borrow_maker_fee = params.borrow_maker_fee;
+    schedule.borrow_taker_fee = params.borrow_taker_fee;
+
+    require!(schedule.is_valid(), FixedTermErrorCode::InvalidFeeSchedule);
+
+    emit!(FeeScheduleConfigured {
+        market: ctx.accounts.market.key(),
+        lend_maker_fee: params.lend_maker_fee,
+        lend_taker_fee: params.lend_taker_fee,
+        borrow_maker_fee: params.borrow_maker_fee,
+        borrow_taker_fee: params.borrow_taker_fee,
+    });
+
+    Ok(())
+}
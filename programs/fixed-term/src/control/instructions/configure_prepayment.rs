@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use jet_airspace::state::Airspace;
+
+use crate::{
+    control::{
+        events::PrepaymentConfigured,
+        state::{Market, PrepaymentMode},
+    },
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct ConfigurePrepayment<'info> {
+    /// The `Market` manages asset tokens for a particular tenor
+    #[account(mut, has_one = airspace @ FixedTermErrorCode::WrongAirspace)]
+    pub market: AccountLoader<'info, Market>,
+
+    /// The authority that must sign to make this change
+    pub authority: Signer<'info>,
+
+    /// The airspace being modified
+    #[cfg_attr(not(feature = "testing"), account(has_one = authority @ FixedTermErrorCode::WrongAirspaceAuthorization))]
+    pub airspace: Account<'info, Airspace>,
+}
+
+pub fn handler(ctx: Context<ConfigurePrepayment>, mode: PrepaymentMode, rebate: u16) -> Result<()> {
+    require!(
+        rebate <= 10_000,
+        FixedTermErrorCode::InvalidPrepaymentRebate
+    );
+
+    let mut market = ctx.accounts.market.load_mut()?;
+    market.prepayment_mode = mode as u8;
+    market.prepayment_rebate = rebate;
+
+    emit!(PrepaymentConfigured {
+        market: ctx.accounts.market.key(),
+        mode,
+        rebate,
+    });
+
+    Ok(())
+}
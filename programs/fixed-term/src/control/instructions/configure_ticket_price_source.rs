@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use jet_airspace::state::Airspace;
+
+use crate::{
+    control::{
+        events::TicketPriceSourceConfigured,
+        state::{Market, TicketPriceSource},
+    },
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct ConfigureTicketPriceSource<'info> {
+    /// The `Market` manages asset tokens for a particular tenor
+    #[account(mut, has_one = airspace @ FixedTermErrorCode::WrongAirspace)]
+    pub market: AccountLoader<'info, Market>,
+
+    /// The authority that must sign to make this change
+    pub authority: Signer<'info>,
+
+    /// The airspace being modified
+    #[cfg_attr(not(feature = "testing"), account(has_one = authority @ FixedTermErrorCode::WrongAirspaceAuthorization))]
+    pub airspace: Account<'info, Airspace>,
+}
+
+pub fn handler(ctx: Context<ConfigureTicketPriceSource>, source: TicketPriceSource) -> Result<()> {
+    let mut market = ctx.accounts.market.load_mut()?;
+    if market.ticket_price_source() != source {
+        // rates seen before the change may be arbitrarily old, so the average starts over
+        market.reset_ticket_twap();
+    }
+    market.ticket_price_source = source as u8;
+
+    emit!(TicketPriceSourceConfigured {
+        market: ctx.accounts.market.key(),
+        source,
+    });
+
+    Ok(())
+}
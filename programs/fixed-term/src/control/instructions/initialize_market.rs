@@ -199,7 +199,16 @@ pub fn handler(ctx: Context<InitializeMarket>, params: InitializeMarketParams) -
             asks,
             bids,
             nonce,
+            ticket_price_source,
+            ticket_twap_rate,
+            ticket_twap_updated_at,
+            default_grace_period,
+            default_penalty,
+            prepayment_rebate,
+            prepayment_mode,
             _reserved,
+            ticket_twap_coverage,
+            ticket_spot_rate,
         }
     }
     emit!(MarketInitialized {
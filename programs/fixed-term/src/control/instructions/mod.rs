@@ -1,4 +1,8 @@
 pub mod authorize_crank;
+pub mod configure_default_handling;
+pub mod configure_fee_schedule;
+pub mod configure_prepayment;
+pub mod configure_ticket_price_source;
 pub mod initialize_market;
 pub mod initialize_orderbook;
 pub mod modify_market;
@@ -9,6 +13,10 @@ pub mod revoke_crank;
 pub mod withdraw_fees;
 
 pub use authorize_crank::*;
+pub use configure_default_handling::*;
+pub use configure_fee_schedule::*;
+pub use configure_prepayment::*;
+pub use configure_ticket_price_source::*;
 pub use initialize_market::*;
 pub use initialize_orderbook::*;
 pub use modify_market::*;
@@ -1,9 +1,53 @@
-use anchor_lang::prelude::*;
-use jet_program_common::pod::PodBool;
+use anchor_lang::{prelude::*, solana_program::clock::UnixTimestamp};
+use jet_margin::PriceChangeInfo;
+use jet_program_common::{
+    interest_pricing::{InterestPricer, PricerImpl},
+    pod::PodBool,
+    traits::SafeAdd,
+};
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
 #[cfg(any(feature = "cli", test))]
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
-use crate::{margin::origination_fee, orderbook::state::OrderParams};
+use crate::{
+    margin::origination_fee,
+    orderbook::state::{MarketSide, OrderParams},
+    FixedTermErrorCode,
+};
+
+/// The period over which the orderbook rate is averaged, in seconds
+pub const TICKET_TWAP_WINDOW: i64 = 60 * 60;
+
+/// The maximum age of the average orderbook rate before it can no longer price tickets, in seconds
+pub const MAX_TICKET_TWAP_AGE: i64 = 60 * 60;
+
+/// How a loan may be repaid before it matures, other than by paying the full balance
+#[derive(AnchorSerialize, AnchorDeserialize, FromPrimitive, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PrepaymentMode {
+    /// Loans can only be repaid for their full balance
+    Disabled = 0,
+
+    /// Loans can be repaid with tickets, which are worth their face value at maturity, so
+    /// borrowers pay the discount of the tickets on the orderbook
+    Orderbook = 1,
+
+    /// Repaying before maturity rebates part of the interest that has not yet accrued, which
+    /// is funded by the fees collected by the market
+    Rebate = 2,
+}
+
+/// Where the price of ticket collateral comes from
+#[derive(AnchorSerialize, AnchorDeserialize, FromPrimitive, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TicketPriceSource {
+    /// The price is read from the `ticket_oracle` feed
+    Oracle = 0,
+
+    /// The price of the underlying token is discounted by the average rate of the orderbook
+    Orderbook = 1,
+}
 
 /// The `Market` contains all the information necessary to run the fixed term market
 ///
@@ -57,8 +101,30 @@ pub struct Market {
     pub orderbook_paused: PodBool,
     /// Can tickets be redeemed
     pub tickets_paused: PodBool,
+    /// The source of prices for ticket collateral, as a [TicketPriceSource]
+    pub ticket_price_source: u8,
+    /// The time-weighted average of the yearly interest rate (in bps) at the middle of the
+    /// orderbook spread, maintained while consuming events
+    pub ticket_twap_rate: u32,
+    /// The time the average orderbook rate was last updated
+    pub ticket_twap_updated_at: i64,
+    /// The time after a `TermLoan` matures before anyone may repay it from the borrower's assets
+    pub default_grace_period: u32,
+    /// The penalty (in basis points of the amount repaid) charged on loans repaid after defaulting
+    pub default_penalty: u16,
+    /// The portion (in basis points) of unaccrued interest rebated when prepaying a loan
+    /// with [PrepaymentMode::Rebate]
+    pub prepayment_rebate: u16,
+    /// How loans may be repaid before maturity, as a [PrepaymentMode]
+    pub prepayment_mode: u8,
     /// reserved for future use
-    pub(crate) _reserved: [u8; 29],
+    pub(crate) _reserved: [u8; 1],
+    /// How many seconds of orderbook rates, up to [TICKET_TWAP_WINDOW], are included in the
+    /// average. Tickets are not priced from the orderbook until a full window is covered.
+    pub ticket_twap_coverage: u16,
+    /// The yearly interest rate (in bps) at the middle of the orderbook spread when the
+    /// average was last updated
+    pub ticket_spot_rate: u32,
     /// Length of time before a borrow is marked as due, in seconds
     pub borrow_tenor: u64,
     /// Length of time before a claim is marked as mature, in seconds
@@ -96,6 +162,113 @@ impl Market {
     pub fn borrow_order_qty(&self, requested: u64) -> u64 {
         origination_fee::borrow_order_qty(requested, self.origination_fee)
     }
+
+    /// Has a loan maturing at a time been past due for longer than the grace period
+    pub fn is_in_default(&self, maturation_timestamp: UnixTimestamp, now: UnixTimestamp) -> bool {
+        maturation_timestamp.saturating_add(self.default_grace_period as i64) <= now
+    }
+
+    /// The default penalty charged when repaying some amount of a defaulted loan
+    pub fn default_penalty_on(&self, amount: u64) -> u64 {
+        (amount as u128 * self.default_penalty as u128 / 10_000) as u64
+    }
+
+    /// How loans in this market may be repaid before maturity
+    pub fn prepayment_mode(&self) -> PrepaymentMode {
+        PrepaymentMode::from_u8(self.prepayment_mode).unwrap_or(PrepaymentMode::Disabled)
+    }
+
+    /// The rebate for prepaying a loan, given the interest that has not yet accrued
+    pub fn prepayment_rebate_on(&self, unaccrued_interest: u64) -> u64 {
+        (unaccrued_interest as u128 * self.prepayment_rebate as u128 / 10_000) as u64
+    }
+
+    /// Where the price of ticket collateral for this market comes from
+    pub fn ticket_price_source(&self) -> TicketPriceSource {
+        TicketPriceSource::from_u8(self.ticket_price_source).unwrap_or(TicketPriceSource::Oracle)
+    }
+
+    /// Record the current mid price of the orderbook. The rate seen at the previous update is
+    /// blended into the average, weighted by how long it was in effect, so a price only moves
+    /// the average once it has persisted.
+    ///
+    /// The first rate seen carries no weight until time has passed at it, so the average only
+    /// reflects rates that were observed over [TICKET_TWAP_WINDOW].
+    pub fn update_ticket_twap(&mut self, mid_price_fp32: u64, timestamp: UnixTimestamp) {
+        let rate = PricerImpl::price_fp32_to_bps_yearly_interest(mid_price_fp32, self.lend_tenor)
+            .min(u32::MAX as u64) as u32;
+
+        if self.ticket_twap_updated_at == 0 {
+            self.ticket_twap_rate = rate;
+            self.ticket_twap_coverage = 0;
+        } else {
+            let window = TICKET_TWAP_WINDOW as u64;
+            let elapsed = timestamp
+                .saturating_sub(self.ticket_twap_updated_at)
+                .clamp(0, TICKET_TWAP_WINDOW) as u64;
+            let kept = (self.ticket_twap_coverage as u64).min(window - elapsed);
+            let covered = kept + elapsed;
+
+            if covered > 0 {
+                let average = self.ticket_twap_rate as u64;
+                let previous = self.ticket_spot_rate as u64;
+
+                self.ticket_twap_rate = ((average * kept + previous * elapsed) / covered) as u32;
+            }
+            self.ticket_twap_coverage = covered as u16;
+        }
+        self.ticket_spot_rate = rate;
+        self.ticket_twap_updated_at = timestamp;
+    }
+
+    /// Forget the orderbook rates seen so far, so the average must be rebuilt from new samples
+    pub fn reset_ticket_twap(&mut self) {
+        self.ticket_twap_rate = 0;
+        self.ticket_spot_rate = 0;
+        self.ticket_twap_coverage = 0;
+        self.ticket_twap_updated_at = 0;
+    }
+
+    /// The price of a ticket collateral token derived from the average orderbook rate.
+    ///
+    /// Ticket collateral matures within at most `lend_tenor`, so the underlying price is
+    /// discounted over the full tenor to value it conservatively.
+    pub fn orderbook_ticket_price(
+        &self,
+        underlying: PriceChangeInfo,
+        timestamp: UnixTimestamp,
+    ) -> Result<PriceChangeInfo> {
+        if self.ticket_twap_updated_at == 0
+            || timestamp.saturating_sub(self.ticket_twap_updated_at) > MAX_TICKET_TWAP_AGE
+        {
+            msg!(
+                "orderbook rate was last updated at {}",
+                self.ticket_twap_updated_at
+            );
+            return err!(FixedTermErrorCode::StaleTicketPrice);
+        }
+        if (self.ticket_twap_coverage as i64) < TICKET_TWAP_WINDOW {
+            msg!(
+                "orderbook rate has only been observed for {} seconds",
+                self.ticket_twap_coverage
+            );
+            return err!(FixedTermErrorCode::StaleTicketPrice);
+        }
+
+        let discount = PricerImpl::yearly_interest_bps_to_fp32_price(
+            self.ticket_twap_rate as u64,
+            self.lend_tenor,
+        ) as i128;
+        let apply = |value: i64| ((value as i128 * discount) >> 32) as i64;
+
+        Ok(PriceChangeInfo {
+            value: apply(underlying.value),
+            confidence: apply(underlying.confidence as i64) as u64,
+            twap: apply(underlying.twap),
+            publish_time: underlying.publish_time.min(self.ticket_twap_updated_at),
+            exponent: underlying.exponent,
+        })
+    }
 }
 
 #[cfg(any(feature = "cli", test))]
@@ -104,7 +277,7 @@ impl Serialize for Market {
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("Market", 22)?;
+        let mut s = serializer.serialize_struct("Market", 31)?;
         s.serialize_field("versionTag", &self.version_tag)?;
         s.serialize_field("airspace", &self.airspace.to_string())?;
         s.serialize_field(
@@ -139,6 +312,15 @@ impl Serialize for Market {
         s.serialize_field("seed", &Pubkey::new_from_array(self.seed).to_string())?;
         s.serialize_field("orderbookPaused", &self.orderbook_paused.as_bool())?;
         s.serialize_field("ticketsPaused", &self.tickets_paused.as_bool())?;
+        s.serialize_field("ticketPriceSource", &self.ticket_price_source)?;
+        s.serialize_field("ticketTwapRate", &self.ticket_twap_rate)?;
+        s.serialize_field("ticketTwapUpdatedAt", &self.ticket_twap_updated_at)?;
+        s.serialize_field("defaultGracePeriod", &self.default_grace_period)?;
+        s.serialize_field("defaultPenalty", &self.default_penalty)?;
+        s.serialize_field("prepaymentRebate", &self.prepayment_rebate)?;
+        s.serialize_field("prepaymentMode", &self.prepayment_mode)?;
+        s.serialize_field("ticketTwapCoverage", &self.ticket_twap_coverage)?;
+        s.serialize_field("ticketSpotRate", &self.ticket_spot_rate)?;
         s.serialize_field("borrowTenor", &self.borrow_tenor)?;
         s.serialize_field("lendTenor", &self.lend_tenor)?;
         s.serialize_field("originationFee", &self.origination_fee)?;
@@ -154,6 +336,110 @@ pub struct CrankAuthorization {
     pub market: Pubkey,
 }
 
+/// The largest fee or rebate, in basis points, that may be set in a [FeeSchedule]
+pub const MAX_FILL_FEE_BPS: u16 = 1_000;
+
+/// The fees charged when orders are filled, in basis points of the tickets filled.
+///
+/// Makers are charged when their fills are processed by `consume_events`, and takers when
+/// their orders are matched. A negative maker fee is a rebate, paid out of the fee vault.
+///
+/// Lenders are charged in tickets, which are only backed by underlying tokens once the loans
+/// they fund are repaid. Lend fees are accrued until those tickets mature, and only then
+/// moved into the fee vault.
+#[account]
+#[derive(Debug, Default)]
+pub struct FeeSchedule {
+    /// The market charging these fees
+    pub market: Pubkey,
+    /// Fee charged to lenders whose resting orders are filled
+    pub lend_maker_fee: i16,
+    /// Fee charged to lenders whose orders fill against the book
+    pub lend_taker_fee: u16,
+    /// Fee charged to borrowers whose resting orders are filled
+    pub borrow_maker_fee: i16,
+    /// Fee charged to borrowers whose orders fill against the book
+    pub borrow_taker_fee: u16,
+    /// Borrow taker fees held in the underlying token vault, which are moved into the fee
+    /// vault when events are consumed
+    pub unswept_fees: u64,
+    /// Lend fees withheld as tickets since the current maturing fees were set aside
+    pub accrued_ticket_fees: u64,
+    /// The time when all the tickets in `accrued_ticket_fees` have matured
+    pub accrued_ticket_fees_mature_at: UnixTimestamp,
+    /// Lend fees withheld as tickets that are waiting to mature
+    pub maturing_ticket_fees: u64,
+    /// The time when all the tickets in `maturing_ticket_fees` have matured
+    pub maturing_ticket_fees_mature_at: UnixTimestamp,
+}
+
+impl FeeSchedule {
+    pub const SPACE: usize = 8 + std::mem::size_of::<FeeSchedule>();
+
+    /// The fee owed by a maker for a fill, which is negative for a rebate
+    pub fn maker_fee(&self, side: MarketSide, tickets: u64) -> i64 {
+        let bps = match side {
+            MarketSide::Lend => self.lend_maker_fee,
+            MarketSide::Borrow => self.borrow_maker_fee,
+        };
+        (tickets as i128 * bps as i128 / 10_000) as i64
+    }
+
+    /// The fee owed by a taker for a fill
+    pub fn taker_fee(&self, side: MarketSide, tickets: u64) -> u64 {
+        let bps = match side {
+            MarketSide::Lend => self.lend_taker_fee,
+            MarketSide::Borrow => self.borrow_taker_fee,
+        };
+        (tickets as u128 * bps as u128 / 10_000) as u64
+    }
+
+    /// Withhold a lend fee charged in tickets, which matures at the same time as the tickets
+    pub fn accrue_ticket_fee(&mut self, fee: u64, matures_at: UnixTimestamp) -> Result<()> {
+        self.accrued_ticket_fees = self.accrued_ticket_fees.safe_add(fee)?;
+        self.accrued_ticket_fees_mature_at = self.accrued_ticket_fees_mature_at.max(matures_at);
+        Ok(())
+    }
+
+    /// Take the ticket fees that have matured, which may be moved into the fee vault.
+    ///
+    /// Once the maturing fees are taken, the fees accrued since then start maturing, so fees
+    /// continue to be swept while new ones accrue.
+    pub fn take_matured_ticket_fees(&mut self, timestamp: UnixTimestamp) -> u64 {
+        let mut matured = 0;
+        if self.maturing_ticket_fees > 0 && self.maturing_ticket_fees_mature_at <= timestamp {
+            matured = std::mem::take(&mut self.maturing_ticket_fees);
+        }
+        if self.maturing_ticket_fees == 0 {
+            self.maturing_ticket_fees = std::mem::take(&mut self.accrued_ticket_fees);
+            self.maturing_ticket_fees_mature_at =
+                std::mem::take(&mut self.accrued_ticket_fees_mature_at);
+        }
+
+        matured
+    }
+
+    /// Load the fees charged by a market, if they have been configured
+    pub fn load<'info>(info: &AccountInfo<'info>) -> Result<Option<Account<'info, FeeSchedule>>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        Account::try_from(info).map(Some)
+    }
+
+    /// Are all the fees within [MAX_FILL_FEE_BPS]
+    pub fn is_valid(&self) -> bool {
+        [
+            self.lend_maker_fee.unsigned_abs(),
+            self.lend_taker_fee,
+            self.borrow_maker_fee.unsigned_abs(),
+            self.borrow_taker_fee,
+        ]
+        .iter()
+        .all(|fee| *fee <= MAX_FILL_FEE_BPS)
+    }
+}
+
 #[test]
 fn serialize_market() {
     let json = serde_json::to_string_pretty(&<Market as bytemuck::Zeroable>::zeroed()).unwrap();
@@ -177,6 +463,15 @@ fn serialize_market() {
       "seed": "11111111111111111111111111111111",
       "orderbookPaused": false,
       "ticketsPaused": false,
+      "ticketPriceSource": 0,
+      "ticketTwapRate": 0,
+      "ticketTwapUpdatedAt": 0,
+      "defaultGracePeriod": 0,
+      "defaultPenalty": 0,
+      "prepaymentRebate": 0,
+      "prepaymentMode": 0,
+      "ticketTwapCoverage": 0,
+      "ticketSpotRate": 0,
       "borrowTenor": 0,
       "lendTenor": 0,
       "originationFee": 0
@@ -186,3 +481,139 @@ fn serialize_market() {
         itertools::Itertools::join(&mut json.split_whitespace(), " ")
     )
 }
+
+#[test]
+fn ticket_twap_is_weighted_by_time() {
+    let mut market = <Market as bytemuck::Zeroable>::zeroed();
+    market.lend_tenor = 31_536_000;
+
+    let price_at = |bps| PricerImpl::yearly_interest_bps_to_fp32_price(bps, market.lend_tenor);
+
+    market.update_ticket_twap(price_at(1_000), 1_000);
+    assert_eq!(market.ticket_twap_rate, 1_000);
+
+    // a new rate does not count until time has passed at it
+    market.update_ticket_twap(price_at(2_000), 1_000 + TICKET_TWAP_WINDOW / 4);
+    assert!((999..=1_001).contains(&market.ticket_twap_rate));
+
+    // only the time that has been observed is averaged, two thirds of it at 2000 bps
+    market.update_ticket_twap(price_at(500), 1_000 + 3 * TICKET_TWAP_WINDOW / 4);
+    assert!((1_665..=1_668).contains(&market.ticket_twap_rate));
+    assert_eq!(
+        market.ticket_twap_coverage as i64,
+        3 * TICKET_TWAP_WINDOW / 4
+    );
+
+    // after a gap of more than a window, the rate that held during the gap is the average
+    market.update_ticket_twap(price_at(3_000), 1_000 + 3 * TICKET_TWAP_WINDOW);
+    assert!((499..=501).contains(&market.ticket_twap_rate));
+    assert_eq!(market.ticket_twap_coverage as i64, TICKET_TWAP_WINDOW);
+}
+
+#[test]
+fn orderbook_ticket_price_discounts_underlying() {
+    let mut market = <Market as bytemuck::Zeroable>::zeroed();
+    market.lend_tenor = 31_536_000;
+    market.ticket_price_source = TicketPriceSource::Orderbook as u8;
+
+    let underlying = PriceChangeInfo {
+        value: 1_000_000,
+        confidence: 1_000,
+        twap: 1_000_000,
+        publish_time: 100,
+        exponent: -6,
+    };
+
+    // no average has been recorded yet
+    assert!(market.orderbook_ticket_price(underlying, 100).is_err());
+
+    let price_fp32 = PricerImpl::yearly_interest_bps_to_fp32_price(1_000, market.lend_tenor);
+    market.update_ticket_twap(price_fp32, 100);
+
+    // the first sample does not price tickets until a full window has been observed
+    assert!(market.orderbook_ticket_price(underlying, 100).is_err());
+    market.update_ticket_twap(price_fp32, 100 + TICKET_TWAP_WINDOW / 2);
+    assert!(market
+        .orderbook_ticket_price(underlying, 100 + TICKET_TWAP_WINDOW / 2)
+        .is_err());
+
+    let now = 100 + TICKET_TWAP_WINDOW;
+    market.update_ticket_twap(price_fp32, now);
+    let price = market.orderbook_ticket_price(underlying, now).unwrap();
+    assert_eq!(market.ticket_price_source(), TicketPriceSource::Orderbook);
+    assert!(price.value < underlying.value);
+    assert!(price.value > 900_000);
+    assert_eq!(price.exponent, underlying.exponent);
+
+    assert!(market
+        .orderbook_ticket_price(underlying, now + 1 + MAX_TICKET_TWAP_AGE)
+        .is_err());
+}
+
+#[test]
+fn default_penalty_applies_after_grace_period() {
+    let mut market = <Market as bytemuck::Zeroable>::zeroed();
+    market.default_grace_period = 100;
+    market.default_penalty = 500;
+
+    assert!(!market.is_in_default(1_000, 1_099));
+    assert!(market.is_in_default(1_000, 1_100));
+    assert_eq!(market.default_penalty_on(10_000), 500);
+    assert_eq!(market.default_penalty_on(19), 0);
+}
+
+#[test]
+fn prepayment_rebate_is_portion_of_unaccrued_interest() {
+    let mut market = <Market as bytemuck::Zeroable>::zeroed();
+    assert_eq!(market.prepayment_mode(), PrepaymentMode::Disabled);
+
+    market.prepayment_mode = PrepaymentMode::Rebate as u8;
+    market.prepayment_rebate = 5_000;
+    assert_eq!(market.prepayment_mode(), PrepaymentMode::Rebate);
+    assert_eq!(market.prepayment_rebate_on(1_000), 500);
+}
+
+#[test]
+fn maker_fees_can_be_rebates() {
+    let schedule = FeeSchedule {
+        lend_maker_fee: -5,
+        lend_taker_fee: 10,
+        borrow_maker_fee: 3,
+        borrow_taker_fee: 20,
+        ..Default::default()
+    };
+    assert!(schedule.is_valid());
+
+    assert_eq!(schedule.maker_fee(MarketSide::Lend, 100_000), -50);
+    assert_eq!(schedule.maker_fee(MarketSide::Borrow, 100_000), 30);
+    assert_eq!(schedule.taker_fee(MarketSide::Lend, 100_000), 100);
+    assert_eq!(schedule.taker_fee(MarketSide::Borrow, 100_000), 200);
+
+    // rebates round towards zero
+    assert_eq!(schedule.maker_fee(MarketSide::Lend, 1_999), 0);
+
+    let excessive = FeeSchedule {
+        lend_maker_fee: -(MAX_FILL_FEE_BPS as i16) - 1,
+        ..Default::default()
+    };
+    assert!(!excessive.is_valid());
+}
+
+#[test]
+fn ticket_fees_are_swept_after_maturity() {
+    let mut schedule = FeeSchedule::default();
+
+    schedule.accrue_ticket_fee(10, 1_000).unwrap();
+    assert_eq!(schedule.take_matured_ticket_fees(100), 0);
+
+    // fees accrued while others are maturing wait for the next sweep
+    schedule.accrue_ticket_fee(20, 2_000).unwrap();
+    schedule.accrue_ticket_fee(5, 1_500).unwrap();
+    assert_eq!(schedule.take_matured_ticket_fees(999), 0);
+    assert_eq!(schedule.take_matured_ticket_fees(1_000), 10);
+    assert_eq!(schedule.maturing_ticket_fees, 25);
+
+    assert_eq!(schedule.take_matured_ticket_fees(1_999), 0);
+    assert_eq!(schedule.take_matured_ticket_fees(2_000), 25);
+    assert_eq!(schedule.take_matured_ticket_fees(3_000), 0);
+}
@@ -152,4 +152,44 @@ pub enum FixedTermErrorCode {
     MarginUserCannotUseInstruction,
     #[msg("cannot place an order with negative interest rates")]
     PriceOutOfBounds,
+    #[msg("the orderbook has not recently provided a price for tickets")]
+    StaleTicketPrice,
+    #[msg("the time in force is not valid for the order")]
+    InvalidTimeInForce,
+    #[msg("a fill-or-kill order could not be filled entirely")]
+    OrderNotFilled,
+    #[msg("the default penalty cannot exceed 100%")]
+    InvalidDefaultPenalty,
+    #[msg("the term loan is not past due beyond the grace period")]
+    TermLoanNotInDefault,
+    #[msg("the prepayment rebate cannot exceed 100% of unaccrued interest")]
+    InvalidPrepaymentRebate,
+    #[msg("the market does not allow loans to be prepaid")]
+    PrepaymentDisabled,
+    #[msg("the term loan has already matured")]
+    TermLoanMatured,
+    #[msg("the offer is not for this term deposit")]
+    WrongDepositOffer,
+    #[msg("the offer can only be accepted by a different buyer")]
+    WrongDepositBuyer,
+    #[msg("the auto roll policy requires positions to be rolled into a different market")]
+    AutoRollWrongMarket,
+    #[msg("the auto roll policy has reached its maximum number of rolls")]
+    AutoRollLimitReached,
+    #[msg("the orderbook rate is outside of the range allowed by the auto roll policy")]
+    AutoRollRateOutOfBand,
+    #[msg("the lend order could still be rolled on the orderbook")]
+    AutoRollFallbackNotNeeded,
+    #[msg("fill fees cannot exceed the maximum fee")]
+    InvalidFeeSchedule,
+    #[msg("an amended order must be smaller than the order on the book, or have a new price")]
+    InvalidOrderAmendment,
+    #[msg("a batch may only contain a limited number of orders, and new orders must be post only")]
+    InvalidBatchOrders,
+    #[msg("the price of the deposit offer is higher than the buyer's maximum price")]
+    DepositOfferPriceTooHigh,
+    #[msg("an order may not be reduced below the minimum order size")]
+    OrderBelowMinimumSize,
+    #[msg("the order has not expired")]
+    OrderNotExpired,
 }
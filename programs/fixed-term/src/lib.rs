@@ -132,7 +132,10 @@ use instructions::*;
 extern crate bitflags;
 
 use anchor_lang::prelude::*;
-use margin::state::{AutoRollConfig, BorrowAutoRollConfig, LendAutoRollConfig};
+use control::state::{PrepaymentMode, TicketPriceSource};
+use margin::state::{
+    AutoRollConfig, AutoRollPolicy, AutoRollPolicyConfig, BorrowAutoRollConfig, LendAutoRollConfig,
+};
 use orderbook::state::OrderParams;
 
 declare_id!("JPTermEg2DwrV39xb1Fs7z1VUxcvdPT7mE7cyGsQ4xt");
@@ -180,6 +183,40 @@ pub mod jet_fixed_term {
         instructions::modify_market::handler(ctx, data, offset)
     }
 
+    /// Select where the price of ticket collateral in the market comes from
+    pub fn configure_ticket_price_source(
+        ctx: Context<ConfigureTicketPriceSource>,
+        source: TicketPriceSource,
+    ) -> Result<()> {
+        instructions::configure_ticket_price_source::handler(ctx, source)
+    }
+
+    /// Set the grace period and penalty for repaying past due loans from the borrower's assets
+    pub fn configure_default_handling(
+        ctx: Context<ConfigureDefaultHandling>,
+        grace_period: u32,
+        penalty: u16,
+    ) -> Result<()> {
+        instructions::configure_default_handling::handler(ctx, grace_period, penalty)
+    }
+
+    /// Set how loans may be repaid before they mature
+    pub fn configure_prepayment(
+        ctx: Context<ConfigurePrepayment>,
+        mode: PrepaymentMode,
+        rebate: u16,
+    ) -> Result<()> {
+        instructions::configure_prepayment::handler(ctx, mode, rebate)
+    }
+
+    /// Set the maker and taker fees charged on orderbook fills
+    pub fn configure_fee_schedule(
+        ctx: Context<ConfigureFeeSchedule>,
+        params: FeeScheduleParams,
+    ) -> Result<()> {
+        instructions::configure_fee_schedule::handler(ctx, params)
+    }
+
     pub fn recover_uninitialized(ctx: Context<RecoverUninitialized>) -> Result<()> {
         instructions::recover_uninitialized::handler(ctx)
     }
@@ -215,6 +252,18 @@ pub mod jet_fixed_term {
         instructions::auto_roll_lend_order::handler(ctx)
     }
 
+    /// Instruction for authorized servicer to roll a matured `TermDeposit` into an order in
+    /// the market targeted by the user's lend policy
+    pub fn auto_roll_lend_order_to_market(ctx: Context<AutoRollLendOrderToMarket>) -> Result<()> {
+        instructions::auto_roll_lend_order_to_market::handler(ctx)
+    }
+
+    /// Instruction for authorized servicer to deposit a matured `TermDeposit` into a margin
+    /// pool when it cannot be rolled
+    pub fn auto_roll_lend_to_pool(ctx: Context<AutoRollLendToPool>) -> Result<()> {
+        instructions::auto_roll_lend_to_pool::handler(ctx)
+    }
+
     /// Configure settings for rolling orders
     pub fn configure_auto_roll_borrow(
         ctx: Context<ConfigureAutoRoll>,
@@ -231,6 +280,22 @@ pub mod jet_fixed_term {
         instructions::configure_auto_roll::handler(ctx, AutoRollConfig::Lend(config))
     }
 
+    /// Configure limits on when loans are rolled
+    pub fn configure_auto_roll_borrow_policy(
+        ctx: Context<ConfigureAutoRollPolicy>,
+        policy: AutoRollPolicy,
+    ) -> Result<()> {
+        instructions::configure_auto_roll_policy::handler(ctx, AutoRollPolicyConfig::Borrow(policy))
+    }
+
+    /// Configure limits on when and where deposits are rolled
+    pub fn configure_auto_roll_lend_policy(
+        ctx: Context<ConfigureAutoRollPolicy>,
+        policy: AutoRollPolicy,
+    ) -> Result<()> {
+        instructions::configure_auto_roll_policy::handler(ctx, AutoRollPolicyConfig::Lend(policy))
+    }
+
     /// Toggle the status of a term deposit's auto-roll
     pub fn toggle_auto_roll_deposit(ctx: Context<ToggleAutoRollDeposit>) -> Result<()> {
         instructions::toggle_auto_roll_deposit::handler(ctx)
@@ -246,6 +311,14 @@ pub mod jet_fixed_term {
         instructions::initialize_margin_user::handler(ctx)
     }
 
+    /// Cancel and place several post only orders on the book in a single instruction
+    pub fn margin_batch_orders(
+        ctx: Context<MarginBatchOrders>,
+        params: BatchOrderParams,
+    ) -> Result<()> {
+        instructions::margin_batch_orders::handler(ctx, params)
+    }
+
     /// Place a borrow order by leveraging margin account value
     pub fn margin_borrow_order(ctx: Context<MarginBorrowOrder>, params: OrderParams) -> Result<()> {
         instructions::margin_borrow_order::handler(ctx, params)
@@ -279,6 +352,23 @@ pub mod jet_fixed_term {
         instructions::repay::handler(ctx, amount)
     }
 
+    /// Repay a TermLoan that is past due beyond the grace period, using the borrower's
+    /// assets in the market before any tokens from the source account, and cancel the
+    /// borrower's resting orders so their value can be used once events are consumed
+    pub fn repay_past_due<'info>(
+        ctx: Context<'_, '_, '_, 'info, RepayPastDue<'info>>,
+        max_source_amount: u64,
+        order_ids: Vec<u128>,
+    ) -> Result<()> {
+        instructions::repay_past_due::handler(ctx, max_source_amount, order_ids)
+    }
+
+    /// Repay part or all of a TermLoan before it matures, at a discount determined by
+    /// the market's prepayment mode
+    pub fn prepay(ctx: Context<Prepay>, amount: u64) -> Result<()> {
+        instructions::prepay::handler(ctx, amount)
+    }
+
     /// Settle payments to a margin account
     pub fn settle(ctx: Context<Settle>) -> Result<()> {
         instructions::settle::handler(ctx)
@@ -303,6 +393,16 @@ pub mod jet_fixed_term {
         instructions::cancel_order::handler(ctx, order_id)
     }
 
+    /// Reduces the size of an order on the book without losing its place in the queue, or
+    /// moves it to a new price
+    pub fn amend_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, AmendOrder<'info>>,
+        order_id: u128,
+        params: AmendOrderParams,
+    ) -> Result<()> {
+        instructions::amend_order::handler(ctx, order_id, params)
+    }
+
     /// Place a `Lend` order to the book by depositing tokens
     pub fn lend_order(ctx: Context<LendOrder>, params: OrderParams, seed: Vec<u8>) -> Result<()> {
         instructions::lend_order::handler(ctx, params, seed)
@@ -317,6 +417,19 @@ pub mod jet_fixed_term {
         instructions::consume_events::handler(ctx, num_events, seed_bytes)
     }
 
+    /// Crank specific instruction, removes `GoodTilTime` orders that have expired from the
+    /// book. Their unfilled amounts are returned to their owners when events are consumed.
+    pub fn expire_orders(ctx: Context<ExpireOrders>, order_ids: Vec<u128>) -> Result<()> {
+        instructions::expire_orders::handler(ctx, order_ids)
+    }
+
+    /// Crank specific instruction, includes the current orderbook rate in the average used to
+    /// price tickets, for markets that price tickets from the orderbook. Events do not need to
+    /// be waiting in the queue, so the average is kept up to date while the market is quiet.
+    pub fn sample_ticket_twap(ctx: Context<SampleTicketTwap>) -> Result<()> {
+        instructions::sample_ticket_twap::handler(ctx)
+    }
+
     //
     // =============================================
     //
@@ -349,6 +462,22 @@ pub mod jet_fixed_term {
     ) -> Result<()> {
         instructions::transfer_deposit::handler(ctx, new_owner)
     }
+
+    /// Offer to sell a term deposit for a price in underlying tokens
+    pub fn offer_deposit(ctx: Context<OfferDeposit>, params: OfferDepositParams) -> Result<()> {
+        instructions::offer_deposit::handler(ctx, params)
+    }
+
+    /// Withdraw an offer to sell a term deposit
+    pub fn cancel_deposit_offer(ctx: Context<CancelDepositOffer>) -> Result<()> {
+        instructions::cancel_deposit_offer::handler(ctx)
+    }
+
+    /// Pay for a term deposit that has been offered, taking ownership of it. Fails if the
+    /// price of the offer is higher than `max_price`.
+    pub fn accept_deposit_offer(ctx: Context<AcceptDepositOffer>, max_price: u64) -> Result<()> {
+        instructions::accept_deposit_offer::handler(ctx, max_price)
+    }
     //
     // =============================================
     //
@@ -409,6 +538,12 @@ pub mod seeds {
     #[constant]
     pub const TERM_DEPOSIT: &[u8] = b"term_deposit";
 
+    #[constant]
+    pub const DEPOSIT_OFFER: &[u8] = b"deposit_offer";
+
+    #[constant]
+    pub const FEE_SCHEDULE: &[u8] = b"fee_schedule";
+
     #[constant]
     pub const USER: &[u8] = b"user";
 
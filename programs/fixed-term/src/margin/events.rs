@@ -1,10 +1,11 @@
 use agnostic_orderbook::state::OrderSummary;
 use anchor_lang::{event, prelude::*};
 
-use crate::tickets::state::TermDepositFlags;
+use crate::{control::state::PrepaymentMode, tickets::state::TermDepositFlags};
 
 use super::state::{
-    BorrowAutoRollConfig, LendAutoRollConfig, MarginUser, SequenceNumber, TermLoanFlags,
+    AutoRollPolicy, BorrowAutoRollConfig, LendAutoRollConfig, MarginUser, SequenceNumber,
+    TermLoanFlags,
 };
 
 #[event]
@@ -75,6 +76,42 @@ pub struct TermLoanFulfilled {
     pub is_auto_roll: bool,
 }
 
+/// A loan was repaid before maturity
+#[event]
+pub struct TermLoanPrepaid {
+    pub term_loan: Pubkey,
+    pub margin_user: Pubkey,
+    pub mode: PrepaymentMode,
+    /// Tokens or tickets paid from the source account
+    pub source_amount: u64,
+    /// Tokens from the market fees credited towards the repayment
+    pub rebate: u64,
+    /// The reduction in the loan balance
+    pub repayment_amount: u64,
+    pub final_balance: u64,
+}
+
+/// A loan was repaid from the borrower's assets after defaulting
+#[event]
+pub struct TermLoanDefaultRepaid {
+    pub term_loan: Pubkey,
+    pub margin_user: Pubkey,
+    pub repayer: Pubkey,
+    /// Entitled tokens of the borrower used for the repayment
+    pub tokens_used: u64,
+    /// Entitled tickets of the borrower used for the repayment
+    pub tickets_used: u64,
+    /// Tickets staked in the borrower's deposits used for the repayment
+    pub staked_tickets_used: u64,
+    /// Tokens paid from the source account
+    pub source_amount: u64,
+    /// The reduction in the loan balance
+    pub repayment_amount: u64,
+    /// The default penalty paid in addition to the repayment, kept in the vault for lenders
+    pub penalty: u64,
+    pub final_balance: u64,
+}
+
 #[event]
 pub struct TermDepositCreated {
     pub term_deposit: Pubkey,
@@ -143,6 +180,25 @@ pub struct LendRollConfigUpdated {
     pub config: LendAutoRollConfig,
 }
 
+#[event]
+pub struct BorrowRollPolicyUpdated {
+    pub policy: AutoRollPolicy,
+}
+
+#[event]
+pub struct LendRollPolicyUpdated {
+    pub policy: AutoRollPolicy,
+}
+
+/// A lend position that could not be rolled was deposited into a margin pool
+#[event]
+pub struct TermDepositRolledToPool {
+    pub deposit: Pubkey,
+    pub margin_account: Pubkey,
+    pub margin_pool: Pubkey,
+    pub amount: u64,
+}
+
 #[event]
 pub struct TermDepositFlagsToggled {
     pub margin_account: Pubkey,
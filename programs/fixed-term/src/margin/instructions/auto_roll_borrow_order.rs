@@ -144,6 +144,7 @@ impl<'info> AutoRollBorrowOrder<'info> {
             post_allowed: false,
             auto_stake: false,
             auto_roll: true,
+            time_in_force: TimeInForce::GoodTilCancelled,
         };
         self.orderbook_mut
             .market
@@ -177,10 +178,23 @@ impl<'info> AutoRollBorrowOrder<'info> {
 
         Ok(())
     }
+
+    /// Check and record the roll against the user's borrow policy, if they have one
+    fn apply_policy(&mut self) -> Result<()> {
+        if let Some(mut policy) = self.margin_user.borrow_roll_policy {
+            let market = self.orderbook_mut.market.key();
+            let tenor = self.orderbook_mut.market.load()?.borrow_tenor;
+            policy.check_roll(&market, &market, self.orderbook_mut.mid_rate(tenor)?)?;
+            policy.record_roll();
+            self.margin_user.borrow_roll_policy = Some(policy);
+        }
+        Ok(())
+    }
 }
 
 pub fn handler(ctx: Context<AutoRollBorrowOrder>) -> Result<()> {
     ctx.accounts.assert_can_auto_roll()?;
+    ctx.accounts.apply_policy()?;
 
     let filled = ctx.accounts.borrow_now(
         ctx.accounts.params()?,
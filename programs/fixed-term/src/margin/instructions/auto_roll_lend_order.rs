@@ -135,6 +135,7 @@ impl<'info> AutoRollLendOrder<'info> {
             post_allowed: true,
             auto_stake: true,
             auto_roll: true,
+            time_in_force: TimeInForce::GoodTilCancelled,
         })
     }
 
@@ -144,10 +145,23 @@ impl<'info> AutoRollLendOrder<'info> {
         }
         Ok(())
     }
+
+    /// Check and record the roll against the user's lend policy, if they have one
+    fn apply_policy(&mut self) -> Result<()> {
+        if let Some(mut policy) = self.margin_user.lend_roll_policy {
+            let market = self.orderbook_mut.market.key();
+            let tenor = self.orderbook_mut.market.load()?.lend_tenor;
+            policy.check_roll(&market, &market, self.orderbook_mut.mid_rate(tenor)?)?;
+            policy.record_roll();
+            self.margin_user.lend_roll_policy = Some(policy);
+        }
+        Ok(())
+    }
 }
 
 pub fn handler(ctx: Context<AutoRollLendOrder>) -> Result<()> {
     ctx.accounts.assert_deposit_can_auto_roll()?;
+    ctx.accounts.apply_policy()?;
     ctx.accounts.margin_redeem()?;
     let params = ctx.accounts.order_params()?;
     let adapter = ctx
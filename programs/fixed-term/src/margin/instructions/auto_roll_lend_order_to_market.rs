@@ -0,0 +1,239 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use jet_margin::MarginAccount;
+
+use crate::{
+    control::state::Market,
+    margin::state::MarginUser,
+    orderbook::state::*,
+    serialization::RemainingAccounts,
+    tickets::state::{
+        MarginRedeemDepositAccounts, RedeemDepositAccounts, TermDeposit, TermDepositFlags,
+    },
+    FixedTermErrorCode,
+};
+
+/// The matured deposit to roll, and the market it is leaving
+#[derive(Accounts)]
+pub struct AutoRollSource<'info> {
+    /// The `MarginUser` account for the market holding the deposit
+    #[account(
+        mut,
+        has_one = market @ FixedTermErrorCode::UserNotInMarket,
+        has_one = ticket_collateral @ FixedTermErrorCode::WrongTicketCollateralAccount,
+    )]
+    pub margin_user: Box<Account<'info, MarginUser>>,
+
+    /// The `TermDeposit` account to roll
+    #[account(
+        mut,
+        close = rent_receiver,
+        has_one = market @ FixedTermErrorCode::WrongMarket,
+        constraint = deposit.owner == margin_user.margin_account @ FixedTermErrorCode::WrongDepositOwner,
+        constraint = deposit.payer == rent_receiver.key() @ FixedTermErrorCode::WrongRentReceiver,
+    )]
+    pub deposit: Box<Account<'info, TermDeposit>>,
+
+    /// Token account used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(mut)]
+    pub ticket_collateral: AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(
+        mut,
+        address = market.load()?.ticket_collateral_mint @ FixedTermErrorCode::WrongTicketCollateralMint,
+    )]
+    pub ticket_collateral_mint: AccountInfo<'info>,
+
+    /// The market the deposit was made in
+    #[account(
+        has_one = underlying_token_vault @ FixedTermErrorCode::WrongVault,
+        constraint = !market.load()?.tickets_paused.as_bool() @ FixedTermErrorCode::TicketsPaused,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// The vault holding the tokens owed for the deposit
+    #[account(mut)]
+    pub underlying_token_vault: Account<'info, TokenAccount>,
+
+    /// Reciever for rent from the closing of the TermDeposit
+    #[account(mut)]
+    pub rent_receiver: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AutoRollLendOrderToMarket<'info> {
+    /// The `MarginUser` account for the market the deposit is rolled into, which must differ
+    /// from the `MarginUser` of the market it is leaving
+    #[account(
+        mut,
+        constraint = margin_user.market == orderbook_mut.market.key() @ FixedTermErrorCode::WrongMarket,
+        constraint = margin_user.key() != source.margin_user.key() @ FixedTermErrorCode::AutoRollWrongMarket,
+        has_one = margin_account @ FixedTermErrorCode::WrongMarginAccount,
+        has_one = ticket_collateral @ FixedTermErrorCode::WrongTicketCollateralAccount,
+    )]
+    pub margin_user: Box<Account<'info, MarginUser>>,
+
+    /// The `MarginAccount` this `TermDeposit` belongs to
+    #[account(
+        constraint = margin_account.key() == source.margin_user.margin_account @ FixedTermErrorCode::WrongMarginAccount,
+    )]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The deposit being rolled, in the market it is leaving
+    pub source: AutoRollSource<'info>,
+
+    /// In the case the order matches, the new `TermDeposit` to account for
+    #[account(mut)]
+    pub new_deposit: AccountInfo<'info>,
+
+    /// Token account used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(
+        mut,
+        constraint = ticket_collateral.mint == ticket_collateral_mint.key() @ FixedTermErrorCode::WrongTicketCollateralAccount,
+    )]
+    pub ticket_collateral: Box<Account<'info, TokenAccount>>,
+
+    /// Token mint used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(
+        mut,
+        address = orderbook_mut.ticket_collateral_mint() @ FixedTermErrorCode::WrongTicketCollateralMint,
+    )]
+    pub ticket_collateral_mint: Box<Account<'info, Mint>>,
+
+    /// The market ticket mint
+    #[account(
+        mut,
+        address = orderbook_mut.ticket_mint() @ FixedTermErrorCode::WrongTicketMint
+    )]
+    pub ticket_mint: Account<'info, Mint>,
+
+    /// The vault of the market the deposit is rolled into
+    #[account(mut, address = orderbook_mut.vault() @ FixedTermErrorCode::WrongVault)]
+    pub underlying_token_vault: Account<'info, TokenAccount>,
+
+    /// The accounts needed to interact with the orderbook
+    pub orderbook_mut: OrderbookMut<'info>,
+
+    /// Payer for PDA initialization
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> AutoRollLendOrderToMarket<'info> {
+    /// Check that the user's lend policy directs the deposit into this market, and record the roll
+    fn apply_policy(&mut self) -> Result<()> {
+        if !self
+            .source
+            .deposit
+            .flags
+            .contains(TermDepositFlags::AUTO_ROLL)
+        {
+            return err!(FixedTermErrorCode::AutoRollDisabled);
+        }
+
+        let source = self.source.market.load()?;
+        let target = self.orderbook_mut.market.load()?;
+        require_keys_eq!(
+            source.underlying_token_mint,
+            target.underlying_token_mint,
+            FixedTermErrorCode::WrongUnderlyingTokenMint
+        );
+        require_keys_eq!(
+            source.airspace,
+            target.airspace,
+            FixedTermErrorCode::WrongAirspace
+        );
+
+        let mut policy = match self.source.margin_user.lend_roll_policy {
+            Some(policy) => policy,
+            None => return err!(FixedTermErrorCode::InvalidAutoRollConfig),
+        };
+        policy.check_roll(
+            &self.source.market.key(),
+            &self.orderbook_mut.market.key(),
+            self.orderbook_mut.mid_rate(target.lend_tenor)?,
+        )?;
+        policy.record_roll();
+        self.source.margin_user.lend_roll_policy = Some(policy);
+
+        Ok(())
+    }
+
+    /// Redeem the deposit, moving its tokens into the vault of the new market
+    fn margin_redeem(&mut self) -> Result<()> {
+        let accounts = &mut MarginRedeemDepositAccounts {
+            margin_user: &mut self.source.margin_user,
+            ticket_collateral: &self.source.ticket_collateral,
+            ticket_collateral_mint: &self.source.ticket_collateral_mint,
+            inner: &RedeemDepositAccounts {
+                deposit: &self.source.deposit,
+                owner: self.margin_account.as_ref(),
+                payer: &self.source.rent_receiver,
+                token_account: self.underlying_token_vault.as_ref(),
+                market: &self.source.market,
+                underlying_token_vault: &self.source.underlying_token_vault,
+                token_program: &self.token_program,
+            },
+        };
+        accounts.margin_redeem(true)
+    }
+
+    fn margin_lend_order(&mut self, params: &OrderParams, adapter: Option<Pubkey>) -> Result<()> {
+        let accounts = &mut MarginLendAccounts {
+            margin_user: &mut self.margin_user,
+            ticket_collateral: self.ticket_collateral.as_ref().as_ref(),
+            ticket_collateral_mint: self.ticket_collateral_mint.as_ref().as_ref(),
+            inner: &mut LendOrderAccounts {
+                authority: self.margin_account.as_ref(),
+                orderbook_mut: &mut self.orderbook_mut,
+                ticket_settlement: &self.new_deposit,
+                lender_tokens: &self.new_deposit, // not needed for this instruction, arbitrary account
+                underlying_token_vault: &self.underlying_token_vault,
+                ticket_mint: &self.ticket_mint,
+                payer: &self.payer,
+                system_program: &self.system_program,
+                token_program: &self.token_program,
+            },
+        };
+        accounts.margin_lend_order(params, adapter, false)
+    }
+
+    /// The order is placed with the lend config of the user in the new market, since limit
+    /// prices depend on the tenor
+    fn order_params(&self) -> Result<OrderParams> {
+        let config = match &self.margin_user.lend_roll_config {
+            Some(config) => config,
+            None => return err!(FixedTermErrorCode::InvalidAutoRollConfig),
+        };
+
+        Ok(OrderParams {
+            max_ticket_qty: u64::MAX,
+            max_underlying_token_qty: self.source.deposit.amount,
+            limit_price: config.limit_price,
+            match_limit: u64::MAX,
+            post_only: false,
+            post_allowed: true,
+            auto_stake: true,
+            auto_roll: true,
+            time_in_force: TimeInForce::GoodTilCancelled,
+        })
+    }
+}
+
+/// Roll a matured deposit into a different market for the same token, as directed by the
+/// user's lend policy in the market the deposit was made in
+pub fn handler(ctx: Context<AutoRollLendOrderToMarket>) -> Result<()> {
+    ctx.accounts.apply_policy()?;
+    let params = ctx.accounts.order_params()?;
+    ctx.accounts.margin_redeem()?;
+    let adapter = ctx
+        .remaining_accounts
+        .iter()
+        .maybe_next_adapter()?
+        .map(|a| a.key());
+    ctx.accounts.margin_lend_order(&params, adapter)
+}
@@ -0,0 +1,195 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use jet_margin::MarginAccount;
+use jet_margin_pool::{cpi::accounts::Deposit, program::JetMarginPool, ChangeKind, MarginPool};
+use jet_program_common::interest_pricing::{InterestPricer, PricerImpl};
+
+use crate::{
+    control::state::Market,
+    events::TermDepositRolledToPool,
+    margin::state::MarginUser,
+    orderbook::state::{orderbook_best_prices, orderbook_mid_price},
+    tickets::state::{
+        MarginRedeemDepositAccounts, RedeemDepositAccounts, TermDeposit, TermDepositFlags,
+    },
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct AutoRollLendToPool<'info> {
+    /// The `MarginUser` account for this market
+    #[account(
+        mut,
+        has_one = market @ FixedTermErrorCode::UserNotInMarket,
+        has_one = margin_account @ FixedTermErrorCode::WrongMarginAccount,
+        has_one = ticket_collateral @ FixedTermErrorCode::WrongTicketCollateralAccount,
+    )]
+    pub margin_user: Box<Account<'info, MarginUser>>,
+
+    /// The `MarginAccount` this `TermDeposit` belongs to
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The `TermDeposit` account that could not be rolled
+    #[account(
+        mut,
+        close = rent_receiver,
+        has_one = market @ FixedTermErrorCode::WrongMarket,
+        constraint = deposit.owner == margin_account.key() @ FixedTermErrorCode::WrongDepositOwner,
+        constraint = deposit.payer == rent_receiver.key() @ FixedTermErrorCode::WrongRentReceiver,
+    )]
+    pub deposit: Box<Account<'info, TermDeposit>>,
+
+    /// Token account used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(mut)]
+    pub ticket_collateral: AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(
+        mut,
+        address = market.load()?.ticket_collateral_mint @ FixedTermErrorCode::WrongTicketCollateralMint,
+    )]
+    pub ticket_collateral_mint: AccountInfo<'info>,
+
+    /// The market the deposit was made in
+    #[account(
+        has_one = underlying_token_vault @ FixedTermErrorCode::WrongVault,
+        constraint = !market.load()?.tickets_paused.as_bool() @ FixedTermErrorCode::TicketsPaused,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// The vault holding the tokens owed for the deposit
+    #[account(mut)]
+    pub underlying_token_vault: Account<'info, TokenAccount>,
+
+    /// Reciever for rent from the closing of the TermDeposit
+    #[account(mut)]
+    pub rent_receiver: AccountInfo<'info>,
+
+    /// The market the deposit would be rolled into, according to the user's lend policy
+    #[account(
+        has_one = bids @ FixedTermErrorCode::WrongBids,
+        has_one = asks @ FixedTermErrorCode::WrongAsks,
+    )]
+    pub roll_market: AccountLoader<'info, Market>,
+
+    /// CHECK: has_one
+    pub bids: AccountInfo<'info>,
+
+    /// CHECK: has_one
+    pub asks: AccountInfo<'info>,
+
+    /// The margin pool for the underlying token
+    #[account(
+        mut,
+        constraint = margin_pool.token_mint == market.load()?.underlying_token_mint @ FixedTermErrorCode::WrongUnderlyingTokenMint,
+    )]
+    pub margin_pool: Box<Account<'info, MarginPool>>,
+
+    /// CHECK: margin pool
+    #[account(mut)]
+    pub margin_pool_vault: AccountInfo<'info>,
+
+    /// CHECK: margin pool
+    #[account(mut)]
+    pub deposit_note_mint: AccountInfo<'info>,
+
+    /// The deposit note account of the margin account, which receives the pool deposit
+    #[account(
+        mut,
+        constraint = pool_deposit.owner == margin_account.key() @ FixedTermErrorCode::WrongMarginAccount,
+    )]
+    pub pool_deposit: Box<Account<'info, TokenAccount>>,
+
+    pub margin_pool_program: Program<'info, JetMarginPool>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> AutoRollLendToPool<'info> {
+    /// The deposit may only fall back to the pool when it cannot be rolled on the orderbook
+    fn assert_cannot_roll(&self) -> Result<()> {
+        if !self.deposit.flags.contains(TermDepositFlags::AUTO_ROLL) {
+            return err!(FixedTermErrorCode::AutoRollDisabled);
+        }
+        let policy = match &self.margin_user.lend_roll_policy {
+            Some(policy) if policy.pool_fallback => policy,
+            _ => return err!(FixedTermErrorCode::InvalidAutoRollConfig),
+        };
+        require_keys_eq!(
+            policy.target_market(&self.market.key()),
+            self.roll_market.key(),
+            FixedTermErrorCode::AutoRollWrongMarket
+        );
+
+        let tenor = self.roll_market.load()?.lend_tenor;
+        let rate = orderbook_mid_price(&self.bids, &self.asks)?
+            .map(|price| PricerImpl::price_fp32_to_bps_yearly_interest(price, tenor));
+        let (_, best_ask) = orderbook_best_prices(&self.bids, &self.asks)?;
+        let fillable = match (&self.margin_user.lend_roll_config, best_ask) {
+            (Some(config), Some(ask)) => ask <= config.limit_price,
+            _ => false,
+        };
+
+        if policy.has_rolls_remaining() && policy.rate_in_band(rate) && fillable {
+            return err!(FixedTermErrorCode::AutoRollFallbackNotNeeded);
+        }
+
+        Ok(())
+    }
+
+    fn margin_redeem(&mut self) -> Result<()> {
+        let accounts = &mut MarginRedeemDepositAccounts {
+            margin_user: &mut self.margin_user,
+            ticket_collateral: &self.ticket_collateral,
+            ticket_collateral_mint: &self.ticket_collateral_mint,
+            inner: &RedeemDepositAccounts {
+                deposit: &self.deposit,
+                owner: self.margin_account.as_ref(),
+                payer: &self.rent_receiver,
+                token_account: self.pool_deposit.as_ref().as_ref(), // not needed for this instruction, arbitrary account
+                market: &self.market,
+                underlying_token_vault: &self.underlying_token_vault,
+                token_program: &self.token_program,
+            },
+        };
+        accounts.margin_redeem(false)
+    }
+
+    fn deposit_to_pool(&self, amount: u64) -> Result<()> {
+        jet_margin_pool::cpi::deposit(
+            CpiContext::new(
+                self.margin_pool_program.to_account_info(),
+                Deposit {
+                    margin_pool: self.margin_pool.to_account_info(),
+                    vault: self.margin_pool_vault.to_account_info(),
+                    deposit_note_mint: self.deposit_note_mint.to_account_info(),
+                    depositor: self.market.to_account_info(),
+                    source: self.underlying_token_vault.to_account_info(),
+                    destination: self.pool_deposit.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                },
+            )
+            .with_signer(&[&self.market.load()?.authority_seeds()]),
+            ChangeKind::ShiftBy,
+            amount,
+        )
+    }
+}
+
+/// Deposit a matured auto-roll deposit into the margin pool when the user's lend policy
+/// does not allow it to be rolled, or the orderbook has no offers at the user's limit price
+pub fn handler(ctx: Context<AutoRollLendToPool>) -> Result<()> {
+    ctx.accounts.assert_cannot_roll()?;
+
+    let amount = ctx.accounts.deposit.amount;
+    ctx.accounts.margin_redeem()?;
+    ctx.accounts.deposit_to_pool(amount)?;
+
+    emit!(TermDepositRolledToPool {
+        deposit: ctx.accounts.deposit.key(),
+        margin_account: ctx.accounts.margin_account.key(),
+        margin_pool: ctx.accounts.margin_pool.key(),
+        amount,
+    });
+
+    Ok(())
+}
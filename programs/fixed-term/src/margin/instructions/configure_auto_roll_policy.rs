@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use jet_margin::MarginAccount;
+
+use crate::{
+    control::state::Market,
+    events::{BorrowRollPolicyUpdated, LendRollPolicyUpdated},
+    margin::state::{AutoRollPolicy, AutoRollPolicyConfig, MarginUser},
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct ConfigureAutoRollPolicy<'info> {
+    /// The `MarginUser` account.
+    /// This account is specific to a particular fixed-term market
+    ///
+    /// Users created before auto roll policies were added are reallocated to make room for them.
+    #[account(
+        mut,
+        has_one = margin_account,
+        has_one = market,
+        realloc = 8 + std::mem::size_of::<MarginUser>(),
+        realloc::payer = payer,
+        realloc::zero = false,
+    )]
+    pub margin_user: Box<Account<'info, MarginUser>>,
+
+    /// The signing authority for this user account
+    #[account(has_one = owner)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The controlling signer for the `MarginAccount`
+    pub owner: Signer<'info>,
+
+    /// The fixed-term market this user belongs to
+    pub market: AccountLoader<'info, Market>,
+
+    /// Payer for any additional space needed by the `MarginUser`
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// assert the rate band of a policy makes sense
+fn check_rate_band(policy: &AutoRollPolicy) -> Result<()> {
+    if policy.max_rate_bps != 0 && policy.min_rate_bps > policy.max_rate_bps {
+        msg!(
+            "Policy rate band is invalid. Given range: [{}, {}]",
+            policy.min_rate_bps,
+            policy.max_rate_bps
+        );
+        return err!(FixedTermErrorCode::InvalidAutoRollConfig);
+    }
+    Ok(())
+}
+
+/// assert the new borrow policy makes sense
+///
+/// Borrow orders are always rolled within their own market, and have no pool fallback.
+fn check_borrow_policy(policy: &AutoRollPolicy) -> Result<()> {
+    check_rate_band(policy)?;
+    if policy.target_market != Pubkey::default() || policy.pool_fallback {
+        msg!("Borrow policies cannot target another market or fall back to a margin pool");
+        return err!(FixedTermErrorCode::InvalidAutoRollConfig);
+    }
+    Ok(())
+}
+
+pub fn handler(ctx: Context<ConfigureAutoRollPolicy>, config: AutoRollPolicyConfig) -> Result<()> {
+    let user = &mut ctx.accounts.margin_user;
+
+    match config {
+        AutoRollPolicyConfig::Borrow(mut policy) => {
+            check_borrow_policy(&policy)?;
+            policy.rolls = 0;
+            user.borrow_roll_policy = Some(policy);
+            emit!(BorrowRollPolicyUpdated { policy })
+        }
+        AutoRollPolicyConfig::Lend(mut policy) => {
+            check_rate_band(&policy)?;
+            policy.rolls = 0;
+            user.lend_roll_policy = Some(policy);
+            emit!(LendRollPolicyUpdated { policy })
+        }
+    }
+
+    Ok(())
+}
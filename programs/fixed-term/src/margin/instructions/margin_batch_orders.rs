@@ -0,0 +1,232 @@
+use agnostic_orderbook::state::Side;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{accessor::mint, transfer, Token, TokenAccount, Transfer};
+use jet_margin::{AdapterResult, MarginAccount, PositionChange};
+use jet_program_common::traits::SafeAdd;
+use jet_program_proc_macros::MarketTokenManager;
+
+use crate::{
+    events::{OrderPlaced, OrderType},
+    margin::state::{return_to_margin, MarginUser},
+    market_token_manager::MarketTokenManager,
+    orderbook::state::*,
+    serialization::RemainingAccounts,
+    FixedTermErrorCode,
+};
+
+/// The maximum number of orders that may be placed or cancelled in one batch
+pub const MAX_BATCH_ORDERS: usize = 8;
+
+/// Orders to cancel and place in a single instruction
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default, Clone)]
+pub struct BatchOrderParams {
+    /// The ids of orders to remove from the book, before any new orders are placed
+    pub cancel: Vec<u128>,
+    /// Lend orders to place, which must be post only
+    pub lend: Vec<OrderParams>,
+    /// Borrow orders to place, which must be post only
+    pub borrow: Vec<OrderParams>,
+}
+
+impl BatchOrderParams {
+    /// Is the batch small enough, and are the new orders only posted to the book
+    pub fn is_valid(&self) -> bool {
+        self.cancel.len() + self.lend.len() + self.borrow.len() <= MAX_BATCH_ORDERS
+            && self.lend.iter().chain(&self.borrow).all(|p| p.post_only)
+    }
+}
+
+#[derive(Accounts, MarketTokenManager)]
+pub struct MarginBatchOrders<'info> {
+    /// The account tracking the orders of the margin account
+    #[account(
+        mut,
+        has_one = margin_account @ FixedTermErrorCode::WrongMarginUserAuthority,
+        has_one = claims @ FixedTermErrorCode::WrongClaimAccount,
+        has_one = ticket_collateral @ FixedTermErrorCode::WrongTicketCollateralAccount,
+        has_one = underlying_collateral @ FixedTermErrorCode::WrongUnderlyingCollateralAccount,
+        constraint = margin_user.market == orderbook_mut.market.key() @ FixedTermErrorCode::UserNotInMarket,
+    )]
+    pub margin_user: Box<Account<'info, MarginUser>>,
+
+    /// The margin account placing the orders
+    #[account(signer)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// Token account used by the margin program to track the debt that must be collateralized
+    /// CHECK: margin_user
+    #[account(mut)]
+    pub claims: AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the debt that must be collateralized
+    /// CHECK: address
+    #[account(mut, address = orderbook_mut.claims_mint() @ FixedTermErrorCode::WrongClaimMint)]
+    pub claims_mint: AccountInfo<'info>,
+
+    /// Token account used by the margin program to track the value of posted lend orders
+    /// CHECK: margin_user
+    #[account(mut)]
+    pub ticket_collateral: AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the value of posted lend orders
+    /// CHECK: address
+    #[account(mut, address = orderbook_mut.ticket_collateral_mint() @ FixedTermErrorCode::WrongTicketCollateralMint)]
+    pub ticket_collateral_mint: AccountInfo<'info>,
+
+    /// Token account used by the margin program to track the value of posted borrow orders
+    /// CHECK: margin_user
+    #[account(mut)]
+    pub underlying_collateral: AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the value of posted borrow orders
+    /// CHECK: address
+    #[account(mut, address = orderbook_mut.underlying_collateral_mint() @ FixedTermErrorCode::WrongUnderlyingCollateralMint)]
+    pub underlying_collateral_mint: AccountInfo<'info>,
+
+    #[market]
+    pub orderbook_mut: OrderbookMut<'info>,
+
+    /// where to loan tokens from
+    #[account(mut, constraint = mint(&lender_tokens.to_account_info())? == orderbook_mut.underlying_mint() @ FixedTermErrorCode::WrongUnderlyingTokenMint)]
+    pub lender_tokens: Account<'info, TokenAccount>,
+
+    /// The market token vault
+    #[account(mut, address = orderbook_mut.vault() @ FixedTermErrorCode::WrongVault)]
+    pub underlying_token_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    // Optional event adapter account
+    // pub event_adapter: AccountInfo<'info>,
+}
+
+/// The combined amounts of the orders placed in a batch
+#[derive(Default)]
+struct Posted {
+    lend_tickets: u64,
+    lend_tokens: u64,
+    borrow_tickets: u64,
+    borrow_tokens: u64,
+    claims: u64,
+}
+
+pub fn handler(ctx: Context<MarginBatchOrders>, params: BatchOrderParams) -> Result<()> {
+    require!(params.is_valid(), FixedTermErrorCode::InvalidBatchOrders);
+    let adapter = ctx
+        .remaining_accounts
+        .iter()
+        .maybe_next_adapter()?
+        .map(|a| a.key());
+    let a = ctx.accounts;
+    let margin_account = a.margin_account.key();
+    let margin_user = a.margin_user.key();
+
+    // cancelled orders are returned to the margin user when events are consumed
+    for order_id in params.cancel {
+        a.orderbook_mut.cancel_order(order_id, margin_account)?;
+    }
+
+    let mut posted = Posted::default();
+    for params in params.lend {
+        let (info, summary) = a.orderbook_mut.place_margin_order(
+            Side::Bid,
+            params,
+            margin_account,
+            margin_user,
+            adapter,
+            margin_lend_order_flags(&a.margin_user, &params)?,
+        )?;
+        posted.lend_tickets = posted.lend_tickets.safe_add(summary.base_posted())?;
+        posted.lend_tokens = posted.lend_tokens.safe_add(summary.quote_combined()?)?;
+        emit_order_placed(a, &params, &info, &summary, OrderType::MarginLend);
+    }
+    for mut params in params.borrow {
+        a.orderbook_mut
+            .market
+            .load()?
+            .add_origination_fee(&mut params);
+        let (info, summary) = a.orderbook_mut.place_margin_order(
+            Side::Ask,
+            params,
+            margin_account,
+            margin_user,
+            adapter,
+            margin_borrow_order_flags(&a.margin_user, &params)?,
+        )?;
+        posted.borrow_tickets = posted.borrow_tickets.safe_add(summary.base_posted())?;
+        posted.borrow_tokens = posted
+            .borrow_tokens
+            .safe_add(summary.quote_posted(RoundingAction::PostBorrow.direction())?)?;
+        posted.claims = posted.claims.safe_add(summary.base_combined())?;
+        emit_order_placed(a, &params, &info, &summary, OrderType::MarginBorrow);
+    }
+
+    a.margin_user.lend_order(0, posted.lend_tickets)?;
+    a.margin_user
+        .post_borrow_order(posted.borrow_tokens, posted.borrow_tickets)?;
+    a.margin_user.emit_all_balances()?;
+
+    if posted.lend_tokens > 0 {
+        transfer(
+            CpiContext::new(
+                a.token_program.to_account_info(),
+                Transfer {
+                    from: a.lender_tokens.to_account_info(),
+                    to: a.underlying_token_vault.to_account_info(),
+                    authority: a.margin_account.to_account_info(),
+                },
+            ),
+            posted.lend_tokens,
+        )?;
+    }
+    for (mint, account, amount) in [
+        (
+            &a.ticket_collateral_mint,
+            &a.ticket_collateral,
+            posted.lend_tickets,
+        ),
+        (
+            &a.underlying_collateral_mint,
+            &a.underlying_collateral,
+            posted.borrow_tokens,
+        ),
+        (&a.claims_mint, &a.claims, posted.claims),
+    ] {
+        if amount > 0 {
+            a.mint(mint, account, amount)?;
+        }
+    }
+
+    // this is just used to make sure the position is still registered.
+    // it's actually registered by initialize_margin_user
+    return_to_margin(
+        &a.margin_account.to_account_info(),
+        &AdapterResult {
+            position_changes: vec![(
+                a.claims_mint.key(),
+                vec![PositionChange::Register(a.claims.key())],
+            )],
+        },
+    )
+}
+
+fn emit_order_placed(
+    accounts: &MarginBatchOrders,
+    params: &OrderParams,
+    info: &MarginCallbackInfo,
+    summary: &SensibleOrderSummary,
+    order_type: OrderType,
+) {
+    emit!(OrderPlaced {
+        market: accounts.orderbook_mut.market.key(),
+        authority: accounts.margin_account.key(),
+        margin_user: Some(accounts.margin_user.key()),
+        order_tag: info.order_tag.as_u128(),
+        order_summary: summary.summary(),
+        limit_price: params.limit_price,
+        auto_stake: params.auto_stake,
+        post_only: params.post_only,
+        post_allowed: params.post_allowed,
+        order_type,
+        auto_roll: params.auto_roll,
+    });
+}
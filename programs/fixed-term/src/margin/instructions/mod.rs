@@ -1,27 +1,39 @@
 pub mod auto_roll_borrow_order;
 pub mod auto_roll_lend_order;
+pub mod auto_roll_lend_order_to_market;
+pub mod auto_roll_lend_to_pool;
 pub mod configure_auto_roll;
+pub mod configure_auto_roll_policy;
 pub mod initialize_margin_user;
+pub mod margin_batch_orders;
 pub mod margin_borrow_order;
 pub mod margin_lend_order;
 pub mod margin_redeem_deposit;
 pub mod margin_sell_tickets_order;
+pub mod prepay;
 pub mod refresh_position;
 pub mod repay;
+pub mod repay_past_due;
 pub mod settle;
 pub mod toggle_auto_roll_deposit;
 pub mod toggle_auto_roll_loan;
 
 pub use auto_roll_borrow_order::*;
 pub use auto_roll_lend_order::*;
+pub use auto_roll_lend_order_to_market::*;
+pub use auto_roll_lend_to_pool::*;
 pub use configure_auto_roll::*;
+pub use configure_auto_roll_policy::*;
 pub use initialize_margin_user::*;
+pub use margin_batch_orders::*;
 pub use margin_borrow_order::*;
 pub use margin_lend_order::*;
 pub use margin_redeem_deposit::*;
 pub use margin_sell_tickets_order::*;
+pub use prepay::*;
 pub use refresh_position::*;
 pub use repay::*;
+pub use repay_past_due::*;
 pub use settle::*;
 pub use toggle_auto_roll_deposit::*;
 pub use toggle_auto_roll_loan::*;
@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{burn, transfer, Burn, Token, TokenAccount};
+use jet_program_proc_macros::MarketTokenManager;
+
+use crate::{
+    control::state::{Market, PrepaymentMode},
+    events::TermLoanPrepaid,
+    margin::state::{MarginUser, RepayAccounts, TermLoan},
+    market_token_manager::MarketTokenManager,
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts, MarketTokenManager)]
+pub struct Prepay<'info> {
+    /// The account tracking information related to this particular user
+    #[account(mut,
+        has_one = claims @ FixedTermErrorCode::WrongClaimAccount,
+        has_one = market @ FixedTermErrorCode::UserNotInMarket
+    )]
+    pub margin_user: Account<'info, MarginUser>,
+
+    #[account(
+        mut,
+        has_one = margin_user @ FixedTermErrorCode::WrongMarginUser,
+        has_one = market @ FixedTermErrorCode::WrongMarket,
+        has_one = payer,
+        constraint = term_loan.sequence_number
+            == margin_user.debt().next_term_loan_to_repay().unwrap()
+            @ FixedTermErrorCode::TermLoanHasWrongSequenceNumber
+    )]
+    pub term_loan: Account<'info, TermLoan>,
+
+    /// No payment will be made towards next_term_loan: it is needed purely for bookkeeping.
+    /// if the user has additional term_loan, this must be the one with the following sequence number.
+    /// otherwise, put whatever address you want in here
+    pub next_term_loan: AccountInfo<'info>,
+
+    /// The token account to pay from. This holds tickets when the market prepays with
+    /// [PrepaymentMode::Orderbook], and underlying tokens otherwise.
+    #[account(mut)]
+    pub source: AccountInfo<'info>,
+
+    /// The signing authority for the source_account
+    pub source_authority: Signer<'info>,
+
+    /// The payer for the `TermLoan` to return rent to
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// The token vault holding the underlying token of the ticket
+    #[account(mut)]
+    pub underlying_token_vault: AccountInfo<'info>,
+
+    /// The market fee vault, which funds any prepayment rebate
+    #[account(mut)]
+    pub fee_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The mint for tickets, which are burned when prepaying with tickets
+    #[account(mut)]
+    pub ticket_mint: AccountInfo<'info>,
+
+    /// The token account representing claims for this margin user
+    #[account(mut)]
+    pub claims: AccountInfo<'info>,
+
+    /// The token account representing claims for this margin user
+    #[account(mut)]
+    pub claims_mint: AccountInfo<'info>,
+
+    #[account(
+        has_one = claims_mint @ FixedTermErrorCode::WrongClaimMint,
+        has_one = underlying_token_vault @ FixedTermErrorCode::WrongVault,
+        has_one = fee_vault @ FixedTermErrorCode::WrongVault,
+        has_one = ticket_mint @ FixedTermErrorCode::WrongTicketMint,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Repay up to `amount` of a loan before it matures.
+///
+/// With [PrepaymentMode::Orderbook], the loan is repaid by burning tickets, which the borrower
+/// may buy from the orderbook at the current discount. With [PrepaymentMode::Rebate], a portion
+/// of the interest that has not yet accrued is paid out of the market fees, so the vault still
+/// holds the full repayment owed to lenders.
+pub fn handler(ctx: Context<Prepay>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let market = *ctx.accounts.market.load()?;
+    let loan = &ctx.accounts.term_loan;
+
+    if loan.maturation_timestamp <= now {
+        msg!("loan matured at {}", loan.maturation_timestamp);
+        return err!(FixedTermErrorCode::TermLoanMatured);
+    }
+    let amount = amount.min(loan.balance);
+
+    let mode = market.prepayment_mode();
+    let rebate = match mode {
+        PrepaymentMode::Disabled => return err!(FixedTermErrorCode::PrepaymentDisabled),
+        PrepaymentMode::Orderbook => 0,
+        PrepaymentMode::Rebate => {
+            let unaccrued = loan.unaccrued_interest(amount, now)?;
+            market
+                .prepayment_rebate_on(unaccrued)
+                .min(ctx.accounts.fee_vault.amount)
+        }
+    };
+    if rebate > 0 {
+        ctx.withdraw(
+            ctx.accounts.fee_vault.to_account_info(),
+            &ctx.accounts.underlying_token_vault,
+            rebate,
+        )?;
+    }
+
+    let a = ctx.accounts;
+    let source_amount = amount - rebate;
+    let mut accounts = RepayAccounts {
+        margin_user: &mut a.margin_user,
+        term_loan: &mut a.term_loan,
+        next_term_loan: &a.next_term_loan,
+        source: &a.source,
+        source_authority: &a.source_authority,
+        payer: &a.payer,
+        underlying_token_vault: &a.underlying_token_vault,
+        claims: &a.claims,
+        claims_mint: &a.claims_mint,
+        market: &a.market,
+        token_program: &a.token_program,
+    };
+
+    match mode {
+        PrepaymentMode::Orderbook => burn(
+            CpiContext::new(
+                a.token_program.to_account_info(),
+                Burn {
+                    mint: a.ticket_mint.to_account_info(),
+                    from: a.source.to_account_info(),
+                    authority: a.source_authority.to_account_info(),
+                },
+            ),
+            source_amount,
+        )?,
+        _ => transfer(accounts.transfer_context(), source_amount)?,
+    }
+    accounts.record_repayment(amount, false)?;
+
+    emit!(TermLoanPrepaid {
+        term_loan: a.term_loan.key(),
+        margin_user: a.margin_user.key(),
+        mode,
+        source_amount,
+        rebate,
+        repayment_amount: amount,
+        final_balance: a.term_loan.balance,
+    });
+
+    Ok(())
+}
@@ -1,12 +1,15 @@
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
 use anchor_lang::{prelude::*, solana_program::clock::UnixTimestamp};
 use anchor_spl::token::Token;
-use jet_margin::{AdapterPositionFlags, AdapterResult, PositionChange};
+use jet_margin::{AdapterPositionFlags, AdapterResult, PositionChange, PriceChangeInfo};
 use pyth_sdk::PriceFeed;
 
 use crate::{
-    control::{events::PositionRefreshed, state::Market},
+    control::{
+        events::PositionRefreshed,
+        state::{Market, TicketPriceSource},
+    },
     margin::state::{return_to_margin, MarginUser},
     FixedTermErrorCode,
 };
@@ -33,6 +36,7 @@ pub struct RefreshPosition<'info> {
     /// The pyth price account
     /// CHECK: has_one on market
     pub underlying_oracle: AccountInfo<'info>,
+    /// CHECK: has_one on market, only read if the market prices tickets with an oracle
     pub ticket_oracle: AccountInfo<'info>,
 
     /// SPL token program
@@ -84,6 +88,16 @@ pub fn refresh_positions_deserialized(
     let mut collateral_ticket_changes = vec![];
     let mut collateral_token_changes = vec![];
 
+    let ticket_price = match market.ticket_price_source() {
+        TicketPriceSource::Oracle => accounts
+            .ticket_oracle
+            .and_then(|price| Ok(PriceChangeInfo::try_from(price)?)),
+        TicketPriceSource::Orderbook => match &accounts.underlying_oracle {
+            Ok(price) => market.orderbook_ticket_price((*price).try_into()?, unix_timestamp),
+            Err(_) => err!(FixedTermErrorCode::OracleError),
+        },
+    };
+
     // always try to update the price, but conditionally permit position updates if price fails
     // so we can continue to mark positions as past due even if there is an oracle failure
     match accounts.underlying_oracle {
@@ -94,8 +108,8 @@ pub fn refresh_positions_deserialized(
         Err(e) if expect_price => Err(e)?,
         Err(e) => msg!("skipping underlying price update due to error: {:?}", e),
     }
-    match accounts.ticket_oracle {
-        Ok(price) => collateral_ticket_changes.push(PositionChange::Price(price.try_into()?)),
+    match ticket_price {
+        Ok(price) => collateral_ticket_changes.push(PositionChange::Price(price)),
         Err(e) if expect_price => Err(e)?,
         Err(e) => msg!("skipping ticket price update due to error: {:?}", e),
     }
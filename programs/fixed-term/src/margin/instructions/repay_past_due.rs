@@ -0,0 +1,206 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, TokenAccount};
+use jet_program_proc_macros::MarketTokenManager;
+
+use crate::{
+    events::TermLoanDefaultRepaid,
+    margin::state::{MarginUser, RepayAccounts, TermLoan},
+    market_token_manager::MarketTokenManager,
+    orderbook::state::OrderbookMut,
+    tickets::state::{TermDeposit, TermDepositFlags},
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts, MarketTokenManager)]
+pub struct RepayPastDue<'info> {
+    /// The account tracking information related to this particular user
+    #[account(mut,
+        has_one = claims @ FixedTermErrorCode::WrongClaimAccount,
+        has_one = ticket_collateral @ FixedTermErrorCode::WrongTicketCollateralAccount,
+        constraint = margin_user.market == orderbook_mut.market.key() @ FixedTermErrorCode::UserNotInMarket,
+    )]
+    pub margin_user: Box<Account<'info, MarginUser>>,
+
+    #[account(
+        mut,
+        has_one = margin_user @ FixedTermErrorCode::WrongMarginUser,
+        constraint = term_loan.market == orderbook_mut.market.key() @ FixedTermErrorCode::WrongMarket,
+        has_one = payer,
+        constraint = term_loan.sequence_number
+            == margin_user.debt().next_term_loan_to_repay().unwrap()
+            @ FixedTermErrorCode::TermLoanHasWrongSequenceNumber
+    )]
+    pub term_loan: Account<'info, TermLoan>,
+
+    /// No payment will be made towards next_term_loan: it is needed purely for bookkeeping.
+    /// if the user has additional term_loan, this must be the one with the following sequence number.
+    /// otherwise, put whatever address you want in here
+    pub next_term_loan: AccountInfo<'info>,
+
+    /// The token account to pay any amount not covered by the borrower's assets in the market.
+    /// Only read when `max_source_amount` is nonzero.
+    #[account(mut)]
+    pub source: AccountInfo<'info>,
+
+    /// The account executing the repayment, and the signing authority for the source account.
+    /// This may be the margin account when invoked through the margin program, such as to
+    /// repay with margin pool deposits during a liquidation.
+    pub source_authority: Signer<'info>,
+
+    /// The payer for the `TermLoan` to return rent to
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// The token vault holding the underlying token of the ticket, which keeps the default
+    /// penalty for the lenders
+    #[account(mut, address = orderbook_mut.vault() @ FixedTermErrorCode::WrongVault)]
+    pub underlying_token_vault: AccountInfo<'info>,
+
+    /// The token account representing claims for this margin user
+    #[account(mut)]
+    pub claims: AccountInfo<'info>,
+
+    /// The token account representing claims for this margin user
+    #[account(mut, address = orderbook_mut.claims_mint() @ FixedTermErrorCode::WrongClaimMint)]
+    pub claims_mint: AccountInfo<'info>,
+
+    /// Token account used by the margin program to track the collateral value of the
+    /// borrower's tickets
+    #[account(mut)]
+    pub ticket_collateral: Box<Account<'info, TokenAccount>>,
+
+    /// Token mint used by the margin program to track the collateral value of tickets
+    #[account(mut, address = orderbook_mut.ticket_collateral_mint() @ FixedTermErrorCode::WrongTicketCollateralMint)]
+    pub ticket_collateral_mint: AccountInfo<'info>,
+
+    /// The orderbook, to cancel the resting orders of the borrower
+    #[market]
+    pub orderbook_mut: OrderbookMut<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+    // Remaining accounts: `TermDeposit`s of the borrower whose tickets may be used
+}
+
+/// Repay a loan that has been past due for longer than the market's grace period.
+///
+/// The tokens and tickets the borrower is entitled to from the market are used first, then
+/// the tickets staked in the deposits passed as remaining accounts, followed by up to
+/// `max_source_amount` from the source account. Every amount paid includes the default
+/// penalty, which is left in the underlying token vault to back the tickets held by lenders.
+///
+/// The borrower's orders in `order_ids` are cancelled. Their value is returned to the borrower
+/// when events are consumed, after which it can be used by another repayment.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RepayPastDue<'info>>,
+    max_source_amount: u64,
+    order_ids: Vec<u128>,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let market = *ctx.accounts.orderbook_mut.market.load()?;
+    let market_key = ctx.accounts.orderbook_mut.market.key();
+    let a = &mut ctx.accounts;
+
+    if !market.is_in_default(a.term_loan.maturation_timestamp, now) {
+        msg!(
+            "loan matured at {}, with a grace period of {} seconds",
+            a.term_loan.maturation_timestamp,
+            market.default_grace_period
+        );
+        return err!(FixedTermErrorCode::TermLoanNotInDefault);
+    }
+
+    for order_id in order_ids {
+        a.orderbook_mut
+            .cancel_order(order_id, a.margin_user.margin_account)?;
+    }
+
+    let balance = a.term_loan.balance;
+    let owed = balance.saturating_add(market.default_penalty_on(balance));
+
+    let (tokens_used, tickets_used) = a.margin_user.use_entitled_assets(owed)?;
+    let mut remaining = owed - tokens_used - tickets_used;
+
+    let mut staked_tickets_used = 0;
+    for info in ctx.remaining_accounts {
+        if remaining == 0 {
+            break;
+        }
+        let mut deposit = Account::<TermDeposit>::try_from(info)?;
+        require_keys_eq!(
+            deposit.owner,
+            a.margin_user.margin_account,
+            FixedTermErrorCode::WrongDepositOwner
+        );
+        require_keys_eq!(deposit.market, market_key, FixedTermErrorCode::WrongMarket);
+        if !deposit.flags.contains(TermDepositFlags::MARGIN) {
+            msg!(
+                "deposit {} is not custodied by the margin account",
+                info.key
+            );
+            return err!(FixedTermErrorCode::WrongDepositOwner);
+        }
+
+        let used = remaining.min(deposit.amount);
+        deposit.amount -= used;
+        deposit.exit(&crate::ID)?;
+
+        a.margin_user.use_staked_tickets(used)?;
+        staked_tickets_used += used;
+        remaining -= used;
+    }
+
+    let source_amount = remaining.min(max_source_amount);
+    let paid = tokens_used + tickets_used + staked_tickets_used + source_amount;
+
+    let repaid = if paid >= owed {
+        balance
+    } else {
+        (paid as u128 * 10_000 / (10_000 + market.default_penalty as u128)) as u64
+    };
+    let penalty = paid - repaid;
+
+    let mut accounts = RepayAccounts {
+        margin_user: &mut a.margin_user,
+        term_loan: &mut a.term_loan,
+        next_term_loan: &a.next_term_loan,
+        source: &a.source,
+        source_authority: &a.source_authority,
+        payer: &a.payer,
+        underlying_token_vault: &a.underlying_token_vault,
+        claims: &a.claims,
+        claims_mint: &a.claims_mint,
+        market: &a.orderbook_mut.market,
+        token_program: &a.token_program,
+    };
+
+    if source_amount > 0 {
+        transfer(accounts.transfer_context(), source_amount)?;
+    }
+    accounts.record_repayment(repaid, false)?;
+
+    let staked_notes = staked_tickets_used.min(a.ticket_collateral.amount);
+    if staked_notes > 0 {
+        a.burn_notes(
+            &a.ticket_collateral_mint,
+            a.ticket_collateral.to_account_info(),
+            staked_notes,
+        )?;
+    }
+
+    a.margin_user.emit_asset_balances()?;
+    emit!(TermLoanDefaultRepaid {
+        term_loan: a.term_loan.key(),
+        margin_user: a.margin_user.key(),
+        repayer: a.source_authority.key(),
+        tokens_used,
+        tickets_used,
+        staked_tickets_used,
+        source_amount,
+        repayment_amount: repaid,
+        penalty,
+        final_balance: balance - repaid,
+    });
+
+    Ok(())
+}
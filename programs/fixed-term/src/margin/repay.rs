@@ -44,6 +44,11 @@ impl<'a, 'info> RepayAccounts<'a, 'info> {
             transfer(self.transfer_context(), amount)?;
         }
 
+        self.record_repayment(amount, skip_token_transfer)
+    }
+
+    /// Account for a repayment of the loan that is already held by the market vault
+    pub fn record_repayment(&mut self, amount: u64, is_auto_roll: bool) -> Result<()> {
         // reduce claim on the margin account
         self.burn_claim_notes(amount)?;
 
@@ -58,7 +63,7 @@ impl<'a, 'info> RepayAccounts<'a, 'info> {
                 term_loan: self.term_loan.key(),
                 repayment_amount: amount,
                 final_balance: self.term_loan.balance,
-                is_auto_roll: skip_token_transfer,
+                is_auto_roll,
             });
         } else {
             let next_term_loan =
@@ -81,14 +86,14 @@ impl<'a, 'info> RepayAccounts<'a, 'info> {
                 borrower: self.term_loan.margin_user,
                 repayment_amount: amount,
                 timestamp: Clock::get()?.unix_timestamp,
-                is_auto_roll: skip_token_transfer,
+                is_auto_roll,
             });
         }
 
         Ok(())
     }
 
-    fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+    pub fn transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
         CpiContext::new(
             self.token_program.to_account_info(),
             Transfer {
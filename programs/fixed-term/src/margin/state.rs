@@ -55,6 +55,13 @@ pub struct MarginUser {
     pub borrow_roll_config: Option<BorrowAutoRollConfig>,
     /// Settings for lend order "auto rolling"
     pub lend_roll_config: Option<LendAutoRollConfig>,
+    /// Additional limits on borrow order "auto rolling"
+    ///
+    /// This is stored after all other fields, so users created before it was added read it
+    /// as `None` until it is configured.
+    pub borrow_roll_policy: Option<AutoRollPolicy>,
+    /// Additional limits on lend order "auto rolling"
+    pub lend_roll_policy: Option<AutoRollPolicy>,
 }
 
 impl MarginUser {
@@ -76,6 +83,8 @@ impl MarginUser {
             underlying_collateral,
             borrow_roll_config: Default::default(),
             lend_roll_config: Default::default(),
+            borrow_roll_policy: Default::default(),
+            lend_roll_policy: Default::default(),
             debt: Default::default(),
             assets: Default::default(),
         }
@@ -156,17 +165,22 @@ impl MarginUser {
     }
 
     /// Account for a lend order being filled as a maker
+    ///
+    /// The tickets received may differ from the tickets filled by the maker fee or rebate
     pub fn maker_fill_lend_order(
         &mut self,
         auto_stake: bool,
-        tickets: u64,
+        tickets_filled: u64,
+        tickets_received: u64,
     ) -> Result<SequenceNumber> {
-        self.assets.tickets_posted.try_sub_assign(tickets)?;
+        self.assets.tickets_posted.try_sub_assign(tickets_filled)?;
 
         if auto_stake {
-            self.assets.new_deposit(tickets)
+            self.assets.new_deposit(tickets_received)
         } else {
-            self.assets.entitled_tickets.try_add_assign(tickets)?;
+            self.assets
+                .entitled_tickets
+                .try_add_assign(tickets_received)?;
             Ok(0)
         }
     }
@@ -184,6 +198,40 @@ impl MarginUser {
         self.assets.tickets_posted.try_add_assign(value_posted)
     }
 
+    /// Account for a posted lend order being moved to a new price. Any underlying tokens
+    /// that are no longer posted become entitled to the user.
+    pub fn reprice_lend_order(
+        &mut self,
+        tickets_removed: u64,
+        tickets_posted: u64,
+        tokens_released: u64,
+    ) -> Result<()> {
+        self.assets.tickets_posted.try_sub_assign(tickets_removed)?;
+        self.assets.tickets_posted.try_add_assign(tickets_posted)?;
+        self.assets
+            .entitled_tokens
+            .try_add_assign(tokens_released)?;
+        self.emit_asset_balances()
+    }
+
+    /// Account for a posted borrow order, or an order to sell tickets, being moved to a new
+    /// price. The posted value is tracked in the same way as when the order was placed.
+    pub fn reprice_borrow_order(
+        &mut self,
+        is_debt: bool,
+        token_value_removed: u64,
+        token_value_posted: u64,
+    ) -> Result<()> {
+        let posted = if is_debt {
+            &mut self.assets.tokens_posted
+        } else {
+            &mut self.assets.tickets_posted
+        };
+        posted.try_sub_assign(token_value_removed)?;
+        posted.try_add_assign(token_value_posted)?;
+        self.emit_asset_balances()
+    }
+
     /// Account for the redemption of underlying tokens from a matured [TermDeposit]
     pub fn redeem_deposit(
         &mut self,
@@ -193,6 +241,23 @@ impl MarginUser {
         self.assets.redeem_deposit(deposit_seqno, tickets_redeemed)
     }
 
+    /// Remove a [TermDeposit] created by this user from its collateral, such as when it is sold
+    pub fn release_deposit(&mut self, deposit_seqno: SequenceNumber, tickets: u64) -> Result<()> {
+        self.assets.release_deposit(deposit_seqno, tickets)
+    }
+
+    /// Add an existing [TermDeposit] to the collateral of this user. It is tracked by its
+    /// amount alone, since it is not part of the sequence of deposits created by this user.
+    pub fn custody_deposit(&mut self, tickets: u64) -> Result<()> {
+        self.assets.tickets_staked.try_add_assign(tickets)
+    }
+
+    /// Remove a [TermDeposit] that was added with `custody_deposit` from the collateral of
+    /// this user, when it is redeemed or released
+    pub fn release_custodied_deposit(&mut self, tickets: u64) -> Result<()> {
+        self.assets.tickets_staked.try_sub_assign(tickets)
+    }
+
     /// Account for a partial loan repayment
     pub fn partially_repay_loan(&mut self, loan: &TermLoan, amount: u64) -> Result<()> {
         self.debt
@@ -210,6 +275,20 @@ impl MarginUser {
             .fully_repay_term_loan(loan.sequence_number, amount, next_loan)
     }
 
+    /// Use the tokens and tickets awaiting settlement to pay towards some amount owed to the
+    /// market, returning the amounts of each that were used.
+    ///
+    /// Both are claims on the market vault, so tickets are applied at their face value.
+    pub fn use_entitled_assets(&mut self, amount: u64) -> Result<(u64, u64)> {
+        self.assets.use_entitled(amount)
+    }
+
+    /// Use tickets staked in a [TermDeposit] to pay towards some amount owed to the market.
+    /// The deposit keeps its sequence number, and is redeemed as usual for what remains.
+    pub fn use_staked_tickets(&mut self, tickets: u64) -> Result<()> {
+        self.assets.tickets_staked.try_sub_assign(tickets)
+    }
+
     /// Updates the internal state to account for a successful call to the `Settle` instruction
     pub fn settlement_complete(&mut self) {
         self.assets.entitled_tickets = 0;
@@ -427,6 +506,10 @@ pub struct Assets {
     /// The sequence number for the oldest deposit that has yet to be redeemed
     next_unredeemed_deposit_seqno: u64,
 
+    /// The deposits after the oldest unredeemed deposit that have been released from
+    /// the account, where bit `n` is for the sequence number `next_unredeemed_deposit_seqno + n`
+    released_deposits: u64,
+
     /// The number of tickets locked up in ClaimTicket or SplitTicket
     tickets_staked: u64,
 
@@ -441,8 +524,8 @@ pub struct Assets {
     /// reserved data that may be used to determine the size of a user's collateral
     /// pessimistically prepared to persist aggregated values for:
     /// base and quote quantities, separately for bid/ask, on open orders and unsettled fills
-    /// 2^3 = 8 u64's
-    _reserved0: [u8; 64],
+    /// 2^3 = 8 u64's, less the space now used by `released_deposits`
+    _reserved0: [u8; 56],
 }
 
 impl Assets {
@@ -507,11 +590,58 @@ impl Assets {
         );
 
         self.next_unredeemed_deposit_seqno += 1;
+        self.released_deposits >>= 1;
         self.tickets_staked = self.tickets_staked.saturating_sub(tickets);
 
+        // skip over any deposits that were released before they could be redeemed
+        while self.released_deposits & 1 != 0 {
+            self.next_unredeemed_deposit_seqno += 1;
+            self.released_deposits >>= 1;
+        }
+
         Ok(())
     }
 
+    /// A [TermDeposit] has been removed from the account without being redeemed
+    ///
+    /// Deposits that are not the oldest are skipped when the deposits before them are
+    /// redeemed, as long as they are among the next 64 deposits.
+    pub fn release_deposit(&mut self, seqno: SequenceNumber, tickets: u64) -> Result<()> {
+        if seqno == self.next_unredeemed_deposit_seqno {
+            return self.redeem_deposit(seqno, tickets);
+        }
+
+        let offset = seqno.wrapping_sub(self.next_unredeemed_deposit_seqno);
+        let is_active = seqno > self.next_unredeemed_deposit_seqno
+            && seqno < self.next_deposit_seqno
+            && offset < u64::BITS as u64;
+
+        if !is_active || self.released_deposits & (1 << offset) != 0 {
+            msg!(
+                "deposit {} cannot be released while {} is the oldest deposit",
+                seqno,
+                self.next_unredeemed_deposit_seqno
+            );
+            return err!(FixedTermErrorCode::TermDepositHasWrongSequenceNumber);
+        }
+
+        self.released_deposits |= 1 << offset;
+        self.tickets_staked = self.tickets_staked.saturating_sub(tickets);
+
+        Ok(())
+    }
+
+    /// Use entitled tokens, then entitled tickets, to pay towards an amount
+    pub fn use_entitled(&mut self, amount: u64) -> Result<(u64, u64)> {
+        let tokens = amount.min(self.entitled_tokens);
+        let tickets = (amount - tokens).min(self.entitled_tickets);
+
+        self.entitled_tokens.try_sub_assign(tokens)?;
+        self.entitled_tickets.try_sub_assign(tickets)?;
+
+        Ok((tokens, tickets))
+    }
+
     /// A posted borrow order has been successfully filled
     pub fn borrow_order_fill(&mut self, token_value_filled: u64, disbursement: u64) -> Result<()> {
         self.tokens_posted.try_sub_assign(token_value_filled)?;
@@ -544,10 +674,12 @@ impl Default for Assets {
             entitled_tickets: 0,
             next_deposit_seqno: 0,
             next_unredeemed_deposit_seqno: 0,
+            released_deposits: 0,
             tickets_staked: 0,
             tickets_posted: 0,
             tokens_posted: 0,
-            _reserved0: [0u8; 64],
+            released_deposits: 0,
+            _reserved0: [0u8; 56],
         }
     }
 }
@@ -581,6 +713,107 @@ pub struct LendAutoRollConfig {
     pub limit_price: u64,
 }
 
+/// An [AutoRollPolicy] for either borrowing or lending
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoRollPolicyConfig {
+    Borrow(AutoRollPolicy),
+    Lend(AutoRollPolicy),
+}
+
+/// Limits on when and where positions are "auto rolled", in addition to their
+/// [AutoRollConfig]
+#[cfg_attr(any(feature = "cli", test), derive(Serialize, Deserialize))]
+#[derive(
+    Zeroable, Default, Debug, Clone, Copy, PartialEq, Eq, AnchorSerialize, AnchorDeserialize,
+)]
+pub struct AutoRollPolicy {
+    /// The lowest interest rate on the orderbook, in basis points, at which positions are rolled
+    pub min_rate_bps: u64,
+
+    /// The highest interest rate on the orderbook, in basis points, at which positions are
+    /// rolled. Zero means there is no upper limit.
+    pub max_rate_bps: u64,
+
+    /// The number of times positions may be rolled under this policy, or zero for no limit
+    pub max_rolls: u32,
+
+    /// The number of times positions have been rolled since this policy was configured
+    pub rolls: u32,
+
+    /// Another market for the same underlying token to roll lend positions into, such as one
+    /// with a different tenor. The default pubkey rolls positions within their own market.
+    pub target_market: Pubkey,
+
+    /// Deposit lend positions into the margin pool for the underlying token when they cannot
+    /// be rolled on the orderbook
+    pub pool_fallback: bool,
+}
+
+impl AutoRollPolicy {
+    /// Whether the policy allows rolling at the current rate of an orderbook
+    pub fn rate_in_band(&self, rate_bps: Option<u64>) -> bool {
+        if self.min_rate_bps == 0 && self.max_rate_bps == 0 {
+            return true;
+        }
+        match rate_bps {
+            Some(rate) => {
+                rate >= self.min_rate_bps && (self.max_rate_bps == 0 || rate <= self.max_rate_bps)
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the policy allows any more positions to be rolled
+    pub fn has_rolls_remaining(&self) -> bool {
+        self.max_rolls == 0 || self.rolls < self.max_rolls
+    }
+
+    /// The market positions from `market` should be rolled into
+    pub fn target_market(&self, market: &Pubkey) -> Pubkey {
+        if self.target_market == Pubkey::default() {
+            *market
+        } else {
+            self.target_market
+        }
+    }
+
+    /// Check that a position from `source_market` may be rolled into `market` at the given
+    /// orderbook rate
+    pub fn check_roll(
+        &self,
+        source_market: &Pubkey,
+        market: &Pubkey,
+        rate_bps: Option<u64>,
+    ) -> Result<()> {
+        if self.target_market(source_market) != *market {
+            msg!(
+                "positions must be rolled into market {}",
+                self.target_market(source_market)
+            );
+            return err!(FixedTermErrorCode::AutoRollWrongMarket);
+        }
+        if !self.has_rolls_remaining() {
+            return err!(FixedTermErrorCode::AutoRollLimitReached);
+        }
+        if !self.rate_in_band(rate_bps) {
+            msg!(
+                "orderbook rate {:?} is outside of [{}, {}]",
+                rate_bps,
+                self.min_rate_bps,
+                self.max_rate_bps
+            );
+            return err!(FixedTermErrorCode::AutoRollRateOutOfBand);
+        }
+
+        Ok(())
+    }
+
+    /// Account for a position being rolled
+    pub fn record_roll(&mut self) {
+        self.rolls = self.rolls.saturating_add(1);
+    }
+}
+
 #[account]
 #[derive(Debug)]
 pub struct TermLoan {
@@ -647,6 +880,24 @@ impl TermLoan {
             .safe_sub(self.strike_timestamp)
             .map(|t| t as u64)
     }
+
+    /// The interest included in some amount of the balance that would not yet have accrued at
+    /// a time, if interest accrued linearly over the tenor
+    pub fn unaccrued_interest(&self, amount: u64, timestamp: UnixTimestamp) -> Result<u64> {
+        let tenor = self.tenor()?;
+        let repayment = self.principal.safe_add(self.interest)?;
+        if tenor == 0 || repayment == 0 {
+            return Ok(0);
+        }
+
+        let remaining = self
+            .maturation_timestamp
+            .saturating_sub(timestamp)
+            .clamp(0, tenor as i64) as u128;
+        let interest = amount as u128 * self.interest as u128 / repayment as u128;
+
+        Ok((interest * remaining / tenor as u128) as u64)
+    }
 }
 
 /// Struct for initializing and writing [TermLoan] accounts
@@ -773,3 +1024,80 @@ pub fn return_to_margin(user: &AccountInfo, adapter_result: &AdapterResult) -> R
 pub fn return_to_margin(_user: &AccountInfo, _adapter_result: &AdapterResult) -> Result<()> {
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn auto_roll_policy_limits_rolls() {
+        let market = Pubkey::new_unique();
+        let mut policy = AutoRollPolicy {
+            min_rate_bps: 100,
+            max_rate_bps: 500,
+            max_rolls: 1,
+            ..Default::default()
+        };
+
+        assert!(policy.check_roll(&market, &market, None).is_err());
+        assert!(policy.check_roll(&market, &market, Some(99)).is_err());
+        assert!(policy.check_roll(&market, &market, Some(501)).is_err());
+        assert!(policy.check_roll(&market, &market, Some(300)).is_ok());
+
+        policy.record_roll();
+        assert!(policy.check_roll(&market, &market, Some(300)).is_err());
+    }
+
+    #[test]
+    fn auto_roll_policy_targets_market() {
+        let market = Pubkey::new_unique();
+        let target = Pubkey::new_unique();
+        let mut policy = AutoRollPolicy::default();
+
+        assert!(policy.rate_in_band(None));
+        assert!(policy.check_roll(&market, &market, None).is_ok());
+
+        policy.target_market = target;
+        assert!(policy.check_roll(&market, &market, None).is_err());
+        assert!(policy.check_roll(&market, &target, None).is_ok());
+    }
+    #[test]
+    fn repriced_orders_keep_posted_balances() {
+        let key = Pubkey::default();
+        let mut user = MarginUser::new(0, key, key, key, key, key);
+
+        user.lend_order(0, 1_000).unwrap();
+        user.reprice_lend_order(1_000, 900, 5).unwrap();
+        assert_eq!(user.assets().tickets_posted(), 900);
+        assert_eq!(user.assets().entitled_tokens(), 5);
+
+        user.post_borrow_order(800, 1_000).unwrap();
+        user.reprice_borrow_order(true, 800, 850).unwrap();
+        assert_eq!(user.assets().tokens_posted(), 850);
+        assert_eq!(user.debt().pending(), 1_000);
+
+        user.reprice_borrow_order(true, 851, 0).unwrap_err();
+    }
+
+    #[test]
+    fn released_deposits_are_skipped_when_redeeming() {
+        let mut assets = Assets::default();
+        for _ in 0..4 {
+            assets.new_deposit(100).unwrap();
+        }
+
+        assets.release_deposit(2, 100).unwrap();
+        assets.release_deposit(2, 100).unwrap_err();
+        assets.release_deposit(4, 100).unwrap_err();
+        assert_eq!(assets.tickets_staked(), 300);
+
+        assets.redeem_deposit(1, 100).unwrap_err();
+        assets.redeem_deposit(0, 100).unwrap();
+        assets.redeem_deposit(1, 100).unwrap();
+        assert_eq!(assets.next_unredeemed_deposit_seqno(), Some(3));
+
+        assets.release_deposit(3, 100).unwrap();
+        assert_eq!(assets.next_unredeemed_deposit_seqno(), None);
+        assert_eq!(assets.tickets_staked(), 0);
+    }
+}
@@ -39,3 +39,31 @@ pub struct OrderRemoved {
     pub base_removed: u64,
     pub quote_removed: u64,
 }
+
+#[event]
+pub struct OrderExpired {
+    pub market: Pubkey,
+    pub authority: Pubkey,
+    pub order_tag: u128,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct MakerFeeCharged {
+    pub market: Pubkey,
+    pub authority: Pubkey,
+    pub order_tag: u128,
+    /// The fee charged to the maker, which is negative for a rebate
+    pub fee: i64,
+}
+
+#[event]
+pub struct OrderAmended {
+    pub market: Pubkey,
+    pub authority: Pubkey,
+    pub order_tag: u128,
+    /// The id of the order on the book after it was amended
+    pub order_id: u128,
+    pub base_removed: u64,
+    pub base_posted: u64,
+}
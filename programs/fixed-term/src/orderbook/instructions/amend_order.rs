@@ -0,0 +1,175 @@
+use agnostic_orderbook::state::Side;
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use jet_program_common::traits::SafeSub;
+use jet_program_proc_macros::MarketTokenManager;
+
+use crate::{
+    margin::state::MarginUser,
+    market_token_manager::MarketTokenManager,
+    orderbook::state::*,
+    serialization::{AnchorAccount, Mut, RemainingAccounts},
+    FixedTermErrorCode,
+};
+
+/// Changes to make to an order resting on the book
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default, Clone, Copy)]
+pub struct AmendOrderParams {
+    /// Reduce the order to this many tickets, keeping its place in the queue
+    pub max_ticket_qty: Option<u64>,
+    /// Move the order to a new price, at the back of the queue for that price
+    pub limit_price: Option<u64>,
+}
+
+#[derive(Accounts, MarketTokenManager)]
+pub struct AmendOrder<'info> {
+    /// The owner of the order
+    pub owner: Signer<'info>,
+
+    #[market]
+    pub orderbook_mut: OrderbookMut<'info>,
+
+    /// The market token vault
+    /// CHECK: address
+    #[account(mut, address = orderbook_mut.vault() @ FixedTermErrorCode::WrongVault)]
+    pub underlying_token_vault: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    // Required when repricing an order:
+    // - for a margin order, the `MarginUser` that placed it, followed by its ticket collateral
+    //   account, the ticket collateral mint, its underlying collateral account, and the
+    //   underlying collateral mint
+    // - the token account of a signer to receive any tokens left over from a lend order
+}
+
+/// Reduce the size of an order on the book, or move it to a new price
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, AmendOrder<'info>>,
+    order_id: u128,
+    params: AmendOrderParams,
+) -> Result<()> {
+    require!(
+        params.max_ticket_qty.is_some() || params.limit_price.is_some(),
+        FixedTermErrorCode::InvalidOrderAmendment
+    );
+    let owner = ctx.accounts.owner.key();
+
+    if let Some(max_ticket_qty) = params.max_ticket_qty {
+        ctx.accounts
+            .orderbook_mut
+            .reduce_order(order_id, owner, max_ticket_qty)?;
+    }
+
+    if let Some(limit_price) = params.limit_price {
+        let repriced = ctx
+            .accounts
+            .orderbook_mut
+            .reprice_order(order_id, owner, limit_price)?;
+        let released = match repriced.side {
+            Side::Bid => repriced.removed_quote.safe_sub(repriced.posted_quote)?,
+            Side::Ask => 0,
+        };
+
+        let mut remaining = ctx.remaining_accounts.iter();
+        match UserCallbackInfo::from(repriced.info) {
+            UserCallbackInfo::Margin(info) => {
+                let mut user: AnchorAccount<'info, MarginUser, Mut> = remaining.next_anchor()?;
+                require_keys_eq!(
+                    user.key(),
+                    info.margin_user,
+                    FixedTermErrorCode::WrongMarginUser
+                );
+                let ticket_collateral = user.assets().ticket_collateral()?;
+                let underlying_collateral = user.assets().underlying_collateral();
+                match repriced.side {
+                    Side::Bid => user.reprice_lend_order(
+                        repriced.removed_base,
+                        repriced.posted_base,
+                        released,
+                    )?,
+                    Side::Ask => user.reprice_borrow_order(
+                        info.flags.contains(CallbackFlags::NEW_DEBT),
+                        repriced.removed_quote,
+                        repriced.posted_quote,
+                    )?,
+                }
+
+                // keep the collateral notes in line with the value the user has posted
+                let ticket_notes = remaining.next_account()?;
+                let ticket_notes_mint = remaining.next_account()?;
+                require_keys_eq!(
+                    ticket_notes.key(),
+                    user.ticket_collateral,
+                    FixedTermErrorCode::WrongTicketCollateralAccount
+                );
+                require_keys_eq!(
+                    ticket_notes_mint.key(),
+                    ctx.accounts.orderbook_mut.ticket_collateral_mint(),
+                    FixedTermErrorCode::WrongTicketCollateralMint
+                );
+                update_notes(
+                    &ctx,
+                    ticket_notes_mint,
+                    ticket_notes,
+                    ticket_collateral,
+                    user.assets().ticket_collateral()?,
+                )?;
+
+                let underlying_notes = remaining.next_account()?;
+                let underlying_notes_mint = remaining.next_account()?;
+                require_keys_eq!(
+                    underlying_notes.key(),
+                    user.underlying_collateral,
+                    FixedTermErrorCode::WrongUnderlyingCollateralAccount
+                );
+                require_keys_eq!(
+                    underlying_notes_mint.key(),
+                    ctx.accounts.orderbook_mut.underlying_collateral_mint(),
+                    FixedTermErrorCode::WrongUnderlyingCollateralMint
+                );
+                update_notes(
+                    &ctx,
+                    underlying_notes_mint,
+                    underlying_notes,
+                    underlying_collateral,
+                    user.assets().underlying_collateral(),
+                )?;
+            }
+            UserCallbackInfo::Signer(info) => {
+                if released > 0 {
+                    let token_account = remaining.next_account()?;
+                    require_keys_eq!(
+                        token_account.key(),
+                        info.token_account,
+                        FixedTermErrorCode::WrongUserAccount
+                    );
+                    ctx.withdraw(
+                        &ctx.accounts.underlying_token_vault,
+                        token_account,
+                        released,
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Mint or burn collateral notes for a change in the value posted by a margin user
+fn update_notes<'info>(
+    ctx: &Context<'_, '_, '_, 'info, AmendOrder<'info>>,
+    mint: &AccountInfo<'info>,
+    notes: &AccountInfo<'info>,
+    before: u64,
+    after: u64,
+) -> Result<()> {
+    if after > before {
+        ctx.mint(mint, notes, after - before)?;
+    }
+    if before > after {
+        ctx.burn_notes(mint, notes, before - after)?;
+    }
+
+    Ok(())
+}
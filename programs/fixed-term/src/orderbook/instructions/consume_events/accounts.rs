@@ -21,6 +21,8 @@ pub struct ConsumeEvents<'info> {
         has_one = fee_vault @ FixedTermErrorCode::WrongVault,
         has_one = orderbook_market_state @ FixedTermErrorCode::WrongMarketState,
         has_one = event_queue @ FixedTermErrorCode::WrongEventQueue,
+        has_one = bids @ FixedTermErrorCode::WrongBids,
+        has_one = asks @ FixedTermErrorCode::WrongAsks,
     )]
     #[account(mut)]
     pub market: AccountLoader<'info, Market>,
@@ -44,6 +46,15 @@ pub struct ConsumeEvents<'info> {
     /// CHECK: handled by aaob
     #[account(mut)]
     pub event_queue: AccountInfo<'info>,
+    /// CHECK: has_one, read to track the orderbook rate
+    pub bids: AccountInfo<'info>,
+    /// CHECK: has_one, read to track the orderbook rate
+    pub asks: AccountInfo<'info>,
+
+    /// The fees charged on fills, which may not be initialized if the market charges no fees
+    /// CHECK: seeds
+    #[account(mut, seeds = [crate::seeds::FEE_SCHEDULE, market.key().as_ref()], bump)]
+    pub fee_schedule: AccountInfo<'info>,
 
     #[account(
         has_one = crank @ FixedTermErrorCode::WrongCrankAuthority,
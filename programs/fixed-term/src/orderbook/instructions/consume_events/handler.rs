@@ -6,17 +6,19 @@ use agnostic_orderbook::{
     },
 };
 use anchor_lang::prelude::*;
+use anchor_spl::token::accessor;
 use num_traits::FromPrimitive;
 
 use jet_program_common::traits::{SafeAdd, SafeSub};
 
 use crate::{
-    control::state::Market,
-    events::{OrderFilled, OrderRemoved, OrderType, TermLoanCreated},
+    control::state::{FeeSchedule, Market},
+    events::{MakerFeeCharged, OrderFilled, OrderRemoved, OrderType, TermLoanCreated},
     margin::state::{MarginUser, TermLoan, TermLoanFlags},
     market_token_manager::MarketTokenManager,
     orderbook::state::{
-        CallbackFlags, CallbackInfo, EventQuote, FillInfo, MarketSide, OutInfo, UserCallbackInfo,
+        sample_ticket_twap, CallbackFlags, CallbackInfo, EventQuote, FillInfo, MarketSide, OutInfo,
+        UserCallbackInfo,
     },
     serialization::{AnchorAccount, Mut},
     tickets::state::TermDepositWriter,
@@ -33,15 +35,21 @@ pub fn handler<'info>(
     seed: Vec<u8>,
 ) -> Result<()> {
     let mut num_iters = 0;
+    let mut schedule = sweep_fees(&ctx)?;
+    let mut no_fees = FeeSchedule::default();
+    let fees = schedule.as_deref_mut().unwrap_or(&mut no_fees);
 
     for event in queue(&ctx, seed)?.take(num_events as usize) {
         match event? {
-            PreparedEvent::Fill(accounts, info) => handle_fill(&ctx, accounts, info)?,
+            PreparedEvent::Fill(accounts, info) => handle_fill(&ctx, fees, accounts, info)?,
             PreparedEvent::Out(accounts, info) => handle_out(&ctx, accounts, info)?,
         }
 
         num_iters += 1;
     }
+    if let Some(schedule) = schedule {
+        schedule.exit(&crate::ID)?;
+    }
     if num_iters == 0 {
         return err!(FixedTermErrorCode::NoEvents);
     }
@@ -57,30 +65,114 @@ pub fn handler<'info>(
         },
     )?;
 
-    Ok(())
+    sample_ticket_twap(&ctx.accounts.market, &ctx.accounts.bids, &ctx.accounts.asks)
+}
+
+/// Move the borrow fees withheld from takers, and the lend fees whose tickets have matured,
+/// into the fee vault. Returns the market's fee schedule, if it has one, to charge makers.
+fn sweep_fees<'info>(
+    ctx: &Context<'_, '_, '_, 'info, ConsumeEvents<'info>>,
+) -> Result<Option<Account<'info, FeeSchedule>>> {
+    let mut schedule = match FeeSchedule::load(&ctx.accounts.fee_schedule)? {
+        Some(schedule) => schedule,
+        None => return Ok(None),
+    };
+
+    let matured = schedule.take_matured_ticket_fees(Clock::get()?.unix_timestamp);
+    let swept = std::mem::take(&mut schedule.unswept_fees).safe_add(matured)?;
+    if swept > 0 {
+        ctx.withdraw(
+            &ctx.accounts.underlying_token_vault,
+            &ctx.accounts.fee_vault,
+            swept,
+        )?;
+    }
+
+    Ok(Some(schedule))
+}
+
+/// Charge a maker the fee for a fill, or pay out its rebate, adjusting the amount of
+/// tokens or tickets the maker receives.
+///
+/// Fees are limited to the amount received, and rebates to the balance of the fee vault,
+/// so that a fill can always be processed. Lend fees are withheld as tickets, so they are
+/// accrued until the tickets mature. Returns the adjusted amount and the fee charged.
+fn settle_maker_fee(
+    ctx: &Context<ConsumeEvents>,
+    fees: &mut FeeSchedule,
+    side: MarketSide,
+    tickets: u64,
+    received: u64,
+) -> Result<(u64, i64)> {
+    let fee = fees.maker_fee(side, tickets);
+
+    if fee >= 0 {
+        let fee = (fee as u64).min(received);
+        if fee > 0 {
+            match side {
+                MarketSide::Lend => {
+                    let tenor = ctx.accounts.market.load()?.lend_tenor;
+                    let matures_at = Clock::get()?.unix_timestamp.safe_add(tenor as i64)?;
+                    fees.accrue_ticket_fee(fee, matures_at)?;
+                }
+                MarketSide::Borrow => ctx.withdraw(
+                    &ctx.accounts.underlying_token_vault,
+                    &ctx.accounts.fee_vault,
+                    fee,
+                )?,
+            }
+        }
+        Ok((received.safe_sub(fee)?, fee as i64))
+    } else {
+        let rebate = fee
+            .unsigned_abs()
+            .min(accessor::amount(&ctx.accounts.fee_vault)?);
+        if rebate > 0 {
+            ctx.withdraw(
+                &ctx.accounts.fee_vault,
+                &ctx.accounts.underlying_token_vault,
+                rebate,
+            )?;
+        }
+        Ok((received.safe_add(rebate)?, -(rebate as i64)))
+    }
+}
+
+fn emit_maker_fee(market: Pubkey, authority: Pubkey, order_tag: u128, fee: i64) {
+    if fee != 0 {
+        emit!(MakerFeeCharged {
+            market,
+            authority,
+            order_tag,
+            fee,
+        });
+    }
 }
 
 #[inline(never)]
 fn handle_fill<'info>(
     ctx: &Context<'_, '_, '_, 'info, ConsumeEvents<'info>>,
+    fees: &mut FeeSchedule,
     accounts: FillAccounts<'info>,
     fill: FillInfo,
 ) -> Result<()> {
     match accounts {
         FillAccounts::Margin(accs) => handle_margin_fill(
             ctx,
+            fees,
             &ctx.accounts.market,
             accs,
             fill,
             ctx.accounts.payer.key(),
         ),
-        FillAccounts::Signer(accs) => handle_signer_fill(ctx, accs, fill),
+        FillAccounts::Signer(accs) => handle_signer_fill(ctx, fees, accs, fill),
     }
 }
 
 #[inline(never)]
 fn handle_margin_fill<'info>(
     ctx: &Context<'_, '_, '_, 'info, ConsumeEvents<'info>>,
+    fees: &mut FeeSchedule,
     market: &AccountLoader<'info, Market>,
     mut accounts: MarginFillAccounts<'info>,
     info: FillInfo,
@@ -102,13 +194,17 @@ fn handle_margin_fill<'info>(
     let maker_side: MarketSide = Side::from_u8(taker_side).unwrap().opposite().into();
     let user = &mut accounts.margin_user;
     let info = maker_info.unwrap_margin();
+    let maker_fee;
 
     let (order_type, sequence_number, tenor) = match maker_side {
         // maker has loaned tokens to the taker
         MarketSide::Lend => {
             let tenor = market.load()?.lend_tenor;
+            let (tickets, fee) = settle_maker_fee(ctx, fees, maker_side, base_size, base_size)?;
+            maker_fee = fee;
+
             let sequence_number = if let Some(term_account) = &mut accounts.term_account {
-                let sequence_number = user.maker_fill_lend_order(true, base_size)?;
+                let sequence_number = user.maker_fill_lend_order(true, base_size, tickets)?;
                 TermDepositWriter {
                     market: user.market,
                     owner: user.margin_account,
@@ -116,7 +212,7 @@ fn handle_margin_fill<'info>(
                     order_tag: info.order_tag.as_u128(),
                     tenor,
                     sequence_number,
-                    amount: base_size,
+                    amount: tickets,
                     principal: quote_size,
                     flags: info.flags.into(),
                     seed: vec![], // account already initialized by the queue iterator,
@@ -125,7 +221,7 @@ fn handle_margin_fill<'info>(
 
                 sequence_number
             } else {
-                user.maker_fill_lend_order(false, base_size)?;
+                user.maker_fill_lend_order(false, base_size, tickets)?;
                 0
             };
             user.emit_asset_balances()?;
@@ -142,16 +238,17 @@ fn handle_margin_fill<'info>(
 
                 (tenor, disburse)
             };
+            let origination_fee = quote_size.safe_sub(disburse)?;
+            let (disburse, fee) = settle_maker_fee(ctx, fees, maker_side, base_size, disburse)?;
+            maker_fee = fee;
             let strike_timestamp = Clock::get()?.unix_timestamp;
             let maturation_timestamp = strike_timestamp.safe_add(tenor as i64)?;
 
             let sequence_number = if let Some(term_account) = accounts.term_account {
-                let fees = quote_size.safe_sub(disburse)?;
-
                 ctx.withdraw(
                     &ctx.accounts.underlying_token_vault,
                     &ctx.accounts.fee_vault,
-                    fees,
+                    origination_fee,
                 )?;
 
                 let sequence_number = user.maker_fill_borrow_order(
@@ -194,7 +291,7 @@ fn handle_margin_fill<'info>(
                     maturation_timestamp,
                     quote_filled: quote_size,
                     base_filled: base_size,
-                    fees,
+                    fees: origination_fee,
                     flags,
                 });
                 user.emit_all_balances()?;
@@ -239,12 +336,19 @@ fn handle_margin_fill<'info>(
         fill_timestamp,
         maturation_timestamp: fill_timestamp.safe_add(tenor as i64)?,
     });
+    emit_maker_fee(
+        user.market,
+        info.margin_account,
+        info.order_tag.as_u128(),
+        maker_fee,
+    );
     Ok(())
 }
 
 #[inline(never)]
 fn handle_signer_fill<'info>(
     ctx: &Context<'_, '_, '_, 'info, ConsumeEvents<'info>>,
+    fees: &mut FeeSchedule,
     account: FillAccount<'info>,
     info: FillInfo,
 ) -> Result<()> {
@@ -263,10 +367,13 @@ fn handle_signer_fill<'info>(
     let quote_size = event.quote_size()?;
     let maker_side: MarketSide = Side::from_u8(taker_side).unwrap().opposite().into();
     let info = maker_info.unwrap_signer();
+    let maker_fee;
 
     let (order_type, tenor) = match maker_side {
         MarketSide::Lend => {
             let tenor = ctx.accounts.market.load()?.lend_tenor;
+            let (tickets, fee) = settle_maker_fee(ctx, fees, maker_side, base_size, base_size)?;
+            maker_fee = fee;
             match account {
                 FillAccount::TermDeposit(mut deposit) => {
                     TermDepositWriter {
@@ -276,7 +383,7 @@ fn handle_signer_fill<'info>(
                         order_tag: info.order_tag.as_u128(),
                         tenor,
                         sequence_number: 0,
-                        amount: base_size,
+                        amount: tickets,
                         principal: quote_size,
                         flags: info.flags.into(),
                         seed: vec![], // account initialized by queue iterator
@@ -284,17 +391,20 @@ fn handle_signer_fill<'info>(
                     .write(&mut deposit)?;
                 }
                 FillAccount::Token(token_account) => {
-                    ctx.mint(&ctx.accounts.ticket_mint, token_account, base_size)?;
+                    ctx.mint(&ctx.accounts.ticket_mint, token_account, tickets)?;
                 }
             }
 
             (OrderType::Lend, tenor)
         }
         MarketSide::Borrow => {
+            let (proceeds, fee) = settle_maker_fee(ctx, fees, maker_side, base_size, quote_size)?;
+            maker_fee = fee;
+
             ctx.withdraw(
                 &ctx.accounts.underlying_token_vault,
                 account.as_token_account(),
-                quote_size,
+                proceeds,
             )?;
 
             (
@@ -328,6 +438,12 @@ fn handle_signer_fill<'info>(
         fill_timestamp,
         maturation_timestamp: fill_timestamp.safe_add(tenor as i64)?
     });
+    emit_maker_fee(
+        ctx.accounts.market.key(),
+        info.signer,
+        info.order_tag.as_u128(),
+        maker_fee,
+    );
 
     Ok(())
 }
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    control::state::{CrankAuthorization, Market},
+    events::OrderExpired,
+    orderbook::state::{find_order, remove_order},
+    utils::orderbook_accounts,
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct ExpireOrders<'info> {
+    /// The `Market` account tracks global information related to this particular fixed term market
+    #[account(
+        has_one = orderbook_market_state @ FixedTermErrorCode::WrongMarketState,
+        has_one = event_queue @ FixedTermErrorCode::WrongEventQueue,
+        has_one = bids @ FixedTermErrorCode::WrongBids,
+        has_one = asks @ FixedTermErrorCode::WrongAsks,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    // aaob accounts
+    /// CHECK: handled by aaob
+    #[account(mut)]
+    pub orderbook_market_state: AccountInfo<'info>,
+    /// CHECK: handled by aaob
+    #[account(mut)]
+    pub event_queue: AccountInfo<'info>,
+    /// CHECK: handled by aaob
+    #[account(mut)]
+    pub bids: AccountInfo<'info>,
+    /// CHECK: handled by aaob
+    #[account(mut)]
+    pub asks: AccountInfo<'info>,
+
+    #[account(
+        has_one = crank @ FixedTermErrorCode::WrongCrankAuthority,
+        constraint = crank_authorization.airspace == market.load()?.airspace @ FixedTermErrorCode::WrongAirspaceAuthorization,
+        constraint = crank_authorization.market == market.key() @ FixedTermErrorCode::WrongCrankAuthority,
+    )]
+    pub crank_authorization: Account<'info, CrankAuthorization>,
+    pub crank: Signer<'info>,
+}
+
+/// Remove expired orders from the book. Their unfilled amounts are returned to their owners
+/// when the resulting out events are consumed.
+pub fn handler(ctx: Context<ExpireOrders>, order_ids: Vec<u128>) -> Result<()> {
+    let timestamp = Clock::get()?.unix_timestamp;
+
+    for order_id in order_ids {
+        // the order may have been filled or cancelled since the crank found it
+        let info = match find_order(&ctx.accounts.bids, &ctx.accounts.asks, order_id)? {
+            Some(info) => info,
+            None => {
+                msg!("order {} is no longer on the book", order_id);
+                continue;
+            }
+        };
+        if !info.is_expired(timestamp) {
+            msg!("order {} has not expired", order_id);
+            return err!(FixedTermErrorCode::OrderNotExpired);
+        }
+
+        remove_order(
+            orderbook_accounts!(ctx.accounts, cancel_order),
+            &ctx.accounts.event_queue,
+            order_id,
+            &info,
+        )?;
+
+        emit!(OrderExpired {
+            market: ctx.accounts.market.key(),
+            authority: info.owner(),
+            order_tag: info.order_tag().as_u128(),
+            expires_at: info.expiration_timestamp().unwrap_or_default(),
+        });
+    }
+
+    Ok(())
+}
@@ -1,11 +1,17 @@
+pub mod amend_order;
 pub mod cancel_order;
 pub mod consume_events;
 pub mod event_adapter;
+pub mod expire_orders;
 pub mod lend_order;
+pub mod sample_ticket_twap;
 pub mod sell_tickets_order;
 
+pub use amend_order::*;
 pub use cancel_order::*;
 pub use consume_events::*;
 pub use event_adapter::*;
+pub use expire_orders::*;
 pub use lend_order::*;
+pub use sample_ticket_twap::*;
 pub use sell_tickets_order::*;
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    control::state::{CrankAuthorization, Market},
+    orderbook::state::sample_ticket_twap,
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct SampleTicketTwap<'info> {
+    /// The `Market` whose average orderbook rate is updated
+    #[account(
+        mut,
+        has_one = bids @ FixedTermErrorCode::WrongBids,
+        has_one = asks @ FixedTermErrorCode::WrongAsks,
+    )]
+    pub market: AccountLoader<'info, Market>,
+
+    /// CHECK: has_one
+    pub bids: AccountInfo<'info>,
+    /// CHECK: has_one
+    pub asks: AccountInfo<'info>,
+
+    #[account(
+        has_one = crank @ FixedTermErrorCode::WrongCrankAuthority,
+        constraint = crank_authorization.airspace == market.load()?.airspace @ FixedTermErrorCode::WrongAirspaceAuthorization,
+        constraint = crank_authorization.market == market.key() @ FixedTermErrorCode::WrongCrankAuthority,
+    )]
+    pub crank_authorization: Account<'info, CrankAuthorization>,
+    pub crank: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SampleTicketTwap>) -> Result<()> {
+    sample_ticket_twap(&ctx.accounts.market, &ctx.accounts.bids, &ctx.accounts.asks)
+}
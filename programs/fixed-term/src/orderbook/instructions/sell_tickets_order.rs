@@ -2,6 +2,7 @@ use agnostic_orderbook::state::Side;
 use anchor_lang::prelude::*;
 use anchor_spl::token::{accessor::mint, burn, transfer, Mint, Token, TokenAccount, Transfer};
 use jet_airspace::state::AirspacePermit;
+use jet_program_common::traits::SafeSub;
 use jet_program_proc_macros::MarketTokenManager;
 
 use crate::{
@@ -69,7 +70,14 @@ impl<'a, 'info> SellTicketsAccounts<'a, 'info> {
         margin_user: Option<Pubkey>,
         order_type: OrderType,
     ) -> Result<()> {
-        // transfer the filled tokens
+        let filled = order_summary.quote_filled(RoundingAction::FillBorrow.direction())?;
+        let fee = self.orderbook_mut.charge_taker_fee(
+            MarketSide::Borrow,
+            order_summary.base_filled(),
+            filled,
+        )?;
+
+        // transfer the filled tokens, less the taker fee
         transfer(
             CpiContext::new(
                 self.token_program.to_account_info(),
@@ -80,7 +88,7 @@ impl<'a, 'info> SellTicketsAccounts<'a, 'info> {
                 },
             )
             .with_signer(&[&self.orderbook_mut.market.load()?.authority_seeds()]),
-            order_summary.quote_filled(RoundingAction::FillBorrow.direction())?,
+            filled.safe_sub(fee)?,
         )?;
 
         // burn spent tickets
@@ -11,7 +11,7 @@ use crate::{
 };
 
 use super::{
-    CallbackFlags, MarginCallbackInfo, OrderParams, OrderbookMut, RoundingAction,
+    CallbackFlags, MarginCallbackInfo, MarketSide, OrderParams, OrderbookMut, RoundingAction,
     SensibleOrderSummary,
 };
 
@@ -176,6 +176,11 @@ impl<'a, 'info> MarginBorrowOrderAccounts<'a, 'info> {
             .load()?
             .loan_to_disburse(filled_token_value);
         let fees = filled_token_value.safe_sub(disburse)?;
+        let taker_fee = self.orderbook_mut.charge_taker_fee(
+            MarketSide::Borrow,
+            filled_ticket_value,
+            disburse,
+        )?;
 
         // write a TermLoan account
         let mut builder = TermLoanBuilder::new_from_order(
@@ -205,7 +210,7 @@ impl<'a, 'info> MarginBorrowOrderAccounts<'a, 'info> {
                 },
             )
             .with_signer(&[&self.orderbook_mut.market.load()?.authority_seeds()]),
-            disburse,
+            disburse.safe_sub(taker_fee)?,
         )?;
 
         // Collect fees from the order fill
@@ -222,24 +227,32 @@ impl<'a, 'info> MarginBorrowOrderAccounts<'a, 'info> {
             fees,
         )?;
 
-        Ok(disburse)
+        disburse.safe_sub(taker_fee)
     }
 
     fn callback_flags(&self, params: &OrderParams) -> Result<CallbackFlags> {
-        let auto_roll = if params.auto_roll {
-            if self.margin_user.borrow_roll_config.is_none() {
-                msg!(
-                    "Auto roll settings have not been configured for margin user [{}]",
-                    self.margin_user.key()
-                );
-                return err!(FixedTermErrorCode::InvalidAutoRollConfig);
-            }
-            CallbackFlags::AUTO_ROLL
-        } else {
-            CallbackFlags::default()
-        };
-
-        let flags = CallbackFlags::NEW_DEBT | CallbackFlags::MARGIN | auto_roll;
-        Ok(flags)
+        margin_borrow_order_flags(self.margin_user, params)
     }
 }
+
+/// The callback flags for a borrow order placed by a [MarginUser]
+pub fn margin_borrow_order_flags(
+    margin_user: &Account<MarginUser>,
+    params: &OrderParams,
+) -> Result<CallbackFlags> {
+    let auto_roll = if params.auto_roll {
+        if margin_user.borrow_roll_config.is_none() {
+            msg!(
+                "Auto roll settings have not been configured for margin user [{}]",
+                margin_user.key()
+            );
+            return err!(FixedTermErrorCode::InvalidAutoRollConfig);
+        }
+        CallbackFlags::AUTO_ROLL
+    } else {
+        CallbackFlags::default()
+    };
+
+    let flags = CallbackFlags::NEW_DEBT | CallbackFlags::MARGIN | auto_roll;
+    Ok(flags)
+}
@@ -1,7 +1,7 @@
 use agnostic_orderbook::state::Side;
 use anchor_lang::prelude::*;
 use anchor_spl::token::{accessor, mint_to, Mint, MintTo, Token, TokenAccount};
-use jet_program_common::traits::SafeAdd;
+use jet_program_common::traits::{SafeAdd, SafeSub};
 
 use crate::{
     margin::state::MarginUser,
@@ -10,8 +10,8 @@ use crate::{
 };
 
 use super::{
-    CallbackFlags, CallbackInfo, MarginCallbackInfo, OrderParams, OrderbookMut, RoundingAction,
-    SensibleOrderSummary,
+    CallbackFlags, CallbackInfo, MarginCallbackInfo, MarketSide, OrderParams, OrderbookMut,
+    RoundingAction, SensibleOrderSummary,
 };
 
 pub struct LendOrderAccounts<'a, 'info> {
@@ -97,7 +97,12 @@ impl<'a, 'info> LendOrderAccounts<'a, 'info> {
         deposit: Option<TermDepositWriter>,
         requires_payment: bool,
     ) -> Result<u64> {
-        let staked = self.issue(summary, deposit)?;
+        let fee = self.orderbook_mut.charge_taker_fee(
+            MarketSide::Lend,
+            summary.base_filled(),
+            summary.base_filled(),
+        )?;
+        let staked = self.issue(summary, deposit, fee)?;
 
         if requires_payment {
             // take all underlying that has been lent plus what may be lent later
@@ -117,22 +122,26 @@ impl<'a, 'info> LendOrderAccounts<'a, 'info> {
         Ok(staked)
     }
 
+    /// Issue the filled tickets, less the taker fee, as a deposit or as ticket tokens
     fn issue(
         &self,
         summary: &SensibleOrderSummary,
         deposit: Option<TermDepositWriter>,
+        fee: u64,
     ) -> Result<u64> {
-        let staked = if let Some(writer) = deposit {
+        let issued = summary.base_filled().safe_sub(fee)?;
+        let staked = if let Some(mut writer) = deposit {
             if summary.base_filled() > 0 {
+                writer.amount = issued;
                 writer.init_and_write(InitTermDepositAccounts {
                     deposit: self.ticket_settlement,
                     payer: self.payer,
                     system_program: self.system_program,
                 })?;
             }
-            summary.base_filled()
+            issued
         } else {
-            self.issue_tickets(summary.base_filled())?;
+            self.issue_tickets(issued)?;
             0
         };
 
@@ -191,6 +200,32 @@ impl<'a, 'info> LendOrderAccounts<'a, 'info> {
     }
 }
 
+/// The callback flags for a lend order placed by a [MarginUser]
+pub fn margin_lend_order_flags(
+    margin_user: &Account<MarginUser>,
+    params: &OrderParams,
+) -> Result<CallbackFlags> {
+    let auto_roll = if params.auto_roll {
+        if margin_user.lend_roll_config.is_none() {
+            msg!(
+                "Auto roll settings have not been configured for margin user [{}]",
+                margin_user.key()
+            );
+            return err!(FixedTermErrorCode::InvalidAutoRollConfig);
+        }
+        CallbackFlags::AUTO_ROLL
+    } else {
+        CallbackFlags::default()
+    };
+    let auto_stake = if params.auto_stake {
+        CallbackFlags::AUTO_STAKE
+    } else {
+        CallbackFlags::empty()
+    };
+
+    Ok(CallbackFlags::MARGIN | auto_roll | auto_stake)
+}
+
 pub struct MarginLendAccounts<'a, 'info> {
     pub margin_user: &'a mut Account<'info, MarginUser>,
     pub ticket_collateral: &'a AccountInfo<'info>,
@@ -273,25 +308,7 @@ impl<'a, 'info> MarginLendAccounts<'a, 'info> {
     }
 
     fn order_flags(&self, params: &OrderParams) -> Result<CallbackFlags> {
-        let auto_roll = if params.auto_roll {
-            if self.margin_user.lend_roll_config.is_none() {
-                msg!(
-                    "Auto roll settings have not been configured for margin user [{}]",
-                    self.margin_user.key()
-                );
-                return err!(FixedTermErrorCode::InvalidAutoRollConfig);
-            }
-            CallbackFlags::AUTO_ROLL
-        } else {
-            CallbackFlags::default()
-        };
-        let auto_stake = if params.auto_stake {
-            CallbackFlags::AUTO_STAKE
-        } else {
-            CallbackFlags::empty()
-        };
-
-        Ok(CallbackFlags::MARGIN | auto_roll | auto_stake)
+        margin_lend_order_flags(self.margin_user, params)
     }
 
     fn emit_margin_lend_order(
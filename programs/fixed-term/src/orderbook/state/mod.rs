@@ -5,7 +5,11 @@ mod rounding;
 
 pub use borrow::*;
 pub use event_queue::*;
-use jet_program_common::FP32_ONE;
+use jet_program_common::{
+    interest_pricing::{InterestPricer, PricerImpl},
+    traits::SafeAdd,
+    FP32_ONE,
+};
 pub use lend::*;
 pub use rounding::*;
 
@@ -15,9 +19,11 @@ use agnostic_orderbook::{
     instruction::{cancel_order, new_order},
     state::{
         critbit::Slab,
-        critbit::{InnerNode, LeafNode, SlabHeader},
+        critbit::{InnerNode, LeafNode, Node, NodeHandle, SlabHeader},
         event_queue::{EventQueueHeader, FillEvent, OutEvent},
-        get_side_from_order_id, OrderSummary, SelfTradeBehavior, Side,
+        get_side_from_order_id,
+        market_state::MarketState,
+        AccountTag, OrderSummary, SelfTradeBehavior, Side,
     },
 };
 use anchor_lang::{
@@ -29,7 +35,10 @@ use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::FromPrimitive;
 
 use crate::{
-    control::state::Market, events::OrderCancelled, utils::orderbook_accounts, FixedTermErrorCode,
+    control::state::{FeeSchedule, Market, TicketPriceSource},
+    events::{OrderAmended, OrderCancelled},
+    utils::orderbook_accounts,
+    FixedTermErrorCode,
 };
 
 /// The tick_size used in fp32 operations on the orderbook
@@ -49,6 +58,171 @@ pub const fn event_queue_len(event_capacity: usize) -> usize {
     event_capacity * (FillEvent::LEN + 2 * CallbackInfo::LEN) + EventQueueHeader::LEN + 8
 }
 
+/// The price halfway between the best bid and the best ask, if both sides have orders
+pub fn orderbook_mid_price(bids: &AccountInfo, asks: &AccountInfo) -> Result<Option<u64>> {
+    Ok(match orderbook_best_prices(bids, asks)? {
+        (Some(bid), Some(ask)) => Some(bid / 2 + ask / 2 + (bid % 2 + ask % 2) / 2),
+        _ => None,
+    })
+}
+
+/// Track the rate at the middle of the orderbook for markets that price tickets with it
+pub fn sample_ticket_twap(
+    market: &AccountLoader<Market>,
+    bids: &AccountInfo,
+    asks: &AccountInfo,
+) -> Result<()> {
+    let mut market = market.load_mut()?;
+
+    if market.ticket_price_source() != TicketPriceSource::Orderbook {
+        return Ok(());
+    }
+
+    if let Some(mid_price) = orderbook_mid_price(bids, asks)? {
+        market.update_ticket_twap(mid_price, Clock::get()?.unix_timestamp);
+    }
+
+    Ok(())
+}
+
+/// The prices of the best bid and the best ask, if either side has orders
+pub fn orderbook_best_prices(
+    bids: &AccountInfo,
+    asks: &AccountInfo,
+) -> Result<(Option<u64>, Option<u64>)> {
+    slab_best_prices(&mut bids.data.borrow_mut(), &mut asks.data.borrow_mut())
+}
+
+/// The prices of the best bid and the best ask in the data of the orderbook slabs
+pub fn slab_best_prices(bids: &mut [u8], asks: &mut [u8]) -> Result<(Option<u64>, Option<u64>)> {
+    let bids =
+        Slab::<CallbackInfo>::from_buffer(bids, agnostic_orderbook::state::AccountTag::Bids)?;
+    let asks =
+        Slab::<CallbackInfo>::from_buffer(asks, agnostic_orderbook::state::AccountTag::Asks)?;
+
+    let best_bid = bids
+        .find_max()
+        .map(|handle| bids.leaf_nodes[handle as usize].price());
+    let best_ask = asks
+        .find_min()
+        .map(|handle| asks.leaf_nodes[handle as usize].price());
+
+    Ok((best_bid, best_ask))
+}
+
+/// Remove an order from the orderbook, and queue an out event so the unfilled portion of the
+/// order is returned to its owner when events are consumed
+pub fn remove_order(
+    accounts: cancel_order::Accounts<AccountInfo>,
+    event_queue: &AccountInfo,
+    order_id: u128,
+    info: &CallbackInfo,
+) -> Result<OrderSummary> {
+    let side = get_side_from_order_id(order_id);
+    let order_summary = agnostic_orderbook::instruction::cancel_order::process::<CallbackInfo>(
+        &crate::id(),
+        accounts,
+        cancel_order::Params { order_id },
+    )?;
+    push_out_event(
+        event_queue,
+        side,
+        order_id,
+        order_summary.total_base_qty,
+        info,
+    )?;
+
+    Ok(order_summary)
+}
+
+/// Queue an out event for some amount of an order, which will be returned to its owner when
+/// events are consumed
+fn push_out_event(
+    event_queue: &AccountInfo,
+    side: Side,
+    order_id: u128,
+    base_size: u64,
+    info: &CallbackInfo,
+) -> Result<()> {
+    let eq_buf = &mut event_queue.data.borrow_mut();
+    let mut event_queue =
+        agnostic_orderbook::state::event_queue::EventQueue::<CallbackInfo>::from_buffer(
+            eq_buf,
+            agnostic_orderbook::state::AccountTag::EventQueue,
+        )?;
+    event_queue
+        .push_back(
+            agnostic_orderbook::state::event_queue::OutEvent {
+                tag: EventTag::Out as u8,
+                side: side as u8,
+                _padding: [0; 14],
+                order_id,
+                base_size,
+            },
+            Some(info),
+            None,
+        )
+        .map_err(|_| error!(FixedTermErrorCode::FailedToPushEvent))?;
+
+    Ok(())
+}
+
+/// Find resting orders that expired before a time, up to a maximum number of orders, in the
+/// data of the bids and asks accounts.
+///
+/// The whole book is searched, so this is meant to be run by a crank to find the orders to
+/// remove with the `expire_orders` instruction.
+pub fn find_expired_orders(
+    bids: &mut [u8],
+    asks: &mut [u8],
+    timestamp: UnixTimestamp,
+    limit: usize,
+) -> Result<Vec<u128>> {
+    let mut expired = vec![];
+
+    for (buf, tag) in [
+        (bids, agnostic_orderbook::state::AccountTag::Bids),
+        (asks, agnostic_orderbook::state::AccountTag::Asks),
+    ] {
+        let slab = Slab::<CallbackInfo>::from_buffer(buf, tag)?;
+        let mut stack: Vec<NodeHandle> = slab.root().into_iter().collect();
+
+        while let Some(handle) = stack.pop() {
+            if expired.len() >= limit {
+                return Ok(expired);
+            }
+            match Node::from_handle(handle) {
+                Node::Inner => stack.extend(slab.inner_nodes[(!handle) as usize].children),
+                Node::Leaf => {
+                    if slab.get_callback_info(handle).is_expired(timestamp) {
+                        expired.push(slab.leaf_nodes[handle as usize].key);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(expired)
+}
+
+/// The callback info of an order, if it is still resting on the book
+pub fn find_order(
+    bids: &AccountInfo,
+    asks: &AccountInfo,
+    order_id: u128,
+) -> Result<Option<CallbackInfo>> {
+    let (slab, tag) = match get_side_from_order_id(order_id) {
+        Side::Bid => (bids, agnostic_orderbook::state::AccountTag::Bids),
+        Side::Ask => (asks, agnostic_orderbook::state::AccountTag::Asks),
+    };
+    let mut buf = slab.data.borrow_mut();
+    let slab = Slab::<CallbackInfo>::from_buffer(&mut buf, tag)?;
+
+    Ok(slab
+        .find_by_key(order_id)
+        .map(|handle| *slab.get_callback_info(handle)))
+}
+
 /// Set of accounts that are commonly needed together whenever the orderbook is modified
 #[derive(Accounts, Clone)]
 pub struct OrderbookMut<'info> {
@@ -76,6 +250,11 @@ pub struct OrderbookMut<'info> {
     /// CHECK: handled by aaob
     #[account(mut)]
     pub asks: AccountInfo<'info>,
+
+    /// The fees charged on fills, which may not be initialized if the market charges no fees
+    /// CHECK: seeds
+    #[account(mut, seeds = [crate::seeds::FEE_SCHEDULE, market.key().as_ref()], bump)]
+    pub fee_schedule: AccountInfo<'info>,
 }
 
 impl<'info> OrderbookMut<'info> {
@@ -111,12 +290,49 @@ impl<'info> OrderbookMut<'info> {
         self.market.load().unwrap().airspace
     }
 
+    /// The interest rate, in basis points, at the middle of the orderbook for a tenor
+    pub fn mid_rate(&self, tenor: u64) -> Result<Option<u64>> {
+        Ok(orderbook_mid_price(&self.bids, &self.asks)?
+            .map(|price| PricerImpl::price_fp32_to_bps_yearly_interest(price, tenor)))
+    }
+
+    /// Charge a taker the fee for a fill, limited to the amount the taker receives.
+    ///
+    /// The fee is withheld from what the taker receives, so it remains in the underlying
+    /// token vault until it is swept into the fee vault by `consume_events`.
+    pub fn charge_taker_fee(&self, side: MarketSide, tickets: u64, received: u64) -> Result<u64> {
+        let mut schedule = match FeeSchedule::load(&self.fee_schedule)? {
+            Some(schedule) => schedule,
+            None => return Ok(0),
+        };
+        let fee = schedule.taker_fee(side, tickets).min(received);
+        if fee > 0 {
+            match side {
+                // lenders are charged in tickets, which are swept once they mature
+                MarketSide::Lend => {
+                    let tenor = self.market.load()?.lend_tenor;
+                    let matures_at = Clock::get()?.unix_timestamp.safe_add(tenor as i64)?;
+                    schedule.accrue_ticket_fee(fee, matures_at)?;
+                }
+                MarketSide::Borrow => {
+                    schedule.unswept_fees = schedule.unswept_fees.safe_add(fee)?;
+                }
+            }
+            schedule.exit(&crate::ID)?;
+        }
+
+        Ok(fee)
+    }
+
     fn place_order(
         &self,
         side: Side,
         params: OrderParams,
         info: &UserCallbackInfo,
     ) -> Result<SensibleOrderSummary> {
+        params
+            .time_in_force
+            .verify(&params, Clock::get()?.unix_timestamp)?;
         let order_params = params.as_new_order_params(side, info.into());
         let limit_price = order_params.limit_price;
         require!(
@@ -132,6 +348,13 @@ impl<'info> OrderbookMut<'info> {
             order_summary.posted_order_id.is_some() || order_summary.total_base_qty > 0,
             FixedTermErrorCode::OrderRejected
         );
+        if params.time_in_force == TimeInForce::FillOrKill {
+            require!(
+                order_summary.total_base_qty >= params.max_ticket_qty
+                    || order_summary.total_quote_qty >= params.max_underlying_token_qty,
+                FixedTermErrorCode::OrderNotFilled
+            );
+        }
 
         Ok(SensibleOrderSummary {
             summary: order_summary,
@@ -155,6 +378,7 @@ impl<'info> OrderbookMut<'info> {
             margin_user,
             adapter_account_key: adapter.unwrap_or_default(),
             order_submitted: Clock::get()?.unix_timestamp,
+            expires_at: params.time_in_force.expires_at(),
             flags,
         };
         let summary = self.place_order(side, params, &UserCallbackInfo::Margin(info.clone()))?;
@@ -180,19 +404,23 @@ impl<'info> OrderbookMut<'info> {
             token_account,
             adapter_account_key: adapter.unwrap_or_default(),
             order_submitted: Clock::get()?.unix_timestamp,
+            expires_at: params.time_in_force.expires_at(),
             flags,
         };
         let summary = self.place_order(side, params, &UserCallbackInfo::Signer(info.clone()))?;
         Ok((info, summary))
     }
 
-    /// cancels an order within the aaob
-    /// you still need to act on the callback to reconcile any balances etc.
-    pub fn cancel_order(
+    /// Find an order on the book, and modify the tickets it has resting on the book.
+    ///
+    /// Checks that the order belongs to the owner, and returns the callback info of the order
+    /// along with the result of the modification.
+    fn modify_order<T>(
         &self,
         order_id: u128,
         owner: Pubkey,
-    ) -> Result<(Side, CallbackFlags, OrderSummary)> {
+        modify: impl FnOnce(&mut u64) -> Result<T>,
+    ) -> Result<(CallbackInfo, T)> {
         let side = get_side_from_order_id(order_id);
         let mut buf;
         let slab: Slab<CallbackInfo> = match side {
@@ -209,46 +437,32 @@ impl<'info> OrderbookMut<'info> {
             msg!("Given Order ID: [{}]", order_id);
             error!(FixedTermErrorCode::OrderNotFound)
         })?;
-        let info: UserCallbackInfo = (*slab.get_callback_info(handle)).into();
+        let info = *slab.get_callback_info(handle);
+        require_keys_eq!(info.owner(), owner, FixedTermErrorCode::WrongUserAccount);
 
-        let (info_owner, flags, order_tag) = match info.clone() {
-            UserCallbackInfo::Margin(info) => {
-                (info.margin_account, info.flags, info.order_tag.as_u128())
-            }
-            UserCallbackInfo::Signer(info) => (info.signer, info.flags, info.order_tag.as_u128()),
-        };
+        let result = modify(&mut slab.leaf_nodes[handle as usize].base_quantity)?;
 
-        // drop the refs so the orderbook can borrow the slab data
-        drop(buf);
+        Ok((info, result))
+    }
 
-        require_keys_eq!(info_owner, owner, FixedTermErrorCode::WrongUserAccount);
-        let orderbook_params = cancel_order::Params { order_id };
-        let order_summary = agnostic_orderbook::instruction::cancel_order::process::<CallbackInfo>(
-            &crate::id(),
+    /// cancels an order within the aaob
+    /// you still need to act on the callback to reconcile any balances etc.
+    pub fn cancel_order(
+        &self,
+        order_id: u128,
+        owner: Pubkey,
+    ) -> Result<(Side, CallbackFlags, OrderSummary)> {
+        let side = get_side_from_order_id(order_id);
+        let (info, _) = self.modify_order(order_id, owner, |_| Ok(()))?;
+        let (flags, order_tag) = (info.flags(), info.order_tag().as_u128());
+
+        let order_summary = remove_order(
             orderbook_accounts!(self, cancel_order),
-            orderbook_params,
+            &self.event_queue,
+            order_id,
+            &info,
         )?;
 
-        let eq_buf = &mut self.event_queue.data.borrow_mut();
-        let mut event_queue =
-            agnostic_orderbook::state::event_queue::EventQueue::<CallbackInfo>::from_buffer(
-                eq_buf,
-                agnostic_orderbook::state::AccountTag::EventQueue,
-            )?;
-        event_queue
-            .push_back(
-                agnostic_orderbook::state::event_queue::OutEvent {
-                    tag: EventTag::Out as u8,
-                    side: side as u8,
-                    _padding: [0; 14],
-                    order_id,
-                    base_size: order_summary.total_base_qty,
-                },
-                Some(&CallbackInfo::from(&info)),
-                None,
-            )
-            .map_err(|_| error!(FixedTermErrorCode::FailedToPushEvent))?;
-
         emit!(OrderCancelled {
             market: self.market.key(),
             authority: owner,
@@ -257,6 +471,154 @@ impl<'info> OrderbookMut<'info> {
 
         Ok((side, flags, order_summary))
     }
+
+    /// Reduce the tickets resting on the book for an order, without changing its place in the
+    /// queue. The removed tickets are returned to the owner when events are consumed, in the
+    /// same way as a cancelled order.
+    pub fn reduce_order(
+        &self,
+        order_id: u128,
+        owner: Pubkey,
+        max_ticket_qty: u64,
+    ) -> Result<CallbackInfo> {
+        let side = get_side_from_order_id(order_id);
+        let min_base_order_size = {
+            let mut buf = self.orderbook_market_state.data.borrow_mut();
+            MarketState::from_buffer(&mut buf, AccountTag::Market)?.min_base_order_size
+        };
+        require!(
+            max_ticket_qty >= min_base_order_size,
+            FixedTermErrorCode::OrderBelowMinimumSize
+        );
+        let (info, reduced) = self.modify_order(order_id, owner, |base_quantity| {
+            require!(
+                max_ticket_qty > 0 && max_ticket_qty < *base_quantity,
+                FixedTermErrorCode::InvalidOrderAmendment
+            );
+            let reduced = *base_quantity - max_ticket_qty;
+            *base_quantity = max_ticket_qty;
+            Ok(reduced)
+        })?;
+        push_out_event(&self.event_queue, side, order_id, reduced, &info)?;
+
+        emit!(OrderAmended {
+            market: self.market.key(),
+            authority: owner,
+            order_tag: info.order_tag().as_u128(),
+            order_id,
+            base_removed: reduced,
+            base_posted: max_ticket_qty,
+        });
+
+        Ok(info)
+    }
+
+    /// Move an order to a new price, at the back of the queue for that price.
+    ///
+    /// The order keeps the amount it has committed to the book: lend orders keep the value of
+    /// their underlying tokens, which buys a different number of tickets at the new price, and
+    /// borrow orders keep their tickets. The order keeps its tag and callback info, and is only
+    /// posted to the book, so the new price must not cross the spread.
+    pub fn reprice_order(
+        &self,
+        order_id: u128,
+        owner: Pubkey,
+        limit_price: u64,
+    ) -> Result<RepricedOrder> {
+        let side = get_side_from_order_id(order_id);
+        let (info, removed_base) = self.modify_order(order_id, owner, |base| Ok(*base))?;
+        let removed_price = (order_id >> 64) as u64;
+        require!(
+            removed_price != limit_price,
+            FixedTermErrorCode::InvalidOrderAmendment
+        );
+
+        agnostic_orderbook::instruction::cancel_order::process::<CallbackInfo>(
+            &crate::id(),
+            orderbook_accounts!(self, cancel_order),
+            cancel_order::Params { order_id },
+        )?;
+
+        let (removed_quote, max_ticket_qty, max_underlying_token_qty) = match side {
+            Side::Bid => {
+                let quote = quote_from_base(
+                    removed_base,
+                    removed_price,
+                    RoundingAction::CancelLend.direction(),
+                )?;
+                let base = fp32_div(quote, limit_price)
+                    .ok_or_else(|| error!(FixedTermErrorCode::FixedPointMath))?;
+                (quote, base, quote)
+            }
+            Side::Ask => {
+                let quote = quote_from_base(
+                    removed_base,
+                    removed_price,
+                    RoundingAction::CancelBorrow.direction(),
+                )?;
+                (quote, removed_base, u64::MAX)
+            }
+        };
+
+        let params = OrderParams {
+            max_ticket_qty,
+            max_underlying_token_qty,
+            limit_price,
+            match_limit: 1,
+            post_only: true,
+            post_allowed: true,
+            time_in_force: match info.expiration_timestamp() {
+                Some(expires_at) => TimeInForce::GoodTilTime { expires_at },
+                None => TimeInForce::GoodTilCancelled,
+            },
+            ..Default::default()
+        };
+        let summary = self.place_order(side, params, &UserCallbackInfo::from(info))?;
+        let posted_base = summary.base_posted();
+        if let Side::Ask = side {
+            require_eq!(
+                posted_base,
+                removed_base,
+                FixedTermErrorCode::InvalidOrderAmendment
+            );
+        }
+        let posted_quote = match side {
+            Side::Bid => summary.quote_posted(RoundingAction::PostLend.direction())?,
+            Side::Ask => summary.quote_posted(RoundingAction::PostBorrow.direction())?,
+        };
+
+        emit!(OrderAmended {
+            market: self.market.key(),
+            authority: owner,
+            order_tag: info.order_tag().as_u128(),
+            order_id: summary.summary.posted_order_id.unwrap_or_default(),
+            base_removed: removed_base,
+            base_posted: posted_base,
+        });
+
+        Ok(RepricedOrder {
+            side,
+            info,
+            removed_base,
+            removed_quote,
+            posted_base,
+            posted_quote,
+        })
+    }
+}
+
+/// The amounts of an order before and after it was moved to a new price
+pub struct RepricedOrder {
+    pub side: Side,
+    pub info: CallbackInfo,
+    /// Tickets removed from the book at the old price
+    pub removed_base: u64,
+    /// The value in underlying tokens of the tickets removed from the book
+    pub removed_quote: u64,
+    /// Tickets posted to the book at the new price
+    pub posted_base: u64,
+    /// The value in underlying tokens of the tickets posted to the book
+    pub posted_quote: u64,
 }
 
 #[cfg_attr(feature = "cli", derive(serde::Serialize, serde::Deserialize))]
@@ -338,7 +700,10 @@ pub struct CallbackInfo {
     order_submitted: [u8; 8],
     /// configuration used by callback execution
     flags: CallbackFlags,
-    _reserved: [u8; 14],
+    /// The unix timestamp after which the order is removed from the book, or zero if it
+    /// does not expire
+    expires_at: [u8; 8],
+    _reserved: [u8; 6],
 }
 
 impl CallbackInfo {
@@ -357,6 +722,20 @@ impl CallbackInfo {
         UnixTimestamp::from_le_bytes(self.order_submitted)
     }
 
+    pub fn expiration_timestamp(&self) -> Option<UnixTimestamp> {
+        match UnixTimestamp::from_le_bytes(self.expires_at) {
+            0 => None,
+            expires_at => Some(expires_at),
+        }
+    }
+
+    /// Has the order passed its expiration time
+    pub fn is_expired(&self, timestamp: UnixTimestamp) -> bool {
+        self.expiration_timestamp()
+            .map(|expires_at| timestamp >= expires_at)
+            .unwrap_or(false)
+    }
+
     pub fn from_signer_info(info: SignerCallbackInfo) -> Self {
         Self::from(&UserCallbackInfo::Signer(info))
     }
@@ -397,7 +776,8 @@ impl<'a, T: Into<&'a UserCallbackInfo>> From<T> for CallbackInfo {
                 adapter_account_key: info.adapter_account_key,
                 order_submitted: info.order_submitted.to_le_bytes(),
                 flags: info.flags,
-                _reserved: [0u8; 14],
+                expires_at: info.expires_at.to_le_bytes(),
+                _reserved: [0u8; 6],
             },
             UserCallbackInfo::Signer(info) => Self {
                 order_tag: info.order_tag,
@@ -407,7 +787,8 @@ impl<'a, T: Into<&'a UserCallbackInfo>> From<T> for CallbackInfo {
                 adapter_account_key: info.adapter_account_key,
                 order_submitted: info.order_submitted.to_le_bytes(),
                 flags: info.flags,
-                _reserved: [0u8; 14],
+                expires_at: info.expires_at.to_le_bytes(),
+                _reserved: [0u8; 6],
             },
         }
     }
@@ -492,6 +873,8 @@ pub struct MarginCallbackInfo {
     pub adapter_account_key: Pubkey,
     /// The unix timestamp for the slot that the order entered the aaob
     pub order_submitted: UnixTimestamp,
+    /// The unix timestamp after which the order is removed from the book, or zero
+    pub expires_at: UnixTimestamp,
     /// configuration used by callback execution
     pub flags: CallbackFlags,
 }
@@ -505,6 +888,7 @@ impl<T: Into<CallbackInfo>> From<T> for MarginCallbackInfo {
             margin_user: info.token_or_margin_user_account,
             adapter_account_key: info.adapter_account_key,
             order_submitted: i64::from_le_bytes(info.order_submitted),
+            expires_at: i64::from_le_bytes(info.expires_at),
             flags: info.flags,
         }
     }
@@ -526,6 +910,8 @@ pub struct SignerCallbackInfo {
     pub adapter_account_key: Pubkey,
     /// The unix timestamp for the slot that the order entered the aaob
     pub order_submitted: UnixTimestamp,
+    /// The unix timestamp after which the order is removed from the book, or zero
+    pub expires_at: UnixTimestamp,
     /// configuration used by callback execution
     pub flags: CallbackFlags,
 }
@@ -540,6 +926,7 @@ impl<T: Into<CallbackInfo>> From<T> for SignerCallbackInfo {
             token_account: info.token_or_margin_user_account,
             adapter_account_key: info.adapter_account_key,
             order_submitted: i64::from_le_bytes(info.order_submitted),
+            expires_at: i64::from_le_bytes(info.expires_at),
             flags: info.flags,
         }
     }
@@ -566,6 +953,59 @@ pub struct OrderParams {
     pub auto_stake: bool,
     /// Should the resulting `TermLoan` or `TermDeposit` be subject to an auto roll
     pub auto_roll: bool,
+    /// How long the order may remain on the orderbook
+    pub time_in_force: TimeInForce,
+}
+
+/// How long an order may remain on the orderbook
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeInForce {
+    /// The order rests on the book until it is filled or cancelled
+    GoodTilCancelled,
+    /// Any portion of the order that is not filled immediately is cancelled
+    ImmediateOrCancel,
+    /// The order must be filled entirely when it is placed, or the transaction fails
+    FillOrKill,
+    /// The order rests on the book until it is filled, cancelled, or expires. Expired orders
+    /// are removed from the book by the crank with `expire_orders`.
+    GoodTilTime { expires_at: UnixTimestamp },
+}
+
+impl Default for TimeInForce {
+    fn default() -> Self {
+        Self::GoodTilCancelled
+    }
+}
+
+impl TimeInForce {
+    /// Can any unfilled portion of the order be posted to the book
+    pub fn allows_posting(&self) -> bool {
+        !matches!(self, Self::ImmediateOrCancel | Self::FillOrKill)
+    }
+
+    /// The time the order expires, or zero if it does not
+    pub fn expires_at(&self) -> UnixTimestamp {
+        match self {
+            Self::GoodTilTime { expires_at } => *expires_at,
+            _ => 0,
+        }
+    }
+
+    /// Check that the time in force is consistent with the other order parameters
+    pub fn verify(&self, params: &OrderParams, timestamp: UnixTimestamp) -> Result<()> {
+        if params.post_only && !self.allows_posting() {
+            msg!("{:?} orders cannot be post only", self);
+            return err!(FixedTermErrorCode::InvalidTimeInForce);
+        }
+        if let Self::GoodTilTime { expires_at } = self {
+            if *expires_at <= timestamp {
+                msg!("order expires at {}, before the current time", expires_at);
+                return err!(FixedTermErrorCode::InvalidTimeInForce);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 // todo remove?
@@ -589,7 +1029,7 @@ impl OrderParams {
             match_limit: self.match_limit,
             callback_info,
             post_only: self.post_only,
-            post_allowed: self.post_allowed,
+            post_allowed: self.post_allowed && self.time_in_force.allows_posting(),
             self_trade_behavior: SelfTradeBehavior::AbortTransaction,
         }
     }
@@ -711,3 +1151,62 @@ impl From<MarketSide> for Side {
         }
     }
 }
+
+#[test]
+fn callback_info_records_expiration() {
+    let info = SignerCallbackInfo {
+        order_tag: OrderTag([1; 16]),
+        signer: Pubkey::new_unique(),
+        ticket_account: Pubkey::new_unique(),
+        token_account: Pubkey::new_unique(),
+        adapter_account_key: Pubkey::default(),
+        order_submitted: 100,
+        expires_at: 200,
+        flags: CallbackFlags::empty(),
+    };
+    let callback = CallbackInfo::from_signer_info(info.clone());
+
+    assert_eq!(callback.expiration_timestamp(), Some(200));
+    assert!(!callback.is_expired(199));
+    assert!(callback.is_expired(200));
+    assert_eq!(SignerCallbackInfo::from(callback).expires_at, 200);
+
+    let callback = CallbackInfo::from_signer_info(SignerCallbackInfo {
+        expires_at: 0,
+        ..info
+    });
+    assert_eq!(callback.expiration_timestamp(), None);
+    assert!(!callback.is_expired(i64::MAX));
+}
+
+#[test]
+fn time_in_force_is_consistent_with_params() {
+    let params = OrderParams::default();
+
+    TimeInForce::GoodTilCancelled.verify(&params, 100).unwrap();
+    TimeInForce::GoodTilTime { expires_at: 101 }
+        .verify(&params, 100)
+        .unwrap();
+    TimeInForce::GoodTilTime { expires_at: 100 }
+        .verify(&params, 100)
+        .unwrap_err();
+
+    let post_only = OrderParams {
+        post_only: true,
+        ..params
+    };
+    TimeInForce::ImmediateOrCancel
+        .verify(&post_only, 100)
+        .unwrap_err();
+    TimeInForce::FillOrKill.verify(&post_only, 100).unwrap_err();
+
+    let ioc = OrderParams {
+        post_allowed: true,
+        time_in_force: TimeInForce::ImmediateOrCancel,
+        ..params
+    };
+    assert!(
+        !ioc.as_new_order_params(Side::Bid, CallbackInfo::default())
+            .post_allowed
+    );
+}
@@ -30,3 +30,28 @@ pub struct DepositTransferred {
     pub previous_owner: Pubkey,
     pub new_owner: Pubkey,
 }
+
+#[event]
+pub struct DepositOffered {
+    pub offer: Pubkey,
+    pub deposit: Pubkey,
+    pub seller: Pubkey,
+    /// The only account that may accept the offer, or the default pubkey for anyone
+    pub buyer: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct DepositOfferCancelled {
+    pub offer: Pubkey,
+    pub deposit: Pubkey,
+}
+
+#[event]
+pub struct DepositTraded {
+    pub deposit: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub price: u64,
+}
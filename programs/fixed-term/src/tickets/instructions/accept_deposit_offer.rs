@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, Transfer};
+
+use crate::{
+    control::state::Market,
+    tickets::{
+        events::DepositTraded,
+        state::{DepositOffer, MarginCustodyAccounts, TermDeposit},
+    },
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct AcceptDepositOffer<'info> {
+    /// The offer to accept
+    #[account(mut,
+        close = payer,
+        has_one = deposit @ FixedTermErrorCode::WrongDepositOffer,
+        has_one = payment_destination @ FixedTermErrorCode::WrongUserAccount,
+        has_one = payer,
+    )]
+    pub offer: Account<'info, DepositOffer>,
+
+    /// The deposit being purchased
+    #[account(mut,
+        has_one = market @ FixedTermErrorCode::WrongMarket,
+        constraint = deposit.owner == offer.seller @ FixedTermErrorCode::WrongDepositOwner,
+    )]
+    pub deposit: Account<'info, TermDeposit>,
+
+    /// The new owner of the deposit, and the signing authority for the payment source. When
+    /// this is a margin account, the deposit is added to its collateral.
+    pub buyer: Signer<'info>,
+
+    /// The token account to pay from
+    #[account(mut)]
+    pub payment_source: AccountInfo<'info>,
+
+    /// The token account of the seller receiving the payment
+    #[account(mut)]
+    pub payment_destination: AccountInfo<'info>,
+
+    /// Receiver for the rent used to track the offer
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// If the buyer is a margin account, its `MarginUser`.
+    /// Otherwise, put whatever address you want in here
+    #[account(mut)]
+    pub margin_user: AccountInfo<'info>,
+
+    /// If the buyer is a margin account, the ticket collateral account of its `MarginUser`.
+    /// Otherwise, put whatever address you want in here
+    #[account(mut)]
+    pub ticket_collateral: AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(mut, address = market.load()?.ticket_collateral_mint)]
+    pub ticket_collateral_mint: AccountInfo<'info>,
+
+    /// The Market responsible for the deposit
+    pub market: AccountLoader<'info, Market>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Pay the price of an offer to take ownership of the deposit, as long as the price is no
+/// higher than the buyer expects
+pub fn handler(ctx: Context<AcceptDepositOffer>, max_price: u64) -> Result<()> {
+    let a = ctx.accounts;
+    a.offer.check_buyer(&a.buyer.key())?;
+    if a.offer.price > max_price {
+        msg!(
+            "offer price is {}, the buyer will pay at most {}",
+            a.offer.price,
+            max_price
+        );
+        return err!(FixedTermErrorCode::DepositOfferPriceTooHigh);
+    }
+
+    transfer(
+        CpiContext::new(
+            a.token_program.to_account_info(),
+            Transfer {
+                from: a.payment_source.to_account_info(),
+                to: a.payment_destination.to_account_info(),
+                authority: a.buyer.to_account_info(),
+            },
+        ),
+        a.offer.price,
+    )?;
+
+    a.deposit.owner = a.buyer.key();
+    if a.buyer.owner == &jet_margin::ID {
+        MarginCustodyAccounts {
+            margin_user: &a.margin_user,
+            ticket_collateral: &a.ticket_collateral,
+            ticket_collateral_mint: &a.ticket_collateral_mint,
+            market: &a.market,
+            token_program: &a.token_program,
+        }
+        .custody(&mut a.deposit)?;
+    }
+
+    emit!(DepositTraded {
+        deposit: a.deposit.key(),
+        seller: a.offer.seller,
+        buyer: a.buyer.key(),
+        amount: a.deposit.amount,
+        price: a.offer.price,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+
+use crate::{
+    control::state::Market,
+    tickets::{
+        events::DepositOfferCancelled,
+        state::{DepositOffer, MarginCustodyAccounts, TermDeposit},
+    },
+    FixedTermErrorCode,
+};
+
+#[derive(Accounts)]
+pub struct CancelDepositOffer<'info> {
+    /// The offer to cancel
+    #[account(mut,
+        close = payer,
+        has_one = deposit @ FixedTermErrorCode::WrongDepositOffer,
+        has_one = seller @ FixedTermErrorCode::WrongDepositOwner,
+        has_one = payer,
+    )]
+    pub offer: Account<'info, DepositOffer>,
+
+    /// The deposit that was offered
+    #[account(mut, has_one = market @ FixedTermErrorCode::WrongMarket)]
+    pub deposit: Account<'info, TermDeposit>,
+
+    /// The account that made the offer
+    pub seller: Signer<'info>,
+
+    /// Receiver for the rent used to track the offer
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// If the deposit was custodied by a margin account, the `MarginUser` of that account.
+    /// Otherwise, put whatever address you want in here
+    #[account(mut)]
+    pub margin_user: AccountInfo<'info>,
+
+    /// If the deposit was custodied by a margin account, the ticket collateral account of the
+    /// `MarginUser`. Otherwise, put whatever address you want in here
+    #[account(mut)]
+    pub ticket_collateral: AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(mut, address = market.load()?.ticket_collateral_mint)]
+    pub ticket_collateral_mint: AccountInfo<'info>,
+
+    /// The Market responsible for the deposit
+    pub market: AccountLoader<'info, Market>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Withdraw an offer, returning the deposit to the collateral of the seller's margin account
+/// if it was custodied by one
+pub fn handler(ctx: Context<CancelDepositOffer>) -> Result<()> {
+    let a = ctx.accounts;
+
+    if a.offer.margin_custody && a.deposit.owner == a.seller.key() {
+        MarginCustodyAccounts {
+            margin_user: &a.margin_user,
+            ticket_collateral: &a.ticket_collateral,
+            ticket_collateral_mint: &a.ticket_collateral_mint,
+            market: &a.market,
+            token_program: &a.token_program,
+        }
+        .custody(&mut a.deposit)?;
+    }
+
+    emit!(DepositOfferCancelled {
+        offer: a.offer.key(),
+        deposit: a.deposit.key(),
+    });
+
+    Ok(())
+}
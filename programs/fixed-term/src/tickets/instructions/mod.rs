@@ -1,9 +1,15 @@
+pub mod accept_deposit_offer;
+pub mod cancel_deposit_offer;
 pub mod exchange_tokens;
+pub mod offer_deposit;
 pub mod redeem_deposit;
 pub mod stake_tickets;
 pub mod transfer_deposit;
 
+pub use accept_deposit_offer::*;
+pub use cancel_deposit_offer::*;
 pub use exchange_tokens::*;
+pub use offer_deposit::*;
 pub use redeem_deposit::*;
 pub use stake_tickets::*;
 pub use transfer_deposit::*;
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::{
+    control::state::Market,
+    seeds,
+    tickets::{
+        events::DepositOffered,
+        state::{DepositOffer, MarginCustodyAccounts, TermDeposit, TermDepositFlags},
+    },
+    FixedTermErrorCode,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct OfferDepositParams {
+    /// The number of underlying tokens to be paid for the deposit
+    pub price: u64,
+
+    /// The only account that may accept the offer, or the default pubkey if anyone may
+    pub buyer: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct OfferDeposit<'info> {
+    /// The deposit to sell
+    #[account(mut,
+        has_one = owner @ FixedTermErrorCode::WrongDepositOwner,
+        has_one = market @ FixedTermErrorCode::WrongMarket,
+    )]
+    pub deposit: Account<'info, TermDeposit>,
+
+    /// The current owner of the deposit, which may be a margin account
+    pub owner: Signer<'info>,
+
+    /// The offer to create
+    #[account(
+        init,
+        seeds = [
+            seeds::DEPOSIT_OFFER,
+            deposit.key().as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<DepositOffer>(),
+    )]
+    pub offer: Account<'info, DepositOffer>,
+
+    /// The token account to receive the payment for the deposit
+    #[account(
+        constraint = payment_destination.mint == market.load()?.underlying_token_mint
+            @ FixedTermErrorCode::WrongUnderlyingTokenMint,
+    )]
+    pub payment_destination: Account<'info, TokenAccount>,
+
+    /// If the deposit is custodied by a margin account, the `MarginUser` of that account.
+    /// Otherwise, put whatever address you want in here
+    #[account(mut)]
+    pub margin_user: AccountInfo<'info>,
+
+    /// If the deposit is custodied by a margin account, the ticket collateral account of the
+    /// `MarginUser`. Otherwise, put whatever address you want in here
+    #[account(mut)]
+    pub ticket_collateral: AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the collateral value of assets custodied by fixed-term market
+    #[account(mut, address = market.load()?.ticket_collateral_mint)]
+    pub ticket_collateral_mint: AccountInfo<'info>,
+
+    /// The Market responsible for the deposit
+    pub market: AccountLoader<'info, Market>,
+
+    /// The account paying rent for the offer
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// SPL token program
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Offer to sell a deposit for a price in underlying tokens.
+///
+/// A deposit custodied by a margin account is removed from the account's collateral while it
+/// is offered, so the margin program can verify the account is still healthy without it.
+pub fn handler(ctx: Context<OfferDeposit>, params: OfferDepositParams) -> Result<()> {
+    let a = ctx.accounts;
+
+    let margin_custody = a.deposit.flags.contains(TermDepositFlags::MARGIN);
+    if margin_custody {
+        MarginCustodyAccounts {
+            margin_user: &a.margin_user,
+            ticket_collateral: &a.ticket_collateral,
+            ticket_collateral_mint: &a.ticket_collateral_mint,
+            market: &a.market,
+            token_program: &a.token_program,
+        }
+        .release(&mut a.deposit)?;
+    }
+
+    *a.offer = DepositOffer {
+        deposit: a.deposit.key(),
+        seller: a.owner.key(),
+        buyer: params.buyer,
+        payment_destination: a.payment_destination.key(),
+        payer: a.payer.key(),
+        price: params.price,
+        margin_custody,
+    };
+
+    emit!(DepositOffered {
+        offer: a.offer.key(),
+        deposit: a.deposit.key(),
+        seller: a.owner.key(),
+        buyer: params.buyer,
+        price: params.price,
+    });
+
+    Ok(())
+}
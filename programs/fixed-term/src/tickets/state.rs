@@ -59,6 +59,145 @@ pub struct InitTermDepositAccounts<'a, 'info> {
     pub system_program: &'a Program<'info, System>,
 }
 
+/// An offer to sell a [TermDeposit] in exchange for underlying tokens
+#[account]
+#[derive(Debug)]
+pub struct DepositOffer {
+    /// The deposit being sold
+    pub deposit: Pubkey,
+
+    /// The owner of the deposit when the offer was made
+    pub seller: Pubkey,
+
+    /// The only account that may accept the offer, or the default pubkey if anyone may
+    pub buyer: Pubkey,
+
+    /// The token account to receive the payment for the deposit
+    pub payment_destination: Pubkey,
+
+    /// Which account recieves the rent when this PDA is destructed
+    pub payer: Pubkey,
+
+    /// The number of underlying tokens to be paid for the deposit
+    pub price: u64,
+
+    /// Whether the deposit was released from the custody of the seller's margin account, and
+    /// should be returned to it if the offer is cancelled
+    pub margin_custody: bool,
+}
+
+impl DepositOffer {
+    pub fn seeds(deposit: &[u8]) -> [&[u8]; 2] {
+        [crate::seeds::DEPOSIT_OFFER, deposit]
+    }
+
+    /// Check that an account is allowed to accept this offer
+    pub fn check_buyer(&self, buyer: &Pubkey) -> Result<()> {
+        if self.buyer != Pubkey::default() && self.buyer != *buyer {
+            msg!("offer can only be accepted by {}", self.buyer);
+            return err!(FixedTermErrorCode::WrongDepositBuyer);
+        }
+
+        Ok(())
+    }
+}
+
+/// Accounts to move a [TermDeposit] into or out of the collateral of the margin account that
+/// owns it
+pub struct MarginCustodyAccounts<'a, 'info> {
+    /// The `MarginUser` for the margin account that owns the deposit
+    pub margin_user: &'a AccountInfo<'info>,
+
+    /// Token account used by the margin program to track the collateral value of assets custodied by fixed-term market
+    pub ticket_collateral: &'a AccountInfo<'info>,
+
+    /// Token mint used by the margin program to track the collateral value of assets custodied by fixed-term market
+    pub ticket_collateral_mint: &'a AccountInfo<'info>,
+
+    /// The Market responsible for the asset
+    pub market: &'a AccountLoader<'info, Market>,
+
+    /// SPL token program
+    pub token_program: &'a Program<'info, Token>,
+}
+
+impl<'a, 'info> MarginCustodyAccounts<'a, 'info> {
+    fn load_user(&self, deposit: &TermDeposit) -> Result<Account<'info, MarginUser>> {
+        let user = Account::<MarginUser>::try_from(self.margin_user)?;
+        require_keys_eq!(
+            user.margin_account,
+            deposit.owner,
+            FixedTermErrorCode::WrongMarginUserAuthority
+        );
+        require_keys_eq!(
+            user.market,
+            deposit.market,
+            FixedTermErrorCode::UserNotInMarket
+        );
+        require_keys_eq!(
+            user.ticket_collateral,
+            self.ticket_collateral.key(),
+            FixedTermErrorCode::WrongTicketCollateralAccount
+        );
+
+        Ok(user)
+    }
+
+    /// Remove a deposit from the collateral of its owner's margin account
+    pub fn release(&self, deposit: &mut TermDeposit) -> Result<()> {
+        let mut user = self.load_user(deposit)?;
+        if deposit.flags.contains(TermDepositFlags::CUSTODIED) {
+            user.release_custodied_deposit(deposit.amount)?;
+        } else {
+            user.release_deposit(deposit.sequence_number, deposit.amount)?;
+        }
+
+        anchor_spl::token::burn(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                anchor_spl::token::Burn {
+                    mint: self.ticket_collateral_mint.to_account_info(),
+                    from: self.ticket_collateral.to_account_info(),
+                    authority: self.market.to_account_info(),
+                },
+            )
+            .with_signer(&[&self.market.load()?.authority_seeds()]),
+            deposit.amount,
+        )?;
+        deposit.flags.remove(
+            TermDepositFlags::MARGIN | TermDepositFlags::AUTO_ROLL | TermDepositFlags::CUSTODIED,
+        );
+
+        user.emit_asset_balances()?;
+        user.exit(&crate::ID)
+    }
+
+    /// Add a deposit to the collateral of its owner's margin account
+    pub fn custody(&self, deposit: &mut TermDeposit) -> Result<()> {
+        let mut user = self.load_user(deposit)?;
+        user.custody_deposit(deposit.amount)?;
+
+        anchor_spl::token::mint_to(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                anchor_spl::token::MintTo {
+                    mint: self.ticket_collateral_mint.to_account_info(),
+                    to: self.ticket_collateral.to_account_info(),
+                    authority: self.market.to_account_info(),
+                },
+            )
+            .with_signer(&[&self.market.load()?.authority_seeds()]),
+            deposit.amount,
+        )?;
+        deposit
+            .flags
+            .insert(TermDepositFlags::MARGIN | TermDepositFlags::CUSTODIED);
+
+        user.emit_asset_balances()?;
+        user.exit(&crate::ID)
+    }
+}
+
 pub struct TermDepositWriter {
     pub market: Pubkey,
     pub owner: Pubkey,
@@ -122,6 +261,10 @@ bitflags! {
 
         /// Is this term deposit custodied by the margin account
         const MARGIN    = 1 << 1;
+
+        /// Was this term deposit added to the collateral of the margin account after it was
+        /// created, so that it is tracked by its amount rather than its sequence number
+        const CUSTODIED = 1 << 2;
     }
 }
 
@@ -223,9 +366,18 @@ impl<'a, 'info> MarginRedeemDepositAccounts<'a, 'info> {
     /// Run TermDeposit redemption and margin accounting logic
     pub fn margin_redeem(&mut self, is_withdrawing: bool) -> Result<()> {
         let seq_no = self.inner.deposit.sequence_number;
+        let custodied = self
+            .inner
+            .deposit
+            .flags
+            .contains(TermDepositFlags::CUSTODIED);
         let redeemed = self.inner.redeem(is_withdrawing)?;
 
-        self.margin_user.redeem_deposit(seq_no, redeemed)?;
+        if custodied {
+            self.margin_user.release_custodied_deposit(redeemed)?;
+        } else {
+            self.margin_user.redeem_deposit(seq_no, redeemed)?;
+        }
 
         // remove the collateral for the redeemed tickets
         anchor_spl::token::burn(
@@ -1,4 +1,4 @@
-use crate::{MarginPool, MarginPoolConfig};
+use crate::{InterestRateCurve, MarginPool, MarginPoolConfig, PoolLimits};
 use anchor_lang::prelude::*;
 
 #[event]
@@ -23,6 +23,18 @@ pub struct PoolConfigured {
     pub config: MarginPoolConfig,
 }
 
+#[event]
+pub struct InterestCurveConfigured {
+    pub margin_pool: Pubkey,
+    pub curve: InterestRateCurve,
+}
+
+#[event]
+pub struct PoolLimitsConfigured {
+    pub margin_pool: Pubkey,
+    pub limits: Option<PoolLimits>,
+}
+
 #[event]
 pub struct Deposit {
     pub margin_pool: Pubkey,
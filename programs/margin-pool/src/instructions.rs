@@ -18,6 +18,8 @@
 mod close_loan;
 mod collect;
 mod configure;
+mod configure_interest_curve;
+mod configure_limits;
 mod create_pool;
 mod deposit;
 mod margin_borrow;
@@ -33,6 +35,8 @@ mod admin;
 pub use close_loan::*;
 pub use collect::*;
 pub use configure::*;
+pub use configure_interest_curve::*;
+pub use configure_limits::*;
 pub use create_pool::*;
 pub use deposit::*;
 pub use margin_borrow::*;
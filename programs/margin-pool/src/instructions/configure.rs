@@ -18,6 +18,7 @@
 use anchor_lang::prelude::*;
 
 use jet_metadata::ControlAuthority;
+use jet_program_common::oracle::PushPriceFeed;
 
 use crate::ErrorCode;
 use crate::{events, state::*};
@@ -25,28 +26,51 @@ use crate::{events, state::*};
 #[derive(Accounts)]
 pub struct Configure<'info> {
     /// The pool to be configured
-    #[account(mut)]
+    #[account(mut,
+              realloc = MarginPool::SPACE,
+              realloc::payer = payer,
+              realloc::zero = false)]
     pub margin_pool: Account<'info, MarginPool>,
 
     /// The authority allowed to modify the pool, which must sign
     #[cfg_attr(not(feature = "testing"), account(signer))]
     pub authority: Account<'info, ControlAuthority>,
 
+    /// The pyth product for the token, or the owning program when using a push oracle
     /// CHECK:
     pub pyth_product: AccountInfo<'info>,
 
+    /// The price account for the token, which may be a pyth or push oracle
     /// CHECK:
     pub pyth_price: AccountInfo<'info>,
+
+    /// The payer for any additional rent needed to fit the push oracle program
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 pub fn configure_handler(ctx: Context<Configure>, config: Option<MarginPoolConfig>) -> Result<()> {
     let pool = &mut ctx.accounts.margin_pool;
 
     if let Some(new_config) = config {
+        new_config.validate()?;
         pool.config = new_config;
     }
 
-    if *ctx.accounts.pyth_price.key != Pubkey::default() {
+    let push_feed = PushPriceFeed::load(&ctx.accounts.pyth_price.try_borrow_data()?);
+
+    if push_feed.is_some() {
+        // For push oracles, the product account is the program expected to publish prices
+        if ctx.accounts.pyth_price.owner != ctx.accounts.pyth_product.key {
+            msg!("push oracle is not owned by the expected program");
+            return err!(ErrorCode::InvalidPoolOracle);
+        }
+
+        pool.token_price_oracle = ctx.accounts.pyth_price.key();
+        pool.token_price_program = Some(ctx.accounts.pyth_product.key());
+    } else if *ctx.accounts.pyth_price.key != Pubkey::default() {
         let product_data = ctx.accounts.pyth_product.try_borrow_data()?;
         let product_account = pyth_sdk_solana::state::load_product_account(&product_data)
             .map_err(|_| ErrorCode::InvalidPoolOracle)?;
@@ -71,6 +95,7 @@ pub fn configure_handler(ctx: Context<Configure>, config: Option<MarginPoolConfi
         }
 
         pool.token_price_oracle = ctx.accounts.pyth_price.key();
+        pool.token_price_program = None;
     }
 
     emit!(events::PoolConfigured {
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_metadata::ControlAuthority;
+
+use crate::{events, state::*, ErrorCode};
+
+#[derive(Accounts)]
+pub struct ConfigureInterestCurve<'info> {
+    /// The pool to be configured
+    #[account(mut,
+              realloc = MarginPool::SPACE,
+              realloc::payer = payer,
+              realloc::zero = false)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The authority allowed to modify the pool, which must sign
+    #[cfg_attr(not(feature = "testing"), account(signer))]
+    pub authority: Account<'info, ControlAuthority>,
+
+    /// The payer for any additional rent needed to fit the curve
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn configure_interest_curve_handler(
+    ctx: Context<ConfigureInterestCurve>,
+    curve: InterestRateCurve,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.margin_pool;
+    let clock = Clock::get()?;
+
+    curve.validate()?;
+
+    // Interest up to now is charged at the rate from the previous curve
+    if !pool.accrue_interest(clock.unix_timestamp) {
+        msg!("interest accrual is too far behind");
+        return err!(ErrorCode::InterestAccrualBehind);
+    }
+
+    pool.interest_curve = curve.clone();
+
+    emit!(events::InterestCurveConfigured {
+        margin_pool: pool.key(),
+        curve,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_metadata::ControlAuthority;
+
+use crate::{events, state::*};
+
+#[derive(Accounts)]
+pub struct ConfigureLimits<'info> {
+    /// The pool to be configured
+    #[account(mut,
+              realloc = MarginPool::SPACE,
+              realloc::payer = payer,
+              realloc::zero = false)]
+    pub margin_pool: Account<'info, MarginPool>,
+
+    /// The authority allowed to modify the pool, which must sign
+    #[cfg_attr(not(feature = "testing"), account(signer))]
+    pub authority: Account<'info, ControlAuthority>,
+
+    /// The payer for any additional rent needed to fit the limits
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn configure_limits_handler(
+    ctx: Context<ConfigureLimits>,
+    limits: Option<PoolLimits>,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.margin_pool;
+
+    pool.limits = limits;
+
+    emit!(events::PoolLimitsConfigured {
+        margin_pool: pool.key(),
+        limits,
+    });
+
+    Ok(())
+}
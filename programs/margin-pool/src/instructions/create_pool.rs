@@ -32,7 +32,7 @@ pub struct CreatePool<'info> {
         seeds = [token_mint.key().as_ref()],
         bump,
         payer = payer,
-        space = 8 + std::mem::size_of::<MarginPool>(),
+        space = MarginPool::SPACE,
     )]
     pub margin_pool: Box<Account<'info, MarginPool>>,
 
@@ -110,6 +110,7 @@ pub fn deposit_handler(ctx: Context<Deposit>, change_kind: ChangeKind, amount: u
     )?;
     debug_msg!("Executing deposit {:?}", deposit_amount);
     pool.deposit(&deposit_amount);
+    pool.verify_deposit_cap()?;
 
     let pool = &ctx.accounts.margin_pool;
     let signer = [&pool.signer_seeds()?[..]];
@@ -109,6 +109,13 @@ pub fn margin_borrow_handler(
     let borrow_amount =
         pool.calculate_full_amount(ctx.accounts.loan_account.amount, change, PoolAction::Borrow)?;
     pool.borrow(&borrow_amount)?;
+    pool.verify_account_borrow_cap(
+        ctx.accounts
+            .loan_account
+            .amount
+            .checked_add(borrow_amount.notes)
+            .unwrap(),
+    )?;
 
     // Then record a deposit of the same borrowed tokens
     let deposit_amount =
@@ -104,6 +104,13 @@ pub fn margin_borrow_v2_handler(ctx: Context<MarginBorrowV2>, amount: u64) -> Re
     let borrow_amount =
         pool.calculate_full_amount(ctx.accounts.loan_account.amount, change, PoolAction::Borrow)?;
     pool.borrow(&borrow_amount)?;
+    pool.verify_account_borrow_cap(
+        ctx.accounts
+            .loan_account
+            .amount
+            .checked_add(borrow_amount.notes)
+            .unwrap(),
+    )?;
 
     // Finish by minting the loan notes
     let pool = &ctx.accounts.margin_pool;
@@ -17,10 +17,12 @@
 
 use anchor_lang::prelude::*;
 
-use jet_margin::{AdapterResult, MarginAccount, PositionChange, PriceChangeInfo};
+use jet_margin::{
+    seeds::TOKEN_CONFIG_SEED, AdapterResult, MarginAccount, PositionChange, PriceChangeInfo,
+    TokenConfig, TokenOracleSet,
+};
 
-use crate::state::*;
-use crate::ErrorCode;
+use crate::{state::*, util, ErrorCode};
 
 #[derive(Accounts)]
 pub struct MarginRefreshPosition<'info> {
@@ -31,31 +33,35 @@ pub struct MarginRefreshPosition<'info> {
     #[account(has_one = token_price_oracle)]
     pub margin_pool: Account<'info, MarginPool>,
 
-    /// The pyth price account for the pool's token
+    /// The price account for the pool's token, either a pyth or push oracle
     /// CHECK:
     pub token_price_oracle: AccountInfo<'info>,
+    // Remaining accounts, in order:
+    //
+    // The margin token config for the pool's token in the margin account's airspace,
+    // which may not exist:
+    // pub token_config: AccountInfo<'info>,
+    //
+    // When the token config has an oracle set:
+    // pub oracle_set: Account<'info, TokenOracleSet>,
+    // pub fallback_oracles: [AccountInfo<'info>; oracle_set.fallbacks.len()],
 }
 
 pub fn margin_refresh_position_handler(ctx: Context<MarginRefreshPosition>) -> Result<()> {
     let pool = &ctx.accounts.margin_pool;
 
-    // read from the pyth oracle
-    let token_oracle = match pyth_sdk_solana::load_price_feed_from_account_info(
-        &ctx.accounts.token_price_oracle,
-    ) {
-        Ok(pf) => pf,
-        Err(e) => {
-            msg!("the oracle account is not valid: {:?}", e);
-            return err!(ErrorCode::InvalidPoolOracle);
-        }
-    };
-
     // This is safe as pool.calculate_prices will check the price's validity,
     // while this price is used after that check. The margin program will also
     // check the price.
-    let price = token_oracle.get_price_unchecked();
+    let price = util::load_oracle_price(pool, &ctx.accounts.token_price_oracle)?;
+    let price = apply_oracle_set(
+        pool,
+        &ctx.accounts.margin_account,
+        ctx.remaining_accounts,
+        price,
+    )?;
 
-    let prices = pool.calculate_prices(&token_oracle)?;
+    let prices = pool.calculate_prices(&price)?;
 
     // Tell the margin program what the current prices are
     jet_margin::write_adapter_result(
@@ -66,7 +72,7 @@ pub fn margin_refresh_position_handler(ctx: Context<MarginRefreshPosition>) -> R
                     pool.deposit_note_mint,
                     vec![PositionChange::Price(PriceChangeInfo {
                         publish_time: price.publish_time,
-                        exponent: price.expo,
+                        exponent: price.exponent,
                         value: prices.deposit_note_price,
                         confidence: prices.deposit_note_conf,
                         twap: prices.deposit_note_twap,
@@ -76,7 +82,7 @@ pub fn margin_refresh_position_handler(ctx: Context<MarginRefreshPosition>) -> R
                     pool.loan_note_mint,
                     vec![PositionChange::Price(PriceChangeInfo {
                         publish_time: price.publish_time,
-                        exponent: price.expo,
+                        exponent: price.exponent,
                         value: prices.loan_note_price,
                         confidence: prices.loan_note_conf,
                         twap: prices.loan_note_twap,
@@ -88,3 +94,98 @@ pub fn margin_refresh_position_handler(ctx: Context<MarginRefreshPosition>) -> R
 
     Ok(())
 }
+
+/// Check the pool's price against the fallback oracles configured for the token in the
+/// margin account's airspace, if there are any.
+///
+/// The pool's oracle takes the place of the primary oracle in the token config, and the
+/// oracle set selects which price is used. When the prices cannot be aggregated, an
+/// invalid price is returned for the margin program to reject.
+fn apply_oracle_set(
+    pool: &MarginPool,
+    margin_account: &AccountLoader<MarginAccount>,
+    remaining_accounts: &[AccountInfo],
+    price: PriceChangeInfo,
+) -> Result<PriceChangeInfo> {
+    let mut remaining_accounts = remaining_accounts.iter();
+    let airspace = margin_account.load()?.airspace;
+
+    let (expected_config, _) = Pubkey::find_program_address(
+        &[
+            TOKEN_CONFIG_SEED,
+            airspace.as_ref(),
+            pool.token_mint.as_ref(),
+        ],
+        &jet_margin::ID,
+    );
+
+    let config_info = match remaining_accounts.next() {
+        Some(info) if info.key() == expected_config => info,
+        _ => {
+            msg!("the token config {} must be provided", expected_config);
+            return err!(ErrorCode::InvalidPoolOracle);
+        }
+    };
+
+    // the token is not configured in this airspace, so there are no other oracles
+    if config_info.owner != &jet_margin::ID {
+        return Ok(price);
+    }
+
+    let config = TokenConfig::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+
+    if !config.has_oracle_set {
+        return Ok(price);
+    }
+
+    let oracle_set = match remaining_accounts.next() {
+        Some(info) if info.owner == &jet_margin::ID => {
+            TokenOracleSet::try_deserialize(&mut &info.try_borrow_data()?[..])?
+        }
+        _ => {
+            msg!("the oracle set for the token must be provided");
+            return err!(ErrorCode::InvalidPoolOracle);
+        }
+    };
+
+    if oracle_set.token_config != config_info.key() {
+        msg!("the oracle set is for a different token");
+        return err!(ErrorCode::InvalidPoolOracle);
+    }
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    let mut candidates = vec![price];
+
+    for fallback in &oracle_set.fallbacks {
+        let oracle_info = match remaining_accounts.next() {
+            Some(info) => info,
+            None => {
+                msg!("missing fallback oracle {}", fallback.price_account());
+                return err!(ErrorCode::InvalidPoolOracle);
+            }
+        };
+
+        candidates.push(fallback.load_price(oracle_info)?);
+    }
+
+    let prices = candidates
+        .iter()
+        .map(|candidate| PriceChangeInfo::try_into(*candidate, timestamp))
+        .collect::<Result<Vec<_>>>()?;
+
+    let selected = oracle_set.aggregate(&prices);
+    let selected_candidate = candidates.iter().zip(&prices).find(|(_, p)| {
+        p.is_valid() && p.value == selected.value && p.exponent == selected.exponent
+    });
+
+    match selected_candidate {
+        Some((candidate, _)) if selected.is_valid() => Ok(*candidate),
+        _ => Ok(PriceChangeInfo {
+            publish_time: 0,
+            exponent: price.exponent,
+            value: 0,
+            confidence: 0,
+            twap: 0,
+        }),
+    }
+}
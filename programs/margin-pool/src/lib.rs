@@ -25,7 +25,10 @@ mod state;
 mod util;
 use instructions::*;
 
-pub use state::{MarginPool, MarginPoolConfig, PoolAction, PoolFlags};
+pub use state::{
+    InterestRateCurve, MarginPool, MarginPoolConfig, PoolAction, PoolFlags, PoolLimits,
+    RateCurvePoint,
+};
 pub mod events;
 
 declare_id!("JPPooLEqRo3NCSx82EdE2VZY5vUaSsgskpZPBHNGVLZ");
@@ -39,6 +42,9 @@ pub mod authority {
 /// Defines the maximum utilisation ratio up to which a borrow from a pool will be allowed.
 pub const MAX_POOL_UTIL_RATIO_AFTER_BORROW_BPS: u64 = 9500;
 
+/// The maximum number of points in a piecewise interest rate curve for a pool
+pub const MAX_RATE_CURVE_POINTS: usize = 8;
+
 #[program]
 mod jet_margin_pool {
     use super::*;
@@ -131,6 +137,67 @@ mod jet_margin_pool {
         instructions::configure_handler(ctx, config)
     }
 
+    /// Set the curve used to determine the interest rate for the pool
+    ///
+    /// Interest is accrued up to the current time with the previous curve before the
+    /// new curve takes effect. The pool account is resized to fit the curve if needed.
+    ///
+    /// * `curve` - The new interest rate curve for the pool.
+    ///
+    /// # [Accounts](jet_margin_pool::accounts::ConfigureInterestCurve)
+    ///
+    /// |     |     |     |
+    /// | --- | --- | --- |
+    /// | **Name** | **Type** | **Description** |
+    /// | `margin_pool` | `writable` | The pool to be configured. |
+    /// | `authority` | `read_only` | The authority to modify the pool, which must sign. |
+    /// | `payer` | `writable, signer` | The payer for any additional rent. |
+    /// | `system_program` | `read_only` | The system program. |
+    ///
+    /// # Events
+    ///
+    /// |     |     |
+    /// | --- | --- |
+    /// | **Event Name** | **Description** |
+    /// | [`events::InterestCurveConfigured`] | Marks the change to the interest curve. |
+    pub fn configure_interest_curve(
+        ctx: Context<ConfigureInterestCurve>,
+        curve: InterestRateCurve,
+    ) -> Result<()> {
+        instructions::configure_interest_curve_handler(ctx, curve)
+    }
+
+    /// Set the caps on the amount of tokens that can be deposited or borrowed from the pool
+    ///
+    /// The caps only restrict new deposits and borrows, so a pool may remain above
+    /// a cap that is lowered below its current balances. The pool account is resized
+    /// to fit the caps if needed.
+    ///
+    /// * `limits` - The new caps for the pool, or `None` to remove all caps.
+    ///
+    /// # [Accounts](jet_margin_pool::accounts::ConfigureLimits)
+    ///
+    /// |     |     |     |
+    /// | --- | --- | --- |
+    /// | **Name** | **Type** | **Description** |
+    /// | `margin_pool` | `writable` | The pool to be configured. |
+    /// | `authority` | `read_only` | The authority to modify the pool, which must sign. |
+    /// | `payer` | `writable, signer` | The payer for any additional rent. |
+    /// | `system_program` | `read_only` | The system program. |
+    ///
+    /// # Events
+    ///
+    /// |     |     |
+    /// | --- | --- |
+    /// | **Event Name** | **Description** |
+    /// | [`events::PoolLimitsConfigured`] | Marks the change to the pool caps. |
+    pub fn configure_limits(
+        ctx: Context<ConfigureLimits>,
+        limits: Option<PoolLimits>,
+    ) -> Result<()> {
+        instructions::configure_limits_handler(ctx, limits)
+    }
+
     /// Deposit tokens into the pool in exchange for notes
     ///
     /// TODO: check my def for change_kind, expand on it w more detail...
@@ -320,6 +387,9 @@ mod jet_margin_pool {
     /// | `margin_pool` | `read_only` | The pool to be refreshed. |
     /// | `token_price_oracle` | `read_only` | The pyth price account for the pool's token. |
     ///
+    /// The remaining accounts start with the margin token config for the pool's token,
+    /// followed by its token oracle set and fallback oracles when it has one.
+    ///
     pub fn margin_refresh_position(ctx: Context<MarginRefreshPosition>) -> Result<()> {
         instructions::margin_refresh_position_handler(ctx)
     }
@@ -499,4 +569,20 @@ pub enum ErrorCode {
     /// 141109 - This borrow pushes the pool util ratio above the limit for new borrows
     #[msg("This borrow pushes the pool util ratio above the limit for new borrows")]
     ExceedsMaxBorrowUtilRatio,
+
+    /// 141110 - The pool configuration is not valid
+    #[msg("The pool configuration is not valid")]
+    InvalidPoolConfig,
+
+    /// 141111 - This deposit pushes the pool above its deposit cap
+    #[msg("This deposit pushes the pool above its deposit cap")]
+    DepositCapExceeded,
+
+    /// 141112 - This borrow pushes the pool above its borrow cap
+    #[msg("This borrow pushes the pool above its borrow cap")]
+    BorrowCapExceeded,
+
+    /// 141113 - This borrow pushes the account above the borrow cap for a single account
+    #[msg("This borrow pushes the account above the borrow cap for a single account")]
+    AccountBorrowCapExceeded,
 }
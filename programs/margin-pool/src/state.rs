@@ -19,8 +19,8 @@ use std::cmp::Ordering;
 use std::convert::TryFrom;
 
 use anchor_lang::{prelude::*, solana_program::clock::UnixTimestamp};
+use jet_margin::PriceChangeInfo;
 use jet_program_common::{Number, BPS_EXPONENT};
-use pyth_sdk::PriceFeed;
 
 #[cfg(any(test, feature = "no-entrypoint"))]
 use serde::{
@@ -30,7 +30,7 @@ use serde::{
 
 use crate::{
     util, Amount, AmountKind, ChangeKind, ErrorCode, TokenChange,
-    MAX_POOL_UTIL_RATIO_AFTER_BORROW_BPS,
+    MAX_POOL_UTIL_RATIO_AFTER_BORROW_BPS, MAX_RATE_CURVE_POINTS,
 };
 
 /// Account containing information about a margin pool, which
@@ -89,6 +89,22 @@ pub struct MarginPool {
 
     /// The time the interest was last accrued up to
     pub accrued_until: i64,
+
+    /// The curve used to determine the interest rate for borrows
+    pub interest_curve: InterestRateCurve,
+
+    /// Caps on the amount of tokens that can be deposited or borrowed, if any
+    ///
+    /// These are kept at the end of the account rather than in the [MarginPoolConfig],
+    /// so that the layout of existing pools is unchanged.
+    pub limits: Option<PoolLimits>,
+
+    /// The program that must own the price account, when the token is priced by a
+    /// push oracle rather than pyth
+    ///
+    /// Pools created before this was added are resized to fit it by `configure_limits`
+    /// or `configure_interest_curve`.
+    pub token_price_program: Option<Pubkey>,
 }
 
 impl std::fmt::Debug for MarginPool {
@@ -110,6 +126,9 @@ impl std::fmt::Debug for MarginPool {
             .field("deposit_notes", &self.deposit_notes)
             .field("loan_notes", &self.loan_notes)
             .field("accrued_until", &self.accrued_until)
+            .field("interest_curve", &self.interest_curve)
+            .field("limits", &self.limits)
+            .field("token_price_program", &self.token_price_program)
             .finish()
     }
 }
@@ -142,6 +161,12 @@ impl Serialize for MarginPool {
 }
 
 impl MarginPool {
+    /// The space required for a pool account, including a curve with the maximum
+    /// number of points
+    pub const SPACE: usize = 8
+        + std::mem::size_of::<MarginPool>()
+        + MAX_RATE_CURVE_POINTS * std::mem::size_of::<RateCurvePoint>();
+
     /// Get the seeds needed to sign for the vault
     pub fn signer_seeds(&self) -> Result<[&[u8]; 2]> {
         if self.flags().contains(PoolFlags::DISABLED) {
@@ -191,6 +216,41 @@ impl MarginPool {
             return Err(ErrorCode::ExceedsMaxBorrowUtilRatio.into());
         }
 
+        if let Some(cap) = self.limits.and_then(|l| l.borrow_cap()) {
+            if *self.total_borrowed() > Number::from(cap) {
+                msg!("the pool cannot lend more than {} tokens", cap);
+                return err!(ErrorCode::BorrowCapExceeded);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the deposits in the pool are within the configured cap
+    pub fn verify_deposit_cap(&self) -> Result<()> {
+        if let Some(cap) = self.limits.and_then(|l| l.deposit_cap()) {
+            if self.total_value() > Number::from(cap) {
+                msg!("the pool cannot hold more than {} tokens", cap);
+                return err!(ErrorCode::DepositCapExceeded);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that a single account's loan is within the configured cap
+    ///
+    /// * `loan_notes` - The total loan notes held by the account
+    pub fn verify_account_borrow_cap(&self, loan_notes: u64) -> Result<()> {
+        if let Some(cap) = self.limits.and_then(|l| l.account_borrow_cap()) {
+            let borrowed = self.loan_note_exchange_rate() * Number::from(loan_notes);
+
+            if borrowed > Number::from(cap) {
+                msg!("an account cannot borrow more than {} tokens", cap);
+                return err!(ErrorCode::AccountBorrowCapExceeded);
+            }
+        }
+
         Ok(())
     }
 
@@ -286,6 +346,21 @@ impl MarginPool {
 
     /// Gets the current interest rate for loans from this pool
     pub fn interest_rate(&self) -> Number {
+        match &self.interest_curve {
+            InterestRateCurve::ThreeRegime => self.three_regime_interest_rate(),
+            InterestRateCurve::Piecewise(points) => {
+                // Catch the edge case of empty pool
+                let util_rate = match self.deposit_notes {
+                    0 => Number::ZERO,
+                    _ => self.utilization_rate(),
+                };
+
+                util::piecewise_interpolate(util_rate, points)
+            }
+        }
+    }
+
+    fn three_regime_interest_rate(&self) -> Number {
         let borrow_1 = Number::from_bps(self.config.borrow_rate_1);
 
         // Catch the edge case of empty pool
@@ -354,33 +429,26 @@ impl MarginPool {
 
     /// Calculate the prices for the deposit and loan notes, based on
     /// the price of the underlying token.
-    pub fn calculate_prices(&self, pyth_price: &PriceFeed) -> Result<PriceResult> {
-        let price_obj = pyth_price.get_price_unchecked();
-        let ema_obj = pyth_price.get_ema_price_unchecked();
+    pub fn calculate_prices(&self, token_price: &PriceChangeInfo) -> Result<PriceResult> {
+        let expo = token_price.exponent;
 
-        let price_value = Number::from_decimal(price_obj.price, price_obj.expo);
-        let conf_value = Number::from_decimal(price_obj.conf, price_obj.expo);
-        let twap_value = Number::from_decimal(ema_obj.price, ema_obj.expo);
+        let price_value = Number::from_decimal(token_price.value, expo);
+        let conf_value = Number::from_decimal(token_price.confidence, expo);
+        let twap_value = Number::from_decimal(token_price.twap, expo);
 
         let deposit_note_exchange_rate = self.deposit_note_exchange_rate();
         let loan_note_exchange_rate = self.loan_note_exchange_rate();
 
-        let deposit_note_price = i64::try_from(
-            (price_value * deposit_note_exchange_rate).as_u64_rounded(price_obj.expo),
-        )
-        .unwrap();
-        let deposit_note_conf =
-            (conf_value * deposit_note_exchange_rate).as_u64_rounded(price_obj.expo);
+        let deposit_note_price =
+            i64::try_from((price_value * deposit_note_exchange_rate).as_u64_rounded(expo)).unwrap();
+        let deposit_note_conf = (conf_value * deposit_note_exchange_rate).as_u64_rounded(expo);
         let deposit_note_twap =
-            i64::try_from((twap_value * deposit_note_exchange_rate).as_u64_rounded(price_obj.expo))
-                .unwrap();
+            i64::try_from((twap_value * deposit_note_exchange_rate).as_u64_rounded(expo)).unwrap();
         let loan_note_price =
-            i64::try_from((price_value * loan_note_exchange_rate).as_u64_rounded(price_obj.expo))
-                .unwrap();
-        let loan_note_conf = (conf_value * loan_note_exchange_rate).as_u64_rounded(price_obj.expo);
+            i64::try_from((price_value * loan_note_exchange_rate).as_u64_rounded(expo)).unwrap();
+        let loan_note_conf = (conf_value * loan_note_exchange_rate).as_u64_rounded(expo);
         let loan_note_twap =
-            i64::try_from((twap_value * loan_note_exchange_rate).as_u64_rounded(price_obj.expo))
-                .unwrap();
+            i64::try_from((twap_value * loan_note_exchange_rate).as_u64_rounded(expo)).unwrap();
 
         Ok(PriceResult {
             deposit_note_price,
@@ -648,6 +716,139 @@ pub struct MarginPoolConfig {
     pub reserved: u64,
 }
 
+impl MarginPoolConfig {
+    pub fn validate(&self) -> Result<()> {
+        let utilization_rates = [0, self.utilization_rate_1, self.utilization_rate_2, 10_000];
+        let borrow_rates = [
+            self.borrow_rate_0,
+            self.borrow_rate_1,
+            self.borrow_rate_2,
+            self.borrow_rate_3,
+        ];
+
+        if utilization_rates.windows(2).any(|w| w[0] > w[1]) {
+            msg!("utilization rates must be increasing, and at most 10000 bps");
+            return err!(ErrorCode::InvalidPoolConfig);
+        }
+
+        if borrow_rates.windows(2).any(|w| w[0] > w[1]) {
+            msg!("borrow rates must not decrease as utilization increases");
+            return err!(ErrorCode::InvalidPoolConfig);
+        }
+
+        Ok(())
+    }
+}
+
+/// Caps on the amount of tokens held by a pool
+///
+/// A cap of zero means that no cap is applied.
+#[derive(Debug, Default, AnchorDeserialize, AnchorSerialize, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "no-entrypoint", derive(Serialize, Deserialize))]
+pub struct PoolLimits {
+    /// The maximum amount of tokens that can be deposited in the pool, including
+    /// tokens that are currently lent out
+    pub deposit_cap: u64,
+
+    /// The maximum amount of tokens that can be lent out by the pool
+    pub borrow_cap: u64,
+
+    /// The maximum amount of tokens that can be borrowed by a single margin account
+    pub account_borrow_cap: u64,
+}
+
+impl PoolLimits {
+    pub fn deposit_cap(&self) -> Option<u64> {
+        Some(self.deposit_cap).filter(|cap| *cap > 0)
+    }
+
+    pub fn borrow_cap(&self) -> Option<u64> {
+        Some(self.borrow_cap).filter(|cap| *cap > 0)
+    }
+
+    pub fn account_borrow_cap(&self) -> Option<u64> {
+        Some(self.account_borrow_cap).filter(|cap| *cap > 0)
+    }
+}
+
+/// A point on a piecewise linear interest rate curve
+#[derive(Debug, Default, AnchorDeserialize, AnchorSerialize, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "no-entrypoint", derive(Serialize, Deserialize))]
+pub struct RateCurvePoint {
+    /// The utilization rate (in bps) at this point on the curve
+    pub utilization_rate: u16,
+
+    /// The borrow rate (in bps) when the pool is at the utilization rate
+    pub borrow_rate: u16,
+}
+
+/// Describes how the interest rate for a pool is determined from its utilization
+///
+/// The variant also serves as the version for the pool's interest configuration. Pools
+/// created before the curve was introduced have zeroed space at the end of the account,
+/// which is read as [InterestRateCurve::ThreeRegime].
+#[derive(Debug, AnchorDeserialize, AnchorSerialize, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "no-entrypoint", derive(Serialize, Deserialize))]
+pub enum InterestRateCurve {
+    /// Three linear regimes, described by the utilization and borrow rates in
+    /// the [MarginPoolConfig]
+    ThreeRegime,
+
+    /// A linear interpolation between the given points
+    ///
+    /// The first point must be at 0% utilization and the last at 100%, with
+    /// utilization strictly increasing and borrow rates never decreasing.
+    Piecewise(Vec<RateCurvePoint>),
+}
+
+impl Default for InterestRateCurve {
+    fn default() -> Self {
+        Self::ThreeRegime
+    }
+}
+
+impl InterestRateCurve {
+    pub fn validate(&self) -> Result<()> {
+        let points = match self {
+            Self::ThreeRegime => return Ok(()),
+            Self::Piecewise(points) => points,
+        };
+
+        if points.len() < 2 || points.len() > MAX_RATE_CURVE_POINTS {
+            msg!(
+                "a curve must have between 2 and {} points",
+                MAX_RATE_CURVE_POINTS
+            );
+            return err!(ErrorCode::InvalidPoolConfig);
+        }
+
+        if points[0].utilization_rate != 0 || points[points.len() - 1].utilization_rate != 10_000 {
+            msg!("a curve must start at 0% utilization and end at 100%");
+            return err!(ErrorCode::InvalidPoolConfig);
+        }
+
+        // Interest can only be compounded for rates up to 200%
+        if points[points.len() - 1].borrow_rate > 20_000 {
+            msg!("borrow rates in a curve must be at most 20000 bps");
+            return err!(ErrorCode::InvalidPoolConfig);
+        }
+
+        for pair in points.windows(2) {
+            if pair[0].utilization_rate >= pair[1].utilization_rate {
+                msg!("utilization rates in a curve must be strictly increasing");
+                return err!(ErrorCode::InvalidPoolConfig);
+            }
+
+            if pair[0].borrow_rate > pair[1].borrow_rate {
+                msg!("borrow rates in a curve must not decrease");
+                return err!(ErrorCode::InvalidPoolConfig);
+            }
+        }
+
+        Ok(())
+    }
+}
+
 bitflags::bitflags! {
     pub struct PoolFlags: u64 {
         /// The pool is not allowed to sign for anything, preventing
@@ -707,6 +908,79 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pool_caps() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+        margin_pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+        margin_pool.limits = Some(PoolLimits {
+            deposit_cap: 1_000_000,
+            borrow_cap: 500_000,
+            account_borrow_cap: 0,
+        });
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+        margin_pool.verify_deposit_cap()?;
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1,
+            notes: 1,
+        });
+        assert_eq!(
+            margin_pool.verify_deposit_cap().unwrap_err(),
+            ErrorCode::DepositCapExceeded.into()
+        );
+
+        margin_pool.borrow(&FullAmount {
+            tokens: 500_000,
+            notes: 500_000,
+        })?;
+        assert_eq!(
+            margin_pool
+                .borrow(&FullAmount {
+                    tokens: 1,
+                    notes: 1
+                })
+                .unwrap_err(),
+            ErrorCode::BorrowCapExceeded.into()
+        );
+
+        // no cap is applied for a single account
+        margin_pool.verify_account_borrow_cap(u64::MAX / 2)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_borrow_cap() -> Result<()> {
+        let mut margin_pool = MarginPool::default();
+        margin_pool.config.flags = PoolFlags::ALLOW_LENDING.bits();
+        margin_pool.limits = Some(PoolLimits {
+            account_borrow_cap: 100_000,
+            ..Default::default()
+        });
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+        margin_pool.borrow(&FullAmount {
+            tokens: 200_000,
+            notes: 100_000,
+        })?;
+
+        // each loan note is worth 2 tokens
+        margin_pool.verify_account_borrow_cap(50_000)?;
+        assert_eq!(
+            margin_pool.verify_account_borrow_cap(50_001).unwrap_err(),
+            ErrorCode::AccountBorrowCapExceeded.into()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_deposit_note_rounding() -> Result<()> {
         let mut margin_pool = MarginPool::default();
@@ -962,6 +1236,96 @@ mod tests {
         Ok(())
     }
 
+    fn curve(points: &[(u16, u16)]) -> InterestRateCurve {
+        InterestRateCurve::Piecewise(
+            points
+                .iter()
+                .map(|&(utilization_rate, borrow_rate)| RateCurvePoint {
+                    utilization_rate,
+                    borrow_rate,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_interest_curve_validation() {
+        assert!(InterestRateCurve::ThreeRegime.validate().is_ok());
+        assert!(curve(&[(0, 10), (10_000, 100)]).validate().is_ok());
+        assert!(curve(&[(0, 10), (5_000, 50), (9_000, 50), (10_000, 1_000)])
+            .validate()
+            .is_ok());
+
+        // too few points
+        assert!(curve(&[(0, 10)]).validate().is_err());
+        // must span the full range of utilization
+        assert!(curve(&[(100, 10), (10_000, 100)]).validate().is_err());
+        assert!(curve(&[(0, 10), (9_000, 100)]).validate().is_err());
+        // must be monotonic
+        assert!(curve(&[(0, 10), (5_000, 50), (5_000, 60), (10_000, 100)])
+            .validate()
+            .is_err());
+        assert!(curve(&[(0, 10), (5_000, 50), (10_000, 40)])
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = MarginPoolConfig {
+            utilization_rate_1: 85_00,
+            utilization_rate_2: 95_00,
+            borrow_rate_0: 50,
+            borrow_rate_1: 600,
+            borrow_rate_2: 4000,
+            borrow_rate_3: 16000,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = MarginPoolConfig {
+            utilization_rate_1: 95_00,
+            utilization_rate_2: 85_00,
+            ..config
+        };
+        assert!(config.validate().is_err());
+
+        let config = MarginPoolConfig {
+            utilization_rate_1: 85_00,
+            utilization_rate_2: 95_00,
+            borrow_rate_2: 500,
+            ..config
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_piecewise_interest_rate() {
+        let mut margin_pool = MarginPool {
+            interest_curve: curve(&[(0, 0), (5_000, 1_000), (8_000, 2_000), (10_000, 10_000)]),
+            ..Default::default()
+        };
+
+        // An empty pool uses the rate at zero utilization
+        assert_eq!(margin_pool.interest_rate(), Number::ZERO);
+
+        margin_pool.deposit(&FullAmount {
+            tokens: 1_000_000,
+            notes: 1_000_000,
+        });
+        *margin_pool.total_borrowed_mut() = Number::from(250_000u64);
+        margin_pool.deposit_tokens = 750_000;
+        assert_eq!(margin_pool.interest_rate(), Number::from_bps(500));
+
+        *margin_pool.total_borrowed_mut() = Number::from(650_000u64);
+        margin_pool.deposit_tokens = 350_000;
+        assert_eq!(margin_pool.interest_rate(), Number::from_bps(1_500));
+
+        *margin_pool.total_borrowed_mut() = Number::from(900_000u64);
+        margin_pool.deposit_tokens = 100_000;
+        assert_eq!(margin_pool.interest_rate(), Number::from_bps(6_000));
+    }
+
     #[test]
     fn margin_pool_serialization() {
         let pool = MarginPool::default();
@@ -15,8 +15,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use anchor_lang::solana_program::clock::UnixTimestamp;
-use jet_program_common::Number;
+use std::convert::TryFrom;
+
+use anchor_lang::{prelude::*, solana_program::clock::UnixTimestamp};
+use jet_margin::PriceChangeInfo;
+use jet_program_common::{oracle::PushPriceFeed, Number};
+
+use crate::{
+    state::{MarginPool, RateCurvePoint},
+    ErrorCode,
+};
 
 pub const SECONDS_PER_HOUR: UnixTimestamp = 3600;
 pub const SECONDS_PER_2H: UnixTimestamp = SECONDS_PER_HOUR * 2;
@@ -68,3 +76,52 @@ pub fn interpolate(x: Number, x0: Number, x1: Number, y0: Number, y1: Number) ->
 
     y0 + ((x - x0) * (y1 - y0)) / (x1 - x0)
 }
+
+/// Linear interpolation along a curve of points, which must be sorted by utilization.
+///
+/// Utilization beyond the last point is given the rate of the last point.
+pub fn piecewise_interpolate(utilization: Number, points: &[RateCurvePoint]) -> Number {
+    for pair in points.windows(2) {
+        let util_1 = Number::from_bps(pair[1].utilization_rate);
+
+        if utilization <= util_1 {
+            let util_0 = Number::from_bps(pair[0].utilization_rate);
+            let borrow_0 = Number::from_bps(pair[0].borrow_rate);
+            let borrow_1 = Number::from_bps(pair[1].borrow_rate);
+
+            return interpolate(utilization, util_0, util_1, borrow_0, borrow_1);
+        }
+    }
+
+    points
+        .last()
+        .map(|p| Number::from_bps(p.borrow_rate))
+        .unwrap_or(Number::ZERO)
+}
+
+/// Read the current price of the pool's token from its oracle account.
+///
+/// The oracle may either be a pyth price account, or a [PushPriceFeed] owned by the
+/// program configured for the pool.
+pub fn load_oracle_price(pool: &MarginPool, oracle_info: &AccountInfo) -> Result<PriceChangeInfo> {
+    if let Some(feed) = PushPriceFeed::load(&oracle_info.try_borrow_data()?) {
+        if pool.token_price_program != Some(*oracle_info.owner) {
+            msg!(
+                "push oracle is owned by {} instead of {:?}",
+                oracle_info.owner,
+                pool.token_price_program
+            );
+            return err!(ErrorCode::InvalidPoolOracle);
+        }
+
+        return Ok(feed.into());
+    }
+
+    match pyth_sdk_solana::load_price_feed_from_account_info(oracle_info) {
+        Ok(feed) => Ok(PriceChangeInfo::try_from(feed)?),
+        Err(e) => {
+            msg!("the oracle account is not valid: {:?}", e);
+            err!(ErrorCode::InvalidPoolOracle)
+        }
+    }
+}
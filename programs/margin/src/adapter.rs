@@ -22,15 +22,15 @@ use anchor_lang::{
     solana_program::{instruction::Instruction, program},
 };
 use anchor_spl::token::{Mint, TokenAccount};
-use jet_program_common::Number128;
+use jet_program_common::{oracle::PushPriceFeed, Number128};
 use solana_program::clock::UnixTimestamp;
 
 use crate::{
     syscall::{sys, Sys},
     util::{log_on_error, Require},
     AccountPositionKey, AdapterPositionFlags, Approver, ErrorCode, MarginAccount,
-    PositionConfigUpdate, PriceInfo, SignerSeeds, TokenConfig, MAX_ORACLE_CONFIDENCE,
-    MAX_ORACLE_STALENESS,
+    PositionConfigUpdate, PositionPages, PriceInfo, SignerSeeds, TokenConfig, TokenIsolationConfig,
+    MAX_ORACLE_CONFIDENCE, MAX_ORACLE_STALENESS,
 };
 pub struct InvokeAdapter<'a, 'info> {
     /// The margin account to proxy an action for
@@ -42,6 +42,9 @@ pub struct InvokeAdapter<'a, 'info> {
     /// The accounts to be passed through to the adapter
     pub accounts: &'a [AccountInfo<'info>],
 
+    /// The position pages of the margin account, which are not passed to the adapter
+    pub pages: &'a PositionPages<'info>,
+
     /// The transaction was signed by the authority of the margin account.
     /// Thus, the invocation should be signed by the margin account.
     pub signed: bool,
@@ -153,6 +156,18 @@ impl TryFrom<pyth_sdk::PriceFeed> for PriceChangeInfo {
     }
 }
 
+impl From<PushPriceFeed> for PriceChangeInfo {
+    fn from(feed: PushPriceFeed) -> Self {
+        PriceChangeInfo {
+            publish_time: feed.publish_time,
+            exponent: feed.exponent,
+            value: feed.price,
+            confidence: feed.confidence,
+            twap: feed.ema_price,
+        }
+    }
+}
+
 /// Invoke a margin adapter with the requested data
 /// * `signed` - sign with the margin account
 pub fn invoke(ctx: &InvokeAdapter, data: Vec<u8>) -> Result<()> {
@@ -221,7 +236,18 @@ fn update_balances(ctx: &InvokeAdapter) -> Result<()> {
                     account.amount,
                 ) {
                     Ok(_) => (),
-                    Err(ErrorCode::PositionNotRegistered) => (),
+                    Err(ErrorCode::PositionNotRegistered) => {
+                        let in_pages = ctx.pages.get_position(&account.mint)?;
+
+                        if matches!(in_pages, Some(p) if p.address == *account_info.key) {
+                            ctx.pages.set_position_balance(
+                                &account.mint,
+                                account_info.key,
+                                account.amount,
+                                sys().unix_timestamp(),
+                            )?;
+                        }
+                    }
                     Err(err) => return Err(err.into()),
                 }
             }
@@ -322,10 +348,17 @@ fn register_position(
         return err!(ErrorCode::PositionNotRegisterable);
     }
 
+    // Pages are never passed to adapters, so their positions must fit in the account
+    if !margin_account.has_position_space() {
+        msg!("deposits may be moved to a position page to free space in the account");
+        return err!(ErrorCode::AdapterPositionsFull);
+    }
+
     let key = match token_config {
         Some(config) => margin_account.register_position(
             PositionConfigUpdate::new_from_config(
                 &config,
+                TokenIsolationConfig::find(&config, remaining_accounts)?,
                 mint.decimals,
                 token_account.key(),
                 config.adapter_program().unwrap_or_default(),
@@ -404,6 +437,7 @@ mod test {
             margin_account: &AccountLoader::try_from(&margin_account).unwrap(),
             adapter_program: &adapter,
             accounts: &[],
+            pages: &PositionPages { pages: vec![] },
             signed: true,
         };
 
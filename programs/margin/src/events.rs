@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
 
-use crate::{Liquidation, Permissions, TokenConfigUpdate, Valuation};
+use crate::{
+    ActionTrigger, Liquidation, LiquidationConfigUpdate, MarginDelegateUpdate, Permissions,
+    TokenConfigUpdate, TokenIsolation, TokenOracleSetUpdate, Valuation,
+};
 
 #[event]
 pub struct AccountCreated {
@@ -72,6 +75,35 @@ pub struct LiquidationEnded {
     pub timed_out: bool,
 }
 
+#[event]
+pub struct LiquidationAuctionBegun {
+    pub margin_account: Pubkey,
+    pub auction: Pubkey,
+    pub min_discount: u16,
+    pub max_discount: u16,
+    pub max_liabilities_repaid: i128,
+    pub valuation_summary: ValuationSummary,
+}
+
+#[event]
+pub struct LiquidationAuctionBid {
+    pub margin_account: Pubkey,
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub claim_mint: Pubkey,
+    pub claim_repaid: u64,
+    pub collateral_mint: Pubkey,
+    pub collateral_paid: u64,
+    pub discount: i128,
+}
+
+#[event]
+pub struct LiquidationAuctionEnded {
+    pub margin_account: Pubkey,
+    pub auction: Pubkey,
+    pub timed_out: bool,
+}
+
 #[event]
 pub struct TransferPosition {
     pub source_margin_account: Pubkey,
@@ -88,6 +120,51 @@ pub struct TokenConfigured {
     pub mint: Pubkey,
 }
 
+#[event]
+pub struct TokenOraclesConfigured {
+    pub airspace: Pubkey,
+    pub token_config: Pubkey,
+    pub update: Option<TokenOracleSetUpdate>,
+}
+
+#[event]
+pub struct TokenIsolationConfigured {
+    pub airspace: Pubkey,
+    pub token_config: Pubkey,
+    pub update: Option<TokenIsolation>,
+}
+
+#[event]
+pub struct DelegateConfigured {
+    pub margin_account: Pubkey,
+    pub delegate: Pubkey,
+    pub update: Option<MarginDelegateUpdate>,
+}
+
+#[event]
+pub struct ConditionalActionCreated {
+    pub margin_account: Pubkey,
+    pub action: Pubkey,
+    pub trigger: ActionTrigger,
+    pub adapter_program: Pubkey,
+    pub tip: u64,
+}
+
+#[event]
+pub struct ConditionalActionClosed {
+    pub margin_account: Pubkey,
+    pub action: Pubkey,
+
+    /// The keeper that executed the action, or none if it was cancelled
+    pub keeper: Option<Pubkey>,
+}
+
+#[event]
+pub struct LiquidationConfigured {
+    pub airspace: Pubkey,
+    pub update: Option<LiquidationConfigUpdate>,
+}
+
 #[event]
 pub struct AdapterConfigured {
     pub airspace: Pubkey,
@@ -102,6 +179,14 @@ pub struct PermitConfigured {
     pub permissions: Permissions,
 }
 
+#[event]
+pub struct IsolatedDebtRecorded {
+    pub margin_account: Pubkey,
+    pub isolation_config: Pubkey,
+    pub amount: u64,
+    pub total_isolated_debt: u64,
+}
+
 #[derive(AnchorDeserialize, AnchorSerialize)]
 pub struct ValuationSummary {
     pub equity: i128,
@@ -19,6 +19,7 @@ mod accounting_invoke;
 mod adapter_invoke;
 mod close_account;
 mod close_position;
+mod configure_delegate;
 mod create_account;
 mod liquidate_begin;
 mod liquidate_end;
@@ -29,6 +30,8 @@ mod verify_healthy;
 mod verify_unhealthy;
 
 mod admin;
+mod auction;
+mod conditional;
 mod configure;
 mod lookup_tables;
 mod positions;
@@ -37,6 +40,7 @@ pub use accounting_invoke::*;
 pub use adapter_invoke::*;
 pub use close_account::*;
 pub use close_position::*;
+pub use configure_delegate::*;
 pub use create_account::*;
 pub use liquidate_begin::*;
 pub use liquidate_end::*;
@@ -47,6 +51,8 @@ pub use verify_healthy::*;
 pub use verify_unhealthy::*;
 
 pub use admin::*;
+pub use auction::*;
+pub use conditional::*;
 pub use configure::*;
 pub use lookup_tables::*;
 pub use positions::*;
@@ -18,7 +18,10 @@
 use anchor_lang::prelude::*;
 
 use crate::adapter::{self, InvokeAdapter};
-use crate::{events, AdapterConfig, ErrorCode, MarginAccount};
+use crate::{
+    events, AdapterConfig, ErrorCode, MarginAccount, MarginDelegate, PositionPages,
+    PositionSnapshot,
+};
 
 #[derive(Accounts)]
 pub struct AccountingInvoke<'info> {
@@ -35,12 +38,37 @@ pub struct AccountingInvoke<'info> {
               constraint = adapter_config.airspace == margin_account.load()?.airspace @ ErrorCode::WrongAirspace
     )]
     pub adapter_config: Account<'info, AdapterConfig>,
+    // Remaining accounts are passed through to the adapter, except for any
+    // position pages of the margin account, which have their balances updated,
+    // and the delegation addresses of every signer other than the owner. These
+    // hold the delegations that limit the changes made by delegates, and are
+    // empty for any other signer:
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
+    //
+    // #[account(mut)]
+    // pub delegations: [Account<'info, MarginDelegate>],
 }
 
 pub fn accounting_invoke_handler<'info>(
     ctx: Context<'_, '_, '_, 'info, AccountingInvoke<'info>>,
     data: Vec<u8>,
 ) -> Result<()> {
+    let margin_account = ctx.accounts.margin_account.key();
+    let owner = ctx.accounts.margin_account.load()?.owner;
+    let (pages, accounts) = PositionPages::split(&margin_account, ctx.remaining_accounts)?;
+    let (mut delegations, accounts) =
+        MarginDelegate::split_signed(&margin_account, &owner, &accounts)?;
+
+    for delegation in &delegations {
+        delegation.verify_active(Clock::get()?.unix_timestamp)?;
+    }
+    let before = if delegations.is_empty() {
+        vec![]
+    } else {
+        PositionSnapshot::capture(&*ctx.accounts.margin_account.load()?, &pages)?
+    };
+
     emit!(events::AccountingInvokeBegin {
         margin_account: ctx.accounts.margin_account.key(),
         adapter_program: ctx.accounts.adapter_program.key(),
@@ -50,7 +78,8 @@ pub fn accounting_invoke_handler<'info>(
         &InvokeAdapter {
             margin_account: &ctx.accounts.margin_account,
             adapter_program: &ctx.accounts.adapter_program,
-            accounts: ctx.remaining_accounts,
+            accounts: &accounts,
+            pages: &pages,
             signed: false,
         },
         data,
@@ -58,5 +87,14 @@ pub fn accounting_invoke_handler<'info>(
 
     emit!(events::AccountingInvokeEnd {});
 
+    if !delegations.is_empty() {
+        let after = PositionSnapshot::capture(&*ctx.accounts.margin_account.load()?, &pages)?;
+
+        for delegation in &mut delegations {
+            delegation.verify_changes(&before, &after)?;
+            delegation.exit(&crate::ID)?;
+        }
+    }
+
     Ok(())
 }
@@ -19,15 +19,19 @@ use anchor_lang::prelude::*;
 
 use crate::adapter::{self, InvokeAdapter};
 use crate::syscall::{sys, Sys};
-use crate::{events, AdapterConfig, ErrorCode, MarginAccount};
+use crate::{
+    events, AdapterConfig, ConditionalAction, ErrorCode, MarginAccount, MarginDelegate,
+    PositionPages, PositionSnapshot, TokenIsolationConfig,
+};
 
 #[derive(Accounts)]
 pub struct AdapterInvoke<'info> {
-    /// The authority that owns the margin account
+    /// The authority that owns the margin account, one of its delegates, or a
+    /// keeper executing a conditional action, which must be writable to receive the tip
     pub owner: Signer<'info>,
 
     /// The margin account to proxy an action for
-    #[account(mut, has_one = owner)]
+    #[account(mut)]
     pub margin_account: AccountLoader<'info, MarginAccount>,
 
     /// The program to be invoked
@@ -39,6 +43,20 @@ pub struct AdapterInvoke<'info> {
               constraint = adapter_config.airspace == margin_account.load()?.airspace @ ErrorCode::WrongAirspace
     )]
     pub adapter_config: Account<'info, AdapterConfig>,
+    // Remaining accounts are passed through to the adapter, except for any
+    // position pages of the margin account, which are used in the health check,
+    // the isolation configs for any isolated collateral backing debt:
+    //
+    // #[account(mut)]
+    // pub isolation_configs: [Account<'info, TokenIsolationConfig>],
+    //
+    // and one of these when the signer is not the owner:
+    //
+    // #[account(mut)]
+    // pub delegation: Account<'info, MarginDelegate>,
+    //
+    // #[account(mut)]
+    // pub action: Account<'info, ConditionalAction>,
 }
 
 pub fn adapter_invoke_handler<'info>(
@@ -50,6 +68,42 @@ pub fn adapter_invoke_handler<'info>(
         return Err(ErrorCode::Liquidating.into());
     }
 
+    let margin_account = ctx.accounts.margin_account.key();
+    let signer = ctx.accounts.owner.key();
+    let (pages, accounts) = PositionPages::split(&margin_account, ctx.remaining_accounts)?;
+    let (mut isolation_configs, accounts) =
+        TokenIsolationConfig::split(&ctx.accounts.margin_account.load()?.airspace, &accounts)?;
+
+    let mut delegation = None;
+    let mut action = None;
+
+    let accounts = if ctx.accounts.margin_account.load()?.owner == signer {
+        accounts
+    } else if let (Some(found), accounts) =
+        MarginDelegate::split(&margin_account, &signer, &accounts)?
+    {
+        found.verify_active(Clock::get()?.unix_timestamp)?;
+        delegation = Some(found);
+        accounts
+    } else if let (Some(found), accounts) = ConditionalAction::split(&margin_account, &accounts)? {
+        let account = ctx.accounts.margin_account.load()?;
+        let timestamp = sys().unix_timestamp();
+
+        // a price trigger may be for a position stored in any of the pages
+        pages.verify_complete(&account)?;
+        pages.verify_trigger(&found, &account, timestamp)?;
+        found.verify_instruction(&ctx.accounts.adapter_program.key(), &data, &accounts)?;
+        action = Some(found);
+        accounts
+    } else {
+        msg!("signer {} is not the owner or a delegate", signer);
+        return err!(ErrorCode::UnauthorizedInvocation);
+    };
+    let before = match delegation {
+        Some(_) => PositionSnapshot::capture(&*ctx.accounts.margin_account.load()?, &pages)?,
+        None => vec![],
+    };
+
     emit!(events::AdapterInvokeBegin {
         margin_account: ctx.accounts.margin_account.key(),
         adapter_program: ctx.accounts.adapter_program.key(),
@@ -59,7 +113,8 @@ pub fn adapter_invoke_handler<'info>(
         &InvokeAdapter {
             margin_account: &ctx.accounts.margin_account,
             adapter_program: &ctx.accounts.adapter_program,
-            accounts: ctx.remaining_accounts,
+            accounts: &accounts,
+            pages: &pages,
             signed: true,
         },
         data,
@@ -67,10 +122,42 @@ pub fn adapter_invoke_handler<'info>(
 
     emit!(events::AdapterInvokeEnd {});
 
-    ctx.accounts
-        .margin_account
-        .load()?
-        .valuation(sys().unix_timestamp())?
+    if let Some(delegation) = &mut delegation {
+        let after = PositionSnapshot::capture(&*ctx.accounts.margin_account.load()?, &pages)?;
+
+        delegation.verify_changes(&before, &after)?;
+        delegation.exit(&crate::ID)?;
+    }
+
+    for isolation_config in &mut isolation_configs {
+        let amount =
+            isolation_config.record_debt(&mut *ctx.accounts.margin_account.load_mut()?, &pages)?;
+
+        emit!(events::IsolatedDebtRecorded {
+            margin_account,
+            isolation_config: isolation_config.key(),
+            amount,
+            total_isolated_debt: isolation_config.isolated_debt,
+        });
+
+        isolation_config.exit(&crate::ID)?;
+    }
+
+    if let Some(action) = action {
+        emit!(events::ConditionalActionClosed {
+            margin_account,
+            action: action.key(),
+            keeper: Some(signer),
+        });
+
+        action.close(ctx.accounts.owner.to_account_info())?;
+    }
+
+    pages
+        .valuation(
+            &*ctx.accounts.margin_account.load()?,
+            sys().unix_timestamp(),
+        )?
         .verify_healthy()?;
 
     Ok(())
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::prelude::*;
+
+use crate::{
+    events,
+    seeds::{LIQUIDATION_AUCTION_SEED, LIQUIDATION_CONFIG_SEED},
+    syscall::{sys, Sys},
+    ErrorCode, LiquidationAuction, LiquidationConfig, MarginAccount, PositionPages,
+};
+
+#[derive(Accounts)]
+pub struct AuctionBegin<'info> {
+    /// The account in need of liquidation
+    #[account(mut)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The address paying rent, which may be anyone
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The liquidation config for the airspace, which may not be initialized
+    /// CHECK: loaded in the handler, the default config is used if uninitialized
+    #[account(seeds = [
+                LIQUIDATION_CONFIG_SEED,
+                margin_account.load()?.airspace.as_ref()
+              ],
+              bump
+    )]
+    pub liquidation_config: AccountInfo<'info>,
+
+    /// Account to persist the state of the auction
+    #[account(
+        init,
+        seeds = [
+            LIQUIDATION_AUCTION_SEED,
+            margin_account.key().as_ref()
+        ],
+        bump,
+        payer = payer,
+        space = LiquidationAuction::SPACE,
+    )]
+    pub auction: Account<'info, LiquidationAuction>,
+
+    system_program: Program<'info, System>,
+    // Remaining accounts, which must include every page of the account:
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
+}
+
+pub fn auction_begin_handler(ctx: Context<AuctionBegin>) -> Result<()> {
+    let mut account = ctx.accounts.margin_account.load_mut()?;
+    let timestamp = sys().unix_timestamp();
+
+    let (pages, _) =
+        PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
+    pages.verify_complete(&account)?;
+
+    // verify the account is subject to liquidation
+    let valuation = pages.valuation(&account, timestamp)?;
+    valuation.verify_unhealthy()?;
+
+    if account.is_liquidating() {
+        return err!(ErrorCode::Liquidating);
+    }
+
+    // the auction acts as the liquidator, which prevents the owner from
+    // changing the account until the auction ends
+    account.start_liquidation(ctx.accounts.auction.key());
+
+    let config =
+        LiquidationConfig::load_or_default(&ctx.accounts.liquidation_config, account.airspace)?;
+
+    let auction = &mut ctx.accounts.auction;
+    **auction = LiquidationAuction::new(
+        ctx.accounts.margin_account.key(),
+        ctx.accounts.payer.key(),
+        Clock::get()?.unix_timestamp,
+        &config,
+        &account,
+        valuation.liabilities,
+    );
+
+    emit!(events::LiquidationAuctionBegun {
+        margin_account: auction.margin_account,
+        auction: auction.key(),
+        min_discount: auction.min_discount,
+        max_discount: auction.max_discount,
+        max_liabilities_repaid: auction.max_liabilities_repaid,
+        valuation_summary: valuation.into(),
+    });
+
+    Ok(())
+}
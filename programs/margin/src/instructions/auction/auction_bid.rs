@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program};
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use jet_program_common::Number128;
+
+use crate::{
+    events,
+    syscall::{sys, Sys},
+    AdapterConfig, ErrorCode, LiquidationAuction, MarginAccount, SignerSeeds, TokenKind,
+    MAX_PRICE_QUOTE_AGE,
+};
+
+#[derive(Accounts)]
+pub struct AuctionBid<'info> {
+    /// The bidder receiving collateral in exchange for repaying claims
+    pub bidder: Signer<'info>,
+
+    /// The account being liquidated
+    #[account(mut,
+              constraint = margin_account.load()?.liquidator == auction.key()
+                           @ ErrorCode::WrongLiquidationState
+    )]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The auction for the account
+    #[account(mut, has_one = margin_account @ ErrorCode::WrongLiquidationState)]
+    pub auction: Account<'info, LiquidationAuction>,
+
+    /// The token account for the claim being repaid
+    pub claim_account: Account<'info, TokenAccount>,
+
+    /// The token account for the collateral being paid to the bidder
+    #[account(mut)]
+    pub collateral_account: Account<'info, TokenAccount>,
+
+    /// The bidder's account receiving the collateral
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// The adapter program that repays the claim
+    /// CHECK:
+    pub adapter_program: AccountInfo<'info>,
+
+    /// The metadata about the adapter program
+    #[account(has_one = adapter_program,
+              constraint = adapter_config.airspace == margin_account.load()?.airspace
+                  @ ErrorCode::WrongAirspace)]
+    pub adapter_config: Account<'info, AdapterConfig>,
+
+    pub token_program: Program<'info, Token>,
+    // Remaining accounts are passed through to the adapter to repay the claim
+}
+
+/// Repay a claim in an auctioned account on behalf of a bidder, and pay them for it.
+///
+/// The adapter is invoked without the margin account signing, so the repayment must be
+/// authorized by the bidder, and only the reduction of the claim balance during the
+/// invocation is paid for. Claims repaid in any other way are not paid for by any bid.
+pub fn auction_bid_handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, AuctionBid<'info>>,
+    data: Vec<u8>,
+) -> Result<()> {
+    let timestamp = sys().unix_timestamp();
+    let claim_mint = ctx.accounts.claim_account.mint;
+
+    ctx.accounts
+        .auction
+        .record_claim_balance(&claim_mint, ctx.accounts.claim_account.amount)?;
+
+    repay_claim(&ctx, data)?;
+    ctx.accounts.claim_account.reload()?;
+
+    let auction = &mut ctx.accounts.auction;
+    let claim_account = &ctx.accounts.claim_account;
+    let collateral_account = &ctx.accounts.collateral_account;
+
+    let mut account = ctx.accounts.margin_account.load_mut()?;
+
+    account.set_position_balance(
+        &claim_account.mint,
+        &claim_account.key(),
+        claim_account.amount,
+        timestamp,
+    )?;
+
+    // fails if any claims have stale prices
+    account.valuation(timestamp)?;
+
+    let claim_repaid = auction.record_claim_balance(&claim_account.mint, claim_account.amount)?;
+
+    if claim_repaid == 0 {
+        msg!("the adapter did not repay the claim");
+        return err!(ErrorCode::InvalidAuctionBid);
+    }
+
+    let claim = account.get_position(&claim_account.mint).unwrap();
+    let repaid_value = Number128::from_decimal(claim_repaid, claim.exponent)
+        * Number128::from_decimal(claim.price.value, claim.price.exponent);
+
+    auction.add_liabilities_repaid(repaid_value)?;
+
+    let collateral = match account.get_position(&collateral_account.mint) {
+        Some(p) if p.address == collateral_account.key() => p,
+        _ => return err!(ErrorCode::PositionNotRegistered),
+    };
+
+    // only collateral custodied by the margin account can be paid out
+    if collateral.kind() != TokenKind::Collateral {
+        msg!(
+            "collateral position {} is not transferable",
+            collateral.token
+        );
+        return err!(ErrorCode::InvalidAuctionBid);
+    }
+    if !collateral.price.is_valid() {
+        return err!(ErrorCode::InvalidPrice);
+    }
+    if timestamp - collateral.price.timestamp > MAX_PRICE_QUOTE_AGE {
+        return err!(ErrorCode::OutdatedPrice);
+    }
+
+    let discount = auction.discount(Clock::get()?.unix_timestamp);
+    let payout_value = repaid_value * (Number128::ONE + discount);
+    let collateral_price =
+        Number128::from_decimal(collateral.price.value, collateral.price.exponent);
+    let collateral_paid = std::cmp::min(
+        (payout_value / collateral_price).as_u64(collateral.exponent),
+        collateral_account.amount,
+    );
+
+    let seeds = account.signer_seeds_owned();
+    drop(account);
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.collateral_account.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.margin_account.to_account_info(),
+            },
+            &[&seeds.signer_seeds()],
+        ),
+        collateral_paid,
+    )?;
+
+    let collateral_account = &mut ctx.accounts.collateral_account;
+    collateral_account.reload()?;
+
+    ctx.accounts
+        .margin_account
+        .load_mut()?
+        .set_position_balance(
+            &collateral_account.mint,
+            &collateral_account.key(),
+            collateral_account.amount,
+            timestamp,
+        )?;
+
+    emit!(events::LiquidationAuctionBid {
+        margin_account: ctx.accounts.margin_account.key(),
+        auction: ctx.accounts.auction.key(),
+        bidder: ctx.accounts.bidder.key(),
+        claim_mint: ctx.accounts.claim_account.mint,
+        claim_repaid,
+        collateral_mint: collateral_account.mint,
+        collateral_paid,
+        discount: discount.to_i128(),
+    });
+
+    Ok(())
+}
+
+fn repay_claim<'info>(
+    ctx: &Context<'_, '_, '_, 'info, AuctionBid<'info>>,
+    data: Vec<u8>,
+) -> Result<()> {
+    let margin_account = ctx.accounts.margin_account.key();
+
+    let accounts = ctx
+        .remaining_accounts
+        .iter()
+        .map(|info| {
+            // the bid may only repay claims with the bidder's authority
+            if info.key() == margin_account {
+                msg!("the margin account cannot authorize the repayment");
+                return err!(ErrorCode::InvalidAuctionBid);
+            }
+
+            Ok(AccountMeta {
+                pubkey: info.key(),
+                is_signer: info.is_signer,
+                is_writable: info.is_writable,
+            })
+        })
+        .collect::<Result<Vec<AccountMeta>>>()?;
+
+    let instruction = Instruction {
+        program_id: ctx.accounts.adapter_program.key(),
+        accounts,
+        data,
+    };
+
+    program::invoke(&instruction, ctx.remaining_accounts)?;
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::prelude::*;
+
+use crate::{
+    events,
+    syscall::{sys, Sys},
+    ErrorCode, LiquidationAuction, MarginAccount, PositionPages, LIQUIDATION_AUCTION_TIMEOUT,
+};
+
+#[derive(Accounts)]
+pub struct AuctionEnd<'info> {
+    /// The account being liquidated
+    #[account(mut,
+              constraint = margin_account.load()?.liquidator == auction.key()
+                           @ ErrorCode::WrongLiquidationState
+    )]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The address that paid rent for the auction
+    /// CHECK: verified by the auction
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// The auction to end
+    #[account(mut,
+              has_one = margin_account @ ErrorCode::WrongLiquidationState,
+              has_one = payer,
+              close = payer,
+    )]
+    pub auction: Account<'info, LiquidationAuction>,
+    // Optional accounts (remaining accounts):
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
+}
+
+/// End an auction once the account is healthy again, or after the timeout
+pub fn auction_end_handler(ctx: Context<AuctionEnd>) -> Result<()> {
+    let mut account = ctx.accounts.margin_account.load_mut()?;
+    let auction = &ctx.accounts.auction;
+
+    let timed_out =
+        Clock::get()?.unix_timestamp - auction.start_time >= LIQUIDATION_AUCTION_TIMEOUT;
+
+    if !timed_out {
+        let (pages, _) =
+            PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
+        let valuation = pages.valuation(&account, sys().unix_timestamp())?;
+
+        if valuation.verify_healthy().is_err() {
+            msg!(
+                "the account must be healthy to end the auction before the timeout of {} seconds",
+                LIQUIDATION_AUCTION_TIMEOUT
+            );
+            return err!(ErrorCode::LiquidationAuctionActive);
+        }
+    }
+
+    account.end_liquidation();
+
+    emit!(events::LiquidationAuctionEnded {
+        margin_account: ctx.accounts.margin_account.key(),
+        auction: auction.key(),
+        timed_out,
+    });
+
+    Ok(())
+}
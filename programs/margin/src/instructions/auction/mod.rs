@@ -0,0 +1,7 @@
+mod auction_begin;
+mod auction_bid;
+mod auction_end;
+
+pub use auction_begin::*;
+pub use auction_bid::*;
+pub use auction_end::*;
@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use crate::{events::ConditionalActionClosed, ConditionalAction, MarginAccount};
+
+#[derive(Accounts)]
+pub struct CancelConditionalAction<'info> {
+    /// The owner of the margin account
+    pub owner: Signer<'info>,
+
+    /// The receiver for the rent and tip released
+    /// CHECK:
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+
+    /// The margin account the action is for
+    #[account(has_one = owner)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The action to cancel
+    #[account(mut,
+              has_one = margin_account,
+              close = receiver,
+    )]
+    pub action: Account<'info, ConditionalAction>,
+}
+
+pub fn cancel_conditional_action_handler(ctx: Context<CancelConditionalAction>) -> Result<()> {
+    emit!(ConditionalActionClosed {
+        margin_account: ctx.accounts.margin_account.key(),
+        action: ctx.accounts.action.key(),
+        keeper: None,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+
+use crate::{
+    events::ConditionalActionCreated, seeds::CONDITIONAL_ACTION_SEED, ActionTrigger,
+    ConditionalAction, MarginAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(seed: u16)]
+pub struct CreateConditionalAction<'info> {
+    /// The owner of the margin account
+    pub owner: Signer<'info>,
+
+    /// The payer for the rent and the keeper tip
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The margin account the action is for
+    #[account(has_one = owner)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The account storing the action
+    #[account(init,
+              seeds = [
+                CONDITIONAL_ACTION_SEED,
+                margin_account.key().as_ref(),
+                seed.to_le_bytes().as_ref()
+              ],
+              bump,
+              payer = payer,
+              space = ConditionalAction::SPACE,
+    )]
+    pub action: Account<'info, ConditionalAction>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_conditional_action_handler(
+    ctx: Context<CreateConditionalAction>,
+    seed: u16,
+    trigger: ActionTrigger,
+    adapter_program: Pubkey,
+    instruction_hash: [u8; 32],
+    tip: u64,
+) -> Result<()> {
+    let action = &mut ctx.accounts.action;
+
+    action.margin_account = ctx.accounts.margin_account.key();
+    action.seed = seed;
+    action.trigger = trigger;
+    action.adapter_program = adapter_program;
+    action.instruction_hash = instruction_hash;
+    action.tip = tip;
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.payer.to_account_info(),
+                to: action.to_account_info(),
+            },
+        ),
+        tip,
+    )?;
+
+    emit!(ConditionalActionCreated {
+        margin_account: action.margin_account,
+        action: action.key(),
+        trigger,
+        adapter_program,
+        tip,
+    });
+
+    Ok(())
+}
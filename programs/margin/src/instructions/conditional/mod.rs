@@ -0,0 +1,5 @@
+mod cancel_conditional_action;
+mod create_conditional_action;
+
+pub use cancel_conditional_action::*;
+pub use create_conditional_action::*;
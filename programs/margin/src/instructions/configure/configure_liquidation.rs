@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::{prelude::*, AccountsClose};
+
+use jet_airspace::state::Airspace;
+use jet_program_common::serialization::StorageSpace;
+
+use crate::{events::LiquidationConfigured, seeds::LIQUIDATION_CONFIG_SEED, LiquidationConfig};
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default, Eq, PartialEq, Clone)]
+pub struct LiquidationConfigUpdate {
+    /// The maximum fraction (in basis points) of an account's liabilities that may be
+    /// repaid during a single liquidation
+    pub close_factor: u16,
+
+    /// The liquidator bonus (in basis points) when the account is only just unhealthy
+    pub min_bonus: u16,
+
+    /// The liquidator bonus (in basis points) when the account has no effective collateral
+    pub max_bonus: u16,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureLiquidation<'info> {
+    /// The authority allowed to make changes to configuration
+    pub authority: Signer<'info>,
+
+    /// The airspace being modified
+    #[account(has_one = authority)]
+    pub airspace: Account<'info, Airspace>,
+
+    /// The payer for any rent costs, if required
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The config account to be modified
+    #[account(init_if_needed,
+              seeds = [
+                LIQUIDATION_CONFIG_SEED,
+                airspace.key().as_ref()
+              ],
+              bump,
+              payer = payer,
+              space = LiquidationConfig::SPACE,
+    )]
+    pub liquidation_config: Account<'info, LiquidationConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn configure_liquidation_handler(
+    ctx: Context<ConfigureLiquidation>,
+    update: Option<LiquidationConfigUpdate>,
+) -> Result<()> {
+    let config = &mut ctx.accounts.liquidation_config;
+
+    emit!(LiquidationConfigured {
+        airspace: ctx.accounts.airspace.key(),
+        update: update.clone(),
+    });
+
+    let update = match update {
+        Some(update) => update,
+        None => return config.close(ctx.accounts.payer.to_account_info()),
+    };
+
+    config.airspace = ctx.accounts.airspace.key();
+    config.close_factor = update.close_factor;
+    config.min_bonus = update.min_bonus;
+    config.max_bonus = update.max_bonus;
+
+    config.validate()
+}
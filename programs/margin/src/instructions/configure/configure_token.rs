@@ -89,6 +89,14 @@ pub fn configure_token_handler(
 
     let updated_config = match updated_config {
         Some(update) => update,
+        None if config.has_oracle_set => {
+            msg!("the oracle set must be removed before the token config");
+            return err!(ErrorCode::InvalidConfig);
+        }
+        None if config.is_isolated => {
+            msg!("the isolation config must be removed before the token config");
+            return err!(ErrorCode::InvalidConfig);
+        }
         None => return config.close(ctx.accounts.payer.to_account_info()),
     };
 
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::{prelude::*, AccountsClose};
+
+use jet_airspace::state::Airspace;
+
+use crate::{
+    events::TokenIsolationConfigured, seeds::TOKEN_ISOLATION_SEED, ErrorCode, TokenConfig,
+    TokenIsolation, TokenIsolationConfig,
+};
+
+#[derive(Accounts)]
+pub struct ConfigureTokenIsolation<'info> {
+    /// The authority allowed to make changes to configuration
+    pub authority: Signer<'info>,
+
+    /// The airspace being modified
+    #[account(has_one = authority)]
+    pub airspace: Account<'info, Airspace>,
+
+    /// The payer for any rent costs, if required
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The config for the token being isolated
+    #[account(mut,
+              constraint = token_config.airspace == airspace.key() @ ErrorCode::WrongAirspace
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// The isolation config account to be modified
+    #[account(init_if_needed,
+              seeds = [
+                TOKEN_ISOLATION_SEED,
+                token_config.key().as_ref()
+              ],
+              bump,
+              payer = payer,
+              space = TokenIsolationConfig::SPACE,
+    )]
+    pub isolation_config: Account<'info, TokenIsolationConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn configure_token_isolation_handler(
+    ctx: Context<ConfigureTokenIsolation>,
+    update: Option<TokenIsolation>,
+) -> Result<()> {
+    let config = &mut ctx.accounts.token_config;
+    let isolation_config = &mut ctx.accounts.isolation_config;
+
+    emit!(TokenIsolationConfigured {
+        airspace: ctx.accounts.airspace.key(),
+        token_config: config.key(),
+        update,
+    });
+
+    let update = match update {
+        Some(update) => update,
+        None if isolation_config.isolated_debt > 0 => {
+            msg!("the debt recorded against the ceiling must be released first");
+            return err!(ErrorCode::IsolatedDebtReserved);
+        }
+        None => {
+            config.is_isolated = false;
+            return isolation_config.close(ctx.accounts.payer.to_account_info());
+        }
+    };
+
+    update.validate()?;
+
+    isolation_config.airspace = config.airspace;
+    isolation_config.mint = config.mint;
+    isolation_config.isolation = update;
+
+    config.is_isolated = true;
+
+    Ok(())
+}
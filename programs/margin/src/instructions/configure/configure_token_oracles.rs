@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::{prelude::*, AccountsClose};
+
+use jet_airspace::state::Airspace;
+
+use crate::{
+    events::TokenOraclesConfigured, seeds::TOKEN_ORACLE_SET_SEED, ErrorCode, OracleAggregation,
+    TokenConfig, TokenOracle, TokenOracleSet,
+};
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Eq, PartialEq, Clone)]
+pub struct TokenOracleSetUpdate {
+    /// How the prices from each oracle are combined
+    pub aggregation: OracleAggregation,
+
+    /// The maximum deviation (in basis points) allowed between oracle prices
+    pub max_deviation: u16,
+
+    /// The oracles to use after the oracle in the token config, in order of preference
+    pub fallbacks: Vec<TokenOracle>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureTokenOracles<'info> {
+    /// The authority allowed to make changes to configuration
+    pub authority: Signer<'info>,
+
+    /// The airspace being modified
+    #[account(has_one = authority)]
+    pub airspace: Account<'info, Airspace>,
+
+    /// The payer for any rent costs, if required
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The config for the token being priced
+    #[account(mut,
+              constraint = token_config.airspace == airspace.key() @ ErrorCode::WrongAirspace
+    )]
+    pub token_config: Account<'info, TokenConfig>,
+
+    /// The oracle set account to be modified
+    #[account(init_if_needed,
+              seeds = [
+                TOKEN_ORACLE_SET_SEED,
+                token_config.key().as_ref()
+              ],
+              bump,
+              payer = payer,
+              space = TokenOracleSet::SPACE,
+    )]
+    pub oracle_set: Account<'info, TokenOracleSet>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn configure_token_oracles_handler(
+    ctx: Context<ConfigureTokenOracles>,
+    update: Option<TokenOracleSetUpdate>,
+) -> Result<()> {
+    let config = &mut ctx.accounts.token_config;
+    let oracle_set = &mut ctx.accounts.oracle_set;
+
+    emit!(TokenOraclesConfigured {
+        airspace: ctx.accounts.airspace.key(),
+        token_config: config.key(),
+        update: update.clone(),
+    });
+
+    let update = match update {
+        Some(update) => update,
+        None => {
+            config.has_oracle_set = false;
+            return oracle_set.close(ctx.accounts.payer.to_account_info());
+        }
+    };
+
+    let primary = match config.oracle() {
+        Some(oracle) => oracle,
+        None => {
+            msg!("only tokens priced by an oracle can have fallback oracles");
+            return err!(ErrorCode::InvalidOracle);
+        }
+    };
+
+    oracle_set.token_config = config.key();
+    oracle_set.aggregation = update.aggregation;
+    oracle_set.max_deviation = update.max_deviation;
+    oracle_set.fallbacks = update.fallbacks;
+
+    oracle_set.validate(&primary)?;
+
+    config.has_oracle_set = true;
+
+    Ok(())
+}
@@ -1,9 +1,15 @@
 mod configure_account_airspace;
 mod configure_adapter;
+mod configure_liquidation;
 mod configure_permit;
 mod configure_token;
+mod configure_token_isolation;
+mod configure_token_oracles;
 
 pub use configure_account_airspace::*;
 pub use configure_adapter::*;
+pub use configure_liquidation::*;
 pub use configure_permit::*;
 pub use configure_token::*;
+pub use configure_token_isolation::*;
+pub use configure_token_oracles::*;
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::{prelude::*, AccountsClose};
+use solana_program::clock::UnixTimestamp;
+
+use crate::{
+    events::DelegateConfigured, seeds::MARGIN_DELEGATE_SEED, DelegatePermissions, MarginAccount,
+    MarginDelegate,
+};
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default, Eq, PartialEq, Clone)]
+pub struct MarginDelegateUpdate {
+    /// The kinds of changes the delegate may make to the account
+    pub permissions: DelegatePermissions,
+
+    /// The time after which the delegate may no longer sign, or zero if it never expires
+    pub expires_at: UnixTimestamp,
+
+    /// The loss in value (in basis points) tolerated when exchanging positions
+    pub max_slippage: u16,
+
+    /// Limits on how much the delegate may reduce the balance of a position
+    pub limits: Vec<DelegateMintLimitUpdate>,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct DelegateMintLimitUpdate {
+    /// The token for the position
+    pub mint: Pubkey,
+
+    /// The maximum total reduction in the position balance
+    pub limit: u64,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureDelegate<'info> {
+    /// The owner of the margin account
+    pub owner: Signer<'info>,
+
+    /// The payer for any rent costs, if required
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The margin account being delegated
+    #[account(has_one = owner)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The address being authorized
+    /// CHECK:
+    pub delegate: AccountInfo<'info>,
+
+    /// The account storing the delegation
+    #[account(init_if_needed,
+              seeds = [
+                MARGIN_DELEGATE_SEED,
+                margin_account.key().as_ref(),
+                delegate.key().as_ref()
+              ],
+              bump,
+              payer = payer,
+              space = MarginDelegate::SPACE,
+    )]
+    pub delegation: Account<'info, MarginDelegate>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn configure_delegate_handler(
+    ctx: Context<ConfigureDelegate>,
+    update: Option<MarginDelegateUpdate>,
+) -> Result<()> {
+    let delegation = &mut ctx.accounts.delegation;
+
+    emit!(DelegateConfigured {
+        margin_account: ctx.accounts.margin_account.key(),
+        delegate: ctx.accounts.delegate.key(),
+        update: update.clone(),
+    });
+
+    let update = match update {
+        Some(update) => update,
+        None => return delegation.close(ctx.accounts.payer.to_account_info()),
+    };
+
+    delegation.margin_account = ctx.accounts.margin_account.key();
+    delegation.delegate = ctx.accounts.delegate.key();
+    delegation.update(&update)
+}
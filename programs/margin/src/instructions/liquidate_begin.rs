@@ -17,13 +17,12 @@
 
 use anchor_lang::prelude::*;
 
-use jet_program_common::Number128;
-
 use crate::{
     events,
+    seeds::LIQUIDATION_CONFIG_SEED,
     syscall::{sys, Sys},
-    ErrorCode, Liquidation, LiquidationState, MarginAccount, Permissions, Permit,
-    LIQUIDATION_MAX_TOTAL_EQUITY_LOSS_BPS,
+    ErrorCode, Liquidation, LiquidationConfig, LiquidationState, MarginAccount, Permissions,
+    Permit, PositionPages,
 };
 
 #[derive(Accounts)]
@@ -47,6 +46,16 @@ pub struct LiquidateBegin<'info> {
     )]
     pub permit: Account<'info, Permit>,
 
+    /// The liquidation config for the airspace, which may not be initialized
+    /// CHECK: loaded in the handler, the default config is used if uninitialized
+    #[account(seeds = [
+                LIQUIDATION_CONFIG_SEED,
+                margin_account.load()?.airspace.as_ref()
+              ],
+              bump
+    )]
+    pub liquidation_config: AccountInfo<'info>,
+
     /// Account to persist the state of the liquidation
     #[account(
         init,
@@ -62,15 +71,21 @@ pub struct LiquidateBegin<'info> {
     pub liquidation: AccountLoader<'info, LiquidationState>,
 
     system_program: Program<'info, System>,
+    // Remaining accounts, which must include every page of the account:
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
 }
 
 pub fn liquidate_begin_handler(ctx: Context<LiquidateBegin>) -> Result<()> {
     let liquidator = ctx.accounts.liquidator.key();
     let mut account = ctx.accounts.margin_account.load_mut()?;
     let timestamp = sys().unix_timestamp();
+    let (pages, _) =
+        PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
+    pages.verify_complete(&account)?;
 
     // verify the account is subject to liquidation
-    account.valuation(timestamp)?.verify_unhealthy()?;
+    pages.valuation(&account, timestamp)?.verify_unhealthy()?;
 
     // verify not already being liquidated
     match account.liquidator {
@@ -91,15 +106,23 @@ pub fn liquidate_begin_handler(ctx: Context<LiquidateBegin>) -> Result<()> {
         }
     }
 
-    let valuation = account.valuation(timestamp)?;
+    let valuation = pages.valuation(&account, timestamp)?;
+    let config =
+        LiquidationConfig::load_or_default(&ctx.accounts.liquidation_config, account.airspace)?;
 
-    let max_equity_loss =
-        valuation.liabilities * Number128::from_bps(LIQUIDATION_MAX_TOTAL_EQUITY_LOSS_BPS);
+    // the liquidator is paid through the equity lost from the account, in proportion to
+    // the liabilities repaid and how far the account is below the required collateral
+    let max_liabilities_repaid = config.max_liabilities_repaid(valuation.liabilities);
+    let max_equity_loss = max_liabilities_repaid * config.bonus(valuation.collateral_shortfall());
 
     let liquidation_state = LiquidationState {
         liquidator,
         margin_account: ctx.accounts.margin_account.key(),
-        state: Liquidation::new(Clock::get()?.unix_timestamp, max_equity_loss),
+        state: Liquidation::new(
+            Clock::get()?.unix_timestamp,
+            max_equity_loss,
+            max_liabilities_repaid,
+        ),
     };
     *ctx.accounts.liquidation.load_init()? = liquidation_state;
 
@@ -20,7 +20,8 @@ use anchor_lang::prelude::*;
 use crate::adapter::{self, InvokeAdapter};
 use crate::syscall::{sys, Sys};
 use crate::{
-    events, AdapterConfig, ErrorCode, Liquidation, LiquidationState, MarginAccount, Valuation,
+    events, AdapterConfig, ErrorCode, Liquidation, LiquidationState, MarginAccount, PositionPages,
+    Valuation,
 };
 
 #[derive(Accounts)]
@@ -55,7 +56,8 @@ pub fn liquidator_invoke_handler<'info>(
     data: Vec<u8>,
 ) -> Result<()> {
     let margin_account = &ctx.accounts.margin_account;
-    let start_value = margin_account.load()?.valuation(sys().unix_timestamp())?;
+    let (pages, accounts) = PositionPages::split(&margin_account.key(), ctx.remaining_accounts)?;
+    let start_value = pages.valuation(&*margin_account.load()?, sys().unix_timestamp())?;
 
     emit!(events::LiquidatorInvokeBegin {
         margin_account: ctx.accounts.margin_account.key(),
@@ -67,7 +69,8 @@ pub fn liquidator_invoke_handler<'info>(
         &InvokeAdapter {
             margin_account: &ctx.accounts.margin_account,
             adapter_program: &ctx.accounts.adapter_program,
-            accounts: ctx.remaining_accounts,
+            accounts: &accounts,
+            pages: &pages,
             signed: true,
         },
         data,
@@ -76,6 +79,7 @@ pub fn liquidator_invoke_handler<'info>(
     let liquidation = &mut ctx.accounts.liquidation.load_mut()?.state;
     let end_value = update_and_verify_liquidation(
         &*ctx.accounts.margin_account.load()?,
+        &pages,
         liquidation,
         start_value,
     )?;
@@ -90,10 +94,11 @@ pub fn liquidator_invoke_handler<'info>(
 
 fn update_and_verify_liquidation(
     margin_account: &MarginAccount,
+    pages: &PositionPages,
     liquidation: &mut Liquidation,
     start_value: Valuation,
 ) -> Result<Valuation> {
-    let end_value = margin_account.valuation(sys().unix_timestamp())?;
+    let end_value = pages.valuation(margin_account, sys().unix_timestamp())?;
 
     *liquidation.equity_loss_mut() += start_value.equity - end_value.equity;
 
@@ -106,5 +111,27 @@ fn update_and_verify_liquidation(
         return err!(ErrorCode::LiquidationLostValue);
     }
 
+    *liquidation.liabilities_repaid_mut() += start_value.liabilities - end_value.liabilities;
+
+    // the bonus is only paid in proportion to the liabilities actually repaid
+    if liquidation.equity_loss() > &liquidation.earned_equity_loss() {
+        msg!(
+            "Illegal liquidation: net loss of {} equity which exceeds the bonus of {} for repaying {} of liabilities",
+            liquidation.equity_loss(),
+            liquidation.earned_equity_loss(),
+            liquidation.liabilities_repaid()
+        );
+        return err!(ErrorCode::LiquidationLostValue);
+    }
+
+    if liquidation.liabilities_repaid() > &liquidation.max_liabilities_repaid() {
+        msg!(
+            "Illegal liquidation: repaid {} of liabilities which exceeds the close factor limit of {}",
+            liquidation.liabilities_repaid(),
+            liquidation.max_liabilities_repaid()
+        );
+        return err!(ErrorCode::LiquidationExceedsCloseFactor);
+    }
+
     Ok(end_value)
 }
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::prelude::*;
+
+use crate::{
+    seeds::POSITION_PAGE_SEED, ErrorCode, MarginAccount, MarginPositionPage,
+    MARGIN_ACCOUNT_VERSION, MAX_POSITION_PAGES,
+};
+
+#[derive(Accounts)]
+pub struct AddPositionPage<'info> {
+    /// The owner of the margin account
+    pub owner: Signer<'info>,
+
+    /// The address paying for rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The margin account to add a page to
+    #[account(mut, has_one = owner)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The new page
+    #[account(init,
+              seeds = [
+                POSITION_PAGE_SEED,
+                margin_account.key().as_ref(),
+                &[margin_account.load()?.position_pages]
+              ],
+              bump,
+              payer = payer,
+              space = 8 + std::mem::size_of::<MarginPositionPage>(),
+    )]
+    pub page: AccountLoader<'info, MarginPositionPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn add_position_page_handler(ctx: Context<AddPositionPage>) -> Result<()> {
+    let mut account = ctx.accounts.margin_account.load_mut()?;
+    let mut page = ctx.accounts.page.load_init()?;
+
+    if account.position_pages >= MAX_POSITION_PAGES {
+        msg!(
+            "the account already has {} position pages",
+            MAX_POSITION_PAGES
+        );
+        return err!(ErrorCode::MaxPositions);
+    }
+
+    page.margin_account = ctx.accounts.margin_account.key();
+    page.page = account.position_pages;
+
+    // version 1 accounts only need their version bumped to support pages, since
+    // the page count was previously reserved space
+    account.position_pages += 1;
+    account.version = MARGIN_ACCOUNT_VERSION;
+
+    Ok(())
+}
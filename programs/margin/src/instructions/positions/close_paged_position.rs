@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount};
+
+use crate::{Approver, ErrorCode, MarginAccount, MarginPositionPage, SignerSeeds};
+
+#[derive(Accounts)]
+pub struct ClosePagedPosition<'info> {
+    /// The authority that can change the margin account
+    pub authority: Signer<'info>,
+
+    /// The receiver for the rent released
+    /// CHECK:
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+
+    /// The margin account with the position to close
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The page storing the position
+    #[account(mut,
+              constraint = page.load()?.margin_account == margin_account.key()
+                           @ ErrorCode::InvalidPositionPages
+    )]
+    pub page: AccountLoader<'info, MarginPositionPage>,
+
+    /// The mint for the position token being deregistered
+    pub position_token_mint: Account<'info, Mint>,
+
+    /// The token account for the position being closed
+    #[account(mut)]
+    pub token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn close_paged_position_handler(ctx: Context<ClosePagedPosition>) -> Result<()> {
+    let account = ctx.accounts.margin_account.load()?;
+    account.verify_authority(ctx.accounts.authority.key())?;
+
+    ctx.accounts.page.load_mut()?.unregister_position(
+        &ctx.accounts.position_token_mint.key(),
+        &ctx.accounts.token_account.key(),
+        &[Approver::MarginAccountAuthority],
+    )?;
+
+    token::close_account(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                authority: ctx.accounts.margin_account.to_account_info(),
+                destination: ctx.accounts.receiver.to_account_info(),
+            },
+        )
+        .with_signer(&[&account.signer_seeds()]),
+    )?;
+
+    Ok(())
+}
@@ -21,7 +21,9 @@ use anchor_spl::{
     token::{Mint, Token, TokenAccount},
 };
 
-use crate::{Approver, ErrorCode, MarginAccount, PositionConfigUpdate, TokenConfig};
+use crate::{
+    Approver, ErrorCode, MarginAccount, PositionConfigUpdate, TokenConfig, TokenIsolationConfig,
+};
 
 #[derive(Accounts)]
 pub struct CreateDepositPosition<'info> {
@@ -56,10 +58,14 @@ pub struct CreateDepositPosition<'info> {
     pub token_program: Program<'info, Token>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
+    // Remaining accounts, which must include the isolation config when the token is isolated:
+    //
+    // pub isolation_config: Account<'info, TokenIsolationConfig>,
 }
 
 pub fn create_deposit_position_handler(ctx: Context<CreateDepositPosition>) -> Result<()> {
     let config = &ctx.accounts.config;
+    let isolation = TokenIsolationConfig::find(config, ctx.remaining_accounts)?;
     let mut account = ctx.accounts.margin_account.load_mut()?;
     let position_token = &ctx.accounts.mint;
     let address = ctx.accounts.token_account.key();
@@ -68,6 +74,7 @@ pub fn create_deposit_position_handler(ctx: Context<CreateDepositPosition>) -> R
     account.register_position(
         PositionConfigUpdate::new_from_config(
             config,
+            isolation,
             position_token.decimals,
             address,
             config.adapter_program().unwrap_or_default(),
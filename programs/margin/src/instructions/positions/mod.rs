@@ -1,9 +1,17 @@
+mod add_position_page;
+mod close_paged_position;
 mod create_deposit_position;
 mod refresh_deposit_position;
 mod refresh_position_config;
+mod register_paged_position;
+mod release_isolated_debt;
 mod transfer_deposit;
 
+pub use add_position_page::*;
+pub use close_paged_position::*;
 pub use create_deposit_position::*;
 pub use refresh_deposit_position::*;
 pub use refresh_position_config::*;
+pub use register_paged_position::*;
+pub use release_isolated_debt::*;
 pub use transfer_deposit::*;
@@ -21,7 +21,7 @@ use solana_program::clock::UnixTimestamp;
 
 use crate::{
     syscall::{sys, Sys},
-    ErrorCode, MarginAccount, PriceChangeInfo, TokenConfig, TokenOracle,
+    ErrorCode, MarginAccount, PositionPages, TokenConfig, TokenOracleSet,
 };
 
 #[derive(Accounts)]
@@ -36,65 +36,105 @@ pub struct RefreshDepositPosition<'info> {
 
     /// The oracle for the token
     pub price_oracle: AccountInfo<'info>,
-    // Optional account (remaining accounts)
+    // Optional accounts (remaining accounts), in order:
+    //
+    // When the config has an oracle set:
+    // pub oracle_set: Account<'info, TokenOracleSet>,
+    // pub fallback_oracles: [AccountInfo<'info>; oracle_set.fallbacks.len()],
+    //
     // pub position_token_account: Account<'info, TokenAccount>,
+    //
+    // When the position is stored in a page, in any order with the token account:
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
 }
 
 pub fn refresh_deposit_position_handler(ctx: Context<RefreshDepositPosition>) -> Result<()> {
     let mut margin_account = ctx.accounts.margin_account.load_mut()?;
     let config = &ctx.accounts.config;
+    let timestamp = sys().unix_timestamp() as UnixTimestamp;
+    let mut remaining_accounts = ctx.remaining_accounts.iter();
 
-    match config.oracle() {
-        Some(TokenOracle::Pyth { price, .. }) => {
-            let price_oracle_key = ctx.accounts.price_oracle.key();
-            if price_oracle_key != price {
-                msg!("expected oracle {} but got {}", price, price_oracle_key);
-                return err!(ErrorCode::InvalidOracle);
-            }
-
-            let price_feed = match pyth_sdk_solana::load_price_feed_from_account_info(
-                &ctx.accounts.price_oracle,
-            ) {
-                Ok(pf) => pf,
-                Err(e) => {
-                    msg!("the oracle account is not valid: {:?}", e);
+    let oracle = match config.oracle() {
+        Some(oracle) => oracle,
+        None => return err!(ErrorCode::InvalidOracle),
+    };
+
+    // Price will be checked by the margin program
+    let mut price_info = oracle
+        .load_price(&ctx.accounts.price_oracle)?
+        .try_into(timestamp)?;
+
+    if config.has_oracle_set {
+        let oracle_set = load_oracle_set(config, remaining_accounts.next())?;
+        let mut prices = vec![price_info];
+
+        for fallback in &oracle_set.fallbacks {
+            let oracle_info = match remaining_accounts.next() {
+                Some(info) => info,
+                None => {
+                    msg!("missing fallback oracle {}", fallback.price_account());
                     return err!(ErrorCode::InvalidOracle);
                 }
             };
 
-            // Price will be checked by the margin program
-            let price_obj = price_feed.get_price_unchecked();
-            let ema_obj = price_feed.get_ema_price_unchecked();
+            prices.push(fallback.load_price(oracle_info)?.try_into(timestamp)?);
+        }
 
-            let price_info = PriceChangeInfo {
-                value: price_obj.price,
-                confidence: price_obj.conf,
-                twap: ema_obj.price,
-                exponent: price_obj.expo,
-                publish_time: price_obj.publish_time,
-            };
+        price_info = oracle_set.aggregate(&prices);
+    }
 
-            if let Some(position_token_account) = ctx.remaining_accounts.first() {
-                let balance = token::accessor::amount(position_token_account)?;
+    let (pages, others) = PositionPages::split(
+        &ctx.accounts.margin_account.key(),
+        remaining_accounts.as_slice(),
+    )?;
 
-                margin_account.set_position_balance(
-                    &config.mint,
-                    &position_token_account.key(),
-                    balance,
-                    sys().unix_timestamp(),
-                )?;
-            }
+    if let Some(position_token_account) = others.first() {
+        let balance = token::accessor::amount(position_token_account)?;
 
-            margin_account.set_position_price(
+        if margin_account.get_position(&config.mint).is_some() {
+            margin_account.set_position_balance(
+                &config.mint,
+                &position_token_account.key(),
+                balance,
+                sys().unix_timestamp(),
+            )?;
+        } else {
+            pages.set_position_balance(
                 &config.mint,
-                &price_info.try_into(sys().unix_timestamp() as UnixTimestamp)?,
+                &position_token_account.key(),
+                balance,
+                sys().unix_timestamp(),
             )?;
         }
+    }
 
-        None => {
+    let in_pages = pages.set_position_price(&config.mint, &price_info)?;
+
+    if margin_account.get_position(&config.mint).is_some() || !in_pages {
+        margin_account.set_position_price(&config.mint, &price_info)?;
+    }
+
+    Ok(())
+}
+
+fn load_oracle_set(
+    config: &Account<TokenConfig>,
+    info: Option<&AccountInfo>,
+) -> Result<TokenOracleSet> {
+    let info = match info {
+        Some(info) if info.owner == &crate::ID => info,
+        _ => {
+            msg!("the oracle set for the token must be provided");
             return err!(ErrorCode::InvalidOracle);
         }
+    };
+
+    let oracle_set = TokenOracleSet::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+    if oracle_set.token_config != config.key() {
+        msg!("the oracle set is for a different token");
+        return err!(ErrorCode::InvalidOracle);
     }
 
-    Ok(())
+    Ok(oracle_set)
 }
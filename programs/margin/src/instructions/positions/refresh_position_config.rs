@@ -17,7 +17,7 @@
 
 use anchor_lang::prelude::*;
 
-use crate::{ErrorCode, MarginAccount, Permissions, Permit, TokenConfig};
+use crate::{ErrorCode, MarginAccount, Permissions, Permit, TokenConfig, TokenIsolationConfig};
 
 #[derive(Accounts)]
 pub struct RefreshPositionConfig<'info> {
@@ -35,6 +35,9 @@ pub struct RefreshPositionConfig<'info> {
 
     /// account that is authorized to refresh position metadata
     pub refresher: Signer<'info>,
+    // Remaining accounts, which must include the isolation config when the token is isolated:
+    //
+    // pub isolation_config: Account<'info, TokenIsolationConfig>,
 }
 
 /// Refresh the metadata for a position
@@ -47,12 +50,14 @@ pub fn refresh_position_config_handler(ctx: Context<RefreshPositionConfig>) -> R
         Permissions::REFRESH_POSITION_CONFIG,
     )?;
     let config = &ctx.accounts.config;
+    let isolation = TokenIsolationConfig::find(config, ctx.remaining_accounts)?;
 
     account.refresh_position_metadata(
         &config.mint,
         config.token_kind,
         config.value_modifier,
         config.max_staleness,
+        isolation,
     )?;
 
     Ok(())
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{
+    Approver, ErrorCode, MarginAccount, MarginPositionPage, PositionConfigUpdate, PositionPages,
+    TokenConfig, TokenIsolationConfig,
+};
+
+#[derive(Accounts)]
+pub struct RegisterPagedPosition<'info> {
+    /// The authority that can change the margin account
+    pub authority: Signer<'info>,
+
+    /// The address paying for rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The margin account to register the position with
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The page to store the position in
+    #[account(mut,
+              constraint = page.load()?.margin_account == margin_account.key()
+                           @ ErrorCode::InvalidPositionPages
+    )]
+    pub page: AccountLoader<'info, MarginPositionPage>,
+
+    /// The mint for the token being stored in this account
+    pub mint: Account<'info, Mint>,
+
+    /// The margin config for the token
+    #[account(
+        has_one = mint,
+        constraint = config.airspace == margin_account.load()?.airspace @ ErrorCode::WrongAirspace
+    )]
+    pub config: Account<'info, TokenConfig>,
+
+    /// The token account to store deposits, which is unique to the page so that
+    /// the same tokens can never be counted by more than one position
+    #[account(init,
+              seeds = [
+                page.key().as_ref(),
+                mint.key().as_ref()
+              ],
+              bump,
+              payer = payer,
+              token::mint = mint,
+              token::authority = margin_account,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+    // Remaining accounts, which must include every page of the account, so that
+    // the token can be checked as not registered in any of them:
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
+    //
+    // and the isolation config when the token is isolated:
+    //
+    // pub isolation_config: Account<'info, TokenIsolationConfig>,
+}
+
+/// Create a deposit account for a token, registered as a position in one of the pages
+/// for the margin account.
+pub fn register_paged_position_handler(ctx: Context<RegisterPagedPosition>) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let account = ctx.accounts.margin_account.load()?;
+    account.verify_authority(ctx.accounts.authority.key())?;
+
+    let (pages, others) =
+        PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
+    let isolation = TokenIsolationConfig::find(config, &others)?;
+    pages.verify_complete(&account)?;
+
+    if account.get_position(&config.mint).is_some() || pages.get_position(&config.mint)?.is_some() {
+        msg!("the token is already registered in the margin account");
+        return err!(ErrorCode::PositionAlreadyRegistered);
+    }
+
+    let mut page = ctx.accounts.page.load_mut()?;
+
+    page.register_position(
+        PositionConfigUpdate::new_from_config(
+            config,
+            isolation,
+            ctx.accounts.mint.decimals,
+            ctx.accounts.token_account.key(),
+            config.adapter_program().unwrap_or_default(),
+        ),
+        &[Approver::MarginAccountAuthority],
+    )?;
+
+    Ok(())
+}
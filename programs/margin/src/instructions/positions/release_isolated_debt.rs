@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::IsolatedDebtRecorded, ErrorCode, MarginAccount, PositionPages, TokenIsolationConfig,
+};
+
+#[derive(Accounts)]
+pub struct ReleaseIsolatedDebt<'info> {
+    /// The margin account with the isolated collateral position
+    #[account(mut)]
+    pub margin_account: AccountLoader<'info, MarginAccount>,
+
+    /// The isolation config for the collateral, which tracks the debt recorded
+    /// against its ceiling across the airspace
+    #[account(mut,
+              constraint = isolation_config.airspace == margin_account.load()?.airspace
+                  @ ErrorCode::WrongAirspace)]
+    pub isolation_config: Account<'info, TokenIsolationConfig>,
+    // Optional accounts (remaining accounts):
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
+}
+
+/// Release the debt recorded against the ceiling by an isolated collateral position,
+/// once the account has repaid all the debt in the isolation group.
+pub fn release_isolated_debt_handler(ctx: Context<ReleaseIsolatedDebt>) -> Result<()> {
+    let isolation_config = &mut ctx.accounts.isolation_config;
+    let (pages, _) =
+        PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
+
+    let mut account = ctx.accounts.margin_account.load_mut()?;
+
+    let amount = isolation_config.record_debt(&mut account, &pages)?;
+
+    if amount > 0 {
+        msg!(
+            "the account still has {} of debt in the isolation group",
+            amount
+        );
+        return err!(ErrorCode::IsolatedDebtReserved);
+    }
+
+    emit!(IsolatedDebtRecorded {
+        margin_account: ctx.accounts.margin_account.key(),
+        isolation_config: isolation_config.key(),
+        amount,
+        total_isolated_debt: isolation_config.isolated_debt,
+    });
+
+    Ok(())
+}
@@ -23,6 +23,7 @@ use crate::{
     syscall::{sys, Sys},
     ErrorCode,
     MarginAccount,
+    PositionPages,
     SignerSeeds,
 };
 
@@ -49,16 +50,34 @@ pub struct TransferDeposit<'info> {
     pub destination: Account<'info, TokenAccount>,
 
     pub token_program: Program<'info, Token>,
+    // Optional accounts (remaining accounts), when the position is in a page:
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
 }
 
 pub fn transfer_deposit_handler(ctx: Context<TransferDeposit>, amount: u64) -> Result<()> {
     let mut margin_account = ctx.accounts.margin_account.load_mut()?;
     let source_owner = &ctx.accounts.source_owner;
-
-    let position = match margin_account.get_position(&ctx.accounts.source.mint) {
-        None => return err!(ErrorCode::PositionNotRegistered),
-        Some(pos) => pos,
+    let (pages, _) =
+        PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
+    let mint = ctx.accounts.source.mint;
+
+    let position = match margin_account.get_position(&mint) {
+        Some(pos) => *pos,
+        None => pages
+            .pages
+            .iter()
+            .map(|page| Ok(page.load()?.get_position(&mint).copied()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .find(|pos| {
+                pos.address == ctx.accounts.source.key()
+                    || pos.address == ctx.accounts.destination.key()
+            })
+            .ok_or(ErrorCode::PositionNotRegistered)?,
     };
+    let paged = margin_account.get_position(&mint).is_none();
 
     if position.address == ctx.accounts.source.key() {
         let seeds = margin_account.signer_seeds_owned();
@@ -81,12 +100,21 @@ pub fn transfer_deposit_handler(ctx: Context<TransferDeposit>, amount: u64) -> R
         let mut margin_account = ctx.accounts.margin_account.load_mut()?;
 
         source.reload()?;
-        margin_account.set_position_balance(
-            &source.mint,
-            &source.key(),
-            source.amount,
-            sys().unix_timestamp(),
-        )?;
+        if paged {
+            pages.set_position_balance(
+                &source.mint,
+                &source.key(),
+                source.amount,
+                sys().unix_timestamp(),
+            )?;
+        } else {
+            margin_account.set_position_balance(
+                &source.mint,
+                &source.key(),
+                source.amount,
+                sys().unix_timestamp(),
+            )?;
+        }
     } else {
         token::transfer(
             CpiContext::new(
@@ -103,12 +131,21 @@ pub fn transfer_deposit_handler(ctx: Context<TransferDeposit>, amount: u64) -> R
         let destination = &mut ctx.accounts.destination;
 
         destination.reload()?;
-        margin_account.set_position_balance(
-            &destination.mint,
-            &destination.key(),
-            destination.amount,
-            sys().unix_timestamp(),
-        )?;
+        if paged {
+            pages.set_position_balance(
+                &destination.mint,
+                &destination.key(),
+                destination.amount,
+                sys().unix_timestamp(),
+            )?;
+        } else {
+            margin_account.set_position_balance(
+                &destination.mint,
+                &destination.key(),
+                destination.amount,
+                sys().unix_timestamp(),
+            )?;
+        }
     };
 
     Ok(())
@@ -18,7 +18,9 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
-use crate::{Approver, ErrorCode, MarginAccount, PositionConfigUpdate, TokenConfig};
+use crate::{
+    Approver, ErrorCode, MarginAccount, PositionConfigUpdate, TokenConfig, TokenIsolationConfig,
+};
 
 #[derive(Accounts)]
 pub struct RegisterPosition<'info> {
@@ -58,10 +60,14 @@ pub struct RegisterPosition<'info> {
     pub token_program: Program<'info, Token>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
+    // Remaining accounts, which must include the isolation config when the token is isolated:
+    //
+    // pub isolation_config: Account<'info, TokenIsolationConfig>,
 }
 
 pub fn register_position_handler(ctx: Context<RegisterPosition>) -> Result<()> {
     let config = &ctx.accounts.config;
+    let isolation = TokenIsolationConfig::find(config, ctx.remaining_accounts)?;
     let mut account = ctx.accounts.margin_account.load_mut()?;
     let position_token = &ctx.accounts.position_token_mint;
     let address = ctx.accounts.token_account.key();
@@ -70,6 +76,7 @@ pub fn register_position_handler(ctx: Context<RegisterPosition>) -> Result<()> {
     account.register_position(
         PositionConfigUpdate::new_from_config(
             config,
+            isolation,
             position_token.decimals,
             address,
             config
@@ -21,7 +21,9 @@ use anchor_spl::token::TokenAccount;
 use crate::{
     // events,
     syscall::{sys, Sys},
+    ErrorCode,
     MarginAccount,
+    PositionPages,
 };
 
 #[derive(Accounts)]
@@ -32,18 +34,38 @@ pub struct UpdatePositionBalance<'info> {
 
     /// The token account to update the balance for
     pub token_account: Account<'info, TokenAccount>,
+    // Optional accounts (remaining accounts), when the position is in a page:
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
 }
 
 pub fn update_position_balance_handler(ctx: Context<UpdatePositionBalance>) -> Result<()> {
     let mut margin_account = ctx.accounts.margin_account.load_mut()?;
     let token_account = &ctx.accounts.token_account;
 
-    margin_account.set_position_balance(
+    let result = margin_account.set_position_balance(
         &token_account.mint,
         &token_account.key(),
         token_account.amount,
         sys().unix_timestamp(),
-    )?;
+    );
+
+    match result {
+        Err(ErrorCode::PositionNotRegistered) => {
+            let (pages, _) =
+                PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
+
+            pages.set_position_balance(
+                &token_account.mint,
+                &token_account.key(),
+                token_account.amount,
+                sys().unix_timestamp(),
+            )?;
+        }
+        result => {
+            result?;
+        }
+    }
 
     Ok(())
 }
@@ -20,20 +20,25 @@ use anchor_lang::prelude::*;
 use crate::{
     events,
     syscall::{sys, Sys},
-    MarginAccount,
+    MarginAccount, PositionPages,
 };
 
 #[derive(Accounts)]
 pub struct VerifyHealthy<'info> {
     /// The account verify the health of
     pub margin_account: AccountLoader<'info, MarginAccount>,
+    // Optional accounts (remaining accounts):
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
 }
 
 pub fn verify_healthy_handler(ctx: Context<VerifyHealthy>) -> Result<()> {
     let account = ctx.accounts.margin_account.load()?;
+    let (pages, _) =
+        PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
 
-    account
-        .valuation(sys().unix_timestamp())?
+    pages
+        .valuation(&account, sys().unix_timestamp())?
         .verify_healthy()?;
 
     emit!(events::VerifiedHealthy {
@@ -20,20 +20,26 @@ use anchor_lang::prelude::*;
 use crate::{
     events,
     syscall::{sys, Sys},
-    MarginAccount,
+    MarginAccount, PositionPages,
 };
 
 #[derive(Accounts)]
 pub struct VerifyUnhealthy<'info> {
     /// The account verify the health of
     pub margin_account: AccountLoader<'info, MarginAccount>,
+    // Remaining accounts, which must include every page of the account:
+    //
+    // pub position_pages: [AccountLoader<'info, MarginPositionPage>],
 }
 
 pub fn verify_unhealthy_handler(ctx: Context<VerifyUnhealthy>) -> Result<()> {
     let account = ctx.accounts.margin_account.load()?;
+    let (pages, _) =
+        PositionPages::split(&ctx.accounts.margin_account.key(), ctx.remaining_accounts)?;
+    pages.verify_complete(&account)?;
 
-    account
-        .valuation(sys().unix_timestamp())?
+    pages
+        .valuation(&account, sys().unix_timestamp())?
         .verify_unhealthy()?;
 
     emit!(events::VerifiedUnealthy {
@@ -39,7 +39,10 @@ pub use state::*;
 pub use util::Invocation;
 
 pub use adapter::{AdapterResult, PositionChange, PriceChangeInfo};
-pub use instructions::TokenConfigUpdate;
+pub use instructions::{
+    DelegateMintLimitUpdate, LiquidationConfigUpdate, MarginDelegateUpdate, TokenConfigUpdate,
+    TokenOracleSetUpdate,
+};
 
 /// The maximum confidence deviation allowed for an oracle price.
 ///
@@ -54,22 +57,45 @@ pub const MAX_ORACLE_CONFIDENCE: u16 = 5_00;
 #[constant]
 pub const MAX_ORACLE_STALENESS: i64 = 30;
 
+/// The maximum number of fallback oracles that can be used to price a token,
+/// in addition to the oracle in its token config.
+pub const MAX_FALLBACK_ORACLES: usize = 3;
+
 /// The maximum age to allow for a quoted price for a position (seconds)
 #[constant]
 pub const MAX_PRICE_QUOTE_AGE: u64 = 30;
 
 /// The maximum amount of equity that can be deducted from an account during liquidation
-/// as a fraction of the account's entire liabilities value
+/// as a fraction of the account's entire liabilities value, when the airspace has no
+/// [LiquidationConfig]
 pub const LIQUIDATION_MAX_TOTAL_EQUITY_LOSS_BPS: u16 = 4_00;
 
 /// The maximum duration in seconds of a liquidation before another user may cancel it
 #[constant]
 pub const LIQUIDATION_TIMEOUT: UnixTimestamp = 60;
 
-/// The maximum number of positions that a user can register.
-/// This may be exceeded by a liquidator.
+/// The duration in seconds over which the discount offered by a liquidation auction
+/// grows from its minimum to its maximum
+#[constant]
+pub const LIQUIDATION_AUCTION_DURATION: UnixTimestamp = 300;
+
+/// The duration in seconds after which anyone may end a liquidation auction, even if
+/// the account is still unhealthy
+#[constant]
+pub const LIQUIDATION_AUCTION_TIMEOUT: UnixTimestamp = 900;
+
+/// The maximum number of positions that a user can register in the margin account itself.
+/// This may be exceeded by a liquidator, and additional deposits can be stored in position pages.
 pub const MAX_USER_POSITIONS: u64 = 24;
 
+/// The maximum number of additional position pages a margin account can have
+#[constant]
+pub const MAX_POSITION_PAGES: u8 = 4;
+
+/// The maximum number of positions a delegate can have balance limits for
+#[constant]
+pub const MAX_DELEGATE_LIMITS: usize = 8;
+
 /// This crate documents the instructions used in the `margin` program of the
 /// [jet-v2 repo](https://github.com/jet-lab/jet-v2/).
 ///
@@ -273,6 +299,54 @@ pub mod jet_margin {
         verify_unhealthy_handler(ctx)
     }
 
+    /// Authorize another address to sign for the margin account when invoking adapters.
+    ///
+    /// The delegate is limited to the kinds of changes in the update, and to the limits on how
+    /// much it may reduce the balances of positions. Updating a delegate resets its usage of
+    /// the limits, and providing `None` revokes it.
+    pub fn configure_delegate(
+        ctx: Context<ConfigureDelegate>,
+        update: Option<MarginDelegateUpdate>,
+    ) -> Result<()> {
+        configure_delegate_handler(ctx, update)
+    }
+
+    /// Pre-authorize an adapter instruction that any keeper may execute through
+    /// [adapter_invoke] once the trigger holds, such as a stop-loss swap.
+    ///
+    /// The `tip` is deposited into the action account, and paid to the keeper along with
+    /// the rent when the action is executed.
+    ///
+    /// # Parameters
+    ///
+    /// * `seed` - A seed to distinguish the actions for the margin account
+    /// * `trigger` - The condition under which the action may be executed
+    /// * `adapter_program` - The adapter program to be invoked
+    /// * `instruction_hash` - The hash of the adapter instruction, see [ConditionalAction::hash_instruction]
+    /// * `tip` - The lamports paid to the keeper
+    pub fn create_conditional_action(
+        ctx: Context<CreateConditionalAction>,
+        seed: u16,
+        trigger: ActionTrigger,
+        adapter_program: Pubkey,
+        instruction_hash: [u8; 32],
+        tip: u64,
+    ) -> Result<()> {
+        create_conditional_action_handler(
+            ctx,
+            seed,
+            trigger,
+            adapter_program,
+            instruction_hash,
+            tip,
+        )
+    }
+
+    /// Cancel a conditional action, returning its rent and tip.
+    pub fn cancel_conditional_action(ctx: Context<CancelConditionalAction>) -> Result<()> {
+        cancel_conditional_action_handler(ctx)
+    }
+
     /// Perform an action by invoking other programs, allowing them to alter
     /// the balances of the token accounts belonging to this margin account.
     ///
@@ -286,6 +360,17 @@ pub mod jet_margin {
     /// All extra accounts passed in are used as the input accounts when invoking
     /// the provided adapter porgram.
     ///
+    /// The signer may be a delegate of the owner, in which case its [MarginDelegate] must be
+    /// included in the extra accounts, and the changes to the account are checked against the
+    /// permissions and limits of the delegation.
+    ///
+    /// Any other signer may execute a [ConditionalAction] for the account by including it in
+    /// the extra accounts, once its trigger holds.
+    ///
+    /// Any [TokenIsolationConfig] in the extra accounts is not passed to the adapter. After the
+    /// adapter returns, the debt in the isolation group of its token is recorded against the
+    /// debt ceiling, which is required for isolated collateral to back any new debt.
+    ///
     /// # Parameters
     ///
     /// * `data` - The instruction data to pass to the adapter program
@@ -295,7 +380,7 @@ pub mod jet_margin {
     /// |     |     |     |
     /// | --- | --- | --- |
     /// | **Name** | **Type** | **Description** |
-    /// | `owner` | `signer` | The authority that owns the margin account. |
+    /// | `owner` | `signer` | The authority that owns the margin account, or one of its delegates. |
     /// | `margin_account` | `writable` | The margin account to proxy an action for. |
     /// | `adapter_program` | `read_only` | The program to be invoked. |
     /// | `adapter_metadata` | `read_only` | The metadata about the proxy program. |
@@ -308,6 +393,7 @@ pub mod jet_margin {
     /// | [`events::AdapterInvokeBegin`] | Marks the start of the adapter invocation (includes the margin account pubkey and the adapter program pubkey). |
     /// | [`events::PositionEvent`] _(Note that each single event represents a different adapter position)_ | The [PositionEvent](events::PositionEvent) marks the change in position. |
     /// | [`events::AdapterInvokeEnd`] | Marks the ending of the adapter invocation (includes no data except for the event itself being emitted). |
+    /// | [`events::IsolatedDebtRecorded`] | The debt recorded against the ceiling for each isolation config provided. |
     pub fn adapter_invoke<'info>(
         ctx: Context<'_, '_, '_, 'info, AdapterInvoke<'info>>,
         data: Vec<u8>,
@@ -327,6 +413,9 @@ pub mod jet_margin {
     /// All extra accounts passed in are used as the input accounts when invoking
     /// the provided adapter porgram.
     ///
+    /// Any signer of the instruction other than the owner must also pass the account at
+    /// its [MarginDelegate] address, so the limits of a delegate always apply.
+    ///
     /// # Parameters
     ///
     /// * `data` - The instruction data to pass to the adapter program
@@ -372,6 +461,7 @@ pub mod jet_margin {
     /// | `payer` | `signer` | The address paying rent. |
     /// | `liquidator` | `signer` | The liquidator account performing the liquidation. |
     /// | `liquidator_metadata` | `read_only` | The metadata describing the liquidator. |
+    /// | `liquidation_config` | `read_only` | The liquidation config for the airspace, which may be uninitialized. |
     /// | `liquidation` | `writable` | The account to persist the state of liquidation. |
     /// | `system_program` | `read_only` | The [system native program](https://docs.solana.com/developing/runtime-facilities/programs#system-program). |
     ///
@@ -415,6 +505,9 @@ pub mod jet_margin {
     /// Requires the account already be in the liquidation state, and the signer must
     /// be the same liquidator that started the liquidation state.      
     ///
+    /// The equity lost by the account may not exceed the bonus earned on the liabilities
+    /// repaid so far, at the rate fixed when the liquidation began.
+    ///
     /// # [Accounts](jet_margin::accounts::LiquidatorInvoke)
     /// |     |     |     |
     /// | --- | --- | --- |
@@ -440,6 +533,93 @@ pub mod jet_margin {
         liquidator_invoke_handler(ctx, data)
     }
 
+    /// Begin a liquidation auction for an unhealthy account
+    ///
+    /// Unlike [liquidate_begin], this does not require a permit, so anyone may start an
+    /// auction. The account will enter a state preventing the owner from taking any action,
+    /// until the auction has ended.
+    ///
+    /// # [Accounts](jet_margin::accounts::AuctionBegin)
+    ///
+    /// |     |     |     |
+    /// | --- | --- | --- |
+    /// | **Name** | **Type** | **Description** |
+    /// | `margin_account` | `writable` | The account in need of liquidation. |
+    /// | `payer` | `signer` | The address paying rent. |
+    /// | `liquidation_config` | `read_only` | The liquidation config for the airspace, which may be uninitialized. |
+    /// | `auction` | `writable` | The account to persist the state of the auction. |
+    /// | `system_program` | `read_only` | The [system native program](https://docs.solana.com/developing/runtime-facilities/programs#system-program). |
+    ///
+    /// # Events
+    ///
+    /// |     |     |
+    /// | --- | --- |
+    /// | **Event Name** | **Description** |
+    /// | [`events::LiquidationAuctionBegun`] | Marks the beginning of the auction. |
+    pub fn auction_begin(ctx: Context<AuctionBegin>) -> Result<()> {
+        auction_begin_handler(ctx)
+    }
+
+    /// Repay a claim in an auctioned account by invoking an adapter, and pay out collateral
+    /// to the bidder in exchange.
+    ///
+    /// The adapter is invoked with the remaining accounts, without the margin account
+    /// signing, so the repayment is authorized by the bidder. Only the reduction of the
+    /// claim balance during the invocation is paid for.
+    ///
+    /// The collateral is priced at a discount to the value repaid, which grows over
+    /// [LIQUIDATION_AUCTION_DURATION].
+    ///
+    /// # [Accounts](jet_margin::accounts::AuctionBid)
+    ///
+    /// |     |     |     |
+    /// | --- | --- | --- |
+    /// | **Name** | **Type** | **Description** |
+    /// | `bidder` | `signer` | The bidder receiving the collateral. |
+    /// | `margin_account` | `writable` | The account being liquidated. |
+    /// | `auction` | `writable` | The auction for the account. |
+    /// | `claim_account` | `read_only` | The token account for the claim being repaid. |
+    /// | `collateral_account` | `writable` | The token account for the collateral paid out. |
+    /// | `destination` | `writable` | The bidder's token account receiving the collateral. |
+    /// | `adapter_program` | `read_only` | The adapter program that repays the claim. |
+    /// | `adapter_config` | `read_only` | The metadata about the adapter program. |
+    /// | `token_program` | `read_only` | The [spl token program](https://spl.solana.com/token). |
+    ///
+    /// # Events
+    ///
+    /// |     |     |
+    /// | --- | --- |
+    /// | **Event Name** | **Description** |
+    /// | [`events::LiquidationAuctionBid`] | The claims repaid and the collateral paid out. |
+    pub fn auction_bid<'info>(
+        ctx: Context<'_, '_, '_, 'info, AuctionBid<'info>>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        auction_bid_handler(ctx, data)
+    }
+
+    /// End a liquidation auction, which can be done by anyone once the account is healthy
+    /// again or after [LIQUIDATION_AUCTION_TIMEOUT] has elapsed.
+    ///
+    /// # [Accounts](jet_margin::accounts::AuctionEnd)
+    ///
+    /// |     |     |     |
+    /// | --- | --- | --- |
+    /// | **Name** | **Type** | **Description** |
+    /// | `margin_account` | `writable` | The account being liquidated. |
+    /// | `payer` | `writable` | The address that paid rent for the auction, which is refunded. |
+    /// | `auction` | `writable` | The auction to end. |
+    ///
+    /// # Events
+    ///
+    /// |     |     |
+    /// | --- | --- |
+    /// | **Event Name** | **Description** |
+    /// | [`events::LiquidationAuctionEnded`] | Marks the end of the auction. |
+    pub fn auction_end(ctx: Context<AuctionEnd>) -> Result<()> {
+        auction_end_handler(ctx)
+    }
+
     /// Update the config for a token position stored in the margin account,
     /// in the case where the token config has changed after the position was
     /// created.
@@ -462,6 +642,41 @@ pub mod jet_margin {
         transfer_deposit_handler(ctx, amount)
     }
 
+    /// Add a page for storing positions beyond those that fit in the margin account.
+    ///
+    /// Pages can only store collateral deposits registered with `register_paged_position`,
+    /// and must be provided to any instruction that needs to count them. Positions
+    /// registered by adapters are always stored in the margin account itself, so an adapter
+    /// cannot register a new position once the account is full. Version 1 accounts are
+    /// migrated when the first page is added.
+    pub fn add_position_page(ctx: Context<AddPositionPage>) -> Result<()> {
+        add_position_page_handler(ctx)
+    }
+
+    /// Create a new account for holding SPL token deposits, registered as a position in
+    /// one of the pages for a margin account.
+    ///
+    /// Every page of the account must be provided as a remaining account, since a token
+    /// may only be registered in one of them.
+    pub fn register_paged_position(ctx: Context<RegisterPagedPosition>) -> Result<()> {
+        register_paged_position_handler(ctx)
+    }
+
+    /// Close a deposit position stored in one of the pages for a margin account.
+    pub fn close_paged_position(ctx: Context<ClosePagedPosition>) -> Result<()> {
+        close_paged_position_handler(ctx)
+    }
+
+    /// Release the debt that an isolated collateral position has recorded against the
+    /// airspace-wide debt ceiling, after the account has repaid its debt in the isolation
+    /// group without providing the isolation config to `adapter_invoke`.
+    ///
+    /// Anyone may release the debt, since the account no longer needs it to be recorded.
+    /// The position may be stored in a page, so any position pages must be provided.
+    pub fn release_isolated_debt(ctx: Context<ReleaseIsolatedDebt>) -> Result<()> {
+        release_isolated_debt_handler(ctx)
+    }
+
     /// Set the configuration for a token, which allows it to be used as a position in a margin
     /// account.
     ///
@@ -477,6 +692,51 @@ pub mod jet_margin {
         configure_token_handler(ctx, update)
     }
 
+    /// Set the fallback oracles used to price a token, along with how the prices from
+    /// all oracles are combined.
+    ///
+    /// Once set, the oracle set must be provided whenever the price of the token is
+    /// refreshed, and the price is marked invalid if the oracles disagree by more than
+    /// the configured deviation.
+    ///
+    /// The account storing the oracle set will be funded if not already. If a `None` is provided as
+    /// the updated configuration, then the account will be defunded.
+    pub fn configure_token_oracles(
+        ctx: Context<ConfigureTokenOracles>,
+        update: Option<TokenOracleSetUpdate>,
+    ) -> Result<()> {
+        configure_token_oracles_handler(ctx, update)
+    }
+
+    /// Isolate a token, so that it can only back debt in tokens with the same isolation
+    /// group, up to a ceiling on the debt it backs across the airspace.
+    ///
+    /// Once set, the isolation config must be provided whenever a position for the token is
+    /// registered or its config is refreshed.
+    ///
+    /// The account storing the isolation config will be funded if not already. If a `None` is
+    /// provided as the updated configuration, then the account will be defunded.
+    pub fn configure_token_isolation(
+        ctx: Context<ConfigureTokenIsolation>,
+        update: Option<TokenIsolation>,
+    ) -> Result<()> {
+        configure_token_isolation_handler(ctx, update)
+    }
+
+    /// Set the close factor and bonus schedule used when liquidating accounts in an airspace.
+    ///
+    /// Liquidations in an airspace without a config may repay all of an account's liabilities,
+    /// with a flat bonus of [LIQUIDATION_MAX_TOTAL_EQUITY_LOSS_BPS].
+    ///
+    /// The account storing the configuration will be funded if not already. If a `None` is provided as
+    /// the updated configuration, then the account will be defunded.
+    pub fn configure_liquidation(
+        ctx: Context<ConfigureLiquidation>,
+        update: Option<LiquidationConfigUpdate>,
+    ) -> Result<()> {
+        configure_liquidation_handler(ctx, update)
+    }
+
     /// Set the configuration for an adapter.
     ///
     /// The configuration for a token only applies for the associated airspace, and changing any
@@ -604,6 +864,10 @@ pub enum ErrorCode {
     #[msg("dependencies are not satisfied to auto-register a required but unregistered position")]
     PositionNotRegisterable,
 
+    /// 141019 - A position page is missing or belongs to a different account
+    #[msg("the position pages for the account were not all provided")]
+    InvalidPositionPages,
+
     /// 141020 - The adapter providing a position change is not authorized for this asset
     #[msg("wrong adapter to modify the position")]
     InvalidPositionAdapter = 135_020,
@@ -651,6 +915,18 @@ pub enum ErrorCode {
     #[msg("liquidationState does not match given margin account")]
     WrongLiquidationState,
 
+    /// 141043 - The liquidation attempted to repay more than the close factor allows
+    #[msg("attempted to repay too many liabilities during liquidation")]
+    LiquidationExceedsCloseFactor,
+
+    /// 141044 - The liquidation auction cannot be ended yet
+    #[msg("the liquidation auction is still active")]
+    LiquidationAuctionActive,
+
+    /// 141045 - The bid does not satisfy the liquidation auction
+    #[msg("invalid bid for the liquidation auction")]
+    InvalidAuctionBid,
+
     /// 141050 - The airspace does not match
     #[msg("attempting to mix entities from different airspaces")]
     WrongAirspace = 135_050,
@@ -674,6 +950,42 @@ pub enum ErrorCode {
     /// 141061
     #[msg("the permit is not owned by the current user")]
     PermitNotOwned = 135_061,
+
+    /// 141062
+    #[msg("the delegation for the signer has expired")]
+    DelegationExpired = 135_062,
+
+    /// 141063
+    #[msg("the delegate has exceeded its limit for a position")]
+    DelegationLimitExceeded = 135_063,
+
+    /// 141064
+    #[msg("the trigger for the conditional action does not hold")]
+    ConditionNotMet = 135_064,
+
+    /// 141065
+    #[msg("the instruction does not match the conditional action")]
+    ConditionalActionMismatch = 135_065,
+
+    /// 141066
+    #[msg("the debt ceiling for the isolated collateral would be exceeded")]
+    IsolatedDebtCeilingExceeded = 135_066,
+
+    /// 141067
+    #[msg("the position still has debt recorded against an isolated debt ceiling")]
+    IsolatedDebtReserved = 135_067,
+
+    /// 141068
+    #[msg("the isolation config for the token must be provided")]
+    IsolationConfigRequired = 135_068,
+
+    /// 141069
+    #[msg("the delegation account of a signer must be provided")]
+    DelegationRequired = 135_069,
+
+    /// 141070 - Adapters can only register positions in the margin account, not its pages
+    #[msg("the margin account is full, and adapter positions cannot be stored in pages")]
+    AdapterPositionsFull = 135_070,
 }
 
 /// Writes the result of position changes from an adapter invocation.
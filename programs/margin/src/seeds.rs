@@ -20,6 +20,27 @@ use anchor_lang::prelude::*;
 #[constant]
 pub const TOKEN_CONFIG_SEED: &[u8] = b"token-config";
 
+#[constant]
+pub const TOKEN_ORACLE_SET_SEED: &[u8] = b"token-oracle-set";
+
+#[constant]
+pub const TOKEN_ISOLATION_SEED: &[u8] = b"token-isolation";
+
+#[constant]
+pub const LIQUIDATION_CONFIG_SEED: &[u8] = b"liquidation-config";
+
+#[constant]
+pub const LIQUIDATION_AUCTION_SEED: &[u8] = b"liquidation-auction";
+
+#[constant]
+pub const POSITION_PAGE_SEED: &[u8] = b"position-page";
+
+#[constant]
+pub const MARGIN_DELEGATE_SEED: &[u8] = b"margin-delegate";
+
+#[constant]
+pub const CONDITIONAL_ACTION_SEED: &[u8] = b"conditional-action";
+
 #[constant]
 pub const ADAPTER_CONFIG_SEED: &[u8] = b"adapter-config";
 
@@ -1,5 +1,11 @@
 mod account;
+mod auction;
+mod conditional;
 mod config;
+mod delegate;
 
 pub use account::*;
+pub use auction::*;
+pub use conditional::*;
 pub use config::*;
+pub use delegate::*;
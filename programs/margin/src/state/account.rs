@@ -30,15 +30,20 @@ use std::result::Result;
 use crate::{
     syscall::{sys, Sys},
     util::{Invocation, Require},
-    ErrorCode, TokenKind, MAX_PRICE_QUOTE_AGE, MAX_USER_POSITIONS,
+    ErrorCode, TokenIsolation, TokenKind, MAX_PRICE_QUOTE_AGE, MAX_USER_POSITIONS,
 };
 
+mod pages;
 mod positions;
 
+pub use pages::*;
 pub use positions::*;
 
 /// The current version for the margin account state
-pub const MARGIN_ACCOUNT_VERSION: u8 = 1;
+///
+/// Version 2 accounts may have additional [MarginPositionPage]s. Version 1 accounts
+/// are migrated when their first page is added.
+pub const MARGIN_ACCOUNT_VERSION: u8 = 2;
 
 #[account(zero_copy)]
 #[repr(C)]
@@ -53,7 +58,10 @@ pub struct MarginAccount {
     /// Must normally be zeroed, except during an invocation.
     pub invocation: Invocation,
 
-    pub reserved0: [u8; 3],
+    /// The number of [MarginPositionPage]s that store additional positions for this account
+    pub position_pages: u8,
+
+    pub reserved0: [u8; 2],
 
     /// The owner of this account, which generally has to sign for any changes to it
     pub owner: Pubkey,
@@ -90,6 +98,7 @@ impl std::fmt::Debug for MarginAccount {
         acc.field("version", &self.version)
             .field("bump_seed", &self.bump_seed)
             .field("user_seed", &self.user_seed)
+            .field("position_pages", &self.position_pages)
             .field("reserved0", &self.reserved0)
             .field("invocation", &self.invocation)
             .field("owner", &self.owner)
@@ -156,6 +165,11 @@ impl MarginAccount {
         self.liquidator != Pubkey::default()
     }
 
+    /// Whether the account itself has space to register another position
+    pub fn has_position_space(&self) -> bool {
+        self.is_liquidating() || self.position_list().length < MAX_USER_POSITIONS
+    }
+
     pub fn initialize(&mut self, airspace: Pubkey, owner: Pubkey, seed: u16, bump_seed: u8) {
         self.version = MARGIN_ACCOUNT_VERSION;
         self.airspace = airspace;
@@ -167,10 +181,14 @@ impl MarginAccount {
 
     /// Get the list of positions on this account
     pub fn positions(&self) -> impl Iterator<Item = &AccountPosition> {
-        self.position_list()
-            .positions
-            .iter()
-            .filter(|p| p.address != Pubkey::default())
+        self.position_list().iter()
+    }
+
+    /// The total value of the claims in an isolation group
+    pub fn isolated_debt(&self, group: u16) -> Number128 {
+        self.positions()
+            .filter(|p| p.kind() == TokenKind::Claim && p.isolation_group() == Some(group))
+            .fold(Number128::ZERO, |total, p| total + p.value())
     }
 
     /// Register the space for a new position into this account
@@ -180,35 +198,14 @@ impl MarginAccount {
         config: PositionConfigUpdate,
         approvals: &[Approver],
     ) -> AnchorResult<AccountPositionKey> {
-        if !self.is_liquidating() && self.position_list().length >= MAX_USER_POSITIONS {
+        if !self.has_position_space() {
             return err!(ErrorCode::MaxPositions);
         }
         if self.airspace != config.airspace {
             return err!(ErrorCode::WrongAirspace);
         }
 
-        let (key, free_position) = self.position_list_mut().add(config.mint)?;
-
-        if let Some(free_position) = free_position {
-            free_position.exponent = -(config.decimals as i16);
-            free_position.address = config.address;
-            free_position.adapter = config.adapter;
-            free_position.kind = config.kind.into_integer();
-            free_position.balance = 0;
-            free_position.value_modifier = config.value_modifier;
-            free_position.max_staleness = config.max_staleness;
-
-            if !free_position.may_be_registered_or_closed(approvals) {
-                msg!(
-                    "{:?} is not authorized to register {:?}",
-                    approvals,
-                    free_position
-                );
-                return err!(ErrorCode::InvalidPositionOwner);
-            }
-        }
-
-        Ok(key)
+        self.position_list_mut().register(config, approvals)
     }
 
     /// Free the space from a previously registered position no longer needed
@@ -218,20 +215,8 @@ impl MarginAccount {
         account: &Pubkey,
         approvals: &[Approver],
     ) -> AnchorResult<()> {
-        let removed = self.position_list_mut().remove(mint, account)?;
-
-        if !removed.may_be_registered_or_closed(approvals) {
-            msg!("{:?} is not authorized to close {:?}", approvals, removed);
-            return err!(ErrorCode::InvalidPositionOwner);
-        }
-        if removed.balance != 0 {
-            return err!(ErrorCode::CloseNonZeroPosition);
-        }
-        if removed.flags.contains(AdapterPositionFlags::REQUIRED) {
-            return err!(ErrorCode::CloseRequiredPosition);
-        }
-
-        Ok(())
+        self.position_list_mut()
+            .unregister(mint, account, approvals)
     }
 
     pub fn refresh_position_metadata(
@@ -240,6 +225,7 @@ impl MarginAccount {
         kind: TokenKind,
         value_modifier: u16,
         max_staleness: u64,
+        isolation: Option<TokenIsolation>,
     ) -> Result<AccountPosition, ErrorCode> {
         let position = match self.position_list_mut().get_mut(mint) {
             None => return Err(ErrorCode::PositionNotRegistered),
@@ -249,6 +235,7 @@ impl MarginAccount {
         position.kind = kind.into_integer();
         position.value_modifier = value_modifier;
         position.max_staleness = max_staleness;
+        position.set_isolation(isolation);
 
         Ok(*position)
     }
@@ -354,14 +341,31 @@ impl MarginAccount {
     }
 
     pub fn valuation(&self, timestamp: u64) -> AnchorResult<Valuation> {
+        self.valuation_with_pages(timestamp, &[])
+    }
+
+    /// Value the account including the positions stored in its pages.
+    ///
+    /// Pages only store collateral, so leaving out any pages can only understate the
+    /// health of the account.
+    pub fn valuation_with_pages(
+        &self,
+        timestamp: u64,
+        pages: &[&MarginPositionPage],
+    ) -> AnchorResult<Valuation> {
         let mut past_due = false;
         let mut liabilities = Number128::ZERO;
         let mut required_collateral = Number128::ZERO;
         let mut weighted_collateral = Number128::ZERO;
         let mut stale_collateral_list = vec![];
         let mut equity = Number128::ZERO;
+        let mut isolation_groups: Vec<IsolationGroupValuation> = vec![];
+
+        let positions = self
+            .positions()
+            .chain(pages.iter().flat_map(|page| page.positions()));
 
-        for position in self.positions() {
+        for position in positions {
             if position.balance == 0 {
                 continue;
             }
@@ -392,9 +396,17 @@ impl MarginAccount {
                         past_due = true;
                     }
 
+                    let required = position.required_collateral_value();
+
                     equity -= position.value();
                     liabilities += position.value();
-                    required_collateral += position.required_collateral_value();
+                    required_collateral += required;
+
+                    if let Some(group) = position.isolation_group() {
+                        let group = IsolationGroupValuation::find(&mut isolation_groups, group);
+                        group.debt += position.value();
+                        group.required_collateral += required;
+                    }
                 }
                 (TokenKind::Claim, Some(error)) => {
                     msg!("claim position is stale: {:?}", position);
@@ -403,7 +415,14 @@ impl MarginAccount {
 
                 (TokenKind::AdapterCollateral | TokenKind::Collateral, None) => {
                     equity += position.value();
-                    weighted_collateral += position.collateral_value();
+
+                    match position.isolation_group() {
+                        None => weighted_collateral += position.collateral_value(),
+                        Some(group) => {
+                            let group = IsolationGroupValuation::find(&mut isolation_groups, group);
+                            group.add_collateral(position);
+                        }
+                    }
                 }
                 (TokenKind::AdapterCollateral | TokenKind::Collateral, Some(e)) => {
                     stale_collateral_list.push((position.token, e));
@@ -411,6 +430,10 @@ impl MarginAccount {
             }
         }
 
+        for group in isolation_groups {
+            weighted_collateral += group.collateral_credit();
+        }
+
         Ok(Valuation {
             equity,
             liabilities,
@@ -495,14 +518,26 @@ pub struct Liquidation {
 
     /// The maximum amount of collateral allowed to be lost during all steps
     pub max_equity_loss: i128,
+
+    /// The cumulative value of liabilities repaid during liquidation so far
+    pub liabilities_repaid: i128,
+
+    /// The maximum value of liabilities allowed to be repaid during all steps
+    pub max_liabilities_repaid: i128,
 }
 
 impl Liquidation {
-    pub fn new(start_time: i64, max_equity_loss: Number128) -> Self {
+    pub fn new(
+        start_time: i64,
+        max_equity_loss: Number128,
+        max_liabilities_repaid: Number128,
+    ) -> Self {
         Self {
             start_time,
             equity_loss: 0,
             max_equity_loss: max_equity_loss.to_i128(),
+            liabilities_repaid: 0,
+            max_liabilities_repaid: max_liabilities_repaid.to_i128(),
         }
     }
 
@@ -521,6 +556,98 @@ impl Liquidation {
     pub fn max_equity_loss(&self) -> Number128 {
         Number128::from_i128(self.max_equity_loss)
     }
+
+    pub fn liabilities_repaid_mut(&mut self) -> &mut Number128 {
+        bytemuck::cast_mut(&mut self.liabilities_repaid)
+    }
+
+    pub fn liabilities_repaid(&self) -> &Number128 {
+        bytemuck::cast_ref(&self.liabilities_repaid)
+    }
+
+    pub fn max_liabilities_repaid(&self) -> Number128 {
+        Number128::from_i128(self.max_liabilities_repaid)
+    }
+
+    /// The equity the liquidator has earned the right to take by repaying liabilities so far.
+    ///
+    /// The bonus rate is the one fixed when the liquidation began, which is the ratio of the
+    /// max equity loss to the max liabilities repaid.
+    pub fn earned_equity_loss(&self) -> Number128 {
+        if self.max_liabilities_repaid <= 0 {
+            return Number128::ZERO;
+        }
+        let bonus = self.max_equity_loss() / self.max_liabilities_repaid();
+
+        *self.liabilities_repaid() * bonus
+    }
+}
+
+/// Running totals for the positions in one isolation group, used to determine how much of
+/// the isolated collateral can be counted towards the health of the account.
+///
+/// Isolated collateral is only counted to the extent that it's needed to back the claims
+/// in the same group. When the collateral has a debt ceiling, it may only back as much
+/// debt as the account has recorded against the ceiling, which is tracked across the
+/// airspace in the token's isolation config.
+#[derive(Default)]
+struct IsolationGroupValuation {
+    group: u16,
+
+    /// The weighted value of the isolated collateral
+    collateral: Number128,
+
+    /// The total value of debt that isolated collateral may back, if limited
+    debt_ceiling: Option<Number128>,
+
+    /// The value of claims in the group
+    debt: Number128,
+
+    /// The collateral required for the claims in the group
+    required_collateral: Number128,
+}
+
+impl IsolationGroupValuation {
+    fn find(groups: &mut Vec<Self>, group: u16) -> &mut Self {
+        let index = match groups.iter().position(|g| g.group == group) {
+            Some(index) => index,
+            None => {
+                groups.push(Self {
+                    group,
+                    debt_ceiling: Some(Number128::ZERO),
+                    ..Default::default()
+                });
+                groups.len() - 1
+            }
+        };
+
+        &mut groups[index]
+    }
+
+    fn add_collateral(&mut self, position: &AccountPosition) {
+        self.collateral += position.collateral_value();
+        self.debt_ceiling = match (self.debt_ceiling, position.debt_ceiling) {
+            (_, 0) | (None, _) => None,
+            (Some(total), _) => Some(total + Number128::from_decimal(position.isolated_debt, 0)),
+        };
+    }
+
+    /// The weighted collateral that may be counted from the isolated positions
+    fn collateral_credit(&self) -> Number128 {
+        if self.debt == Number128::ZERO || self.required_collateral == Number128::MAX {
+            return Number128::ZERO;
+        }
+
+        // Being healthy requires collateral for the debt itself, plus the required collateral
+        let backed = self.debt + self.required_collateral;
+
+        let backed = match self.debt_ceiling {
+            Some(ceiling) if ceiling < self.debt => backed / self.debt * ceiling,
+            _ => backed,
+        };
+
+        std::cmp::min(self.collateral, backed)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -560,6 +687,19 @@ impl Valuation {
         }
     }
 
+    /// How far the effective collateral falls short of the required collateral, as a fraction
+    /// of the required collateral. This is 0 when the requirement is met, and 1 when there is
+    /// no effective collateral left.
+    pub fn collateral_shortfall(&self) -> Number128 {
+        if self.effective_collateral >= self.required_collateral {
+            Number128::ZERO
+        } else if self.effective_collateral <= Number128::ZERO {
+            Number128::ONE
+        } else {
+            (self.required_collateral - self.effective_collateral) / self.required_collateral
+        }
+    }
+
     pub fn past_due(&self) -> bool {
         self.past_due
     }
@@ -581,8 +721,8 @@ impl Valuation {
         Ok(())
     }
 
-    /// Check that the overall health of the account is *not* acceptable.
-    pub fn verify_unhealthy(&self) -> AnchorResult<()> {
+    /// Check that no collateral was left out of the valuation for being stale.
+    pub fn verify_not_stale(&self) -> AnchorResult<()> {
         if !self.stale_collateral_list.is_empty() {
             for (position_token, error) in self.stale_collateral_list.iter() {
                 msg!("stale position {}: {}", position_token, error)
@@ -590,6 +730,13 @@ impl Valuation {
             return Err(error!(ErrorCode::StalePositions));
         }
 
+        Ok(())
+    }
+
+    /// Check that the overall health of the account is *not* acceptable.
+    pub fn verify_unhealthy(&self) -> AnchorResult<()> {
+        self.verify_not_stale()?;
+
         match self.required_collateral > self.effective_collateral {
             true => Ok(()),
             false if self.past_due => Ok(()),
@@ -601,7 +748,7 @@ impl Valuation {
 #[cfg(test)]
 mod tests {
 
-    use crate::{mock_sys, util::Invocation};
+    use crate::{mock_sys, util::Invocation, ActionTrigger, ConditionalAction};
 
     use super::*;
     use itertools::Itertools;
@@ -627,7 +774,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::default(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
@@ -638,7 +786,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0, 0],
-            reserved0: [0, 0, 0],
+            position_pages: 0,
+            reserved0: [0, 0],
             invocation: Invocation {
                 caller_heights: BitSet(0b10010111)
             },
@@ -664,6 +813,7 @@ mod tests {
                 kind: TokenKind::Collateral,
                 value_modifier: 5000,
                 max_staleness: 1000,
+                isolation: None,
             },
             approvals,
         )
@@ -699,7 +849,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::default(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
@@ -786,7 +937,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::new_unique(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
@@ -807,7 +959,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::new_unique(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
@@ -849,7 +1002,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::new_unique(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
@@ -877,6 +1031,7 @@ mod tests {
                     kind: TokenKind::Collateral,
                     value_modifier: 0,
                     max_staleness: 0,
+                    isolation: None,
                 },
                 user_approval,
             )
@@ -893,6 +1048,7 @@ mod tests {
                     kind: TokenKind::Claim,
                     value_modifier: 0,
                     max_staleness: 0,
+                    isolation: None,
                 },
                 adapter_approval,
             )
@@ -909,6 +1065,7 @@ mod tests {
                     kind: TokenKind::Collateral,
                     value_modifier: 0,
                     max_staleness: 0,
+                    isolation: None,
                 },
                 user_approval,
             )
@@ -943,6 +1100,7 @@ mod tests {
                     kind: TokenKind::Collateral,
                     value_modifier: 0,
                     max_staleness: 100,
+                    isolation: None,
                 },
                 user_approval,
             )
@@ -960,6 +1118,7 @@ mod tests {
                     kind: TokenKind::Collateral,
                     value_modifier: 0,
                     max_staleness: 100,
+                    isolation: None,
                 },
                 user_approval,
             )
@@ -994,7 +1153,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::new_unique(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
@@ -1017,6 +1177,7 @@ mod tests {
                     kind: TokenKind::AdapterCollateral,
                     value_modifier: 0,
                     max_staleness: 0,
+                    isolation: None,
                 },
                 &[],
             )
@@ -1032,6 +1193,7 @@ mod tests {
                     kind: TokenKind::AdapterCollateral,
                     value_modifier: 0,
                     max_staleness: 0,
+                    isolation: None,
                 },
                 &[Approver::MarginAccountAuthority],
             )
@@ -1047,6 +1209,7 @@ mod tests {
                     kind: TokenKind::AdapterCollateral,
                     value_modifier: 0,
                     max_staleness: 0,
+                    isolation: None,
                 },
                 &[Approver::Adapter(adapter)],
             )
@@ -1062,6 +1225,7 @@ mod tests {
                     kind: TokenKind::AdapterCollateral,
                     value_modifier: 0,
                     max_staleness: 0,
+                    isolation: None,
                 },
                 &[Approver::MarginAccountAuthority, Approver::Adapter(adapter)],
             )
@@ -1076,7 +1240,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::new_unique(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
@@ -1096,6 +1261,7 @@ mod tests {
                     kind: TokenKind::AdapterCollateral,
                     value_modifier: 0,
                     max_staleness: 0,
+                    isolation: None,
                 },
                 &[Approver::MarginAccountAuthority, Approver::Adapter(adapter)],
             )
@@ -1108,7 +1274,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::default(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
@@ -1163,6 +1330,7 @@ mod tests {
                 kind,
                 value_modifier: 10000,
                 max_staleness: 0,
+                isolation: None,
             },
             &approvals,
         )?;
@@ -1207,6 +1375,300 @@ mod tests {
         .unwrap()
     }
 
+    fn isolate(acc: &mut MarginAccount, key: Pubkey, group: u16, debt_ceiling: u64) {
+        acc.get_position_mut(&key)
+            .unwrap()
+            .set_isolation(Some(TokenIsolation {
+                group,
+                debt_ceiling,
+            }));
+    }
+
+    #[test]
+    fn isolated_collateral_only_backs_claims_in_its_group() {
+        let mut acc = blank_account();
+        let collateral = register_position(&mut acc, 0, TokenKind::Collateral);
+        let claim = register_position(&mut acc, 1, TokenKind::Claim);
+        isolate(&mut acc, collateral, 1, 0);
+        set_price(&mut acc, collateral, 100);
+        set_price(&mut acc, claim, 100);
+        acc.set_position_balance(&collateral, &collateral, 100, ARBITRARY_TIME)
+            .unwrap();
+        acc.set_position_balance(&claim, &claim, 100, ARBITRARY_TIME)
+            .unwrap();
+        assert_unhealthy(&acc);
+
+        isolate(&mut acc, claim, 2, 0);
+        assert_unhealthy(&acc);
+
+        isolate(&mut acc, claim, 1, 0);
+        assert_healthy(&acc);
+
+        let valuation = acc.valuation(ARBITRARY_TIME).unwrap();
+        assert_eq!(
+            valuation.weighted_collateral,
+            Number128::from_decimal(1010, 0)
+        );
+        assert_eq!(valuation.equity, Number128::ZERO);
+    }
+
+    fn record(acc: &mut MarginAccount, key: Pubkey, amount: u64) {
+        acc.get_position_mut(&key)
+            .unwrap()
+            .record_isolated_debt(amount);
+    }
+
+    #[test]
+    fn isolated_collateral_only_backs_recorded_debt() {
+        let mut acc = blank_account();
+        let collateral = register_position(&mut acc, 0, TokenKind::Collateral);
+        let claim = register_position(&mut acc, 1, TokenKind::Claim);
+        isolate(&mut acc, collateral, 1, 1_000_000);
+        isolate(&mut acc, claim, 1, 0);
+        record(&mut acc, collateral, 500);
+        set_price(&mut acc, collateral, 100);
+        set_price(&mut acc, claim, 100);
+        acc.set_position_balance(&collateral, &collateral, 100, ARBITRARY_TIME)
+            .unwrap();
+        acc.set_position_balance(&claim, &claim, 100, ARBITRARY_TIME)
+            .unwrap();
+        assert_unhealthy(&acc);
+        assert_eq!(
+            acc.isolated_debt(1),
+            acc.valuation(ARBITRARY_TIME).unwrap().liabilities
+        );
+        assert_eq!(acc.isolated_debt(2), Number128::ZERO);
+
+        let valuation = acc.valuation(ARBITRARY_TIME).unwrap();
+        assert_eq!(
+            valuation.weighted_collateral,
+            Number128::from_decimal(505, 0)
+        );
+
+        // the rest of the debt can be backed by collateral that isn't isolated
+        let other = register_position(&mut acc, 2, TokenKind::Collateral);
+        set_price(&mut acc, other, 100);
+        acc.set_position_balance(&other, &other, 100, ARBITRARY_TIME)
+            .unwrap();
+        assert_healthy(&acc);
+
+        acc.set_position_balance(&other, &other, 0, ARBITRARY_TIME)
+            .unwrap();
+        record(&mut acc, collateral, 1000);
+        assert_healthy(&acc);
+    }
+
+    #[test]
+    fn position_with_recorded_debt_cannot_be_closed() {
+        let mut acc = blank_account();
+        let collateral = register_position(&mut acc, 0, TokenKind::Collateral);
+        isolate(&mut acc, collateral, 1, 1_000_000);
+        record(&mut acc, collateral, 500);
+
+        let approvals = [Approver::MarginAccountAuthority];
+        assert!(acc
+            .unregister_position(&collateral, &collateral, &approvals)
+            .is_err());
+
+        record(&mut acc, collateral, 0);
+        acc.unregister_position(&collateral, &collateral, &approvals)
+            .unwrap();
+    }
+
+    #[test]
+    fn position_pages_add_collateral_to_valuation() {
+        let mut acc = blank_account();
+        let claim = register_position(&mut acc, 0, TokenKind::Claim);
+        set_price(&mut acc, claim, 100);
+        acc.set_position_balance(&claim, &claim, 100, ARBITRARY_TIME)
+            .unwrap();
+        assert_unhealthy(&acc);
+
+        let mut page = MarginPositionPage {
+            margin_account: Pubkey::default(),
+            page: 0,
+            reserved0: [0; 7],
+            positions: [0; 7432].into(),
+        };
+        let key = Pubkey::find_program_address(&[&[1]], &crate::id()).0;
+        let config = |kind| PositionConfigUpdate {
+            mint: key,
+            decimals: 2,
+            address: key,
+            airspace: Default::default(),
+            adapter: key,
+            kind,
+            value_modifier: 10000,
+            max_staleness: 0,
+            isolation: None,
+        };
+
+        page.register_position(
+            config(TokenKind::Claim),
+            &[Approver::MarginAccountAuthority, Approver::Adapter(key)],
+        )
+        .unwrap_err();
+        page.register_position(
+            config(TokenKind::Collateral),
+            &[Approver::MarginAccountAuthority],
+        )
+        .unwrap();
+        page.set_position_price(
+            &key,
+            &PriceInfo {
+                value: 100,
+                timestamp: ARBITRARY_TIME,
+                exponent: 1,
+                is_valid: 1,
+                _reserved: [0; 3],
+            },
+        )
+        .unwrap();
+        page.set_position_balance(&key, &key, 200, ARBITRARY_TIME)
+            .unwrap();
+
+        let valuation = acc.valuation_with_pages(ARBITRARY_TIME, &[&page]).unwrap();
+        valuation.verify_healthy().unwrap();
+        assert_eq!(
+            valuation.weighted_collateral,
+            Number128::from_decimal(2000, 0)
+        );
+
+        // leaving out the page can only understate the health of the account
+        assert_unhealthy(&acc);
+
+        // triggers may depend on the prices of positions in the pages
+        let action = ConditionalAction {
+            trigger: ActionTrigger::PriceBelow {
+                mint: key,
+                price: 1000,
+                exponent: 0,
+            },
+            ..Default::default()
+        };
+        action
+            .verify_trigger(&acc, &[&page], ARBITRARY_TIME)
+            .unwrap();
+        action
+            .verify_trigger(&acc, &[], ARBITRARY_TIME)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn full_account_only_has_position_space_while_liquidating() {
+        let mut acc = blank_account();
+        for i in 0..MAX_USER_POSITIONS as u8 {
+            assert!(acc.has_position_space());
+            register_position(&mut acc, i, TokenKind::Collateral);
+        }
+
+        assert!(!acc.has_position_space());
+        try_register_position(&mut acc, MAX_USER_POSITIONS as u8, TokenKind::Collateral)
+            .unwrap_err();
+
+        acc.liquidator = Pubkey::new_unique();
+        assert!(acc.has_position_space());
+        register_position(&mut acc, MAX_USER_POSITIONS as u8, TokenKind::Collateral);
+    }
+
+    #[test]
+    fn conditional_action_triggers_on_price_and_c_ratio() {
+        let mut acc = blank_account();
+        let collateral = register_position(&mut acc, 0, TokenKind::Collateral);
+        let claim = register_position(&mut acc, 1, TokenKind::Claim);
+        set_price(&mut acc, collateral, 100);
+        set_price(&mut acc, claim, 100);
+        acc.set_position_balance(&collateral, &collateral, 200, ARBITRARY_TIME)
+            .unwrap();
+        acc.set_position_balance(&claim, &claim, 100, ARBITRARY_TIME)
+            .unwrap();
+        let valuation = acc.valuation(ARBITRARY_TIME).unwrap();
+        let check = |trigger| {
+            ConditionalAction {
+                trigger,
+                ..Default::default()
+            }
+            .verify_trigger(&acc, &[], ARBITRARY_TIME)
+        };
+
+        // prices are stored with an exponent of 1
+        check(ActionTrigger::PriceBelow {
+            mint: collateral,
+            price: 1000,
+            exponent: 0,
+        })
+        .unwrap();
+        check(ActionTrigger::PriceBelow {
+            mint: collateral,
+            price: 999,
+            exponent: 0,
+        })
+        .unwrap_err();
+        check(ActionTrigger::PriceAbove {
+            mint: collateral,
+            price: 1001,
+            exponent: 0,
+        })
+        .unwrap_err();
+        check(ActionTrigger::PriceAbove {
+            mint: Pubkey::default(),
+            price: 0,
+            exponent: 0,
+        })
+        .unwrap_err();
+
+        let c_ratio = valuation.effective_c_ratio();
+        let bps = (c_ratio * Number128::from_decimal(10_000, 0)).as_u64(0) as u32;
+        check(ActionTrigger::CRatioBelow { c_ratio: bps + 1 }).unwrap();
+        check(ActionTrigger::CRatioBelow { c_ratio: bps }).unwrap_err();
+    }
+
+    #[test]
+    fn conditional_action_instruction_hash_covers_accounts() {
+        let program = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let hash = ConditionalAction::hash_instruction(&program, &[1], [(&account, true)]);
+
+        assert_ne!(
+            hash,
+            ConditionalAction::hash_instruction(&program, &[1], [(&account, false)])
+        );
+        assert_ne!(
+            hash,
+            ConditionalAction::hash_instruction(&program, &[2], [(&account, true)])
+        );
+        assert_ne!(
+            hash,
+            ConditionalAction::hash_instruction(&account, &[1], [(&program, true)])
+        );
+    }
+
+    #[test]
+    fn position_pages_reject_duplicate_page() {
+        let margin_account = Pubkey::new_unique();
+        let page = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; 8 + std::mem::size_of::<MarginPositionPage>()];
+        data[..8].copy_from_slice(&MarginPositionPage::discriminator());
+        data[8..40].copy_from_slice(margin_account.as_ref());
+
+        let info = AccountInfo::new(
+            &page,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &crate::id(),
+            false,
+            0,
+        );
+
+        let (pages, _) = PositionPages::split(&margin_account, &[info.clone()]).unwrap();
+        assert_eq!(pages.pages.len(), 1);
+
+        assert!(PositionPages::split(&margin_account, &[info.clone(), info]).is_err());
+    }
+
     #[test]
     fn proper_account_passes_anchor_verify() {
         MarginAccount::anchor_verify(&AccountInfo::new(
@@ -1307,6 +1769,47 @@ mod tests {
         account.verify_authority(Pubkey::default()).unwrap_err();
     }
 
+    #[test]
+    fn collateral_shortfall_is_measured_against_required_collateral() {
+        let shortfall = |effective: i64, required: i64| {
+            Valuation {
+                equity: Number128::ZERO,
+                liabilities: Number128::from_decimal(1_000, 0),
+                required_collateral: Number128::from_decimal(required, 0),
+                weighted_collateral: Number128::ZERO,
+                effective_collateral: Number128::from_decimal(effective, 0),
+                stale_collateral_list: vec![],
+                past_due: false,
+            }
+            .collateral_shortfall()
+        };
+
+        assert_eq!(shortfall(200, 100), Number128::ZERO);
+        assert_eq!(shortfall(100, 100), Number128::ZERO);
+        assert_eq!(shortfall(75, 100), Number128::from_decimal(25, -2));
+        assert_eq!(shortfall(0, 100), Number128::ONE);
+        assert_eq!(shortfall(-50, 100), Number128::ONE);
+    }
+
+    #[test]
+    fn liquidation_bonus_is_earned_by_repaying_liabilities() {
+        let mut liquidation = Liquidation::new(
+            0,
+            Number128::from_decimal(50, 0),
+            Number128::from_decimal(500, 0),
+        );
+        assert_eq!(liquidation.earned_equity_loss(), Number128::ZERO);
+
+        *liquidation.liabilities_repaid_mut() += Number128::from_decimal(100, 0);
+        assert_eq!(
+            liquidation.earned_equity_loss(),
+            Number128::from_decimal(10, 0)
+        );
+
+        let nothing_to_repay = Liquidation::new(0, Number128::ZERO, Number128::ZERO);
+        assert_eq!(nothing_to_repay.earned_equity_loss(), Number128::ZERO);
+    }
+
     fn pda(index: u8) -> Pubkey {
         Pubkey::find_program_address(&[&[index]], &crate::id()).0
     }
@@ -1316,7 +1819,8 @@ mod tests {
             version: 1,
             bump_seed: [0],
             user_seed: [0; 2],
-            reserved0: [0; 3],
+            position_pages: 0,
+            reserved0: [0; 2],
             owner: Pubkey::default(),
             airspace: Pubkey::default(),
             liquidator: Pubkey::default(),
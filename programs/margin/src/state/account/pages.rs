@@ -0,0 +1,299 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::{prelude::*, Discriminator};
+
+use jet_program_common::pod::PodBytes;
+
+use anchor_lang::Result as AnchorResult;
+use std::result::Result;
+
+use super::{
+    AccountPosition, AccountPositionKey, AccountPositionList, Approver, MarginAccount,
+    PositionConfigUpdate, PriceInfo, Valuation,
+};
+use crate::{ConditionalAction, ErrorCode, TokenKind};
+
+/// Storage for positions beyond those that fit in a [MarginAccount]
+///
+/// Pages only store collateral custodied by the margin account, so that the claims
+/// and adapter positions of an account can always be found in the account itself.
+#[account(zero_copy)]
+#[repr(C)]
+// bytemuck requires a higher alignment than 1 for unit tests to run.
+#[cfg_attr(not(target_arch = "bpf"), repr(align(8)))]
+pub struct MarginPositionPage {
+    /// The margin account the positions belong to
+    pub margin_account: Pubkey,
+
+    /// The index of this page for the margin account
+    pub page: u8,
+
+    pub reserved0: [u8; 7],
+
+    /// The storage for tracking account balances
+    pub positions: PodBytes<7432>,
+}
+
+impl MarginPositionPage {
+    /// Get the list of positions on this page
+    pub fn positions(&self) -> impl Iterator<Item = &AccountPosition> {
+        self.position_list().iter()
+    }
+
+    /// Register the space for a new position into this page
+    pub fn register_position(
+        &mut self,
+        config: PositionConfigUpdate,
+        approvals: &[Approver],
+    ) -> AnchorResult<AccountPositionKey> {
+        if config.kind != TokenKind::Collateral {
+            msg!("only collateral can be registered in a position page");
+            return err!(ErrorCode::PositionNotRegisterable);
+        }
+
+        self.position_list_mut().register(config, approvals)
+    }
+
+    /// Free the space from a previously registered position no longer needed
+    pub fn unregister_position(
+        &mut self,
+        mint: &Pubkey,
+        account: &Pubkey,
+        approvals: &[Approver],
+    ) -> AnchorResult<()> {
+        self.position_list_mut()
+            .unregister(mint, account, approvals)
+    }
+
+    pub fn get_position(&self, mint: &Pubkey) -> Option<&AccountPosition> {
+        self.position_list().get(mint)
+    }
+
+    pub fn get_position_mut(&mut self, mint: &Pubkey) -> Option<&mut AccountPosition> {
+        self.position_list_mut().get_mut(mint)
+    }
+
+    /// Change the balance for a position
+    pub fn set_position_balance(
+        &mut self,
+        mint: &Pubkey,
+        account: &Pubkey,
+        balance: u64,
+        timestamp: u64,
+    ) -> Result<AccountPosition, ErrorCode> {
+        let position = self
+            .position_list_mut()
+            .get_mut(mint)
+            .ok_or(ErrorCode::PositionNotRegistered)?;
+
+        if position.address != *account {
+            return Err(ErrorCode::PositionNotRegistered);
+        }
+
+        position.set_balance(balance, timestamp);
+
+        Ok(*position)
+    }
+
+    /// Change the current price value of a position
+    pub fn set_position_price(
+        &mut self,
+        mint: &Pubkey,
+        price: &PriceInfo,
+    ) -> Result<(), ErrorCode> {
+        self.position_list_mut()
+            .get_mut(mint)
+            .ok_or(ErrorCode::PositionNotRegistered)?
+            .set_price(price)
+    }
+
+    fn position_list(&self) -> &AccountPositionList {
+        bytemuck::from_bytes(&self.positions.0)
+    }
+
+    fn position_list_mut(&mut self) -> &mut AccountPositionList {
+        bytemuck::from_bytes_mut(&mut self.positions.0)
+    }
+}
+
+/// The position pages for a margin account that were provided to an instruction
+pub struct PositionPages<'info> {
+    pub pages: Vec<AccountLoader<'info, MarginPositionPage>>,
+}
+
+impl<'info> PositionPages<'info> {
+    /// Separate the pages for a margin account from any other accounts, which are returned
+    pub fn split(
+        margin_account: &Pubkey,
+        accounts: &[AccountInfo<'info>],
+    ) -> AnchorResult<(Self, Vec<AccountInfo<'info>>)> {
+        let mut pages: Vec<AccountLoader<MarginPositionPage>> = vec![];
+        let mut others = vec![];
+
+        for info in accounts {
+            let is_page = info.owner == &crate::ID
+                && info
+                    .try_borrow_data()?
+                    .starts_with(&MarginPositionPage::discriminator());
+
+            if !is_page {
+                others.push(info.clone());
+                continue;
+            }
+
+            if info.key == margin_account {
+                msg!("the margin account cannot be one of its own pages");
+                return err!(ErrorCode::InvalidPositionPages);
+            }
+
+            let page = AccountLoader::<MarginPositionPage>::try_from(info)?;
+            let loaded = page.load()?;
+
+            if loaded.margin_account != *margin_account {
+                msg!("position page {} is for a different account", info.key);
+                return err!(ErrorCode::InvalidPositionPages);
+            }
+
+            // Counting the same page twice would overstate the collateral in the account
+            for other in &pages {
+                if other.load()?.page == loaded.page {
+                    msg!("position page {} was provided more than once", loaded.page);
+                    return err!(ErrorCode::InvalidPositionPages);
+                }
+            }
+
+            drop(loaded);
+            pages.push(page);
+        }
+
+        Ok((Self { pages }, others))
+    }
+
+    /// Verify that every page of the account was provided, which is required
+    /// whenever understating the collateral in the account would be unsafe.
+    pub fn verify_complete(&self, account: &MarginAccount) -> AnchorResult<()> {
+        let mut indices = self
+            .pages
+            .iter()
+            .map(|p| Ok(p.load()?.page))
+            .collect::<AnchorResult<Vec<u8>>>()?;
+
+        indices.sort_unstable();
+        indices.dedup();
+
+        if indices.len() != account.position_pages as usize {
+            msg!(
+                "expected {} position pages, but got {}",
+                account.position_pages,
+                indices.len()
+            );
+            return err!(ErrorCode::InvalidPositionPages);
+        }
+
+        Ok(())
+    }
+
+    /// Value the account including the positions in the provided pages
+    pub fn valuation(&self, account: &MarginAccount, timestamp: u64) -> AnchorResult<Valuation> {
+        self.with_loaded(|pages| account.valuation_with_pages(timestamp, pages))
+    }
+
+    /// Check that the trigger for a conditional action holds, including the positions
+    /// in the provided pages
+    pub fn verify_trigger(
+        &self,
+        action: &ConditionalAction,
+        account: &MarginAccount,
+        timestamp: u64,
+    ) -> AnchorResult<()> {
+        self.with_loaded(|pages| action.verify_trigger(account, pages, timestamp))
+    }
+
+    fn with_loaded<T>(
+        &self,
+        f: impl FnOnce(&[&MarginPositionPage]) -> AnchorResult<T>,
+    ) -> AnchorResult<T> {
+        let loaded = self
+            .pages
+            .iter()
+            .map(|p| p.load())
+            .collect::<AnchorResult<Vec<_>>>()?;
+        let pages = loaded.iter().map(|p| &**p).collect::<Vec<_>>();
+
+        f(&pages)
+    }
+
+    /// Find the position for a mint in any of the pages
+    pub fn get_position(&self, mint: &Pubkey) -> AnchorResult<Option<AccountPosition>> {
+        for page in &self.pages {
+            if let Some(position) = page.load()?.get_position(mint) {
+                return Ok(Some(*position));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Change the balance for a position stored in one of the pages
+    pub fn set_position_balance(
+        &self,
+        mint: &Pubkey,
+        account: &Pubkey,
+        balance: u64,
+        timestamp: u64,
+    ) -> AnchorResult<AccountPosition> {
+        for page in &self.pages {
+            let mut page = page.load_mut()?;
+
+            if matches!(page.get_position(mint), Some(p) if p.address == *account) {
+                return Ok(page.set_position_balance(mint, account, balance, timestamp)?);
+            }
+        }
+
+        err!(ErrorCode::PositionNotRegistered)
+    }
+
+    /// Change the debt recorded by a position stored in one of the pages, returning
+    /// the previous value
+    pub fn record_isolated_debt(&self, mint: &Pubkey, amount: u64) -> AnchorResult<u64> {
+        for page in &self.pages {
+            let mut page = page.load_mut()?;
+
+            if let Some(position) = page.get_position_mut(mint) {
+                return Ok(position.record_isolated_debt(amount));
+            }
+        }
+
+        err!(ErrorCode::PositionNotRegistered)
+    }
+
+    /// Change the current price value of a position in every page that has it
+    pub fn set_position_price(&self, mint: &Pubkey, price: &PriceInfo) -> AnchorResult<bool> {
+        let mut found = false;
+
+        for page in &self.pages {
+            let mut page = page.load_mut()?;
+
+            if page.get_position(mint).is_some() {
+                page.set_position_price(mint, price)?;
+                found = true;
+            }
+        }
+
+        Ok(found)
+    }
+}
@@ -27,7 +27,7 @@ use anchor_lang::Result as AnchorResult;
 use std::{convert::TryFrom, result::Result};
 
 use super::Approver;
-use crate::{ErrorCode, TokenConfig, TokenKind};
+use crate::{ErrorCode, TokenConfig, TokenIsolation, TokenKind};
 const POS_PRICE_VALID: u8 = 1;
 
 #[assert_size(24)]
@@ -124,7 +124,21 @@ pub struct AccountPosition {
     pub flags: AdapterPositionFlags,
 
     /// Unused
-    pub _reserved: [u8; 23],
+    pub _reserved0: u8,
+
+    /// The isolation group of the token, or zero if the token is not isolated
+    pub isolation_group: u16,
+
+    /// Unused
+    pub _reserved1: [u8; 4],
+
+    /// The ceiling on the total value of debt backed by the token across the airspace,
+    /// if it is isolated collateral. Zero means there is no ceiling.
+    pub debt_ceiling: u64,
+
+    /// The value of debt this position has recorded against the ceiling in the token's
+    /// isolation config, which limits the debt that it may back.
+    pub isolated_debt: u64,
 }
 
 #[repr(transparent)]
@@ -172,6 +186,14 @@ impl AccountPosition {
         Number128::from_bits(self.value)
     }
 
+    /// The isolation group this position belongs to, if any
+    pub fn isolation_group(&self) -> Option<u16> {
+        match self.isolation_group {
+            0 => None,
+            group => Some(group),
+        }
+    }
+
     pub fn collateral_value(&self) -> Number128 {
         assert!(
             self.kind() == TokenKind::Collateral || self.kind() == TokenKind::AdapterCollateral
@@ -193,6 +215,19 @@ impl AccountPosition {
         }
     }
 
+    /// Update the isolation restrictions for this position
+    pub fn set_isolation(&mut self, isolation: Option<TokenIsolation>) {
+        let isolation = isolation.unwrap_or_default();
+
+        self.isolation_group = isolation.group;
+        self.debt_ceiling = isolation.debt_ceiling;
+    }
+
+    /// Change the value of debt recorded by this position, returning the previous value
+    pub fn record_isolated_debt(&mut self, amount: u64) -> u64 {
+        std::mem::replace(&mut self.isolated_debt, amount)
+    }
+
     /// Update the balance for this position
     pub fn set_balance(&mut self, balance: u64, timestamp: u64) {
         self.balance = balance;
@@ -350,6 +385,72 @@ impl AccountPositionList {
         Ok((key, Some(free_position)))
     }
 
+    /// Register a position in the list, initializing it from the config if
+    /// it's not already registered.
+    pub fn register(
+        &mut self,
+        config: PositionConfigUpdate,
+        approvals: &[Approver],
+    ) -> AnchorResult<AccountPositionKey> {
+        let (key, free_position) = self.add(config.mint)?;
+
+        if let Some(free_position) = free_position {
+            free_position.exponent = -(config.decimals as i16);
+            free_position.address = config.address;
+            free_position.adapter = config.adapter;
+            free_position.kind = config.kind.into_integer();
+            free_position.balance = 0;
+            free_position.value_modifier = config.value_modifier;
+            free_position.max_staleness = config.max_staleness;
+            free_position.set_isolation(config.isolation);
+
+            if !free_position.may_be_registered_or_closed(approvals) {
+                msg!(
+                    "{:?} is not authorized to register {:?}",
+                    approvals,
+                    free_position
+                );
+                return err!(ErrorCode::InvalidPositionOwner);
+            }
+        }
+
+        Ok(key)
+    }
+
+    /// Remove a position from the list, if it's allowed to be closed
+    pub fn unregister(
+        &mut self,
+        mint: &Pubkey,
+        account: &Pubkey,
+        approvals: &[Approver],
+    ) -> AnchorResult<()> {
+        let removed = self.remove(mint, account)?;
+
+        if !removed.may_be_registered_or_closed(approvals) {
+            msg!("{:?} is not authorized to close {:?}", approvals, removed);
+            return err!(ErrorCode::InvalidPositionOwner);
+        }
+        if removed.balance != 0 {
+            return err!(ErrorCode::CloseNonZeroPosition);
+        }
+        if removed.flags.contains(AdapterPositionFlags::REQUIRED) {
+            return err!(ErrorCode::CloseRequiredPosition);
+        }
+        if removed.isolated_debt != 0 {
+            msg!("the debt recorded by the position must be released first");
+            return err!(ErrorCode::IsolatedDebtReserved);
+        }
+
+        Ok(())
+    }
+
+    /// Iterate over the registered positions
+    pub fn iter(&self) -> impl Iterator<Item = &AccountPosition> {
+        self.positions
+            .iter()
+            .filter(|p| p.address != Pubkey::default())
+    }
+
     /// Remove a position from the margin account.
     ///
     /// # Error
@@ -436,12 +537,16 @@ pub struct PositionConfigUpdate {
 
     /// Max staleness in seconds for the position balance
     pub max_staleness: u64,
+
+    /// Restrictions on the debt backed by the position, if the token is isolated
+    pub isolation: Option<TokenIsolation>,
 }
 
 impl PositionConfigUpdate {
     /// Generate the configuration from `TokenConfig` account data
     pub fn new_from_config(
         config: &Account<TokenConfig>,
+        isolation: Option<TokenIsolation>,
         mint_decimals: u8,
         address: Pubkey,
         adapter: Pubkey,
@@ -455,6 +560,7 @@ impl PositionConfigUpdate {
             kind: config.token_kind,
             value_modifier: config.value_modifier,
             max_staleness: config.max_staleness,
+            isolation,
         }
     }
 }
@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::UnixTimestamp;
+
+use jet_program_common::Number128;
+
+use crate::{
+    ErrorCode, LiquidationConfig, MarginAccount, TokenKind, LIQUIDATION_AUCTION_DURATION,
+    MAX_USER_POSITIONS,
+};
+
+/// A permissionless liquidation, where the collateral in an unhealthy account is offered
+/// to anyone repaying its claims, at a discount that grows over time.
+#[account]
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct LiquidationAuction {
+    /// The margin account being liquidated
+    pub margin_account: Pubkey,
+
+    /// The address that paid rent for the auction, which is refunded when it ends
+    pub payer: Pubkey,
+
+    /// The time the auction started
+    pub start_time: UnixTimestamp,
+
+    /// The discount (in basis points) offered on collateral when the auction starts
+    pub min_discount: u16,
+
+    /// The discount (in basis points) offered on collateral once the auction has
+    /// run for [LIQUIDATION_AUCTION_DURATION]
+    pub max_discount: u16,
+
+    /// The cumulative value of liabilities repaid by bidders
+    pub liabilities_repaid: i128,
+
+    /// The maximum value of liabilities that may be repaid during the auction
+    pub max_liabilities_repaid: i128,
+
+    /// The claim balances that have not been paid for by a bid yet
+    pub claims: Vec<AuctionClaim>,
+}
+
+/// The balance of a claim in an account being auctioned
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct AuctionClaim {
+    /// The token representing the claim
+    pub mint: Pubkey,
+
+    /// The claim balance after the latest bid to repay it
+    pub balance: u64,
+}
+
+impl LiquidationAuction {
+    pub const SPACE: usize = 8
+        + 32
+        + 32
+        + 8
+        + 2
+        + 2
+        + 16
+        + 16
+        + 4
+        + MAX_USER_POSITIONS as usize * std::mem::size_of::<AuctionClaim>();
+
+    pub fn new(
+        margin_account: Pubkey,
+        payer: Pubkey,
+        start_time: UnixTimestamp,
+        config: &LiquidationConfig,
+        account: &MarginAccount,
+        liabilities: Number128,
+    ) -> Self {
+        let claims = account
+            .positions()
+            .filter(|p| p.kind() == TokenKind::Claim && p.balance > 0)
+            .map(|p| AuctionClaim {
+                mint: p.token,
+                balance: p.balance,
+            })
+            .collect();
+
+        Self {
+            margin_account,
+            payer,
+            start_time,
+            min_discount: config.min_bonus,
+            max_discount: config.max_bonus,
+            liabilities_repaid: 0,
+            max_liabilities_repaid: config.max_liabilities_repaid(liabilities).to_i128(),
+            claims,
+        }
+    }
+
+    /// The discount offered on collateral at the given time, as a fraction of
+    /// the value repaid.
+    ///
+    /// Grows linearly from the min discount to the max discount over the auction duration.
+    pub fn discount(&self, timestamp: UnixTimestamp) -> Number128 {
+        let min_discount = Number128::from_bps(self.min_discount);
+        let max_discount = Number128::from_bps(self.max_discount);
+
+        let elapsed = (timestamp - self.start_time).clamp(0, LIQUIDATION_AUCTION_DURATION);
+        let progress = Number128::from_decimal(elapsed, 0)
+            / Number128::from_decimal(LIQUIDATION_AUCTION_DURATION, 0);
+
+        min_discount + (max_discount - min_discount) * progress
+    }
+
+    /// Record the current balance of a claim, returning the amount repaid since
+    /// the last recorded balance.
+    pub fn record_claim_balance(&mut self, mint: &Pubkey, balance: u64) -> Result<u64> {
+        let claim = match self.claims.iter_mut().find(|c| c.mint == *mint) {
+            Some(claim) => claim,
+            None => {
+                msg!("claim {} is not part of the auction", mint);
+                return err!(ErrorCode::InvalidAuctionBid);
+            }
+        };
+
+        let repaid = claim.balance.saturating_sub(balance);
+        claim.balance = balance;
+
+        Ok(repaid)
+    }
+
+    /// Add to the value repaid by bidders, failing if it exceeds the close factor
+    pub fn add_liabilities_repaid(&mut self, value: Number128) -> Result<()> {
+        let repaid = Number128::from_i128(self.liabilities_repaid) + value;
+
+        if repaid > Number128::from_i128(self.max_liabilities_repaid) {
+            msg!(
+                "repaid {} of liabilities which exceeds the close factor limit of {}",
+                repaid,
+                Number128::from_i128(self.max_liabilities_repaid)
+            );
+            return err!(ErrorCode::LiquidationExceedsCloseFactor);
+        }
+
+        self.liabilities_repaid = repaid.to_i128();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auction() -> LiquidationAuction {
+        LiquidationAuction {
+            start_time: 1_000,
+            min_discount: 2_00,
+            max_discount: 10_00,
+            max_liabilities_repaid: Number128::from_decimal(100, 0).to_i128(),
+            claims: vec![AuctionClaim {
+                mint: Pubkey::new_from_array([1; 32]),
+                balance: 500,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn discount_grows_over_auction() {
+        let auction = auction();
+        let halfway = 1_000 + LIQUIDATION_AUCTION_DURATION / 2;
+
+        assert_eq!(auction.discount(1_000), Number128::from_bps(2_00));
+        assert_eq!(auction.discount(halfway), Number128::from_bps(6_00));
+        assert_eq!(
+            auction.discount(1_000 + LIQUIDATION_AUCTION_DURATION * 2),
+            Number128::from_bps(10_00)
+        );
+    }
+
+    #[test]
+    fn claims_are_only_paid_for_once() {
+        let mut auction = auction();
+        let mint = Pubkey::new_from_array([1; 32]);
+
+        assert_eq!(auction.record_claim_balance(&mint, 400).unwrap(), 100);
+        assert_eq!(auction.record_claim_balance(&mint, 400).unwrap(), 0);
+        assert_eq!(auction.record_claim_balance(&mint, 450).unwrap(), 0);
+        assert_eq!(auction.record_claim_balance(&mint, 300).unwrap(), 150);
+        auction
+            .record_claim_balance(&Pubkey::default(), 0)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn repaid_liabilities_limited_by_close_factor() {
+        let mut auction = auction();
+
+        auction
+            .add_liabilities_repaid(Number128::from_decimal(60, 0))
+            .unwrap();
+        auction
+            .add_liabilities_repaid(Number128::from_decimal(60, 0))
+            .unwrap_err();
+        auction
+            .add_liabilities_repaid(Number128::from_decimal(40, 0))
+            .unwrap();
+    }
+}
@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::Discriminator;
+
+use jet_program_common::{Number128, BPS_EXPONENT};
+
+use crate::{ErrorCode, MarginAccount, MarginPositionPage, MAX_PRICE_QUOTE_AGE};
+
+/// An adapter instruction pre-authorized by the owner of a margin account, which any
+/// keeper may execute through `adapter_invoke` once its trigger holds.
+///
+/// The keeper that executes the action receives the lamports in this account, which
+/// includes the tip deposited when it was created.
+#[account]
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct ConditionalAction {
+    /// The margin account the action is for
+    pub margin_account: Pubkey,
+
+    /// The seed used to derive the address of this action
+    pub seed: u16,
+
+    /// The condition under which the action may be executed
+    pub trigger: ActionTrigger,
+
+    /// The adapter program to be invoked
+    pub adapter_program: Pubkey,
+
+    /// The hash of the adapter instruction, as computed by [ConditionalAction::hash_instruction]
+    pub instruction_hash: [u8; 32],
+
+    /// The lamports paid to the keeper in addition to the rent for this account
+    pub tip: u64,
+}
+
+/// The condition under which a [ConditionalAction] may be executed
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ActionTrigger {
+    /// The price of a position is at or below a value, such as for a stop-loss
+    PriceBelow {
+        mint: Pubkey,
+        price: i64,
+        exponent: i32,
+    },
+
+    /// The price of a position is at or above a value, such as for a take-profit
+    PriceAbove {
+        mint: Pubkey,
+        price: i64,
+        exponent: i32,
+    },
+
+    /// The effective collateralization ratio (in basis points) of the account is below a value
+    CRatioBelow { c_ratio: u32 },
+}
+
+impl Default for ActionTrigger {
+    fn default() -> Self {
+        Self::CRatioBelow { c_ratio: 0 }
+    }
+}
+
+impl ConditionalAction {
+    pub const SPACE: usize = 8 + 32 + 2 + (1 + 32 + 8 + 4) + 32 + 32 + 8;
+
+    /// Hash an adapter instruction, as it is passed through `adapter_invoke`
+    pub fn hash_instruction<'a>(
+        program_id: &Pubkey,
+        data: &[u8],
+        accounts: impl IntoIterator<Item = (&'a Pubkey, bool)>,
+    ) -> [u8; 32] {
+        let mut accounts_data = vec![];
+
+        for (key, is_writable) in accounts {
+            accounts_data.extend_from_slice(key.as_ref());
+            accounts_data.push(is_writable as u8);
+        }
+
+        hashv(&[program_id.as_ref(), data, &accounts_data]).to_bytes()
+    }
+
+    /// Find an action for the margin account among a list of accounts, which is returned
+    /// along with the other accounts.
+    pub fn split<'info>(
+        margin_account: &Pubkey,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<(
+        Option<Account<'info, ConditionalAction>>,
+        Vec<AccountInfo<'info>>,
+    )> {
+        let mut action = None;
+        let mut others = vec![];
+
+        for info in accounts {
+            let is_action = action.is_none()
+                && info.owner == &crate::ID
+                && info
+                    .try_borrow_data()?
+                    .starts_with(&ConditionalAction::discriminator());
+
+            if is_action {
+                let account = Account::<ConditionalAction>::try_from(info)?;
+
+                if account.margin_account == *margin_account {
+                    action = Some(account);
+                    continue;
+                }
+            }
+
+            others.push(info.clone());
+        }
+
+        Ok((action, others))
+    }
+
+    /// Check that the instruction being invoked is the one that was authorized
+    pub fn verify_instruction(
+        &self,
+        program_id: &Pubkey,
+        data: &[u8],
+        accounts: &[AccountInfo],
+    ) -> Result<()> {
+        let hash = Self::hash_instruction(
+            program_id,
+            data,
+            accounts.iter().map(|info| (info.key, info.is_writable)),
+        );
+
+        if *program_id != self.adapter_program || hash != self.instruction_hash {
+            msg!("the instruction does not match the conditional action");
+            return err!(ErrorCode::ConditionalActionMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Check that the trigger for the action holds, using the positions in the account
+    /// and the provided pages
+    pub fn verify_trigger(
+        &self,
+        account: &MarginAccount,
+        pages: &[&MarginPositionPage],
+        timestamp: u64,
+    ) -> Result<()> {
+        let triggered = match self.trigger {
+            ActionTrigger::PriceBelow {
+                mint,
+                price,
+                exponent,
+            } => {
+                current_price(account, pages, &mint, timestamp)?
+                    <= Number128::from_decimal(price, exponent)
+            }
+            ActionTrigger::PriceAbove {
+                mint,
+                price,
+                exponent,
+            } => {
+                current_price(account, pages, &mint, timestamp)?
+                    >= Number128::from_decimal(price, exponent)
+            }
+            ActionTrigger::CRatioBelow { c_ratio } => {
+                let valuation = account.valuation_with_pages(timestamp, pages)?;
+
+                valuation.verify_not_stale()?;
+                valuation.effective_c_ratio() < Number128::from_decimal(c_ratio, BPS_EXPONENT)
+            }
+        };
+
+        if !triggered {
+            msg!("the trigger {:?} does not hold", self.trigger);
+            return err!(ErrorCode::ConditionNotMet);
+        }
+
+        Ok(())
+    }
+}
+
+fn current_price(
+    account: &MarginAccount,
+    pages: &[&MarginPositionPage],
+    mint: &Pubkey,
+    timestamp: u64,
+) -> Result<Number128> {
+    let position = account
+        .get_position(mint)
+        .or_else(|| pages.iter().find_map(|page| page.get_position(mint)));
+
+    let position = match position {
+        Some(position) => position,
+        None => return err!(ErrorCode::PositionNotRegistered),
+    };
+
+    if !position.price.is_valid() {
+        return err!(ErrorCode::InvalidPrice);
+    }
+
+    if timestamp.saturating_sub(position.price.timestamp) > MAX_PRICE_QUOTE_AGE {
+        return err!(ErrorCode::OutdatedPrice);
+    }
+
+    Ok(Number128::from_decimal(
+        position.price.value,
+        position.price.exponent,
+    ))
+}
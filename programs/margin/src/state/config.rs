@@ -15,11 +15,17 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::convert::TryFrom;
+
 use anchor_lang::prelude::*;
 use bitflags::bitflags;
 use bytemuck::Contiguous;
+use jet_program_common::{oracle::PushPriceFeed, Number128};
 
-use crate::{ErrorCode, TokenConfigUpdate};
+use crate::{
+    ErrorCode, MarginAccount, PositionPages, PriceChangeInfo, PriceInfo, TokenConfigUpdate,
+    LIQUIDATION_MAX_TOTAL_EQUITY_LOSS_BPS, MAX_FALLBACK_ORACLES,
+};
 
 /// Description of the token's usage
 #[derive(AnchorSerialize, AnchorDeserialize, Contiguous, Eq, PartialEq, Clone, Copy, Debug)]
@@ -83,6 +89,16 @@ pub struct TokenConfig {
     /// The administrator of this token, which has the authority to provide information
     /// about (e.g. prices) and otherwise modify position states for these tokens.
     pub admin: TokenAdmin,
+
+    /// Whether the token is also priced by the oracles in a [TokenOracleSet], which
+    /// must then be provided whenever the price is refreshed.
+    pub has_oracle_set: bool,
+
+    /// Whether the token is isolated, with the restrictions in a [TokenIsolationConfig], which
+    /// must then be provided whenever a position for the token is registered or refreshed.
+    pub is_isolated: bool,
+    // Configs created before these flags were added were only allocated 8 + 176 bytes,
+    // so any other new fields must be stored in a separate account.
 }
 
 impl PartialEq<TokenConfigUpdate> for TokenConfig {
@@ -104,6 +120,10 @@ impl TokenConfig {
             return err!(ErrorCode::InvalidConfig);
         }
 
+        if let TokenAdmin::Margin { oracle } = self.admin {
+            oracle.validate()?;
+        }
+
         Ok(())
     }
 
@@ -122,6 +142,182 @@ impl TokenConfig {
     }
 }
 
+/// Restrictions on the use of a token that is isolated from other positions in
+/// a margin account
+///
+/// Isolated collateral can only back claims configured with the same isolation group,
+/// whereas claims in an isolation group can be backed by any non-isolated collateral
+/// as well.
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct TokenIsolation {
+    /// The group of tokens this token is isolated with, which must not be zero
+    pub group: u16,
+
+    /// The maximum value (in USD) of debt that the token can back, in total across
+    /// all the margin accounts in the airspace. Zero means there is no ceiling.
+    ///
+    /// The debt in an account is recorded against the ceiling when the isolation config is
+    /// provided to `adapter_invoke`, and the token only counts as collateral for the debt
+    /// that has been recorded. Only applies to collateral.
+    pub debt_ceiling: u64,
+}
+
+impl TokenIsolation {
+    pub fn validate(&self) -> Result<()> {
+        if self.group == 0 {
+            msg!("the isolation group must be set");
+            return err!(ErrorCode::InvalidConfig);
+        }
+
+        Ok(())
+    }
+}
+
+/// The restrictions for an isolated token, along with the debt it backs across the airspace
+#[account]
+#[derive(Debug, Eq, PartialEq)]
+pub struct TokenIsolationConfig {
+    /// The airspace the restrictions apply in
+    pub airspace: Pubkey,
+
+    /// The mint for the isolated token
+    pub mint: Pubkey,
+
+    /// The restrictions on the debt that can be backed by the token
+    pub isolation: TokenIsolation,
+
+    /// The total value (in USD) of debt that margin accounts in the airspace have
+    /// recorded against the debt ceiling for the token.
+    pub isolated_debt: u64,
+}
+
+impl TokenIsolationConfig {
+    pub const SPACE: usize = 8 + 32 + 32 + 2 + 8 + 8;
+
+    /// Find the isolation restrictions for a token among a list of accounts, which must
+    /// include the isolation config if the token is isolated.
+    pub fn find(
+        config: &Account<TokenConfig>,
+        accounts: &[AccountInfo],
+    ) -> Result<Option<TokenIsolation>> {
+        if !config.is_isolated {
+            return Ok(None);
+        }
+
+        for info in accounts {
+            let is_isolation = info.owner == &crate::ID
+                && info
+                    .try_borrow_data()?
+                    .starts_with(&TokenIsolationConfig::discriminator());
+
+            if is_isolation {
+                let isolation = Account::<TokenIsolationConfig>::try_from(info)?;
+
+                if isolation.airspace == config.airspace && isolation.mint == config.mint {
+                    return Ok(Some(isolation.isolation));
+                }
+            }
+        }
+
+        msg!(
+            "the isolation config for token {} must be provided",
+            config.mint
+        );
+        err!(ErrorCode::IsolationConfigRequired)
+    }
+
+    /// Separate the isolation configs for an airspace from a list of accounts
+    pub fn split<'info>(
+        airspace: &Pubkey,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<(
+        Vec<Account<'info, TokenIsolationConfig>>,
+        Vec<AccountInfo<'info>>,
+    )> {
+        let mut configs: Vec<Account<TokenIsolationConfig>> = vec![];
+        let mut others = vec![];
+
+        for info in accounts {
+            let is_isolation = info.owner == &crate::ID
+                && info
+                    .try_borrow_data()?
+                    .starts_with(&TokenIsolationConfig::discriminator());
+
+            if !is_isolation {
+                others.push(info.clone());
+                continue;
+            }
+
+            let config = Account::<TokenIsolationConfig>::try_from(info)?;
+
+            if config.airspace != *airspace {
+                return err!(ErrorCode::WrongAirspace);
+            }
+
+            // Recording the same debt twice would overstate it against the ceiling
+            if configs.iter().any(|other| other.key() == config.key()) {
+                msg!("isolation config {} was provided more than once", info.key);
+                return err!(ErrorCode::InvalidConfig);
+            }
+
+            configs.push(config);
+        }
+
+        Ok((configs, others))
+    }
+
+    /// Record all of the debt in the isolation group of the token against the ceiling, as
+    /// the debt that the account's position for the token may back. This replaces the debt
+    /// the position recorded before, and returns the amount recorded.
+    ///
+    /// The debt is rounded up to a whole USD value, and it can't be increased beyond the
+    /// ceiling. Since claims are never stored in position pages, the pages are only needed
+    /// when the position for the token is stored in one.
+    pub fn record_debt(
+        &mut self,
+        account: &mut MarginAccount,
+        pages: &PositionPages,
+    ) -> Result<u64> {
+        if account.airspace != self.airspace {
+            return err!(ErrorCode::WrongAirspace);
+        }
+
+        let debt = account.isolated_debt(self.isolation.group);
+        let mut amount = debt.as_u64(0);
+
+        if Number128::from_decimal(amount, 0) < debt {
+            amount += 1;
+        }
+
+        let previous = match account.get_position_mut(&self.mint) {
+            Some(position) => position.record_isolated_debt(amount),
+            None => pages.record_isolated_debt(&self.mint, amount)?,
+        };
+
+        if amount <= previous {
+            self.isolated_debt = self.isolated_debt.saturating_sub(previous - amount);
+            return Ok(amount);
+        }
+
+        let total = self.isolated_debt + (amount - previous);
+        let ceiling = self.isolation.debt_ceiling;
+
+        if ceiling > 0 && total > ceiling {
+            msg!(
+                "recording {} would bring the isolated debt to {} of {}",
+                amount,
+                total,
+                ceiling
+            );
+            return err!(ErrorCode::IsolatedDebtCeilingExceeded);
+        }
+
+        self.isolated_debt = total;
+
+        Ok(amount)
+    }
+}
+
 /// Information about where to find the oracle data for a token
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Eq, PartialEq, Clone, Copy)]
 pub enum TokenOracle {
@@ -132,6 +328,196 @@ pub enum TokenOracle {
         /// The pyth address with product information for a token
         product: Pubkey,
     },
+
+    /// A price account in the [PushPriceFeed] format, which is written by some
+    /// other program trusted to publish prices for the token.
+    Push {
+        /// The address containing price information for a token
+        price: Pubkey,
+
+        /// The program that must own the price account
+        program: Pubkey,
+    },
+}
+
+impl TokenOracle {
+    pub fn validate(&self) -> Result<()> {
+        if let TokenOracle::Push { program, .. } = self {
+            if *program == Pubkey::default() {
+                msg!("the program publishing prices must be set");
+                return err!(ErrorCode::InvalidOracle);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The address of the account containing the price data
+    pub fn price_account(&self) -> Pubkey {
+        match self {
+            TokenOracle::Pyth { price, .. } => *price,
+            TokenOracle::Push { price, .. } => *price,
+        }
+    }
+
+    /// Read the current price from the oracle account
+    ///
+    /// The price is not checked for validity here, which is done when the price
+    /// is applied to a position.
+    pub fn load_price(&self, oracle_info: &AccountInfo) -> Result<PriceChangeInfo> {
+        if oracle_info.key() != self.price_account() {
+            msg!(
+                "expected oracle {} but got {}",
+                self.price_account(),
+                oracle_info.key()
+            );
+            return err!(ErrorCode::InvalidOracle);
+        }
+
+        match self {
+            TokenOracle::Pyth { .. } => {
+                match pyth_sdk_solana::load_price_feed_from_account_info(oracle_info) {
+                    Ok(feed) => Ok(PriceChangeInfo::try_from(feed)?),
+                    Err(e) => {
+                        msg!("the oracle account is not valid: {:?}", e);
+                        err!(ErrorCode::InvalidOracle)
+                    }
+                }
+            }
+
+            TokenOracle::Push { program, .. } => {
+                if oracle_info.owner != program {
+                    msg!(
+                        "oracle is owned by {} instead of {}",
+                        oracle_info.owner,
+                        program
+                    );
+                    return err!(ErrorCode::InvalidOracle);
+                }
+
+                match PushPriceFeed::load(&oracle_info.try_borrow_data()?) {
+                    Some(feed) => Ok(feed.into()),
+                    None => {
+                        msg!("the oracle account is not a valid push price feed");
+                        err!(ErrorCode::InvalidOracle)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// How the prices from multiple oracles are combined into a single price
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Eq, PartialEq, Clone, Copy)]
+pub enum OracleAggregation {
+    /// Use the first oracle with a valid price, in order of preference
+    Fallback,
+
+    /// Use the median of all the valid prices. With an even number of valid
+    /// prices, the lower of the two middle prices is used.
+    Median,
+}
+
+/// Oracles used to price a token in addition to the oracle in its [TokenConfig]
+#[account]
+#[derive(Debug, Eq, PartialEq)]
+pub struct TokenOracleSet {
+    /// The token config the oracles are used with
+    pub token_config: Pubkey,
+
+    /// How the prices from each oracle are combined
+    pub aggregation: OracleAggregation,
+
+    /// The maximum deviation (in basis points) allowed between any valid
+    /// oracle price and the aggregated price, before the price is rejected.
+    pub max_deviation: u16,
+
+    /// The oracles to use after the oracle in the token config, in order of preference
+    pub fallbacks: Vec<TokenOracle>,
+}
+
+impl TokenOracleSet {
+    pub const SPACE: usize =
+        8 + 32 + 1 + 2 + 4 + MAX_FALLBACK_ORACLES * std::mem::size_of::<TokenOracle>();
+
+    pub fn validate(&self, primary: &TokenOracle) -> Result<()> {
+        if self.fallbacks.is_empty() || self.fallbacks.len() > MAX_FALLBACK_ORACLES {
+            msg!(
+                "between 1 and {} fallback oracles must be set",
+                MAX_FALLBACK_ORACLES
+            );
+            return err!(ErrorCode::InvalidOracle);
+        }
+
+        if self.max_deviation == 0 || self.max_deviation > 10_000 {
+            msg!("the max deviation must be between 1 and 10000 bps");
+            return err!(ErrorCode::InvalidConfig);
+        }
+
+        let mut seen = vec![primary.price_account()];
+
+        for oracle in &self.fallbacks {
+            oracle.validate()?;
+
+            if seen.contains(&oracle.price_account()) {
+                msg!("oracle {} is used more than once", oracle.price_account());
+                return err!(ErrorCode::InvalidOracle);
+            }
+
+            seen.push(oracle.price_account());
+        }
+
+        Ok(())
+    }
+
+    /// Combine the prices from every oracle into a single price for the token
+    ///
+    /// The `prices` are expected to start with the price from the primary oracle, followed
+    /// by the price from each fallback in order. The result is invalid when no oracle has
+    /// a valid price, or when any valid price deviates too far from the selected price.
+    pub fn aggregate(&self, prices: &[PriceInfo]) -> PriceInfo {
+        let mut valid = prices
+            .iter()
+            .filter(|p| p.is_valid())
+            .map(|p| (Number128::from_decimal(p.value, p.exponent), *p))
+            .collect::<Vec<_>>();
+
+        let selected = match self.aggregation {
+            OracleAggregation::Fallback => valid.first(),
+            OracleAggregation::Median => {
+                valid.sort_by_key(|(value, _)| *value);
+                valid.get(valid.len().saturating_sub(1) / 2)
+            }
+        };
+
+        let (selected_value, selected) = match selected {
+            Some(selected) => *selected,
+            None => {
+                msg!("no oracle has a valid price");
+                return PriceInfo::new_invalid();
+            }
+        };
+
+        if selected_value <= Number128::ZERO {
+            msg!("the selected price must be positive");
+            return PriceInfo::new_invalid();
+        }
+
+        let max_deviation = Number128::from_bps(self.max_deviation);
+
+        for (value, _) in &valid {
+            if (*value - selected_value).abs() / selected_value > max_deviation {
+                msg!(
+                    "oracle prices deviate too far: {} vs {}",
+                    value.as_f64(),
+                    selected_value.as_f64()
+                );
+                return PriceInfo::new_invalid();
+            }
+        }
+
+        selected
+    }
 }
 
 /// Description of which program administers a token
@@ -217,3 +603,238 @@ pub struct AdapterConfig {
     /// The program address allowed to be called as an adapter
     pub adapter_program: Pubkey,
 }
+
+/// Limits on how much of an account may be liquidated at once, and how much
+/// liquidators are paid for it
+#[account]
+#[derive(Debug, Eq, PartialEq)]
+pub struct LiquidationConfig {
+    /// The airspace the config applies to
+    pub airspace: Pubkey,
+
+    /// The maximum fraction (in basis points) of an account's liabilities that may be
+    /// repaid during a single liquidation
+    pub close_factor: u16,
+
+    /// The bonus (in basis points of the repaid liabilities) paid to a liquidator
+    /// when the account is only just unhealthy
+    pub min_bonus: u16,
+
+    /// The bonus (in basis points of the repaid liabilities) paid to a liquidator
+    /// when the account has no effective collateral left
+    pub max_bonus: u16,
+}
+
+impl Default for LiquidationConfig {
+    /// Matches the limits used before liquidations were configurable
+    fn default() -> Self {
+        Self {
+            airspace: Pubkey::default(),
+            close_factor: 100_00,
+            min_bonus: LIQUIDATION_MAX_TOTAL_EQUITY_LOSS_BPS,
+            max_bonus: LIQUIDATION_MAX_TOTAL_EQUITY_LOSS_BPS,
+        }
+    }
+}
+
+impl LiquidationConfig {
+    /// Load the config for an airspace from an account which may not be initialized,
+    /// in which case the default config is used.
+    pub fn load_or_default(info: &AccountInfo, airspace: Pubkey) -> Result<Self> {
+        if info.owner != &Self::owner() {
+            return Ok(Self {
+                airspace,
+                ..Default::default()
+            });
+        }
+
+        let config = Account::<Self>::try_from(info)?;
+
+        if config.airspace != airspace {
+            return err!(ErrorCode::WrongAirspace);
+        }
+
+        Ok(config.into_inner())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.close_factor == 0 || self.close_factor > 100_00 {
+            msg!("the close factor must be between 1 and 10000 bps");
+            return err!(ErrorCode::InvalidConfig);
+        }
+
+        if self.min_bonus > self.max_bonus || self.max_bonus > 100_00 {
+            msg!("the bonus must be non-decreasing and at most 10000 bps");
+            return err!(ErrorCode::InvalidConfig);
+        }
+
+        Ok(())
+    }
+
+    /// The value of liabilities that may be repaid while liquidating an account
+    pub fn max_liabilities_repaid(&self, liabilities: Number128) -> Number128 {
+        liabilities * Number128::from_bps(self.close_factor)
+    }
+
+    /// The bonus paid to a liquidator, as a fraction of the repaid liabilities.
+    ///
+    /// Scales linearly with the account's collateral shortfall, from the min bonus when the
+    /// account just meets its collateral requirement (or does, but has past due claims), to
+    /// the max bonus when it has no effective collateral left.
+    pub fn bonus(&self, collateral_shortfall: Number128) -> Number128 {
+        let min_bonus = Number128::from_bps(self.min_bonus);
+        let max_bonus = Number128::from_bps(self.max_bonus);
+        let shortfall = collateral_shortfall.clamp(Number128::ZERO, Number128::ONE);
+
+        min_bonus + (max_bonus - min_bonus) * shortfall
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oracle_set(aggregation: OracleAggregation) -> TokenOracleSet {
+        TokenOracleSet {
+            token_config: Pubkey::default(),
+            aggregation,
+            max_deviation: 1_00,
+            fallbacks: vec![],
+        }
+    }
+
+    fn price(value: i64) -> PriceInfo {
+        PriceInfo::new_valid(-2, value, 100)
+    }
+
+    #[test]
+    fn fallback_uses_first_valid_price() {
+        let set = oracle_set(OracleAggregation::Fallback);
+
+        assert_eq!(set.aggregate(&[price(1000), price(1005)]), price(1000));
+        assert_eq!(
+            set.aggregate(&[PriceInfo::new_invalid(), price(1005)]),
+            price(1005)
+        );
+        assert!(!set
+            .aggregate(&[PriceInfo::new_invalid(), PriceInfo::new_invalid()])
+            .is_valid());
+    }
+
+    #[test]
+    fn median_ignores_invalid_prices() {
+        let set = oracle_set(OracleAggregation::Median);
+
+        assert_eq!(
+            set.aggregate(&[price(1005), price(1000), price(1002)]),
+            price(1002)
+        );
+        assert_eq!(
+            set.aggregate(&[price(1005), PriceInfo::new_invalid(), price(1000)]),
+            price(1000)
+        );
+    }
+
+    #[test]
+    fn deviating_prices_are_invalid() {
+        let set = oracle_set(OracleAggregation::Fallback);
+        assert!(!set.aggregate(&[price(1000), price(1020)]).is_valid());
+
+        let set = oracle_set(OracleAggregation::Median);
+        assert!(!set
+            .aggregate(&[price(1000), price(1001), price(2000)])
+            .is_valid());
+    }
+
+    #[test]
+    fn prices_with_different_exponents_are_compared() {
+        let set = oracle_set(OracleAggregation::Fallback);
+
+        assert!(set
+            .aggregate(&[price(1000), PriceInfo::new_valid(-3, 10_005, 100)])
+            .is_valid());
+    }
+
+    #[test]
+    fn token_config_fits_in_original_space() {
+        // 8 bytes for the discriminator, and 176 for the original struct
+        const ORIGINAL_SPACE: usize = 8 + 176;
+
+        let mut config = TokenConfig {
+            mint: Pubkey::new_unique(),
+            underlying_mint: Pubkey::new_unique(),
+            airspace: Pubkey::new_unique(),
+            token_kind: TokenKind::Collateral,
+            value_modifier: 1_00,
+            max_staleness: 30,
+            admin: TokenAdmin::Margin {
+                oracle: TokenOracle::Push {
+                    price: Pubkey::new_unique(),
+                    program: Pubkey::new_unique(),
+                },
+            },
+            has_oracle_set: true,
+            is_isolated: true,
+        };
+
+        let mut data = vec![];
+        config.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= ORIGINAL_SPACE);
+
+        // configs written before the flags were added leave them zeroed
+        let original_len = data.len() - 2;
+        data.truncate(original_len);
+        data.resize(ORIGINAL_SPACE, 0);
+
+        config.has_oracle_set = false;
+        config.is_isolated = false;
+        assert_eq!(
+            TokenConfig::try_deserialize(&mut &data[..]).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn liquidation_bonus_scales_with_shortfall() {
+        let config = LiquidationConfig {
+            close_factor: 50_00,
+            min_bonus: 2_00,
+            max_bonus: 10_00,
+            ..Default::default()
+        };
+
+        assert_eq!(config.bonus(-Number128::ONE), Number128::from_bps(2_00));
+        assert_eq!(config.bonus(Number128::ZERO), Number128::from_bps(2_00));
+        assert_eq!(
+            config.bonus(Number128::from_decimal(5, -1)),
+            Number128::from_bps(6_00)
+        );
+        assert_eq!(config.bonus(Number128::ONE), Number128::from_bps(10_00));
+        assert_eq!(config.bonus(Number128::ONE * 2), Number128::from_bps(10_00));
+        assert_eq!(
+            config.max_liabilities_repaid(Number128::from_decimal(1000, 0)),
+            Number128::from_decimal(500, 0)
+        );
+    }
+
+    #[test]
+    fn liquidation_config_validation() {
+        LiquidationConfig::default().validate().unwrap();
+
+        let invalid = [
+            (0, 0, 0),
+            (100_01, 0, 0),
+            (100_00, 5_00, 4_00),
+            (100_00, 0, 100_01),
+        ];
+        for (close_factor, min_bonus, max_bonus) in invalid {
+            let config = LiquidationConfig {
+                close_factor,
+                min_bonus,
+                max_bonus,
+                ..Default::default()
+            };
+            config.validate().unwrap_err();
+        }
+    }
+}
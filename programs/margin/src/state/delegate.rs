@@ -0,0 +1,464 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::UnixTimestamp;
+use anchor_lang::Discriminator;
+use bitflags::bitflags;
+
+use jet_program_common::Number128;
+
+use crate::{
+    seeds::MARGIN_DELEGATE_SEED, AccountPosition, ErrorCode, MarginAccount, MarginDelegateUpdate,
+    PositionPages, TokenKind, MAX_DELEGATE_LIMITS,
+};
+
+/// Authorization for an address other than the owner to operate a margin account
+/// through adapters, within the limits set by the owner.
+#[account]
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct MarginDelegate {
+    /// The margin account that may be operated by the delegate
+    pub margin_account: Pubkey,
+
+    /// The address that may sign for the margin account
+    pub delegate: Pubkey,
+
+    /// The kinds of changes the delegate may make to the account
+    pub permissions: DelegatePermissions,
+
+    /// The time after which the delegate may no longer sign, or zero if it never expires
+    pub expires_at: UnixTimestamp,
+
+    /// The loss in value (in basis points) tolerated when exchanging positions,
+    /// beyond which the change is treated as a withdrawal
+    pub max_slippage: u16,
+
+    /// Limits on how much the delegate may reduce the balance of a position
+    pub limits: Vec<DelegateMintLimit>,
+}
+
+/// A limit on the total amount a delegate may reduce the balance of a position by
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct DelegateMintLimit {
+    /// The token for the position
+    pub mint: Pubkey,
+
+    /// The maximum total reduction in the position balance
+    pub limit: u64,
+
+    /// The total the position balance has been reduced by the delegate
+    pub used: u64,
+}
+
+/// Changes to a margin account that a delegate may be allowed to make
+#[derive(Debug, Eq, PartialEq, Default, AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+#[repr(transparent)]
+pub struct DelegatePermissions(u32);
+
+bitflags! {
+    impl DelegatePermissions: u32 {
+        /// Exchange some collateral for other collateral.
+        const SWAP      = 1 << 0;
+
+        /// Reduce claims on the account.
+        const REPAY     = 1 << 1;
+
+        /// Increase claims on the account.
+        const BORROW    = 1 << 2;
+
+        /// Reduce the equity of the account, such as by moving tokens out of it.
+        const WITHDRAW  = 1 << 3;
+    }
+}
+
+/// The balance and value of a position before or after an invocation
+#[derive(Debug, Clone, Copy)]
+pub struct PositionSnapshot {
+    pub mint: Pubkey,
+    pub kind: TokenKind,
+    pub balance: u64,
+    pub value: Number128,
+}
+
+impl PositionSnapshot {
+    /// Record the current balances of every position in the margin account,
+    /// including those stored in its pages
+    pub fn capture(account: &MarginAccount, pages: &PositionPages) -> Result<Vec<Self>> {
+        let mut snapshot = account.positions().map(Self::from).collect::<Vec<_>>();
+
+        for page in &pages.pages {
+            snapshot.extend(page.load()?.positions().map(Self::from));
+        }
+
+        Ok(snapshot)
+    }
+}
+
+impl From<&AccountPosition> for PositionSnapshot {
+    fn from(position: &AccountPosition) -> Self {
+        Self {
+            mint: position.token,
+            kind: position.kind(),
+            balance: position.balance,
+            value: position.value(),
+        }
+    }
+}
+
+impl MarginDelegate {
+    pub const SPACE: usize = 8
+        + 32
+        + 32
+        + 4
+        + 8
+        + 2
+        + 4
+        + MAX_DELEGATE_LIMITS * std::mem::size_of::<DelegateMintLimit>();
+
+    /// Replace the permissions and limits of the delegate, which resets the usage of all limits
+    pub fn update(&mut self, update: &MarginDelegateUpdate) -> Result<()> {
+        if update.limits.len() > MAX_DELEGATE_LIMITS || update.max_slippage > 10_000 {
+            return err!(ErrorCode::InvalidConfig);
+        }
+
+        self.permissions = update.permissions;
+        self.expires_at = update.expires_at;
+        self.max_slippage = update.max_slippage;
+        self.limits = update
+            .limits
+            .iter()
+            .map(|l| DelegateMintLimit {
+                mint: l.mint,
+                limit: l.limit,
+                used: 0,
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    /// Find the delegation for a signer among a list of accounts, which is returned
+    /// along with the other accounts.
+    pub fn split<'info>(
+        margin_account: &Pubkey,
+        delegate: &Pubkey,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<(
+        Option<Account<'info, MarginDelegate>>,
+        Vec<AccountInfo<'info>>,
+    )> {
+        let mut delegation = None;
+        let mut others = vec![];
+
+        for info in accounts {
+            let is_delegation = delegation.is_none()
+                && info.owner == &crate::ID
+                && info
+                    .try_borrow_data()?
+                    .starts_with(&MarginDelegate::discriminator());
+
+            if is_delegation {
+                let account = Account::<MarginDelegate>::try_from(info)?;
+
+                if account.margin_account == *margin_account && account.delegate == *delegate {
+                    delegation = Some(account);
+                    continue;
+                }
+            }
+
+            others.push(info.clone());
+        }
+
+        Ok((delegation, others))
+    }
+
+    /// The address of the delegation of a margin account to a signer
+    pub fn address(margin_account: &Pubkey, delegate: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                MARGIN_DELEGATE_SEED,
+                margin_account.as_ref(),
+                delegate.as_ref(),
+            ],
+            &crate::ID,
+        )
+        .0
+    }
+
+    /// Find the delegations of the signers among a list of accounts, for instructions
+    /// that don't have a signer of their own. Every signer other than the owner must be
+    /// accompanied by the account at its delegation address, which is empty when the
+    /// signer is not a delegate, so a delegate cannot sign without its limits applying.
+    /// The delegation address of the owner is also removed, if it was provided.
+    pub fn split_signed<'info>(
+        margin_account: &Pubkey,
+        owner: &Pubkey,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<(Vec<Account<'info, MarginDelegate>>, Vec<AccountInfo<'info>>)> {
+        let mut signers: Vec<Pubkey> = vec![];
+        for info in accounts.iter().filter(|info| info.is_signer) {
+            if !signers.contains(info.key) {
+                signers.push(*info.key);
+            }
+        }
+
+        let mut delegations = vec![];
+        let mut others = accounts.to_vec();
+        for signer in signers {
+            let address = Self::address(margin_account, &signer);
+            let info = match others.iter().position(|info| *info.key == address) {
+                Some(index) => others.remove(index),
+                None if signer == *owner => continue,
+                None => {
+                    msg!("the delegation account for signer {} is missing", signer);
+                    return err!(ErrorCode::DelegationRequired);
+                }
+            };
+
+            if signer != *owner && info.owner == &crate::ID {
+                delegations.push(Account::<MarginDelegate>::try_from(&info)?);
+            }
+        }
+
+        Ok((delegations, others))
+    }
+
+    /// Check that the delegate may still sign for the account
+    pub fn verify_active(&self, timestamp: UnixTimestamp) -> Result<()> {
+        if self.expires_at != 0 && timestamp > self.expires_at {
+            msg!("delegation expired at {}", self.expires_at);
+            return err!(ErrorCode::DelegationExpired);
+        }
+
+        Ok(())
+    }
+
+    /// Check that the changes made to the account by the delegate are permitted,
+    /// and record any usage of the position limits.
+    pub fn verify_changes(
+        &mut self,
+        before: &[PositionSnapshot],
+        after: &[PositionSnapshot],
+    ) -> Result<()> {
+        let mut required = DelegatePermissions::empty();
+        let mut value_in = Number128::ZERO;
+        let mut value_out = Number128::ZERO;
+        let mut collateral_in = false;
+        let mut collateral_out = false;
+
+        let mints = before.iter().chain(after).map(|p| (p.mint, p.kind));
+
+        for (index, (mint, kind)) in mints.enumerate() {
+            // skip mints that were already checked
+            if before
+                .iter()
+                .chain(after)
+                .take(index)
+                .any(|p| p.mint == mint)
+            {
+                continue;
+            }
+
+            let find = |list: &[PositionSnapshot]| {
+                list.iter()
+                    .find(|p| p.mint == mint)
+                    .map(|p| (p.balance, p.value))
+                    .unwrap_or((0, Number128::ZERO))
+            };
+            let (start_balance, start_value) = find(before);
+            let (end_balance, end_value) = find(after);
+
+            match (kind, end_balance.cmp(&start_balance)) {
+                (_, std::cmp::Ordering::Equal) => continue,
+                (TokenKind::Claim, std::cmp::Ordering::Greater) => {
+                    required |= DelegatePermissions::BORROW;
+                    value_out += end_value - start_value;
+                }
+                (TokenKind::Claim, std::cmp::Ordering::Less) => {
+                    required |= DelegatePermissions::REPAY;
+                    value_in += start_value - end_value;
+                }
+                (_, std::cmp::Ordering::Greater) => {
+                    collateral_in = true;
+                    value_in += end_value - start_value;
+                }
+                (_, std::cmp::Ordering::Less) => {
+                    collateral_out = true;
+                    value_out += start_value - end_value;
+                    self.use_limit(&mint, start_balance - end_balance)?;
+                }
+            }
+        }
+
+        if collateral_in && collateral_out {
+            required |= DelegatePermissions::SWAP;
+        }
+
+        let max_loss = value_out * Number128::from_bps(self.max_slippage);
+
+        if value_out > value_in && value_out - value_in > max_loss {
+            required |= DelegatePermissions::WITHDRAW;
+        }
+
+        if !self.permissions.contains(required) {
+            msg!(
+                "delegate permissions: {:?}, required: {:?}",
+                self.permissions,
+                required
+            );
+            return err!(ErrorCode::InsufficientPermissions);
+        }
+
+        Ok(())
+    }
+
+    fn use_limit(&mut self, mint: &Pubkey, amount: u64) -> Result<()> {
+        if let Some(limit) = self.limits.iter_mut().find(|l| l.mint == *mint) {
+            limit.used = limit.used.saturating_add(amount);
+
+            if limit.used > limit.limit {
+                msg!(
+                    "delegate has reduced {} by {}, above the limit of {}",
+                    mint,
+                    limit.used,
+                    limit.limit
+                );
+                return err!(ErrorCode::DelegationLimitExceeded);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(mint: u8, kind: TokenKind, balance: u64) -> PositionSnapshot {
+        PositionSnapshot {
+            mint: Pubkey::new_from_array([mint; 32]),
+            kind,
+            balance,
+            value: Number128::from_decimal(balance, 0),
+        }
+    }
+
+    fn delegate(permissions: DelegatePermissions) -> MarginDelegate {
+        MarginDelegate {
+            permissions,
+            max_slippage: 100,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn delegate_permissions_are_required_for_each_kind_of_change() {
+        let before = vec![
+            snapshot(1, TokenKind::Collateral, 1000),
+            snapshot(2, TokenKind::Claim, 500),
+        ];
+        let swap = vec![
+            snapshot(1, TokenKind::Collateral, 900),
+            snapshot(2, TokenKind::Claim, 500),
+            snapshot(3, TokenKind::Collateral, 99),
+        ];
+        let repay = vec![
+            snapshot(1, TokenKind::Collateral, 900),
+            snapshot(2, TokenKind::Claim, 400),
+        ];
+        let borrow = vec![
+            snapshot(1, TokenKind::Collateral, 1100),
+            snapshot(2, TokenKind::Claim, 600),
+        ];
+        let withdraw = vec![
+            snapshot(1, TokenKind::Collateral, 900),
+            snapshot(2, TokenKind::Claim, 500),
+        ];
+
+        for (after, permission) in [
+            (swap, DelegatePermissions::SWAP),
+            (repay, DelegatePermissions::REPAY),
+            (borrow, DelegatePermissions::BORROW),
+            (withdraw, DelegatePermissions::WITHDRAW),
+        ] {
+            delegate(DelegatePermissions::all() - permission)
+                .verify_changes(&before, &after)
+                .unwrap_err();
+            delegate(permission)
+                .verify_changes(&before, &after)
+                .unwrap();
+        }
+
+        // deposits don't require any permission
+        delegate(DelegatePermissions::empty())
+            .verify_changes(
+                &before,
+                &[
+                    snapshot(1, TokenKind::Collateral, 2000),
+                    snapshot(2, TokenKind::Claim, 500),
+                ],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn swaps_losing_too_much_value_are_withdrawals() {
+        let before = vec![snapshot(1, TokenKind::Collateral, 1000)];
+        let after = vec![
+            snapshot(1, TokenKind::Collateral, 900),
+            snapshot(2, TokenKind::Collateral, 98),
+        ];
+
+        delegate(DelegatePermissions::SWAP)
+            .verify_changes(&before, &after)
+            .unwrap_err();
+        delegate(DelegatePermissions::SWAP | DelegatePermissions::WITHDRAW)
+            .verify_changes(&before, &after)
+            .unwrap();
+    }
+
+    #[test]
+    fn delegate_limits_are_cumulative() {
+        let mint = Pubkey::new_from_array([1; 32]);
+        let mut delegation = delegate(DelegatePermissions::WITHDRAW);
+        delegation.limits = vec![DelegateMintLimit {
+            mint,
+            limit: 150,
+            used: 0,
+        }];
+
+        let before = vec![snapshot(1, TokenKind::Collateral, 1000)];
+        let after = vec![snapshot(1, TokenKind::Collateral, 900)];
+        delegation.verify_changes(&before, &after).unwrap();
+        assert_eq!(delegation.limits[0].used, 100);
+
+        let later = vec![snapshot(1, TokenKind::Collateral, 800)];
+        delegation.verify_changes(&after, &later).unwrap_err();
+    }
+
+    #[test]
+    fn expired_delegation_is_inactive() {
+        let mut delegation = delegate(DelegatePermissions::all());
+        delegation.verify_active(i64::MAX).unwrap();
+
+        delegation.expires_at = 100;
+        delegation.verify_active(100).unwrap();
+        delegation.verify_active(101).unwrap_err();
+    }
+}
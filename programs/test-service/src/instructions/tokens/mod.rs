@@ -1,11 +1,15 @@
 mod token_create;
+mod token_create_push_oracle;
 mod token_init_native;
 mod token_register;
 mod token_request;
+mod token_update_push_price;
 mod token_update_pyth_price;
 
 pub use token_create::*;
+pub use token_create_push_oracle::*;
 pub use token_init_native::*;
 pub use token_register::*;
 pub use token_request::*;
+pub use token_update_push_price::*;
 pub use token_update_pyth_price::*;
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_program_common::oracle::PushPriceFeed;
+
+use crate::{error::TestServiceError, seeds::TOKEN_PUSH_PRICE, state::TokenInfo};
+
+#[derive(Accounts)]
+pub struct TokenCreatePushOracle<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    oracle_authority: Signer<'info>,
+
+    info: Account<'info, TokenInfo>,
+
+    #[account(init,
+              seeds = [
+                TOKEN_PUSH_PRICE,
+                info.mint.as_ref()
+              ],
+              bump,
+              space = PushPriceFeed::SIZE,
+              payer = payer
+    )]
+    push_price: AccountInfo<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+pub fn token_create_push_oracle_handler(
+    ctx: Context<TokenCreatePushOracle>,
+    expo: i32,
+) -> Result<()> {
+    let oracle_authority = ctx.accounts.oracle_authority.key();
+
+    if ctx.accounts.info.oracle_authority != oracle_authority {
+        msg!("only the oracle authority may create a push oracle for the token");
+        return err!(TestServiceError::PermissionDenied);
+    }
+
+    PushPriceFeed::new(expo, oracle_authority)
+        .store(&mut ctx.accounts.push_price.try_borrow_mut_data()?)
+        .unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//
+// Copyright (C) 2022 JET PROTOCOL HOLDINGS, LLC.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anchor_lang::prelude::*;
+
+use jet_program_common::oracle::PushPriceFeed;
+
+use crate::{error::TestServiceError, seeds::TOKEN_PUSH_PRICE, state::TokenInfo};
+
+#[derive(Accounts)]
+pub struct TokenUpdatePushPrice<'info> {
+    oracle_authority: Signer<'info>,
+
+    info: Account<'info, TokenInfo>,
+
+    #[account(mut,
+              seeds = [
+                TOKEN_PUSH_PRICE,
+                info.mint.as_ref()
+              ],
+              bump,
+    )]
+    push_price: AccountInfo<'info>,
+}
+
+pub fn token_update_push_price_handler(
+    ctx: Context<TokenUpdatePushPrice>,
+    price: i64,
+    conf: i64,
+) -> Result<()> {
+    let mut data = ctx.accounts.push_price.try_borrow_mut_data()?;
+    let mut feed = PushPriceFeed::load(&data).unwrap();
+
+    if feed.authority != ctx.accounts.oracle_authority.key() {
+        msg!("only the oracle authority may publish prices");
+        return err!(TestServiceError::PermissionDenied);
+    }
+
+    feed.price = price;
+    feed.confidence = conf as u64;
+    feed.ema_price = price;
+    feed.publish_time = Clock::get()?.unix_timestamp;
+
+    feed.store(&mut data).unwrap();
+
+    Ok(())
+}
@@ -50,6 +50,9 @@ pub mod seeds {
     #[constant]
     pub const TOKEN_PYTH_PRODUCT: &[u8] = b"token-pyth-product";
 
+    #[constant]
+    pub const TOKEN_PUSH_PRICE: &[u8] = b"token-push-price";
+
     #[constant]
     pub const SWAP_POOL_INFO: &[u8] = b"swap-pool-info";
 
@@ -125,6 +128,20 @@ pub mod jet_test_service {
         token_update_pyth_price_handler(ctx, price, conf, expo)
     }
 
+    /// Create a push oracle price account for a token, as an alternative to the pyth oracle
+    pub fn token_create_push_oracle(ctx: Context<TokenCreatePushOracle>, expo: i32) -> Result<()> {
+        token_create_push_oracle_handler(ctx, expo)
+    }
+
+    /// Update the push oracle price account for a token
+    pub fn token_update_push_price(
+        ctx: Context<TokenUpdatePushPrice>,
+        price: i64,
+        conf: i64,
+    ) -> Result<()> {
+        token_update_push_price_handler(ctx, price, conf)
+    }
+
     /// Create a SPL swap pool
     pub fn spl_swap_pool_create(
         ctx: Context<SplSwapPoolCreate>,
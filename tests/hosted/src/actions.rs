@@ -3,7 +3,7 @@ use jet_client::{
     margin::MarginAccountClient,
     ClientResult, JetClient,
 };
-use jet_instructions::fixed_term::OrderParams;
+use jet_instructions::fixed_term::{OrderParams, TimeInForce};
 use jet_margin_sdk::{
     fixed_term::event_consumer::EventConsumer,
     solana::{keypair::KeypairExt, transaction::TransactionBuilderExt},
@@ -193,6 +193,7 @@ pub async fn offer_loan_no_auto_stake(
         post_allowed: true,
         auto_stake: false,
         auto_roll: false,
+        time_in_force: TimeInForce::GoodTilCancelled,
     };
 
     account
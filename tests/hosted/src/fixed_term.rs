@@ -13,11 +13,24 @@ use async_trait::async_trait;
 
 use jet_fixed_term::{
     control::state::Market,
-    margin::state::{
-        AutoRollConfig, BorrowAutoRollConfig, LendAutoRollConfig, MarginUser, TermLoan,
+    margin::{
+        instructions::BatchOrderParams,
+        state::{
+            AutoRollConfig, AutoRollPolicyConfig, BorrowAutoRollConfig, LendAutoRollConfig,
+            MarginUser, TermLoan,
+        },
+    },
+    orderbook::{
+        instructions::AmendOrderParams,
+        state::{
+            event_queue_len, find_expired_orders, orderbook_slab_len, CallbackInfo, OrderParams,
+            TimeInForce,
+        },
+    },
+    tickets::{
+        instructions::OfferDepositParams,
+        state::{DepositOffer, TermDeposit},
     },
-    orderbook::state::{event_queue_len, orderbook_slab_len, CallbackInfo, OrderParams},
-    tickets::state::TermDeposit,
 };
 use jet_instructions::{
     fixed_term::{derive, InitializeMarketParams},
@@ -281,6 +294,21 @@ impl TestManager {
         Ok(())
     }
 
+    /// Removes every order on the book that has expired by the current clock
+    pub async fn expire_orders(&self) -> Result<()> {
+        let mut bids = self.load_data(&self.orderbook.bids.pubkey()).await?;
+        let mut asks = self.load_data(&self.orderbook.asks.pubkey()).await?;
+        let timestamp = self.client.get_clock().await?.unix_timestamp;
+
+        let expired = find_expired_orders(&mut bids, &mut asks, timestamp, usize::MAX)?;
+        if !expired.is_empty() {
+            let expire = self.ix_builder.expire_orders(expired);
+            self.sign_send_transaction(&[expire], &[]).await?;
+        }
+
+        Ok(())
+    }
+
     /// Two jobs:
     /// - Verifies that the event consumer has notified us that the expected
     ///   account needs to be settled. panic on failure.
@@ -389,6 +417,18 @@ impl TestManager {
         Ok(())
     }
 
+    pub async fn configure_default_handling(
+        &self,
+        grace_period: u32,
+        penalty: u16,
+    ) -> Result<Signature> {
+        let configure = self
+            .ix_builder
+            .configure_default_handling(grace_period, penalty);
+
+        self.sign_send_transaction(&[configure], &[]).await
+    }
+
     pub async fn pause_ticket_redemption(&self) -> Result<Signature> {
         let pause = self.ix_builder.pause_ticket_redemption();
 
@@ -679,6 +719,23 @@ impl OwnedBook {
 
         Ok(*self.inner()?.bids.get_callback_info(handle))
     }
+
+    /// The ids of the orders on both sides of the book that belong to `owner`
+    pub fn orders_of(&mut self, owner: &Pubkey) -> Result<Vec<u128>> {
+        let mut orders = vec![];
+        for (pos, bid) in self.bids()?.into_iter().enumerate() {
+            if self.bids_order_callback(pos)?.owner() == *owner {
+                orders.push(bid.key);
+            }
+        }
+        for (pos, ask) in self.asks()?.into_iter().enumerate() {
+            if self.asks_order_callback(pos)?.owner() == *owner {
+                orders.push(ask.key);
+            }
+        }
+
+        Ok(orders)
+    }
 }
 
 impl TestManager {
@@ -714,6 +771,24 @@ impl TestManager {
         })
     }
 
+    /// Loads the supplies of the claims, ticket collateral, and underlying collateral notes
+    pub async fn note_supplies(&self) -> Result<[u64; 3]> {
+        let market = self.ix_builder.market();
+        let mut supplies = [0; 3];
+        for (supply, mint) in supplies.iter_mut().zip([
+            derive::claims_mint(&market),
+            derive::ticket_collateral_mint(&market),
+            derive::underlying_collateral_mint(&market),
+        ]) {
+            *supply = self
+                .load_anchor::<anchor_spl::token::Mint>(&mint)
+                .await?
+                .supply;
+        }
+
+        Ok(supplies)
+    }
+
     pub async fn collected_fees(&self) -> Result<u64> {
         let key = self.load_market().await?.fee_vault;
         let vault = self.load_anchor::<TokenAccount>(&key).await?;
@@ -1057,6 +1132,14 @@ impl<P: Proxy> FixedTermUser<P> {
         Ok(self.proxy.invoke_signed(ix).with_signer(&self.owner))
     }
 
+    pub fn margin_batch_orders(&self, params: BatchOrderParams) -> TransactionBuilder {
+        let ix = self
+            .manager
+            .ix_builder
+            .margin_batch_orders(self.proxy.pubkey(), None, params);
+        self.proxy.invoke_signed(ix).with_signer(&self.owner)
+    }
+
     pub async fn lend_order(&self, params: OrderParams, seed: &[u8]) -> Result<Signature> {
         let lend =
             self.manager
@@ -1077,6 +1160,69 @@ impl<P: Proxy> FixedTermUser<P> {
             .await
     }
 
+    pub async fn amend_order(&self, order_id: u128, params: AmendOrderParams) -> Result<Signature> {
+        let amend = self
+            .manager
+            .ix_builder
+            .amend_order(self.proxy.pubkey(), order_id, params);
+        self.client
+            .send_and_confirm_1tx(&[self.proxy.invoke_signed(amend)], [&self.owner])
+            .await
+    }
+
+    pub fn margin_amend_order(
+        &self,
+        order_id: u128,
+        params: AmendOrderParams,
+    ) -> TransactionBuilder {
+        let amend =
+            self.manager
+                .ix_builder
+                .margin_amend_order(self.proxy.pubkey(), order_id, params);
+        self.proxy.invoke_signed(amend).with_signer(&self.owner)
+    }
+
+    pub async fn offer_deposit(
+        &self,
+        deposit: Pubkey,
+        price: u64,
+        buyer: Pubkey,
+    ) -> Result<Vec<TransactionBuilder>> {
+        let ix = self.manager.ix_builder.offer_deposit(
+            self.proxy.pubkey(),
+            deposit,
+            OfferDepositParams { price, buyer },
+            None,
+        );
+        self.proxy
+            .refresh_and_invoke_signed(ix, clone(&self.owner))
+            .await
+    }
+
+    pub async fn accept_deposit_offer(&self, deposit: Pubkey) -> Result<Vec<TransactionBuilder>> {
+        let offer = self.load_deposit_offer(&deposit).await?;
+        let ix = self.manager.ix_builder.accept_deposit_offer(
+            self.proxy.pubkey(),
+            deposit,
+            &offer,
+            None,
+        );
+        self.proxy
+            .refresh_and_invoke_signed(ix, clone(&self.owner))
+            .await
+    }
+
+    pub async fn cancel_deposit_offer(&self, deposit: Pubkey) -> Result<Vec<TransactionBuilder>> {
+        let offer = self.load_deposit_offer(&deposit).await?;
+        let ix =
+            self.manager
+                .ix_builder
+                .cancel_deposit_offer(self.proxy.pubkey(), deposit, offer.payer);
+        self.proxy
+            .refresh_and_invoke_signed(ix, clone(&self.owner))
+            .await
+    }
+
     pub async fn settle(&self) -> Result<Signature> {
         let settle = self.manager.ix_builder.settle(self.proxy.pubkey());
         self.client
@@ -1106,6 +1252,17 @@ impl<P: Proxy> FixedTermUser<P> {
             .await
     }
 
+    pub async fn set_roll_policy(&self, config: AutoRollPolicyConfig) -> Result<Signature> {
+        let set_policy = self.manager.ix_builder.configure_auto_roll_policy(
+            self.proxy.pubkey(),
+            self.owner.pubkey(),
+            config,
+        );
+        self.client
+            .send_and_confirm_1tx(&[self.proxy.invoke_signed(set_policy)], [&self.owner])
+            .await
+    }
+
     pub async fn repay(&self, term_loan_seqno: u64, amount: u64) -> Result<Signature> {
         // we are not sure if the user or a crank paid for the rent, so we just fetch the data
         let payer = {
@@ -1132,6 +1289,41 @@ impl<P: Proxy> FixedTermUser<P> {
             .await
     }
 
+    /// Repays a loan of another margin account that has defaulted, using its assets and the
+    /// staked tickets in `deposits` before up to `max_source_amount` tokens from this user
+    pub async fn repay_past_due(
+        &self,
+        borrower: &Pubkey,
+        term_loan_seqno: u64,
+        max_source_amount: u64,
+        deposits: &[Pubkey],
+    ) -> Result<Signature> {
+        let payer = {
+            let margin_user = self.manager.ix_builder.margin_user_account(*borrower);
+            let loan_key = self
+                .manager
+                .ix_builder
+                .term_loan_key(&margin_user, &term_loan_seqno.to_le_bytes());
+            let loan: TermLoan = self.load_anchor(&loan_key).await?;
+
+            loan.payer
+        };
+        let repay = self.manager.ix_builder.repay_past_due(
+            &self.proxy.pubkey(),
+            &payer,
+            borrower,
+            &self.token_acc,
+            term_loan_seqno,
+            max_source_amount,
+            vec![],
+            deposits,
+        );
+
+        self.client
+            .send_and_confirm_1tx(&[self.proxy.invoke_signed(repay)], [&self.owner])
+            .await
+    }
+
     pub async fn get_active_term_loans(&self) -> Result<Vec<TermLoan>> {
         let mut loans = vec![];
 
@@ -1193,6 +1385,12 @@ impl<P: Proxy> FixedTermUser<P> {
         self.manager.load_anchor(&key).await
     }
 
+    pub async fn load_deposit_offer(&self, deposit: &Pubkey) -> Result<DepositOffer> {
+        self.manager
+            .load_anchor(&derive::deposit_offer(deposit))
+            .await
+    }
+
     pub async fn load_term_loan(&self, seqno: u64) -> Result<TermLoan> {
         let key = self.term_loan_key(&seqno.to_le_bytes());
 
@@ -1316,6 +1514,7 @@ impl OrderAmount {
             post_allowed: true,
             auto_stake: true,
             auto_roll: false,
+            time_in_force: TimeInForce::GoodTilCancelled,
         }
     }
 
@@ -2,7 +2,7 @@ use anchor_lang::prelude::Pubkey;
 use anyhow::Result;
 use futures::future::{join_all, try_join_all};
 use jet_margin_sdk::{
-    fixed_term::{Crank, OrderParams},
+    fixed_term::{Crank, OrderParams, TimeInForce},
     solana::transaction::InverseSendTransactionBuilder,
     util::asynchronous::MapAsync,
 };
@@ -216,5 +216,6 @@ fn underlying(quote: u64, rate_bps: u64) -> OrderParams {
         post_allowed: true,
         auto_stake: true,
         auto_roll: false,
+        time_in_force: TimeInForce::GoodTilCancelled,
     }
 }
@@ -143,7 +143,7 @@ impl MarginClient {
         self.rpc
             .get_program_accounts(
                 &jet_margin_pool::ID,
-                vec![AccountFilter::DataSize(std::mem::size_of::<MarginPool>())],
+                vec![AccountFilter::DataSize(MarginPool::SPACE)],
             )
             .await?
             .into_iter()
@@ -400,6 +400,7 @@ impl MarginUser {
             authority: self.signer.pubkey(),
             airspace: self.tx.airspace(),
             is_liquidator: self.tx.is_liquidator(),
+            is_delegate: self.tx.is_delegate(),
         }
     }
 
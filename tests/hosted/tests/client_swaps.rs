@@ -0,0 +1,99 @@
+use std::ops::Deref;
+
+use jet_environment::builder::WHIRLPOOL_TICK_SPACING;
+use jet_instructions::margin_pool::MarginPoolIxBuilder;
+use jet_instructions::orca::derive_whirlpool;
+use jet_instructions::test_service::derive_whirlpool_config;
+
+use hosted_tests::actions::*;
+use hosted_tests::context::TestContextSetupInfo;
+use hosted_tests::environment::TestToken;
+use hosted_tests::test_context;
+
+#[tokio::test]
+async fn client_route_swap_through_whirlpool() -> anyhow::Result<()> {
+    let ctx = test_context! {
+        setup: &TestContextSetupInfo {
+            is_restricted: false,
+            tokens: vec![
+                TestToken::with_pool("TSOL").into(),
+                TestToken::with_pool("USDC").into(),
+            ],
+            dexes: vec![("orca-whirlpool", "TSOL/USDC")],
+        }
+    };
+
+    // derive mints for default config tokens
+    let usdc = Token::from_context(&ctx, "USDC");
+    let tsol = Token::from_context(&ctx, "TSOL");
+
+    let (token_a, token_b) = (
+        std::cmp::min(tsol.mint, usdc.mint),
+        std::cmp::max(tsol.mint, usdc.mint),
+    );
+    let target_pool_price = if token_a == tsol.mint {
+        22.0
+    } else {
+        1.0 / 22.0
+    };
+
+    // Add liquidity
+    let whirlpool = derive_whirlpool(
+        &derive_whirlpool_config(),
+        &token_a,
+        &token_b,
+        WHIRLPOOL_TICK_SPACING,
+    )
+    .0;
+    jet_testing::whirlpool::set_liquidity(
+        ctx.rpc().payer(),
+        ctx.inner.solana.rpc2.deref(),
+        whirlpool,
+        target_pool_price,
+        9,
+    )
+    .await?;
+
+    // the whirlpool is loaded by the client along with the rest of the state
+    let user = ctx.create_user().await?;
+    user.state().sync_all().await?;
+
+    set_price(&ctx, &usdc, 1.0, 0.01).await;
+    set_price(&ctx, &tsol, 22.0, 0.01).await;
+
+    // Lend some funds to swap with
+    let deposit_amount = usdc.amount(1_000.0);
+    airdrop(&user, &usdc, deposit_amount).await;
+
+    let account = user.margin().accounts()[0].clone();
+    deposit(&account, &usdc, deposit_amount).await?;
+    pool_lend(&account, &usdc, deposit_amount).await?;
+
+    // Quote a swap from USDC to TSOL, which can only go through the whirlpool
+    let quote = account
+        .swaps()
+        .quote(&usdc.mint, &tsol.mint, usdc.amount(100.0))?;
+
+    assert_eq!(1, quote.legs.len());
+    assert_eq!(whirlpool, quote.legs[0].pool);
+    assert!(quote.amount_out > tsol.amount(4.0));
+
+    // Swap along the quoted route
+    let minimum_amount_out = quote.minimum_amount_out(100);
+    account
+        .swaps()
+        .route_swap(&quote, minimum_amount_out)
+        .await?;
+    account.sync().await?;
+
+    let tsol_deposit_notes = MarginPoolIxBuilder::new(tsol.mint).deposit_note_mint;
+    let tsol_position = account
+        .positions()
+        .into_iter()
+        .find(|p| p.token == tsol_deposit_notes)
+        .unwrap();
+
+    assert!(tsol_position.balance >= minimum_amount_out);
+
+    Ok(())
+}
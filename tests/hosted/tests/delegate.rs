@@ -0,0 +1,173 @@
+use anchor_lang::AccountDeserialize;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signer,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+
+use hosted_tests::{actions::Token, test_context, util::assert_program_error};
+use jet_instructions::{
+    margin::{
+        derive_margin_delegate, derive_paged_position_token_account, derive_position_page,
+        DelegateMintLimitUpdate, DelegatePermissions, MarginDelegateUpdate, MarginIxBuilder,
+    },
+    margin_pool::MarginPoolIxBuilder,
+    test_service::{derive_pyth_price, token_request},
+};
+use jet_margin::MarginPositionPage;
+use jet_margin_pool::TokenChange;
+use jet_simulation::send_and_confirm;
+
+/// Refresh the balance and price of a position stored in a page
+fn refresh_paged_position(
+    margin: &MarginIxBuilder,
+    page: Pubkey,
+    token_account: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    let mut ix = margin.refresh_deposit_position(mint, &derive_pyth_price(&mint), false);
+    ix.accounts
+        .push(AccountMeta::new_readonly(token_account, false));
+    ix.accounts.push(AccountMeta::new(page, false));
+
+    ix
+}
+
+#[tokio::test]
+async fn delegate_withdrawal_from_paged_position_is_limited() -> anyhow::Result<()> {
+    let ctx = test_context!();
+    let rpc = ctx.rpc();
+    let usdc = Token::from_context(&ctx, "USDC");
+
+    let owner = ctx.inner.create_margin_user(1_000).await?;
+    let delegate = ctx.inner.solana.create_wallet(1_000).await?;
+
+    let margin = MarginIxBuilder::new(ctx.inner.airspace, owner.signer.pubkey(), owner.seed());
+    let page = derive_position_page(&margin.address, 0);
+    let paged_account = derive_paged_position_token_account(&page, &usdc.mint);
+
+    // The owner keeps USDC in a page, and allows the delegate to move up to 100 of it
+    send_and_confirm(
+        rpc,
+        &[
+            margin.add_position_page(0),
+            margin.register_paged_position(0, usdc.mint, 1),
+            token_request(
+                &owner.signer.pubkey(),
+                &usdc.mint,
+                &paged_account,
+                usdc.amount(1_000.0),
+            ),
+            refresh_paged_position(&margin, page, paged_account, usdc.mint),
+            margin.configure_delegate(
+                delegate.pubkey(),
+                Some(MarginDelegateUpdate {
+                    permissions: DelegatePermissions::SWAP | DelegatePermissions::WITHDRAW,
+                    expires_at: 0,
+                    max_slippage: 0,
+                    limits: vec![DelegateMintLimitUpdate {
+                        mint: usdc.mint,
+                        limit: usdc.amount(100.0),
+                    }],
+                }),
+            ),
+        ],
+        &[&owner.signer],
+    )
+    .await?;
+
+    // The delegate tries to move the paged tokens into a pool deposit that it owns
+    let pool = MarginPoolIxBuilder::new(usdc.mint);
+    let delegate_notes = get_associated_token_address(&delegate.pubkey(), &pool.deposit_note_mint);
+    let delegate_margin = margin.clone().with_authority(delegate.pubkey());
+    let withdraw = |amount| {
+        let mut ix = delegate_margin.adapter_invoke(pool.deposit(
+            margin.address,
+            paged_account,
+            delegate_notes,
+            TokenChange::shift(amount),
+        ));
+        ix.accounts.push(AccountMeta::new(page, false));
+
+        ix
+    };
+
+    send_and_confirm(
+        rpc,
+        &[create_associated_token_account(
+            &delegate.pubkey(),
+            &delegate.pubkey(),
+            &pool.deposit_note_mint,
+            &spl_token::ID,
+        )],
+        &[&delegate],
+    )
+    .await?;
+
+    let result = send_and_confirm(rpc, &[withdraw(usdc.amount(500.0))], &[&delegate]).await;
+    assert_program_error(jet_margin::ErrorCode::DelegationLimitExceeded, result);
+
+    send_and_confirm(rpc, &[withdraw(usdc.amount(100.0))], &[&delegate]).await?;
+
+    let page_data = rpc.get_account(&page).await?.unwrap().data;
+    let page_state = MarginPositionPage::try_deserialize(&mut &page_data[..])?;
+    let position = page_state.get_position(&usdc.mint).unwrap();
+    assert_eq!(usdc.amount(900.0), position.balance);
+
+    // The limit has been used up
+    let result = send_and_confirm(rpc, &[withdraw(usdc.amount(1.0))], &[&delegate]).await;
+    assert_program_error(jet_margin::ErrorCode::DelegationLimitExceeded, result);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn delegate_cannot_sign_accounting_invoke_without_delegation() -> anyhow::Result<()> {
+    let ctx = test_context!();
+    let rpc = ctx.rpc();
+    let usdc = Token::from_context(&ctx, "USDC");
+
+    let owner = ctx.inner.create_margin_user(1_000).await?;
+    let delegate = ctx.inner.solana.create_wallet(1_000).await?;
+
+    let margin = MarginIxBuilder::new(ctx.inner.airspace, owner.signer.pubkey(), owner.seed());
+    send_and_confirm(
+        rpc,
+        &[margin.configure_delegate(
+            delegate.pubkey(),
+            Some(MarginDelegateUpdate {
+                permissions: DelegatePermissions::SWAP,
+                expires_at: 0,
+                max_slippage: 0,
+                limits: vec![],
+            }),
+        )],
+        &[&owner.signer],
+    )
+    .await?;
+
+    // The delegate signs an accounting invoke, which includes its delegation
+    let pool = MarginPoolIxBuilder::new(usdc.mint);
+    let deposit = margin.accounting_invoke(pool.deposit(
+        delegate.pubkey(),
+        get_associated_token_address(&delegate.pubkey(), &usdc.mint),
+        get_associated_token_address(&margin.address, &pool.deposit_note_mint),
+        TokenChange::shift(usdc.amount(1.0)),
+    ));
+
+    // Leaving the delegation out would avoid its limits
+    let mut without_delegation = deposit.clone();
+    let delegation = without_delegation.accounts.pop().unwrap();
+    assert_eq!(
+        derive_margin_delegate(&margin.address, &delegate.pubkey()),
+        delegation.pubkey
+    );
+
+    let result = send_and_confirm(rpc, &[without_delegation], &[&delegate]).await;
+    assert_program_error(jet_margin::ErrorCode::DelegationRequired, result);
+
+    Ok(())
+}
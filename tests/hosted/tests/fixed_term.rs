@@ -15,11 +15,18 @@ use hosted_tests::{
     test_default,
 };
 use jet_fixed_term::{
-    margin::state::{BorrowAutoRollConfig, LendAutoRollConfig, TermLoan},
-    orderbook::state::{
-        CallbackFlags, MarginCallbackInfo, OrderParams, RoundingAction, SensibleOrderSummary,
+    margin::{
+        instructions::BatchOrderParams,
+        state::{BorrowAutoRollConfig, LendAutoRollConfig, TermLoan},
     },
-    tickets::state::TermDeposit,
+    orderbook::{
+        instructions::AmendOrderParams,
+        state::{
+            CallbackFlags, EventQuote, MarginCallbackInfo, OrderParams, RoundingAction,
+            SensibleOrderSummary, TimeInForce,
+        },
+    },
+    tickets::state::{TermDeposit, TermDepositFlags},
 };
 use jet_margin_sdk::{
     fixed_term::{auto_roll_servicer::AutoRollServicer, settler::SETTLES_PER_TX},
@@ -95,6 +102,7 @@ async fn non_margin_orders_for_proxy<P: Proxy + GenerateProxy>(
         post_allowed: true,
         auto_stake: true,
         auto_roll: false,
+        time_in_force: TimeInForce::GoodTilCancelled,
     };
     let order_a_expected_base_to_post = quote_to_base(1_000, 2_000);
 
@@ -148,6 +156,7 @@ async fn non_margin_orders_for_proxy<P: Proxy + GenerateProxy>(
         post_allowed: true,
         auto_stake: true,
         auto_roll: false,
+        time_in_force: TimeInForce::GoodTilCancelled,
     };
     assert!(alice.lend_order(crossing_params, &[]).await.is_err());
 
@@ -162,6 +171,7 @@ async fn non_margin_orders_for_proxy<P: Proxy + GenerateProxy>(
         post_allowed: true,
         auto_stake: true,
         auto_roll: false,
+        time_in_force: TimeInForce::GoodTilCancelled,
     };
     let order_b_expected_base_to_fill = quote_to_base(500, 2000);
 
@@ -193,6 +203,7 @@ async fn non_margin_orders_for_proxy<P: Proxy + GenerateProxy>(
         post_allowed: true,
         auto_stake: true,
         auto_roll: false,
+        time_in_force: TimeInForce::GoodTilCancelled,
     };
 
     // simulate
@@ -1269,6 +1280,744 @@ async fn auto_roll_borrow_order_is_correct() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn deposit_offers_between_margin_and_signer_accounts() -> Result<()> {
+    let ctx = margin_test_context!();
+    let manager = Arc::new(FixedTermTestManager::full(&ctx).await.unwrap());
+    let client = manager.client.clone();
+    let ([], _, pricer) = tokens(&ctx).await.unwrap();
+    let ticket_mint = manager.ix_builder.ticket_mint();
+    let token_mint = manager.ix_builder.token_mint();
+
+    let seller = create_and_fund_fixed_term_market_margin_user(&ctx, manager.clone(), vec![]).await;
+    let buyer = create_and_fund_fixed_term_market_margin_user(&ctx, manager.clone(), vec![]).await;
+    let alice = FixedTermUser::<NoProxy>::generate_funded(ctx.clone(), manager.clone()).await?;
+
+    // the margin seller lends into alice's order twice, creating two deposits
+    alice.convert_tokens(10_000).await?;
+    alice.sell_tickets_order(tickets(5_000, 2_000)).await?;
+    for _ in 0..2 {
+        transactions! {
+            pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+            pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+            seller.refresh_and_margin_lend_order(underlying(1_000, 2_000)).await?,
+        }
+        .send_and_confirm_condensed_in_order(&client)
+        .await?;
+    }
+    let oldest = seller.term_deposit_key(&0u64.to_le_bytes());
+    let deposit = seller.term_deposit_key(&1u64.to_le_bytes());
+    let amount = manager.load_anchor::<TermDeposit>(&deposit).await?.amount;
+    let seller_collateral = seller.ticket_collateral().await?;
+    let seller_tokens = seller.tokens().await?;
+
+    // a margin seller can offer a deposit that is not its oldest
+    const PRICE: u64 = 1_100;
+    transactions! {
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        seller.offer_deposit(deposit, PRICE, buyer.proxy.pubkey()).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+
+    assert_eq!(
+        seller_collateral - amount,
+        seller.ticket_collateral().await?
+    );
+    let offer = seller.load_deposit_offer(&deposit).await?;
+    assert_eq!(offer.seller, seller.proxy.pubkey());
+    assert!(offer.margin_custody);
+
+    // only the chosen buyer may accept the offer
+    assert!(alice
+        .accept_deposit_offer(deposit)
+        .await?
+        .send_and_confirm_condensed_in_order(&client)
+        .await
+        .is_err());
+
+    // a margin buyer takes custody of the deposit
+    transactions! {
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        buyer.accept_deposit_offer(deposit).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+
+    let bought = manager.load_anchor::<TermDeposit>(&deposit).await?;
+    assert_eq!(bought.owner, buyer.proxy.pubkey());
+    assert!(bought
+        .flags
+        .contains(TermDepositFlags::MARGIN | TermDepositFlags::CUSTODIED));
+    assert_eq!(amount, buyer.ticket_collateral().await?);
+    assert_eq!(STARTING_TOKENS - PRICE, buyer.tokens().await?);
+    assert_eq!(seller_tokens + PRICE, seller.tokens().await?);
+    assert!(seller.load_deposit_offer(&deposit).await.is_err());
+
+    // the bought deposit is tracked by its amount, outside the buyer's own deposits
+    let assets = buyer.load_margin_user().await?.assets().clone();
+    assert_eq!(assets.tickets_staked(), amount);
+    assert_eq!(assets.next_unredeemed_deposit_seqno(), None);
+
+    // cancelling returns the deposit to the custody of a margin seller
+    transactions! {
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        buyer.offer_deposit(deposit, PRICE, Pubkey::default()).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    assert_eq!(0, buyer.ticket_collateral().await?);
+
+    transactions! {
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        buyer.cancel_deposit_offer(deposit).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    assert_eq!(amount, buyer.ticket_collateral().await?);
+    assert!(buyer.load_deposit_offer(&deposit).await.is_err());
+    assert!(manager
+        .load_anchor::<TermDeposit>(&deposit)
+        .await?
+        .flags
+        .contains(TermDepositFlags::MARGIN | TermDepositFlags::CUSTODIED));
+
+    // a signer buyer takes the deposit out of margin
+    transactions! {
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        buyer.offer_deposit(deposit, PRICE, alice.proxy.pubkey()).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    let alice_tokens = alice.tokens().await?;
+    alice
+        .accept_deposit_offer(deposit)
+        .await?
+        .send_and_confirm_condensed_in_order(&client)
+        .await?;
+
+    let bought = manager.load_anchor::<TermDeposit>(&deposit).await?;
+    assert_eq!(bought.owner, alice.proxy.pubkey());
+    assert!(!bought.flags.contains(TermDepositFlags::MARGIN));
+    assert_eq!(alice_tokens - PRICE, alice.tokens().await?);
+    assert_eq!(0, buyer.ticket_collateral().await?);
+    assert_eq!(buyer.load_margin_user().await?.assets().tickets_staked(), 0);
+
+    // a signer seller can cancel an offer, keeping the deposit
+    alice
+        .offer_deposit(deposit, PRICE, Pubkey::default())
+        .await?
+        .send_and_confirm_condensed_in_order(&client)
+        .await?;
+    assert!(!alice.load_deposit_offer(&deposit).await?.margin_custody);
+    alice
+        .cancel_deposit_offer(deposit)
+        .await?
+        .send_and_confirm_condensed_in_order(&client)
+        .await?;
+    assert!(alice.load_deposit_offer(&deposit).await.is_err());
+    assert_eq!(
+        manager.load_anchor::<TermDeposit>(&deposit).await?.owner,
+        alice.proxy.pubkey()
+    );
+
+    // and a margin buyer can buy from a signer seller
+    alice
+        .offer_deposit(deposit, PRICE, Pubkey::default())
+        .await?
+        .send_and_confirm_condensed_in_order(&client)
+        .await?;
+    transactions! {
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        buyer.accept_deposit_offer(deposit).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    assert_eq!(alice_tokens, alice.tokens().await?);
+    assert_eq!(amount, buyer.ticket_collateral().await?);
+
+    // releasing the oldest deposit skips the one that was already sold, and cancelling
+    // returns it to the seller's custody by its amount
+    let oldest_amount = manager.load_anchor::<TermDeposit>(&oldest).await?.amount;
+    transactions! {
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        seller.offer_deposit(oldest, PRICE, Pubkey::default()).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    assert_eq!(
+        seller
+            .load_margin_user()
+            .await?
+            .assets()
+            .next_unredeemed_deposit_seqno(),
+        None
+    );
+
+    transactions! {
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        seller.cancel_deposit_offer(oldest).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    assert_eq!(
+        seller_collateral - amount,
+        seller.ticket_collateral().await?
+    );
+    assert_eq!(
+        seller.load_margin_user().await?.assets().tickets_staked(),
+        oldest_amount
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn good_til_time_orders_expire() -> Result<()> {
+    let ctx = margin_test_context!();
+    let manager = Arc::new(FixedTermTestManager::full(&ctx).await?);
+    let alice = FixedTermUser::<NoProxy>::generate_funded(ctx.clone(), manager.clone()).await?;
+
+    const START_TICKETS: u64 = 100_000;
+    alice.convert_tokens(START_TICKETS).await?;
+
+    let now = manager.client.get_clock().await?.unix_timestamp;
+    let expiring = OrderParams {
+        time_in_force: TimeInForce::GoodTilTime {
+            expires_at: now + 60,
+        },
+        ..tickets(10_000, 2_000)
+    };
+    let resting = tickets(20_000, 1_000);
+    alice.sell_tickets_order(expiring).await?;
+    alice.sell_tickets_order(resting).await?;
+    assert_eq!(manager.load_orderbook().await?.asks()?.len(), 2);
+    assert_eq!(
+        alice.tickets().await?,
+        START_TICKETS - expiring.max_ticket_qty - resting.max_ticket_qty
+    );
+
+    // nothing has expired yet
+    manager.expire_orders().await?;
+    manager.consume_events().await?;
+    assert_eq!(manager.load_orderbook().await?.asks()?.len(), 2);
+
+    let mut clock = manager.client.get_clock().await?;
+    clock.unix_timestamp = now + 60;
+    manager.client.set_clock(clock).await?;
+
+    // the expired order is removed and its tickets are returned once the event is consumed
+    manager.expire_orders().await?;
+    let asks = manager.load_orderbook().await?.asks()?;
+    assert_eq!(asks.len(), 1);
+    assert_eq!(asks[0].base_quantity, resting.max_ticket_qty);
+
+    let mut eq = manager.load_event_queue().await?;
+    let local_eq = eq.inner()?;
+    let expire_event = match local_eq.iter().next().unwrap() {
+        EventRef::Out(out) => out,
+        _ => panic!("expected an out event"),
+    };
+    assert_eq!(expire_event.event.base_size, expiring.max_ticket_qty);
+    assert_eq!(expire_event.callback_info.owner(), alice.proxy.pubkey());
+    assert_eq!(
+        alice.tickets().await?,
+        START_TICKETS - expiring.max_ticket_qty - resting.max_ticket_qty
+    );
+
+    manager.consume_events().await?;
+    assert!(manager
+        .load_event_queue()
+        .await?
+        .inner()?
+        .iter()
+        .next()
+        .is_none());
+    assert_eq!(
+        alice.tickets().await?,
+        START_TICKETS - resting.max_ticket_qty
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn repay_past_due_loan_with_penalty() -> Result<()> {
+    let ctx = margin_test_context!();
+    let manager = Arc::new(FixedTermTestManager::full(&ctx).await.unwrap());
+    let client = manager.client.clone();
+    let ([collateral], _, pricer) = tokens(&ctx).await.unwrap();
+    let ticket_mint = manager.ix_builder.ticket_mint();
+    let token_mint = manager.ix_builder.token_mint();
+
+    const GRACE_PERIOD: u32 = 60;
+    const PENALTY: u16 = 500;
+    manager
+        .configure_default_handling(GRACE_PERIOD, PENALTY)
+        .await?;
+
+    let borrower = create_and_fund_fixed_term_market_margin_user(
+        &ctx,
+        manager.clone(),
+        vec![(collateral, 0, u64::MAX / 2)],
+    )
+    .await;
+    let lender = create_and_fund_fixed_term_market_margin_user(&ctx, manager.clone(), vec![]).await;
+    let alice = FixedTermUser::<NoProxy>::generate_funded(ctx.clone(), manager.clone()).await?;
+
+    // the borrower stakes some tickets by lending into alice's order
+    alice.convert_tokens(10_000).await?;
+    alice.sell_tickets_order(tickets(5_000, 2_000)).await?;
+    transactions! {
+        pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        borrower.refresh_and_margin_lend_order(underlying(100, 2_000)).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    let deposit = borrower.term_deposit_key(&0u64.to_le_bytes());
+    let staked = manager.load_anchor::<TermDeposit>(&deposit).await?.amount;
+    assert!(staked > 0);
+
+    // then borrows from the lender
+    transactions! {
+        pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        lender.refresh_and_margin_lend_order(underlying(1_001, 3_000)).await?,
+        borrower.refresh_and_margin_borrow_order(underlying(1_000, 3_000)).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    let loan = borrower.load_term_loan(0).await?;
+    assert!(loan.balance > staked);
+
+    // nobody else may repay the loan during the grace period
+    let mut clock = manager.client.get_clock().await?;
+    clock.unix_timestamp = loan.maturation_timestamp + GRACE_PERIOD as i64 - 1;
+    manager.client.set_clock(clock).await?;
+    assert!(alice
+        .repay_past_due(&borrower.proxy.pubkey(), 0, u64::MAX, &[deposit])
+        .await
+        .is_err());
+
+    let mut clock = manager.client.get_clock().await?;
+    clock.unix_timestamp = loan.maturation_timestamp + GRACE_PERIOD as i64;
+    manager.client.set_clock(clock).await?;
+
+    // the staked tickets repay part of the loan, along with the penalty
+    let vault = manager.load_manager_token_vault().await?.amount;
+    let fees = manager.collected_fees().await?;
+    let claims = borrower.claims().await?;
+    let ticket_collateral = borrower.ticket_collateral().await?;
+    alice
+        .repay_past_due(&borrower.proxy.pubkey(), 0, 0, &[deposit])
+        .await?;
+
+    let repaid = staked * 10_000 / (10_000 + PENALTY as u64);
+    assert_eq!(
+        manager.load_anchor::<TermDeposit>(&deposit).await?.amount,
+        0
+    );
+    assert_eq!(
+        borrower.load_term_loan(0).await?.balance,
+        loan.balance - repaid
+    );
+    assert_eq!(borrower.claims().await?, claims - repaid);
+    assert_eq!(
+        borrower.ticket_collateral().await?,
+        ticket_collateral - staked
+    );
+    assert_eq!(manager.load_manager_token_vault().await?.amount, vault);
+    assert_eq!(manager.collected_fees().await?, fees);
+
+    // alice pays the rest, and the penalty is left in the vault for the lenders
+    let balance = loan.balance - repaid;
+    let owed = balance + balance * PENALTY as u64 / 10_000;
+    let alice_tokens = alice.tokens().await?;
+    alice
+        .repay_past_due(&borrower.proxy.pubkey(), 0, u64::MAX, &[])
+        .await?;
+
+    assert_eq!(alice.tokens().await?, alice_tokens - owed);
+    assert_eq!(
+        manager.load_manager_token_vault().await?.amount,
+        vault + owed
+    );
+    assert_eq!(manager.collected_fees().await?, fees);
+    assert_eq!(borrower.claims().await?, 0);
+    assert!(borrower.load_term_loan(0).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn margin_batch_orders_match_single_orders() -> Result<()> {
+    let ctx = margin_test_context!();
+    let manager = Arc::new(FixedTermTestManager::full(&ctx).await.unwrap());
+    let client = manager.client.clone();
+    let ([collateral], _, pricer) = tokens(&ctx).await.unwrap();
+    let ticket_mint = manager.ix_builder.ticket_mint();
+    let token_mint = manager.ix_builder.token_mint();
+
+    let batched = create_and_fund_fixed_term_market_margin_user(
+        &ctx,
+        manager.clone(),
+        vec![(collateral, 0, u64::MAX / 2)],
+    )
+    .await;
+    let single = create_and_fund_fixed_term_market_margin_user(
+        &ctx,
+        manager.clone(),
+        vec![(collateral, 0, u64::MAX / 2)],
+    )
+    .await;
+
+    // lend orders rest below the borrow orders, so nothing is filled
+    let replaced_lend = post_only(underlying(500, 4_000));
+    let replaced_borrow = post_only(underlying(500, 500));
+    let lend = vec![
+        post_only(underlying(1_000, 3_000)),
+        post_only(underlying(2_000, 2_500)),
+    ];
+    let borrow = vec![post_only(underlying(1_500, 1_000))];
+
+    let mut outcomes = vec![];
+    for (user, batch) in [(&batched, true), (&single, false)] {
+        transactions! {
+            pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+            pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+            pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+            user.refresh_and_margin_lend_order(replaced_lend).await?,
+            user.margin_borrow_order(replaced_borrow).await?,
+        }
+        .send_and_confirm_condensed_in_order(&client)
+        .await?;
+        let supplies = manager.note_supplies().await?;
+        let cancel = manager
+            .load_orderbook()
+            .await?
+            .orders_of(&user.proxy.pubkey())?;
+        assert_eq!(cancel.len(), 2);
+
+        if batch {
+            transactions! {
+                pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+                pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+                pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+                user.proxy.refresh().await?,
+                user.margin_batch_orders(BatchOrderParams {
+                    cancel,
+                    lend: lend.clone(),
+                    borrow: borrow.clone(),
+                }),
+            }
+            .send_and_confirm_condensed_in_order(&client)
+            .await?;
+        } else {
+            for order_id in cancel {
+                user.cancel_order(order_id).await?;
+            }
+            for params in &lend {
+                transactions! {
+                    pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+                    pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+                    pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+                    user.refresh_and_margin_lend_order(*params).await?,
+                }
+                .send_and_confirm_condensed_in_order(&client)
+                .await?;
+            }
+            for params in &borrow {
+                transactions! {
+                    pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+                    pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+                    pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+                    user.refresh_and_margin_borrow_order(*params).await?,
+                }
+                .send_and_confirm_condensed_in_order(&client)
+                .await?;
+            }
+        }
+
+        // the cancelled orders are returned to the margin user once events are consumed
+        manager.consume_events().await?;
+        manager.expect_and_execute_settlement(&[user]).await?;
+
+        let margin_user = user.load_margin_user().await?;
+        let minted: Vec<u64> = manager
+            .note_supplies()
+            .await?
+            .iter()
+            .zip(supplies)
+            .map(|(after, before)| after - before)
+            .collect();
+        outcomes.push((
+            margin_user.assets().clone(),
+            margin_user.debt().pending(),
+            margin_user.debt().committed(),
+            user.tokens().await?,
+            user.claims().await?,
+            user.ticket_collateral().await?,
+            user.underlying_collateral().await?,
+            minted,
+        ));
+        assert_eq!(
+            manager
+                .load_orderbook()
+                .await?
+                .orders_of(&user.proxy.pubkey())?
+                .len(),
+            lend.len() + borrow.len()
+        );
+    }
+    assert_eq!(outcomes[0], outcomes[1]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn amend_signer_and_margin_orders() -> Result<()> {
+    let ctx = margin_test_context!();
+    let manager = Arc::new(FixedTermTestManager::full(&ctx).await.unwrap());
+    let client = manager.client.clone();
+    let ([collateral], _, pricer) = tokens(&ctx).await.unwrap();
+    let ticket_mint = manager.ix_builder.ticket_mint();
+    let token_mint = manager.ix_builder.token_mint();
+    let lower_lend_price = OrderAmount::from_quote_amount_rate(1_000, 3_000).price;
+    let higher_borrow_price = OrderAmount::from_quote_amount_rate(1_000, 500).price;
+
+    let alice = FixedTermUser::<NoProxy>::generate_funded(ctx.clone(), manager.clone()).await?;
+    let lender = create_and_fund_fixed_term_market_margin_user(&ctx, manager.clone(), vec![]).await;
+    let borrower = create_and_fund_fixed_term_market_margin_user(
+        &ctx,
+        manager.clone(),
+        vec![(collateral, 0, u64::MAX / 2)],
+    )
+    .await;
+
+    // a signer reduces a lend order in place, and is refunded when the event is consumed
+    alice
+        .lend_order(post_only(underlying(1_000, 2_000)), &[0])
+        .await?;
+    let order = manager.load_orderbook().await?.bids()?[0];
+    let kept = order.base_quantity / 2;
+    alice
+        .amend_order(
+            order.key,
+            AmendOrderParams {
+                max_ticket_qty: Some(kept),
+                limit_price: None,
+            },
+        )
+        .await?;
+
+    let bid = manager.load_orderbook().await?.bids()?[0];
+    assert_eq!(bid.key, order.key);
+    assert_eq!(bid.base_quantity, kept);
+    let (base_removed, quote_removed) = first_out_event(&manager).await?;
+    assert_eq!(base_removed, order.base_quantity - kept);
+
+    let tokens = alice.tokens().await?;
+    manager.consume_events().await?;
+    assert_eq!(alice.tokens().await?, tokens + quote_removed);
+
+    // repricing moves the order, returning any tokens it no longer needs right away
+    let tokens = alice.tokens().await?;
+    alice
+        .amend_order(
+            order.key,
+            AmendOrderParams {
+                max_ticket_qty: None,
+                limit_price: Some(lower_lend_price),
+            },
+        )
+        .await?;
+    let bid = manager.load_orderbook().await?.bids()?[0];
+    assert_ne!(bid.key, order.key);
+    assert_eq!(bid.price(), lower_lend_price);
+    assert!(bid.base_quantity > kept);
+    assert!(alice.tokens().await? >= tokens);
+
+    // margin users reduce orders, and their posted balances follow the consumed events
+    transactions! {
+        pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        lender.refresh_and_margin_lend_order(post_only(underlying(1_000, 2_000))).await?,
+        borrower.refresh_and_margin_borrow_order(post_only(underlying(1_000, 1_000))).await?,
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    let lend_order = manager
+        .load_orderbook()
+        .await?
+        .orders_of(&lender.proxy.pubkey())?[0];
+    let borrow_order = manager
+        .load_orderbook()
+        .await?
+        .orders_of(&borrower.proxy.pubkey())?[0];
+    let tickets_posted = lender.load_margin_user().await?.assets().tickets_posted();
+    let tokens_posted = borrower.load_margin_user().await?.assets().tokens_posted();
+    let pending_debt = borrower.load_margin_user().await?.debt().pending();
+
+    let mut book = manager.load_orderbook().await?;
+    let lend_kept = book
+        .bids()?
+        .iter()
+        .find(|o| o.key == lend_order)
+        .unwrap()
+        .base_quantity
+        / 2;
+    let borrow_kept = book.asks()?[0].base_quantity / 2;
+    transactions! {
+        pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        lender.proxy.refresh().await?,
+        lender.margin_amend_order(
+            lend_order,
+            AmendOrderParams {
+                max_ticket_qty: Some(lend_kept),
+                limit_price: None,
+            },
+        ),
+        borrower.proxy.refresh().await?,
+        borrower.margin_amend_order(
+            borrow_order,
+            AmendOrderParams {
+                max_ticket_qty: Some(borrow_kept),
+                limit_price: None,
+            },
+        ),
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+    let (lend_removed, lend_quote_removed) = first_out_event(&manager).await?;
+
+    manager.consume_events().await?;
+    let lender_user = lender.load_margin_user().await?;
+    assert_eq!(
+        lender_user.assets().tickets_posted(),
+        tickets_posted - lend_removed
+    );
+    assert_eq!(lender_user.assets().entitled_tokens(), lend_quote_removed);
+
+    manager
+        .expect_and_execute_settlement(&[&lender, &borrower])
+        .await?;
+    assert_eq!(
+        lender.load_margin_user().await?.assets().entitled_tokens(),
+        0
+    );
+    let borrower_user = borrower.load_margin_user().await?;
+    assert!(borrower_user.assets().tokens_posted() < tokens_posted);
+    assert!(borrower_user.debt().pending() < pending_debt);
+    assert_notes_match_balances(&lender).await?;
+    assert_notes_match_balances(&borrower).await?;
+
+    // repricing updates the posted balances and collateral notes immediately
+    transactions! {
+        pricer.set_oracle_price_tx(&collateral, 1.0).await?,
+        pricer.set_oracle_price_tx(&ticket_mint, 1.0).await?,
+        pricer.set_oracle_price_tx(&token_mint, 1.0).await?,
+        lender.proxy.refresh().await?,
+        lender.margin_amend_order(
+            lend_order,
+            AmendOrderParams {
+                max_ticket_qty: None,
+                limit_price: Some(lower_lend_price),
+            },
+        ),
+        borrower.proxy.refresh().await?,
+        borrower.margin_amend_order(
+            borrow_order,
+            AmendOrderParams {
+                max_ticket_qty: None,
+                limit_price: Some(higher_borrow_price),
+            },
+        ),
+    }
+    .send_and_confirm_condensed_in_order(&client)
+    .await?;
+
+    let mut book = manager.load_orderbook().await?;
+    let repriced_lend = book.orders_of(&lender.proxy.pubkey())?[0];
+    let repriced_borrow = book.orders_of(&borrower.proxy.pubkey())?[0];
+    let lend_bid = *book
+        .bids()?
+        .iter()
+        .find(|o| o.key == repriced_lend)
+        .unwrap();
+    let borrow_ask = *book
+        .asks()?
+        .iter()
+        .find(|o| o.key == repriced_borrow)
+        .unwrap();
+    assert_eq!(lend_bid.price(), lower_lend_price);
+    assert_eq!(borrow_ask.price(), higher_borrow_price);
+    assert_eq!(borrow_ask.base_quantity, borrow_kept);
+
+    let lender_user = lender.load_margin_user().await?;
+    assert_eq!(
+        lender_user.assets().tickets_posted(),
+        lend_bid.base_quantity
+    );
+    assert!(manager
+        .load_event_queue()
+        .await?
+        .inner()?
+        .iter()
+        .next()
+        .is_none());
+    assert_notes_match_balances(&lender).await?;
+    assert_notes_match_balances(&borrower).await?;
+
+    Ok(())
+}
+
+/// The base and quote size of the first out event in the queue
+async fn first_out_event(manager: &FixedTermTestManager) -> Result<(u64, u64)> {
+    let mut eq = manager.load_event_queue().await?;
+    let local_eq = eq.inner()?;
+    let out = match local_eq.iter().next().unwrap() {
+        EventRef::Out(out) => out,
+        _ => panic!("expected an out event"),
+    };
+
+    Ok((out.event.base_size, out.event.quote_size()?))
+}
+
+/// The collateral notes and claims of a margin user should match the balances it tracks
+async fn assert_notes_match_balances<P: Proxy>(user: &FixedTermUser<P>) -> Result<()> {
+    let margin_user = user.load_margin_user().await?;
+    assert_eq!(
+        user.ticket_collateral().await?,
+        margin_user.assets().ticket_collateral()?
+    );
+    assert_eq!(
+        user.underlying_collateral().await?,
+        margin_user.assets().underlying_collateral()
+    );
+    assert_eq!(user.claims().await?, margin_user.debt().total());
+
+    Ok(())
+}
+
 fn quote_to_base(quote: u64, rate_bps: u64) -> u64 {
     quote + quote * rate_bps / 10_000
 }
@@ -1284,6 +2033,14 @@ fn underlying(quote: u64, rate_bps: u64) -> OrderParams {
         post_allowed: true,
         auto_stake: true,
         auto_roll: false,
+        time_in_force: TimeInForce::GoodTilCancelled,
+    }
+}
+
+fn post_only(params: OrderParams) -> OrderParams {
+    OrderParams {
+        post_only: true,
+        ..params
     }
 }
 
@@ -1298,5 +2055,6 @@ fn tickets(base: u64, rate_bps: u64) -> OrderParams {
         post_allowed: true,
         auto_stake: true,
         auto_roll: false,
+        time_in_force: TimeInForce::GoodTilCancelled,
     }
 }
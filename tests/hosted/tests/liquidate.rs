@@ -266,12 +266,33 @@ async fn cannot_withdraw_too_much_during_liquidation() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn cannot_withdraw_before_repaying_during_liquidation() -> Result<()> {
+    let scen = scenario1!().unwrap().1;
+
+    let user_b_liq = scen.liquidator.begin(&scen.user_b, true).await.unwrap();
+
+    // the liquidator has not earned any bonus until liabilities are repaid
+    let result = user_b_liq.withdraw(&scen.usdc, 40 * ONE_USDC).await;
+
+    assert_custom_program_error(ErrorCode::LiquidationLostValue, result);
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[cfg_attr(not(feature = "localnet"), serial_test::serial)]
 async fn can_withdraw_some_during_liquidation() -> Result<()> {
     let scen = scenario1!().unwrap().1;
 
     let user_b_liq = scen.liquidator.begin(&scen.user_b, true).await.unwrap();
+
+    // repaying 10'000 USD of liabilities earns a bonus of 400 USD
+    user_b_liq
+        .margin_repay(&scen.usdc, 10_000 * ONE_USDC)
+        .await
+        .unwrap();
     user_b_liq
         .withdraw(&scen.usdc, 40 * ONE_USDC)
         .await
@@ -300,6 +321,12 @@ async fn can_borrow_some_during_liquidation() -> Result<()> {
     let scen = scenario1!().unwrap().1;
 
     let user_b_liq = scen.liquidator.begin(&scen.user_b, false).await.unwrap();
+
+    // the net liabilities repaid must still cover any equity lost while borrowing
+    user_b_liq
+        .margin_repay(&scen.usdc, 10_000 * ONE_USDC)
+        .await
+        .unwrap();
     user_b_liq
         .borrow(&scen.usdc, 5_000 * ONE_USDC)
         .await
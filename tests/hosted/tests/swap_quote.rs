@@ -0,0 +1,433 @@
+use std::{num::NonZeroU64, ops::Deref, rc::Rc, sync::Arc};
+
+use anchor_spl::dex::serum_dex::{
+    fees::FeeTier,
+    instruction::SelfTradeBehavior,
+    matching::{OrderType, Side},
+};
+use anyhow::Error;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Signer};
+
+use jet_client::JetClient;
+use jet_environment::builder::WHIRLPOOL_TICK_SPACING;
+use jet_instructions::{orca::derive_whirlpool, test_service::derive_whirlpool_config};
+use jet_margin::TokenKind;
+use jet_margin_pool::{MarginPoolConfig, PoolFlags, TokenChange};
+use jet_margin_sdk::{
+    ix_builder::{MarginSwapRouteIxBuilder, SwapAccounts, SwapContext},
+    swap::{
+        openbook_swap::OpenBookMarket,
+        quote::{quote_route, RouteLeg, SwapQuoter},
+        saber_swap::SaberSwapPool,
+        spl_swap::SplSwapPool,
+        whirlpool::WhirlpoolSwap,
+    },
+    tokens::TokenPrice,
+    tx_builder::TokenDepositsConfig,
+};
+use jet_solana_client::util::keypair;
+use jet_static_program_registry::spl_token_swap_v2;
+
+use hosted_tests::{
+    actions::*,
+    context::{MarginTestContext, TestContextSetupInfo},
+    environment::TestToken,
+    margin::MarginPoolSetupInfo,
+    margin_test_context,
+    openbook::{price_number_to_lot, OpenBookMarketConfig, OpenBookOrderParams},
+    saber_swap::SaberSwapPoolConfig,
+    spl_swap::SwapPoolConfig,
+    test_context,
+};
+
+const ONE_MSOL: u64 = LAMPORTS_PER_SOL;
+const ONE_TSOL: u64 = LAMPORTS_PER_SOL;
+
+const DEFAULT_POOL_CONFIG: MarginPoolConfig = MarginPoolConfig {
+    borrow_rate_0: 10,
+    borrow_rate_1: 20,
+    borrow_rate_2: 30,
+    borrow_rate_3: 40,
+    utilization_rate_1: 10,
+    utilization_rate_2: 20,
+    management_fee_rate: 10,
+    flags: PoolFlags::ALLOW_LENDING.bits(),
+    reserved: 0,
+};
+
+struct TestEnv {
+    tsol: Pubkey,
+    msol: Pubkey,
+}
+
+async fn setup_environment(ctx: &MarginTestContext) -> Result<TestEnv, Error> {
+    let tsol = ctx.tokens().create_token(9, None, None).await?;
+    let tsol_oracle = ctx.tokens().create_oracle(&tsol).await?;
+    let msol = ctx.tokens().create_token(9, None, None).await?;
+    let msol_oracle = ctx.tokens().create_oracle(&msol).await?;
+
+    let pools = [
+        MarginPoolSetupInfo {
+            token: tsol,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 95,
+            max_leverage: 4_00,
+            config: DEFAULT_POOL_CONFIG,
+            oracle: tsol_oracle,
+        },
+        MarginPoolSetupInfo {
+            token: msol,
+            token_kind: TokenKind::Collateral,
+            collateral_weight: 90,
+            max_leverage: 3_00,
+            config: DEFAULT_POOL_CONFIG,
+            oracle: msol_oracle,
+        },
+    ];
+
+    for pool_info in pools {
+        ctx.margin_client()
+            .configure_token_deposits(
+                &pool_info.token,
+                Some(&TokenDepositsConfig {
+                    oracle: jet_margin::TokenOracle::Pyth {
+                        price: pool_info.oracle.price,
+                        product: pool_info.oracle.product,
+                    },
+                    collateral_weight: pool_info.collateral_weight,
+                }),
+            )
+            .await?;
+        ctx.margin_client().create_pool(&pool_info).await?;
+    }
+
+    ctx.tokens()
+        .set_price(
+            &tsol,
+            &TokenPrice {
+                exponent: -8,
+                price: 10_000_000_000,
+                confidence: 100_000_000,
+                twap: 10_000_000_000,
+            },
+        )
+        .await?;
+    ctx.tokens()
+        .set_price(
+            &msol,
+            &TokenPrice {
+                exponent: -8,
+                price: 10_600_000_000,
+                confidence: 100_000_000,
+                twap: 10_600_000_000,
+            },
+        )
+        .await?;
+
+    Ok(TestEnv { tsol, msol })
+}
+
+/// Swap MSOL for TSOL through a single pool from a margin account, returning the TSOL received
+async fn margin_swap_output(
+    ctx: &Arc<MarginTestContext>,
+    env: &TestEnv,
+    pool: &impl SwapAccounts,
+    amount: u64,
+) -> anyhow::Result<u64> {
+    let wallet = ctx.create_wallet(10).await?;
+    ctx.issue_permit(wallet.pubkey()).await?;
+    let user = ctx.margin_client().user(&wallet, 0).created().await?;
+
+    let user_msol = user.create_deposit_position(&env.msol).await?;
+    let user_tsol = user.create_deposit_position(&env.tsol).await?;
+
+    ctx.tokens().mint(&env.msol, &user_msol, amount).await?;
+    user.tx.refresh_positions(&()).await?;
+
+    let mut swap_builder = MarginSwapRouteIxBuilder::try_new(
+        SwapContext::MarginPositions,
+        *user.address(),
+        env.msol,
+        env.tsol,
+        TokenChange::shift(amount),
+        1,
+    )?;
+    swap_builder.add_swap_leg(pool, 0)?;
+    swap_builder.finalize()?;
+
+    user.route_swap(&swap_builder, &[]).await?;
+
+    ctx.tokens().get_balance(&user_tsol).await
+}
+
+#[cfg_attr(feature = "localnet", ignore = "does not run on localnet")]
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn quote_spl_swap() -> anyhow::Result<()> {
+    let ctx = margin_test_context!();
+    let env = setup_environment(&ctx).await?;
+    let rpc = ctx.solana.rpc2.deref();
+
+    let pool = SplSwapPool::configure(
+        &ctx.solana,
+        &spl_token_swap_v2::id(),
+        &env.msol,
+        &env.tsol,
+        1_000_000 * ONE_MSOL,
+        1_060_000 * ONE_TSOL,
+    )
+    .await?;
+
+    let quote = pool.quote(rpc, &env.msol, 10 * ONE_MSOL).await?;
+    assert_eq!(quote.destination_mint, env.tsol);
+    assert_eq!(quote.amount_in, 10 * ONE_MSOL);
+    assert_eq!(quote.fee_mint, env.msol);
+    assert!(quote.fee > 0);
+    assert!(quote.price_impact > 0.0 && quote.price_impact < 0.001);
+
+    let large_quote = pool.quote(rpc, &env.msol, 10_000 * ONE_MSOL).await?;
+    assert!(large_quote.price_impact > quote.price_impact);
+
+    let received = margin_swap_output(&ctx, &env, &pool, 10 * ONE_MSOL).await?;
+    assert_eq!(received, quote.amount_out);
+
+    // the pool has moved, so a route quote reflects the new reserves
+    let route = quote_route(rpc, &env.msol, 10 * ONE_MSOL, &[RouteLeg::Single(&pool)]).await?;
+    assert_eq!(route.swaps.len(), 1);
+    assert!(route.amount_out < quote.amount_out);
+    assert!(route.minimum_amount_out(100) < route.amount_out);
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "localnet", ignore = "does not run on localnet")]
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn quote_saber_swap() -> anyhow::Result<()> {
+    let ctx = margin_test_context!();
+    let env = setup_environment(&ctx).await?;
+    let rpc = ctx.solana.rpc2.deref();
+
+    let pool = SaberSwapPool::configure(
+        &ctx.solana,
+        &env.msol,
+        &env.tsol,
+        10_000 * ONE_MSOL,
+        10_600 * ONE_TSOL,
+    )
+    .await?;
+
+    let quote = pool.quote(rpc, &env.msol, ONE_MSOL).await?;
+    assert_eq!(quote.destination_mint, env.tsol);
+    assert_eq!(quote.fee_mint, env.tsol);
+    assert!(quote.amount_out > ONE_TSOL / 2);
+
+    let large_quote = pool.quote(rpc, &env.msol, 5_000 * ONE_MSOL).await?;
+    assert!(large_quote.price_impact > quote.price_impact);
+
+    let received = margin_swap_output(&ctx, &env, &pool, ONE_MSOL).await?;
+    assert_eq!(received, quote.amount_out);
+
+    Ok(())
+}
+
+#[cfg_attr(feature = "localnet", ignore = "does not run on localnet")]
+#[tokio::test(flavor = "multi_thread")]
+#[cfg_attr(not(feature = "localnet"), serial_test::serial)]
+async fn quote_openbook_swap() -> anyhow::Result<()> {
+    let ctx = margin_test_context!();
+    let env = setup_environment(&ctx).await?;
+    let rpc = ctx.solana.rpc2.deref();
+
+    let base_lot_size = 1_000_000;
+    let quote_lot_size = 100;
+
+    let market = OpenBookMarket::configure(
+        &ctx.solana,
+        env.msol,
+        env.tsol,
+        base_lot_size,
+        quote_lot_size,
+        100,
+    )
+    .await?;
+
+    let maker = ctx.create_wallet(2).await?;
+    let maker_msol_account = ctx
+        .tokens()
+        .create_account_funded(&env.msol, &maker.pubkey(), 10_000 * ONE_MSOL)
+        .await?;
+    let maker_tsol_account = ctx
+        .tokens()
+        .create_account_funded(&env.tsol, &maker.pubkey(), 10_000 * ONE_TSOL)
+        .await?;
+    let open_orders = market
+        .init_open_orders(&ctx.rpc(), ctx.solana.keygen.generate_key(), &maker)
+        .await?;
+
+    // 100 MSOL bid at 1.04, and 100 MSOL offered at each of 1.06 and 1.08
+    let price = |price: u64| price_number_to_lot(price, ONE_MSOL, base_lot_size, quote_lot_size);
+    let order = |side, client_order_id, limit_price| OpenBookOrderParams {
+        side,
+        client_order_id,
+        limit_price: NonZeroU64::new(limit_price).unwrap(),
+        max_coin_qty: NonZeroU64::new(100 * ONE_MSOL / base_lot_size).unwrap(),
+        max_native_pc_qty_including_fees: NonZeroU64::new(u64::MAX).unwrap(),
+        order_type: OrderType::Limit,
+        self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+        limit: u16::MAX,
+    };
+
+    let bid_price = price(104 * ONE_TSOL / 100);
+    let ask_prices = [price(106 * ONE_TSOL / 100), price(108 * ONE_TSOL / 100)];
+
+    market
+        .new_order(
+            &ctx.rpc(),
+            &maker,
+            &open_orders,
+            &maker_tsol_account,
+            order(Side::Bid, 1, bid_price),
+        )
+        .await?;
+    for (i, ask_price) in ask_prices.into_iter().enumerate() {
+        market
+            .new_order(
+                &ctx.rpc(),
+                &maker,
+                &open_orders,
+                &maker_msol_account,
+                order(Side::Ask, 2 + i as u64, ask_price),
+            )
+            .await?;
+    }
+
+    // selling MSOL fills against the bid, with the fee taken from the TSOL received
+    let quote = market.quote(rpc, &env.msol, 10 * ONE_MSOL).await?;
+    let proceeds = 10 * ONE_MSOL / base_lot_size * bid_price * quote_lot_size;
+    let fee = FeeTier::Base.taker_fee(proceeds);
+
+    assert_eq!(quote.destination_mint, env.tsol);
+    assert_eq!(quote.amount_in, 10 * ONE_MSOL);
+    assert_eq!(quote.amount_out, proceeds - fee);
+    assert_eq!(quote.fee, fee);
+    assert_eq!(quote.fee_mint, env.tsol);
+    assert!(quote.price_impact < 1e-9);
+
+    // buying more MSOL than the best ask holds walks into the next level
+    let quote = market.quote(rpc, &env.tsol, 150 * ONE_TSOL).await?;
+    assert_eq!(quote.destination_mint, env.msol);
+    assert!(quote.amount_out > 100 * ONE_MSOL);
+    assert!(quote.amount_out < 150 * ONE_MSOL * 100 / 106);
+    assert!(quote.amount_in <= 150 * ONE_TSOL);
+    assert!(quote.price_impact > 0.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn quote_whirlpool_swap() -> anyhow::Result<()> {
+    let ctx = test_context! {
+        setup: &TestContextSetupInfo {
+            is_restricted: false,
+            tokens: vec![
+                TestToken::with_pool("TSOL").into(),
+                TestToken::with_pool("USDC").into(),
+            ],
+            dexes: vec![("orca-whirlpool", "TSOL/USDC")],
+        }
+    };
+
+    let rpc = ctx.inner.solana.rpc2.clone();
+
+    let usdc = Token::from_context(&ctx, "USDC");
+    let tsol = Token::from_context(&ctx, "TSOL");
+
+    let (base_real, quote_real) = (
+        std::cmp::min(tsol.mint, usdc.mint),
+        std::cmp::max(tsol.mint, usdc.mint),
+    );
+
+    let target_pool_price = if base_real == tsol.mint {
+        22.0
+    } else {
+        1.0 / 22.0
+    };
+
+    let whirlpool = derive_whirlpool(
+        &derive_whirlpool_config(),
+        &base_real,
+        &quote_real,
+        WHIRLPOOL_TICK_SPACING,
+    )
+    .0;
+    jet_testing::whirlpool::set_liquidity(
+        ctx.rpc().payer(),
+        rpc.deref(),
+        whirlpool,
+        target_pool_price,
+        9,
+    )
+    .await?;
+
+    let margin_user = ctx.inner.create_margin_user(1_000).await.unwrap();
+    let user_client = JetClient::new(
+        ctx.inner.solana.rpc2.clone(),
+        Rc::new(keypair::clone(&margin_user.signer)),
+        ctx.config.clone(),
+        &ctx.config.airspaces[0].name,
+    )
+    .unwrap();
+
+    let swap_amount = usdc.amount(10_000.0);
+    airdrop(&user_client, &usdc, swap_amount).await;
+    airdrop(&user_client, &tsol, 100).await;
+
+    let user_account = user_client.margin().accounts()[0].clone();
+    deposit(&user_account, &usdc, swap_amount).await.unwrap();
+    deposit(&user_account, &tsol, 1).await.unwrap();
+
+    user_account.sync().await.unwrap();
+    let balance_before = position_balance(&user_account, &tsol);
+
+    let whirlpool_swap = WhirlpoolSwap::get_pools(rpc.deref(), &[usdc.mint, tsol.mint].into())
+        .await
+        .unwrap()
+        .into_values()
+        .next()
+        .unwrap();
+
+    let quote = whirlpool_swap
+        .quote(rpc.deref(), &usdc.mint, swap_amount)
+        .await?;
+    assert_eq!(quote.destination_mint, tsol.mint);
+    assert_eq!(quote.amount_in, swap_amount);
+    assert_eq!(quote.fee_mint, usdc.mint);
+    assert!(quote.fee > 0);
+    assert!(quote.price_impact < 0.01);
+
+    let mut swap_builder = MarginSwapRouteIxBuilder::try_new(
+        SwapContext::MarginPositions,
+        user_account.address(),
+        usdc.mint,
+        tsol.mint,
+        TokenChange::shift(swap_amount),
+        quote.minimum_amount_out(0),
+    )
+    .unwrap();
+
+    if base_real == tsol.mint {
+        swap_builder.add_swap_leg(&whirlpool_swap.swap_b_to_a(), 0)?;
+    } else {
+        swap_builder.add_swap_leg(&whirlpool_swap.swap_a_to_b(), 0)?;
+    }
+    swap_builder.finalize()?;
+
+    margin_user.route_swap(&swap_builder, &[]).await?;
+
+    user_account.sync().await.unwrap();
+    let received = position_balance(&user_account, &tsol) - balance_before;
+    assert_eq!(received, quote.amount_out);
+
+    Ok(())
+}
@@ -1,8 +1,9 @@
 use std::collections::HashSet;
 
 use anchor_lang::Id;
-use jet_instructions::test_service::derive_token_mint;
+use jet_instructions::test_service::{derive_push_price, derive_token_mint};
 use jet_margin_sdk::swap::openbook_swap::OpenBookMarket;
+use jet_program_common::oracle::PushPriceFeed;
 use jet_solana_client::rpc::AccountFilter;
 use jet_test_service::TokenCreateParams;
 use openbook::state::OpenOrders;
@@ -251,3 +252,47 @@ async fn openorder_market_make() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn push_oracle_publishes_price() -> anyhow::Result<()> {
+    let ctx = margin_test_context!();
+    let mint = derive_token_mint("PUSH");
+    let payer = ctx.payer().pubkey();
+
+    let create_ix = jet_instructions::test_service::token_create(
+        &payer,
+        &TokenCreateParams {
+            symbol: "PUSH".to_string(),
+            name: "PUSH".to_string(),
+            decimals: 6,
+            authority: payer,
+            oracle_authority: payer,
+            max_amount: 100_000_000_000,
+            source_symbol: "USDC".to_string(),
+            price_ratio: 1.0,
+        },
+    );
+    let create_oracle_ix =
+        jet_instructions::test_service::token_create_push_oracle(&payer, &payer, &mint, -8);
+    let update_ix =
+        jet_instructions::test_service::token_update_push_price(&payer, &mint, 150_000_000, 1_000);
+
+    let tx = ctx
+        .rpc()
+        .create_transaction(&[], &[create_ix, create_oracle_ix, update_ix])
+        .await?;
+
+    ctx.rpc().send_and_confirm_transaction(&tx).await?;
+
+    let feed_address = derive_push_price(&mint);
+    let feed_account = ctx.rpc().get_account(&feed_address).await?.unwrap();
+    let feed = PushPriceFeed::load(&feed_account.data).unwrap();
+
+    assert_eq!(feed_account.owner, jet_test_service::ID);
+    assert_eq!(feed.exponent, -8);
+    assert_eq!(feed.authority, payer);
+    assert_eq!(feed.price, 150_000_000);
+    assert_eq!(feed.confidence, 1_000);
+
+    Ok(())
+}
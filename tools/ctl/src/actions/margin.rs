@@ -16,11 +16,11 @@ use jet_margin_sdk::{
         MarginIxBuilder, MarginPoolIxBuilder,
     },
     jet_airspace::state::Airspace,
-    jet_margin::{self, MarginAccount, PriceInfo, Valuation},
+    jet_margin::{self, MarginAccount, PriceChangeInfo, PriceInfo, Valuation},
     jet_margin_pool::{self, MarginPool},
     jet_metadata::{self},
 };
-use jet_program_common::DEFAULT_AIRSPACE;
+use jet_program_common::{oracle::PushPriceFeed, DEFAULT_AIRSPACE};
 use jet_solana_client::rpc::SolanaRpcExtra;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
@@ -495,10 +495,16 @@ async fn refresh_account_positions(client: &Client, account: &mut MarginAccount)
                     .rpc()
                     .get_account(&margin_pool.token_price_oracle)
                     .await?;
-                let price_oracle = pyth_sdk_solana::load_price_feed_from_account(
-                    &margin_pool.token_price_oracle,
-                    &mut oracle_data,
-                )?;
+                let price_oracle = match PushPriceFeed::load(&oracle_data.data) {
+                    Some(feed) => PriceChangeInfo::from(feed),
+                    None => {
+                        PriceChangeInfo::try_from(pyth_sdk_solana::load_price_feed_from_account(
+                            &margin_pool.token_price_oracle,
+                            &mut oracle_data,
+                        )?)
+                        .map_err(anchor_lang::error::Error::from)?
+                    }
+                };
 
                 let prices = margin_pool.calculate_prices(&price_oracle)?;
 
@@ -510,7 +516,7 @@ async fn refresh_account_positions(client: &Client, account: &mut MarginAccount)
 
                 PriceInfo::new_valid(
                     // SAFETY: We only need the exponent, which won't change if the price is stale
-                    price_oracle.get_ema_price_unchecked().expo,
+                    price_oracle.exponent,
                     price_value,
                     SystemTime::now()
                         .duration_since(UNIX_EPOCH)